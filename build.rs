@@ -0,0 +1,67 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    #[cfg(feature = "grpc")]
+    compile_grpc_service();
+}
+
+/// Generate the gRPC service of `proto/amialive.proto`, whose messages are
+/// defined in `src/grpc.rs`, so that building it doesn't require `protoc`.
+#[cfg(feature = "grpc")]
+fn compile_grpc_service() {
+    use tonic_build::manual::{Builder, Method, Service};
+
+    let method = |name: &str, route_name: &str, input: &str, output: &str| {
+        Method::builder()
+            .name(name)
+            .route_name(route_name)
+            .input_type(format!("crate::grpc::{}", input))
+            .output_type(format!("crate::grpc::{}", output))
+            .codec_path("tonic::codec::ProstCodec")
+    };
+    let service: Service = Service::builder()
+        .name("AmIAlive")
+        .package("amialive.v1")
+        .method(method("get_status", "GetStatus", "StatusRequest", "StatusResponse").build())
+        .method(
+            method(
+                "send_heartbeat",
+                "SendHeartbeat",
+                "HeartbeatRequest",
+                "HeartbeatResponse",
+            )
+            .build(),
+        )
+        .method(
+            method(
+                "stream_history",
+                "StreamHistory",
+                "HistoryRequest",
+                "Heartbeat",
+            )
+            .server_streaming()
+            .build(),
+        )
+        .build();
+
+    Builder::new().build_client(false).compile(&[service]);
+}
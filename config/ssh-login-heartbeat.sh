@@ -0,0 +1,27 @@
+#!/bin/sh
+#
+# Registers a passive heartbeat with "Am I Alive" on every SSH login to this server.
+#
+# Install it as /usr/local/bin/ssh-login-heartbeat.sh (owned by root, mode 700),
+# set AMIALIVE_URL and AMIALIVE_TOKEN below, and add this line to /etc/pam.d/sshd:
+#
+#   session optional pam_exec.so /usr/local/bin/ssh-login-heartbeat.sh
+#
+# The token is the one whose SHA-256 hash is set for this server in `[[ssh_hosts]]`.
+# Logins are never held up or refused because of the hook, as the request is sent in
+# the background, and the PAM module is optional.
+
+AMIALIVE_URL="https://<domain>"
+AMIALIVE_TOKEN="<TOKEN>"
+
+# pam_exec also runs us when the session closes
+[ "$PAM_TYPE" = "open_session" ] || exit 0
+
+curl -s -m 5 -o /dev/null \
+    -X POST "$AMIALIVE_URL/api/v1/ssh-login" \
+    -H "Authorization: Bearer $AMIALIVE_TOKEN" \
+    -H "Content-Type: application/json" \
+    -d "{\"hostname\": \"$(hostname)\", \"user\": \"$PAM_USER\"}" \
+    </dev/null >/dev/null 2>&1 &
+
+exit 0
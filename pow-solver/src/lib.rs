@@ -0,0 +1,197 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+// Solves the PoW challenges served by `pow.rs`. Shared by the daemon, which checks
+// solutions with the same hash, the Rust client, and the web pages, which load it
+// compiled to WebAssembly, so that none of them has to be kept in sync by hand.
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::ops::Range;
+
+#[cfg(target_arch = "wasm32")]
+mod wasm;
+
+/// PoW challenge, as served over `/api/v1/pow` and `/api/v1/pow/challenge`.
+#[derive(Deserialize)]
+pub struct Challenge {
+    pub user_address: String,
+    pub seed: String,
+    /// Hex encoded 128-bit target, that the solution's hash must be below.
+    pub difficulty: String,
+    pub timestamp: u128,
+    /// "sha256" or "argon2id"
+    #[serde(default)]
+    pub algorithm: String,
+    pub argon2: Option<Argon2Parameters>,
+}
+
+/// Parameters of the Argon2id hash of a challenge, whose salt is the seed.
+#[derive(Deserialize)]
+pub struct Argon2Parameters {
+    /// In KiB
+    pub memory_cost: u32,
+    pub time_cost: u32,
+    pub parallelism: u32,
+}
+
+/// A solved challenge, as sent along with a heartbeat or a login.
+#[derive(Serialize)]
+pub struct Solution {
+    pub nonce: u64,
+    /// Hex encoded hash of the solution.
+    pub hash: String,
+    /// Timestamp of the challenge it solves.
+    pub timestamp_ms: u128,
+    /// Digest of the heartbeat the hash commits to, see [`payload_digest`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload_digest: Option<String>,
+}
+
+/// Why a challenge could not be solved.
+#[derive(Debug)]
+pub enum SolveError {
+    InvalidDifficulty,
+    UnsupportedAlgorithm,
+    InvalidArgon2Parameters,
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveError::InvalidDifficulty => write!(f, "Invalid PoW difficulty."),
+            SolveError::UnsupportedAlgorithm => write!(f, "Unsupported PoW algorithm."),
+            SolveError::InvalidArgon2Parameters => write!(f, "Invalid Argon2 parameters."),
+        }
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+/// Hash function of the attempts at a challenge.
+pub enum Hasher {
+    Sha256,
+    Argon2id(Argon2<'static>),
+}
+
+impl Hasher {
+    pub fn argon2id(parameters: &Argon2Parameters) -> Result<Self, SolveError> {
+        let params: Params = Params::new(
+            parameters.memory_cost,
+            parameters.time_cost,
+            parameters.parallelism,
+            Some(32),
+        )
+        .map_err(|_| SolveError::InvalidArgon2Parameters)?;
+
+        Ok(Hasher::Argon2id(Argon2::new(
+            Algorithm::Argon2id,
+            Version::V0x13,
+            params,
+        )))
+    }
+
+    /// The hash function of the given challenge.
+    pub fn of(challenge: &Challenge) -> Result<Self, SolveError> {
+        match (challenge.algorithm.as_str(), &challenge.argon2) {
+            ("" | "sha256", _) => Ok(Hasher::Sha256),
+            ("argon2id", Some(parameters)) => Hasher::argon2id(parameters),
+            _ => Err(SolveError::UnsupportedAlgorithm),
+        }
+    }
+
+    /// Hash an attempt at a challenge: the SHA-256 hash of address + seed + nonce +
+    /// payload digest, or their Argon2id hash, salted with the seed. The digest is
+    /// empty for solutions that don't commit to a payload.
+    pub fn hash(
+        &self,
+        user_address: &str,
+        seed: &str,
+        nonce: u64,
+        payload_digest: &str,
+    ) -> Result<[u8; 32], SolveError> {
+        let message: String = format!("{}{}{}{}", user_address, seed, nonce, payload_digest);
+
+        match self {
+            Hasher::Sha256 => Ok(Sha256::digest(message.as_bytes()).into()),
+            Hasher::Argon2id(argon2) => {
+                let mut hash: [u8; 32] = [0; 32];
+
+                argon2
+                    .hash_password_into(message.as_bytes(), seed.as_bytes(), &mut hash)
+                    .map_err(|_| SolveError::InvalidArgon2Parameters)?;
+                Ok(hash)
+            }
+        }
+    }
+}
+
+/// Whether a hash solves a challenge of the given target,
+/// by the first 128 bits of the hash being below it.
+pub fn meets_target(hash: &[u8; 32], target: u128) -> bool {
+    u128::from_be_bytes(hash[..16].try_into().unwrap()) < target
+}
+
+/// Hex encoded SHA-256 digest of the fields of a heartbeat, that its proof of work
+/// can commit to, so that an intercepted solution can't be sent along with another
+/// heartbeat. The fields are hashed as the JSON array `[message, updated_note,
+/// remove_current_note]`.
+pub fn payload_digest(message: &str, updated_note: &str, remove_current_note: bool) -> String {
+    let payload: String = serde_json::to_string(&(message, updated_note, remove_current_note))
+        .expect("strings and booleans always serialize");
+
+    hex::encode(Sha256::digest(payload.as_bytes()))
+}
+
+/// Try to solve a challenge with each of the given nonces, in order,
+/// committing to the given payload digest, if any.
+///
+/// Returns `None` if none of them solves it, so that callers
+/// can try more later, e.g. without blocking a web page.
+///
+pub fn solve(
+    challenge: &Challenge,
+    payload_digest: Option<&str>,
+    nonces: Range<u64>,
+) -> Result<Option<Solution>, SolveError> {
+    let target: u128 = u128::from_str_radix(&challenge.difficulty, 16)
+        .map_err(|_| SolveError::InvalidDifficulty)?;
+    let hasher: Hasher = Hasher::of(challenge)?;
+
+    for nonce in nonces {
+        let hash: [u8; 32] = hasher.hash(
+            &challenge.user_address,
+            &challenge.seed,
+            nonce,
+            payload_digest.unwrap_or_default(),
+        )?;
+
+        if meets_target(&hash, target) {
+            return Ok(Some(Solution {
+                nonce,
+                hash: hex::encode(hash),
+                timestamp_ms: challenge.timestamp,
+                payload_digest: payload_digest.map(str::to_owned),
+            }));
+        }
+    }
+    Ok(None)
+}
@@ -0,0 +1,108 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+// Exports of `pow_solver.wasm`, see `www/pow.js`. Strings are passed as UTF-8 in the
+// module's memory: the caller writes the task in a buffer it got from `alloc`,
+// and reads the answer from `output`, the length of which `solve` returns.
+
+use crate::{Challenge, Solution, SolveError, payload_digest, solve as solve_challenge};
+use serde::Deserialize;
+use std::sync::Mutex;
+
+/// A challenge to solve, along with the heartbeat to commit to, if any.
+#[derive(Deserialize)]
+struct Task {
+    challenge: Challenge,
+    payload: Option<Payload>,
+}
+
+#[derive(Deserialize)]
+struct Payload {
+    message: String,
+    updated_note: String,
+    remove_current_note: bool,
+}
+
+/// JSON answer of the last call to `solve`.
+static OUTPUT: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+
+/// Allocate a buffer of `len` bytes for the caller to write into.
+#[unsafe(no_mangle)]
+pub extern "C" fn alloc(len: usize) -> *mut u8 {
+    let mut buffer: Vec<u8> = Vec::with_capacity(len);
+    let ptr: *mut u8 = buffer.as_mut_ptr();
+
+    std::mem::forget(buffer);
+    ptr
+}
+
+/// Free a buffer given out by `alloc`.
+///
+/// # Safety
+///
+/// `ptr` and `len` must be those of a buffer given out by `alloc`, not freed yet.
+///
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dealloc(ptr: *mut u8, len: usize) {
+    drop(unsafe { Vec::from_raw_parts(ptr, 0, len) });
+}
+
+/// Try to solve the JSON task of `len` bytes at `ptr`, `{"challenge": {...}, "payload":
+/// {"message": ..., "updated_note": ..., "remove_current_note": ...}}`, the latter being
+/// optional, with `attempts` nonces from `start`, and put the answer in `output`: the
+/// solution, `null` if none of them solves it, or `{"error": "..."}`. Returns the length
+/// of the answer.
+///
+/// # Safety
+///
+/// `ptr` must point to `len` initialized bytes, e.g. in a buffer given out by `alloc`.
+///
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn solve(ptr: *const u8, len: usize, start: u32, attempts: u32) -> usize {
+    let input: &[u8] = unsafe { std::slice::from_raw_parts(ptr, len) };
+    let nonces = start as u64..start as u64 + attempts as u64;
+
+    let solved: Result<Option<Solution>, String> = serde_json::from_slice::<Task>(input)
+        .map_err(|err| err.to_string())
+        .and_then(|task| {
+            let digest: Option<String> = task.payload.map(|payload| {
+                payload_digest(
+                    &payload.message,
+                    &payload.updated_note,
+                    payload.remove_current_note,
+                )
+            });
+            solve_challenge(&task.challenge, digest.as_deref(), nonces)
+                .map_err(|err: SolveError| err.to_string())
+        });
+    let answer: String = match solved {
+        Ok(solution) => serde_json::to_string(&solution).unwrap(),
+        Err(reason) => serde_json::json!({ "error": reason }).to_string(),
+    };
+    let mut output = OUTPUT.lock().unwrap();
+
+    *output = answer.into_bytes();
+    output.len()
+}
+
+/// Pointer to the answer of the last call to `solve`.
+#[unsafe(no_mangle)]
+pub extern "C" fn output() -> *const u8 {
+    OUTPUT.lock().unwrap().as_ptr()
+}
@@ -0,0 +1,271 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::audit;
+use crate::config::{Role, ThemeMode};
+use crate::database::{self, AuthAttempt, Database, load_database};
+use crate::note::set_note;
+use crate::notify;
+use crate::state::{LifeState, RateLimit, ServerState};
+use crate::state_override::{OverrideState, set_state};
+use crate::templating::{datetime, render};
+use crate::theme;
+use crate::trusted::{Forbidden, OWNER_USERNAME};
+use crate::web_session::{CsrfForm, WebSession};
+use askama::Template;
+use axum::extract::{Form, State};
+use axum::http::StatusCode;
+use axum::response::{Html, IntoResponse, Response};
+use chrono::{FixedOffset, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::MutexGuard;
+
+/// Most recent refused authentication attempts shown on `/admin`.
+const SHOWN_AUTH_FAILURES: usize = 20;
+
+#[derive(Template, Serialize)]
+#[template(path = "admin.html")]
+struct AdminTemplate {
+    theme: ThemeMode,
+    name: String,
+    csrf_token: String,
+    /// What the submitted form did, if one was.
+    message: Option<String>,
+    status: String,
+    status_color: String,
+    note: String,
+    rate_limits: Vec<RateLimitRow>,
+    auth_failures: Vec<AuthFailureRow>,
+}
+
+#[derive(Serialize)]
+struct RateLimitRow {
+    address: String,
+    /// RFC 2822 date of when the rate limit ends, in the configured UTC offset
+    until: String,
+    /// RFC 3339 date, see [`crate::templating::datetime`]
+    datetime: String,
+    /// In minutes
+    period: u64,
+}
+
+#[derive(Serialize)]
+struct AuthFailureRow {
+    /// RFC 2822 date, in the configured UTC offset
+    timestamp: String,
+    /// RFC 3339 date, see [`crate::templating::datetime`]
+    datetime: String,
+    address: String,
+    method: String,
+    outcome: String,
+}
+
+#[derive(Deserialize)]
+pub struct NoteForm {
+    csrf_token: String,
+    note: String,
+    /// "save" or "remove", after the button the form was submitted with.
+    action: String,
+}
+
+#[derive(Deserialize)]
+pub struct StateForm {
+    csrf_token: String,
+    state: OverrideState,
+}
+
+/// Refuse anyone logged in but the person themself.
+fn require_owner(web_session: &WebSession) -> Result<(), Forbidden> {
+    match web_session.session.role.permits(Role::Owner) {
+        true => Ok(()),
+        false => Err(Forbidden),
+    }
+}
+
+/// Render the admin page, telling what the submitted form did, if one was.
+async fn admin_page(
+    server_state: &ServerState,
+    web_session: &WebSession,
+    message: Option<String>,
+) -> Response {
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    // make sure we're not showing a stale state
+    server_state.update(now).await;
+
+    let state: LifeState = **server_state.state.lock().await;
+    let timezone: FixedOffset =
+        FixedOffset::east_opt(server_state.config.global.utc_offset * 60 * 60).unwrap();
+    let format_time = |timestamp: u64| -> String {
+        timezone
+            .timestamp_opt(timestamp as i64, 0)
+            .unwrap()
+            .to_rfc2822()
+    };
+
+    // only those still waiting out their rate limit
+    let mut rate_limits: Vec<RateLimitRow> = {
+        let locked_map: MutexGuard<'_, HashMap<IpAddr, RateLimit>> =
+            server_state.rate_limited_ips.lock().await;
+
+        locked_map
+            .iter()
+            .filter(|(_, rate_limit)| rate_limit.timestamp > now)
+            .map(|(ip, rate_limit)| RateLimitRow {
+                address: ip.to_string(),
+                until: format_time(rate_limit.timestamp),
+                datetime: datetime(timezone, rate_limit.timestamp),
+                period: rate_limit.period.div_ceil(60),
+            })
+            .collect()
+    };
+    rate_limits.sort_by(|a, b| b.datetime.cmp(&a.datetime));
+
+    let db: Database = {
+        let _db_guard: MutexGuard<'_, ()> = database::lock().await;
+
+        match load_database(crate::DB_PATH) {
+            Err(err) => {
+                eprintln!("Could not read the authentication log: {}", err);
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+            Ok(db) => db,
+        }
+    };
+    let auth_failures: Vec<AuthFailureRow> = db
+        .auth_attempts
+        .iter()
+        .rev()
+        .filter(|attempt| attempt.outcome != "accepted")
+        .take(SHOWN_AUTH_FAILURES)
+        .map(|attempt: &AuthAttempt| AuthFailureRow {
+            timestamp: format_time(attempt.timestamp),
+            datetime: datetime(timezone, attempt.timestamp),
+            address: attempt.address.clone(),
+            method: attempt.method.clone(),
+            outcome: attempt.outcome.clone(),
+        })
+        .collect();
+
+    let page: AdminTemplate = AdminTemplate {
+        theme: server_state.config.theme.mode,
+        name: server_state.config.global.full_name.clone(),
+        csrf_token: web_session.csrf_token.clone(),
+        message,
+        status: state.to_string(),
+        status_color: theme::state_color(&server_state.config.theme, state),
+        note: server_state.note.lock().await.clone().unwrap_or_default(),
+        rate_limits,
+        auth_failures,
+    };
+    Html(render(server_state, "admin.html", &page)).into_response()
+}
+
+/// Handles requests on `/admin`, where the person themself manages their status
+/// from a browser, as they otherwise would through the `/api/v1/admin` endpoints.
+///
+pub async fn admin_handler(
+    State(server_state): State<ServerState>,
+    web_session: WebSession,
+) -> Response {
+    if let Err(forbidden) = require_owner(&web_session) {
+        return forbidden.into_response();
+    }
+    admin_page(&server_state, &web_session, None).await
+}
+
+/// Handles `POST` requests on `/admin/note`, which replaces or removes the note.
+pub async fn note_handler(
+    State(server_state): State<ServerState>,
+    web_session: WebSession,
+    Form(form): Form<NoteForm>,
+) -> Response {
+    if let Err(forbidden) = require_owner(&web_session) {
+        return forbidden.into_response();
+    }
+    if !web_session.verify_csrf(&server_state, &form.csrf_token) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    let note: Option<String> = match form.action.as_str() {
+        "remove" => None,
+        _ => Some(form.note).filter(|note| !note.trim().is_empty()),
+    };
+    let message: &str = match note {
+        Some(_) => "updated the note",
+        None => "removed the note",
+    };
+    let message: String = match set_note(&server_state, note).await {
+        Ok(()) => {
+            audit::record(OWNER_USERNAME, message.into()).await;
+            format!("You {}.", message)
+        }
+        Err(problem) => problem
+            .reason()
+            .unwrap_or("The note could not be saved. See the server's logs.")
+            .into(),
+    };
+    admin_page(&server_state, &web_session, Some(message)).await
+}
+
+/// Handles `POST` requests on `/admin/state`, where the person sets the state themself.
+pub async fn state_handler(
+    State(server_state): State<ServerState>,
+    web_session: WebSession,
+    Form(form): Form<StateForm>,
+) -> Response {
+    if let Err(forbidden) = require_owner(&web_session) {
+        return forbidden.into_response();
+    }
+    if !web_session.verify_csrf(&server_state, &form.csrf_token) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    set_state(&server_state, form.state).await;
+
+    let message: String = format!("The state is now {}.", LifeState::from(form.state));
+    admin_page(&server_state, &web_session, Some(message)).await
+}
+
+/// Handles `POST` requests on `/admin/test-notification`, which sends a
+/// test notification to every notification target.
+///
+pub async fn test_notification_handler(
+    State(server_state): State<ServerState>,
+    web_session: WebSession,
+    Form(form): Form<CsrfForm>,
+) -> Response {
+    if let Err(forbidden) = require_owner(&web_session) {
+        return forbidden.into_response();
+    }
+    if !web_session.verify_csrf(&server_state, &form.csrf_token) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    let message: String = match notify::send_test(server_state.config.clone()).await {
+        (_, 0) => "No notification targets are configured.".into(),
+        (delivered, targets) => format!(
+            "The test notification was delivered to {} of {} targets.",
+            delivered, targets
+        ),
+    };
+    admin_page(&server_state, &web_session, Some(message)).await
+}
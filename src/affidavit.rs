@@ -0,0 +1,200 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::audit;
+use crate::config::{Affidavit, Role, ServerConfig};
+use crate::database::{self, Database, load_database};
+use crate::state::{LifeState, ServerState};
+use crate::trusted::TrustedUser;
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use chrono::{FixedOffset, TimeZone};
+use ed25519_dalek::{Signer, SigningKey};
+use serde::Serialize;
+use std::fmt::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::MutexGuard;
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema)]
+struct AffidavitResponse {
+    /// The signed document, exactly as it was signed.
+    document: String,
+    algorithm: &'static str,
+    /// Base64 encoded signature of the document's UTF-8 bytes.
+    signature: String,
+    /// Base64 encoded public key to verify the signature with.
+    public_key: String,
+}
+
+/// Parse the Ed25519 signing key from our configuration.
+pub fn signing_key(config: &Affidavit) -> SigningKey {
+    let bytes: Vec<u8> = STANDARD
+        .decode(config.signing_key.trim())
+        .expect("Affidavit signing key is not valid base64.");
+    let seed: [u8; 32] = bytes
+        .try_into()
+        .expect("Affidavit signing key must be 32 bytes long.");
+    SigningKey::from_bytes(&seed)
+}
+
+/// A single event of the timeline.
+struct TimelineEvent {
+    timestamp: u64,
+    description: String,
+}
+
+/// Write the full timeline of heartbeats and state transitions as a plain text document.
+fn write_document(config: &ServerConfig, db: &Database, state: LifeState, now: u64) -> String {
+    let timezone: FixedOffset = FixedOffset::east_opt(config.global.utc_offset * 60 * 60).unwrap();
+    let format_time = |timestamp: u64| -> String {
+        timezone
+            .timestamp_opt(timestamp as i64, 0)
+            .unwrap()
+            .to_rfc3339()
+    };
+
+    let mut timeline: Vec<TimelineEvent> = vec![];
+
+    for log in db.heartbeat_history.iter() {
+        timeline.push(TimelineEvent {
+            timestamp: log.timestamp,
+            description: match log.message.is_empty() {
+                true => "Heartbeat received.".into(),
+                false => format!("Heartbeat received, with message: {:?}", log.message),
+            },
+        });
+    }
+    for transition in db.transitions.iter() {
+        timeline.push(TimelineEvent {
+            timestamp: transition.timestamp,
+            description: format!(
+                "State changed from \"{}\" to \"{}\". ({})",
+                transition.from, transition.to, transition.cause
+            ),
+        });
+    }
+    // heartbeats come before the transitions they caused
+    timeline.sort_by_key(|event| event.timestamp);
+
+    let mut document: String = String::new();
+
+    writeln!(document, "STATE TIMELINE OF {}", config.global.full_name).unwrap();
+    writeln!(document).unwrap();
+    writeln!(document, "Generated on: {} ({})", format_time(now), now).unwrap();
+    writeln!(document, "Current state: {}", state).unwrap();
+    writeln!(
+        document,
+        "Last heartbeat: {} ({})",
+        format_time(db.last_heartbeat),
+        db.last_heartbeat
+    )
+    .unwrap();
+    writeln!(document).unwrap();
+
+    for event in timeline.iter() {
+        writeln!(
+            document,
+            "{} ({}) {}",
+            format_time(event.timestamp),
+            event.timestamp,
+            event.description
+        )
+        .unwrap();
+    }
+    document
+}
+
+/// Handles requests on `/api/v1/affidavit`, which produces a document with the full
+/// timeline of heartbeats and state transitions, signed with the server's Ed25519 key.
+///
+/// Meant to be handed to an executor or lawyer, as evidence of when contact was lost.
+///
+#[utoipa::path(
+    get,
+    path = "/api/v1/affidavit",
+    tag = "trusted users",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The signed affidavit", body = AffidavitResponse),
+        (status = 401, description = "Not logged in"),
+        (status = 403, description = "Requires the executor role"),
+        (status = 404, description = "Affidavits are not enabled"),
+    )
+)]
+pub async fn affidavit_api(
+    State(server_state): State<ServerState>,
+    user: TrustedUser,
+) -> impl IntoResponse {
+    if let Err(forbidden) = user.require(Role::Executor) {
+        return forbidden.into_response();
+    }
+    let Some(affidavit) = server_state.config.affidavit.as_ref() else {
+        return Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::default())
+            .unwrap();
+    };
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    // make sure we're not acting on a stale state
+    server_state.update(now).await;
+
+    let db: Database = {
+        let _db_guard: MutexGuard<'_, ()> = database::lock().await;
+
+        match load_database(crate::DB_PATH) {
+            Err(err) => {
+                eprintln!("Could not read the state timeline: {}", err);
+                return Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(Body::default())
+                    .unwrap();
+            }
+            Ok(db) => db,
+        }
+    };
+    let state: LifeState = **server_state.state.lock().await;
+    let document: String = write_document(&server_state.config, &db, state, now);
+
+    let key: SigningKey = signing_key(affidavit);
+    let resp: AffidavitResponse = AffidavitResponse {
+        algorithm: "Ed25519",
+        signature: STANDARD.encode(key.sign(document.as_bytes()).to_bytes()),
+        public_key: STANDARD.encode(key.verifying_key().to_bytes()),
+        document,
+    };
+    audit::record(
+        &user.session.username,
+        "exported a signed state timeline".into(),
+    )
+    .await;
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&resp).unwrap()))
+        .unwrap()
+}
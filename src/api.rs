@@ -17,34 +17,81 @@
     License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::database::{Database, HeartbeatLog, load_database};
-use crate::pow::verify_pow_solution;
+use crate::auth_log;
+use crate::cache::CacheHeaders;
+use crate::config::Global;
+use crate::database::{self, Database, HeartbeatLog, LabelLog, MediaLog, load_database};
+use crate::device_tokens;
+use crate::heatmap;
+use crate::media::{HeartbeatAttachment, save_attachment};
+use crate::password;
+use crate::pow::{PowError, check_pow_solution};
+use crate::problem::{self, Problem};
+use crate::qr_code;
+use crate::rate_limit;
 use crate::state::{HeartbeatDisplay, LifeState, RateLimit, Redundant, ServerState};
+use crate::status_signing;
+use crate::trusted::{Reader, bearer_token};
 use crate::{INITIAL_RATE_LIMIT_PERIOD, MAX_DISPLAYED_HEARTBEATS, RATE_LIMIT_PERIOD_FACTOR};
-use argon2::{Argon2, PasswordVerifier};
+use argon2::PasswordHash;
+use axum::async_trait;
 use axum::body::Body;
-use axum::extract::{Json, State};
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{ConnectInfo, FromRequestParts, Json, Query, State};
 use axum::http::HeaderMap;
-use axum::http::{HeaderValue, StatusCode};
+use axum::http::StatusCode;
+use axum::http::request::Parts;
+use axum::response::sse::{Event, KeepAlive, Sse};
 use axum::response::{IntoResponse, Response};
 use chrono::{FixedOffset, TimeZone};
+use futures_util::stream::{self, Stream, StreamExt};
 use serde::{Deserialize, Serialize};
-use serde_json::{self, Error};
+use serde_json::{self, Error, Value};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
-use std::net::IpAddr;
+use std::convert::Infallible;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::str::FromStr;
-use std::time::{SystemTime, UNIX_EPOCH};
-use tokio::sync::MutexGuard;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{MutexGuard, broadcast};
+use totp_rs::{Algorithm, Secret, TOTP};
+use utoipa::{IntoParams, ToSchema};
+
+/// Length of a TOTP time step, in seconds.
+const TOTP_STEP: u64 = 30;
+/// How long a heartbeat may be queued by a client before it's sent, in seconds.
+pub const MAX_COMPOSED_AGE: u64 = 7 * 24 * 60 * 60;
+/// Longest heartbeat message accepted, in characters.
+const MAX_MESSAGE_LENGTH: usize = 256;
+/// Longest note accepted, in characters, as it's baked into every status response.
+const MAX_NOTE_LENGTH: usize = 1024;
+/// Largest heartbeat request body without an attachment, in bytes.
+const MAX_BODY_SIZE: usize = 16 * 1024;
 
 /// Rust Representation of the JSON response
-/// that is served on /api/status.
+/// that is served on /api/v1/status.
 ///
-#[derive(Serialize, Deserialize, Debug, Default, Clone)]
-struct StatusApiResponse {
+#[derive(Serialize, Deserialize, Debug, Default, Clone, ToSchema)]
+#[cfg_attr(feature = "graphql", derive(async_graphql::SimpleObject))]
+#[cfg_attr(feature = "graphql", graphql(name = "Status"))]
+pub struct StatusApiResponse {
     /// [`std::fmt::Display`] output of [`crate::LifeState`]
     pub status: String,
+    /// Name of the [`crate::LifeState`] variant, e.g. "ProbablyAlive"
+    pub state: String,
+    /// Code of the state, as stored in the database, from 0 ("Alive") to 4 ("Dead")
+    pub state_code: u8,
     /// Unix timestamp
     pub last_heartbeat: u64,
+    /// RFC 3339 timestamp, in the configured UTC offset
+    pub last_heartbeat_at: String,
+    /// Unix timestamp of when the state changes next without a heartbeat, if it does.
+    pub next_transition: Option<u64>,
+    /// RFC 3339 timestamp, in the configured UTC offset
+    pub next_transition_at: Option<String>,
+    /// Time left until the next transition, as of the response.
+    pub seconds_until_next_transition: Option<u64>,
     pub active_note: String,
 }
 
@@ -55,20 +102,36 @@ impl StatusApiResponse {
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct HeartbeatRequest {
     remove_current_note: bool,
     updated_note: String,
     message: String,
+    /// Not needed along with a device token, see [`crate::device_tokens`]
+    #[serde(default)]
     password: String,
-    pow: PowSolution,
+    /// Not needed along with a device token, nor from the `pow.exempt_ips`.
+    #[serde(default)]
+    pow: Option<PowSolution>,
+    /// Optional proof of life, see [`crate::media`]
+    #[serde(default)]
+    attachment: Option<HeartbeatAttachment>,
+    /// Unix timestamp of when the heartbeat was composed, if it was queued
+    /// while the client was offline, and is only sent now.
+    #[serde(default)]
+    composed_at: Option<u64>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct PowSolution {
     pub nonce: u64,
     pub hash: String,
     pub timestamp_ms: u128,
+    /// Digest of the heartbeat the hash commits to, so that the solution can't be sent
+    /// along with another one, see [`pow_solver::payload_digest`]. Only accepted on
+    /// `/api/heartbeat`, where it must match the heartbeat sent.
+    #[serde(default)]
+    pub payload_digest: Option<String>,
 }
 
 /// Using our shared state, [`ServerState`], build a [`StatusApiResponse`]
@@ -79,13 +142,35 @@ pub async fn bake_status_api_response(server_state: ServerState) -> String {
     // build our response by reading from our shared state
     let mut resp: StatusApiResponse = StatusApiResponse::default();
 
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let timezone: FixedOffset =
+        FixedOffset::east_opt(server_state.config.global.utc_offset * 60 * 60).unwrap();
+    let rfc3339 = |timestamp: u64| -> String {
+        timezone
+            .timestamp_opt(timestamp.try_into().unwrap(), 0)
+            .unwrap()
+            .to_rfc3339()
+    };
+
     let locked_state: MutexGuard<'_, Redundant<LifeState>> = server_state.state.lock().await;
-    resp.status = locked_state.to_string();
+    let state: LifeState = **locked_state;
     drop(locked_state);
+    resp.status = state.to_string();
+    resp.state = format!("{:?}", state);
+    resp.state_code = state.db_code().parse().unwrap();
 
     let locked_heartbeat: MutexGuard<'_, Redundant<u64>> = server_state.last_heartbeat.lock().await;
     resp.last_heartbeat = **locked_heartbeat;
     drop(locked_heartbeat);
+    resp.last_heartbeat_at = rfc3339(resp.last_heartbeat);
+
+    resp.next_transition = server_state.next_transition(state, now).await;
+    resp.next_transition_at = resp.next_transition.map(rfc3339);
+    // `seconds_until_next_transition` is left out, or every response baked would differ,
+    // see [`count_down`]
 
     let locked_note: MutexGuard<'_, Option<String>> = server_state.note.lock().await;
 
@@ -95,6 +180,11 @@ pub async fn bake_status_api_response(server_state: ServerState) -> String {
     };
     drop(locked_note);
 
+    // the final statement takes the place of the note
+    if let Some(statement) = server_state.final_statement().await {
+        resp.active_note = statement.into();
+    }
+
     // finally, serialize our assembled struct to a JSON string
     // and replace the baked response string in our shared state
     let json_string: String = resp
@@ -103,48 +193,601 @@ pub async fn bake_status_api_response(server_state: ServerState) -> String {
 
     let mut locked_baked_resp: MutexGuard<'_, String> =
         server_state.baked_status_api_resp.lock().await;
+
+    // the first response baked isn't a change
+    if !locked_baked_resp.is_empty() && *locked_baked_resp != json_string {
+        *server_state.status_changed_at.lock().await = now;
+        // nobody listening is not an error
+        let _ = server_state.status_events.send(json_string.clone());
+    }
     locked_baked_resp.clear();
     locked_baked_resp.push_str(&json_string);
 
+    // the tag changes along with the response, and is only computed then
+    *server_state.baked_status_etag.lock().await = etag(&json_string);
+
     json_string
 }
 
-/// Handles requests on `/api/status`.
-pub async fn status_api(State(server_state): State<ServerState>) -> impl IntoResponse {
+/// Entity tag of a baked response, quoted as sent in the `ETag` HTTP header.
+///
+/// It is weak, as the time left until the next transition is counted down
+/// on every request, while the rest of the response stays the same.
+///
+fn etag(body: &str) -> String {
+    format!(
+        "W/\"{}\"",
+        &hex::encode(Sha256::digest(body.as_bytes()))[..32]
+    )
+}
+
+/// Whether an `If-None-Match` HTTP header matches the given entity tag,
+/// meaning the client's cached response is still current.
+fn etag_matches(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all("If-None-Match")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag.trim_start_matches("W/"))
+}
+
+/// Whether the `Accept` HTTP header asks for plain text rather than JSON.
+///
+/// The first of `text/plain`, `application/json`, or a wildcard that is
+/// listed wins, so `curl`'s default of `*/*` still gets JSON.
+///
+fn accepts_plain_text(headers: &HeaderMap) -> bool {
+    headers
+        .get_all("Accept")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|media_range| media_range.split(';').next())
+        .map(|media_type| media_type.trim().to_ascii_lowercase())
+        .find(|media_type| {
+            matches!(
+                media_type.as_str(),
+                "text/plain" | "text/*" | "application/json" | "application/*" | "*/*"
+            )
+        })
+        .is_some_and(|media_type| media_type.starts_with("text/"))
+}
+
+/// Turn a baked status API response into a single human-readable line,
+/// e.g. "ALIVE, last heartbeat at 2026-10-16T08:00:00+02:00."
+///
+fn plain_text(baked_response: &str) -> String {
+    let resp: StatusApiResponse = serde_json::from_str(baked_response).unwrap();
+    let mut line: String = format!(
+        "{}, last heartbeat at {}.",
+        resp.status, resp.last_heartbeat_at
+    );
+
+    if !resp.active_note.is_empty() {
+        line = format!("{} Note: {}", line, resp.active_note);
+    }
+    line + "\n"
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct FieldsQuery {
+    /// Comma separated fields to keep, e.g. "status,last_heartbeat". All of them if not given.
+    pub fields: Option<String>,
+}
+
+/// Keep only the given comma separated fields of a JSON object, e.g. "status,last_heartbeat",
+/// so that constrained clients (e.g. e-ink displays) only receive the bytes they need.
+///
+/// Unknown fields are ignored, so clients of a newer version don't get an error.
+///
+pub fn select_fields(object: &mut Value, fields: &str) {
+    let fields: Vec<&str> = fields.split(',').map(str::trim).collect();
+
+    if let Value::Object(map) = object {
+        map.retain(|key, _| fields.contains(&key.as_str()));
+    }
+}
+
+/// Handles requests on `/api/v1/status`.
+///
+/// Answers `304 Not Modified` when the `If-None-Match` HTTP header
+/// holds the ETag of the current response, or when the status didn't change
+/// since the `If-Modified-Since` HTTP header, for clients polling often.
+/// Answers with a single line of plain text given `Accept: text/plain`.
+///
+#[utoipa::path(
+    get,
+    path = "/api/v1/status",
+    tag = "status",
+    params(
+        FieldsQuery,
+        ("If-None-Match" = Option<String>, Header, description = "ETag of a cached response"),
+        ("If-Modified-Since" = Option<String>, Header, description = "Last-Modified date of a cached response"),
+        ("Accept" = Option<String>, Header, description = "`text/plain` for a single line of text"),
+    ),
+    responses(
+        (status = 200, description = "The current state", headers(
+            ("X-Status-Signature" = String, description = "Signature of the body, if status responses are signed"),
+        ), content(
+            (StatusApiResponse = "application/json"),
+            (String = "text/plain"),
+        )),
+        (status = 304, description = "The cached response is still current"),
+        (status = 401, description = "The instance is private"),
+    ),
+    security((), ("bearer" = [])),
+)]
+pub async fn status_api(
+    headers: HeaderMap,
+    _reader: Reader,
+    State(server_state): State<ServerState>,
+    Query(query): Query<FieldsQuery>,
+) -> Response {
+    let plain: bool = accepts_plain_text(&headers);
+
+    serve_status(&headers, &server_state, plain, query.fields.as_deref()).await
+}
+
+/// Handles requests on `/status.txt`, which is always answered
+/// with the single line of plain text of `/api/v1/status`.
+///
+#[utoipa::path(
+    get,
+    path = "/status.txt",
+    tag = "status",
+    params(("If-None-Match" = Option<String>, Header, description = "ETag of a cached response")),
+    responses(
+        (status = 200, description = "The current state", body = String, content_type = "text/plain", headers(
+            ("X-Status-Signature" = String, description = "Signature of the body, if status responses are signed"),
+        )),
+        (status = 304, description = "The cached response is still current"),
+        (status = 401, description = "The instance is private"),
+    ),
+    security((), ("bearer" = [])),
+)]
+pub async fn status_txt(
+    headers: HeaderMap,
+    _reader: Reader,
+    State(server_state): State<ServerState>,
+) -> Response {
+    serve_status(&headers, &server_state, true, None).await
+}
+
+async fn serve_status(
+    headers: &HeaderMap,
+    server_state: &ServerState,
+    plain: bool,
+    fields: Option<&str>,
+) -> Response {
     let now: u64 = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs();
     server_state.update(now).await;
 
+    let (baked_response, mut etag): (String, String) = baked_status(server_state).await;
+
+    // both representations share a URL, so they can't share a tag
+    if plain {
+        etag = format!("{}-txt\"", etag.trim_end_matches('"'));
+    }
+
+    let cache: CacheHeaders = CacheHeaders::new(server_state, now).await;
+
+    if etag_matches(headers, &etag) || cache.not_modified(headers) {
+        let resp: Response = Response::builder()
+            .status(StatusCode::NOT_MODIFIED)
+            .header("ETag", etag)
+            .header("Vary", "Accept")
+            .body(Body::default())
+            .unwrap();
+        return (cache, resp).into_response();
+    }
+    let (content_type, body): (&str, String) = match plain {
+        true => ("text/plain; charset=utf-8", plain_text(&baked_response)),
+        false => ("application/json", count_down(&baked_response, now)),
+    };
+    let body: String = match (plain, fields) {
+        (false, Some(fields)) => {
+            let mut resp: Value = serde_json::from_str(&body).unwrap();
+            select_fields(&mut resp, fields);
+            resp.to_string()
+        }
+        _ => body,
+    };
+    let mut builder = Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", content_type)
+        .header("ETag", etag)
+        .header("Vary", "Accept");
+
+    if let Some(config) = server_state.config.status_signing.as_ref() {
+        builder = builder.header(
+            "X-Status-Signature",
+            status_signing::signature_header(config, now, &body),
+        );
+    }
+    let resp: Response = builder.body(Body::from(body)).unwrap();
+    (cache, resp).into_response()
+}
+
+/// Get the baked status API response and its ETag, baking them first if need be.
+pub async fn baked_status(server_state: &ServerState) -> (String, String) {
     // simply lock the baked response stored in our shared state & clone the JSON string
-    let mut baked_response: String = server_state.baked_status_api_resp.lock().await.clone();
+    let locked_baked_resp: MutexGuard<'_, String> = server_state.baked_status_api_resp.lock().await;
+    let mut baked_response: String = locked_baked_resp.clone();
+    drop(locked_baked_resp);
 
     if baked_response.is_empty() {
         // the server may have just been started and this is its first request
         // for this endpoint. our state has not updated since the initial state
         // was loaded from disk, so lets bake a JSON string for our initial state now.
-        baked_response = bake_status_api_response(server_state).await;
+        baked_response = bake_status_api_response(server_state.clone()).await;
     }
-    Response::builder()
-        .status(StatusCode::OK)
-        .header("Content-Type", "application/json")
-        .body(baked_response)
+    let etag: String = server_state.baked_status_etag.lock().await.clone();
+
+    (baked_response, etag)
+}
+
+/// Get the current status, as served on `/api/v1/status`.
+pub async fn current_status(server_state: &ServerState) -> StatusApiResponse {
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
         .unwrap()
+        .as_secs();
+    server_state.update(now).await;
+
+    let (baked_response, _): (String, String) = baked_status(server_state).await;
+    serde_json::from_str(&count_down(&baked_response, now)).unwrap()
+}
+
+/// Fill in the time left until the next transition in a baked status API response.
+fn count_down(baked_response: &str, now: u64) -> String {
+    let mut resp: StatusApiResponse = serde_json::from_str(baked_response).unwrap();
+
+    resp.seconds_until_next_transition = resp
+        .next_transition
+        .map(|next_transition| next_transition.saturating_sub(now));
+
+    resp.serve().unwrap()
 }
 
-/// Handles requests on `/api/heartbeat` for registering new heartbeats.
+/// Parse the heartbeat TOTP generator from its secret in our configuration.
+pub fn heartbeat_totp(secret: &str) -> TOTP {
+    let bytes: Vec<u8> = Secret::Encoded(secret.trim().trim_end_matches('=').to_uppercase())
+        .to_bytes()
+        .expect("Heartbeat TOTP secret is not valid base32.");
+
+    // no skew, as we check the neighbouring time steps ourselves
+    TOTP::new(Algorithm::SHA1, 6, 0, TOTP_STEP, bytes)
+        .expect("Heartbeat TOTP secret must be at least 128 bits long.")
+}
+
+/// Check whether the given code is a valid heartbeat TOTP code, that wasn't used before.
+///
+/// Codes of the previous and next time steps are accepted too,
+/// to make up for clock drift and typing slowly.
+///
+/// Returns the time step the code is valid for, which is only marked as used
+/// by [`use_totp_step`], once the rest of the heartbeat was checked.
+///
+async fn totp_step(server_state: &ServerState, code: &str, now: u64) -> Option<u64> {
+    let secret: &str = server_state.config.global.heartbeat_totp_secret.as_ref()?;
+
+    if code.len() != 6 || !code.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let totp: TOTP = heartbeat_totp(secret);
+    let current_step: u64 = now / TOTP_STEP;
+    let last_step: u64 = *server_state.last_totp_step.lock().await;
+
+    // check every step, not just until one matches
+    ((current_step - 1)..=(current_step + 1))
+        .filter(|step| totp.check(code, step * TOTP_STEP))
+        .fold(None, |valid, step| match step > last_step {
+            true => valid.or(Some(step)),
+            false => valid,
+        })
+}
+
+/// Mark a TOTP time step as used, unless it, or a later one, already was.
+async fn use_totp_step(server_state: &ServerState, step: u64) -> bool {
+    let mut last_step: MutexGuard<'_, u64> = server_state.last_totp_step.lock().await;
+
+    if step <= *last_step {
+        return false;
+    }
+    *last_step = step;
+    true
+}
+
+/// What a heartbeat was authenticated with.
+enum HeartbeatAuth {
+    Password,
+    /// One of the heartbeat credentials, by name.
+    Credential(String),
+    /// A TOTP code, valid for the given time step.
+    Totp(u64),
+    /// The token of a heartbeat QR code, see [`crate::qr_code`]
+    QrToken,
+}
+
+/// Authenticate a heartbeat with the password, one of the heartbeat
+/// credentials, a TOTP code, or the token of a heartbeat QR code.
+///
+/// Every hash is verified, whichever matches, so that the time it takes
+/// doesn't tell what the given password is, or whether it's right.
+///
+async fn authenticate(
+    server_state: &ServerState,
+    password: &str,
+    now: u64,
+) -> Option<HeartbeatAuth> {
+    let step: Option<u64> = totp_step(server_state, password, now).await;
+    let qr_token: bool = qr_code::is_token(server_state, password, now).await;
+
+    let password_matches: bool = password::verify(password, &server_state.password_hash);
+
+    let credential: Option<String> = server_state
+        .config
+        .global
+        .heartbeat_credentials
+        .iter()
+        .filter(|credential| {
+            let hash: PasswordHash = PasswordHash::new(&credential.password_hash).unwrap();
+
+            password::verify(password, &hash)
+        })
+        .fold(None, |found, credential| {
+            found.or(Some(credential.name.clone()))
+        });
+
+    match (step, qr_token, password_matches, credential) {
+        (Some(step), _, _, _) => Some(HeartbeatAuth::Totp(step)),
+        (None, true, _, _) => Some(HeartbeatAuth::QrToken),
+        (None, false, true, _) => Some(HeartbeatAuth::Password),
+        (None, false, false, Some(name)) => Some(HeartbeatAuth::Credential(name)),
+        (None, false, false, None) => None,
+    }
+}
+
+/// Handles requests on `/api/v1/events`, a stream of Server-Sent Events.
+///
+/// Sends a `status` event with the same JSON as `/api/v1/status` right away,
+/// then again every time it changes, so clients don't have to poll for it.
+///
+#[utoipa::path(
+    get,
+    path = "/api/v1/events",
+    tag = "status",
+    responses(
+        (
+            status = 200,
+            description = "A `status` event for the current state, then one for every change",
+            content_type = "text/event-stream",
+            body = StatusApiResponse,
+        ),
+        (status = 401, description = "The instance is private"),
+    ),
+    security((), ("bearer" = [])),
+)]
+pub async fn events_api(
+    _reader: Reader,
+    State(server_state): State<ServerState>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    // subscribe first, so no change between baking and subscribing is missed
+    let rx: broadcast::Receiver<String> = server_state.status_events.subscribe();
+    let current: String = bake_status_api_response(server_state).await;
+
+    let changes = stream::unfold(rx, |mut rx| async move {
+        loop {
+            match rx.recv().await {
+                Ok(status) => return Some((status, rx)),
+                // only the latest status matters to the client
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+    let events = stream::once(async { current })
+        .chain(changes)
+        .map(|status| {
+            let now: u64 = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            Ok(Event::default()
+                .event("status")
+                .data(count_down(&status, now)))
+        });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// Handles requests on `/api/v1/heartbeat` for registering new heartbeats.
+///
+/// Heartbeats are authenticated by the password (or a TOTP code) and a proof
+/// of work, or by a device token in the `Authorization: Bearer <token>` header.
+///
+#[utoipa::path(
+    post,
+    path = "/api/v1/heartbeat",
+    tag = "heartbeats",
+    request_body = HeartbeatRequest,
+    security((), ("bearer" = [])),
+    responses(
+        (status = 200, description = "The heartbeat was registered"),
+        (status = 401, description = "Wrong password or TOTP code, or unknown device token. Every refusal, with uniform errors", body = Problem, content_type = "application/problem+json"),
+        (status = 403, description = "Heartbeats can't be sent from this address", body = Problem, content_type = "application/problem+json"),
+        (status = 406, description = "The proof of work is invalid or expired", body = Problem, content_type = "application/problem+json"),
+        (status = 413, description = "The request body is too large", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "The heartbeat, its note or its attachment can't be accepted", body = Problem, content_type = "application/problem+json"),
+        (status = 429, description = "Blocked after too many failed attempts", body = Problem, content_type = "application/problem+json"),
+    )
+)]
 pub async fn heartbeat_api(
-    headers: HeaderMap,
+    ClientIp(ip): ClientIp,
     State(server_state): State<ServerState>,
-    Json(req): Json<HeartbeatRequest>,
+    headers: HeaderMap,
+    req: Result<Json<HeartbeatRequest>, JsonRejection>,
 ) -> impl IntoResponse {
-    let ip: IpAddr = get_proxied_client_ip(&headers);
+    let started: Instant = Instant::now();
+
+    let req: HeartbeatRequest = match req {
+        Ok(Json(req)) => req,
+        Err(rejection) => {
+            let code: &'static str = match rejection.status() {
+                StatusCode::PAYLOAD_TOO_LARGE => "body_too_large",
+                _ => "invalid_body",
+            };
+            return Problem::new(rejection.status(), code)
+                .detail(rejection.body_text())
+                .into_response();
+        }
+    };
     let now: u64 = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs();
 
+    // the address lists apply before any credentials are checked
+    if let Err(problem) = check_heartbeat_ip(&server_state, ip) {
+        return problem::uniform(&server_state, started, problem)
+            .await
+            .into_response();
+    }
+    let payload_digest: String =
+        pow_solver::payload_digest(&req.message, &req.updated_note, req.remove_current_note);
+
+    // a device with a token of its own skips the password and proof of work
+    let authorized: Result<Option<String>, Problem> = match (bearer_token(&headers), req.pow) {
+        (Some(token), _) => authorize_device_token(&server_state, ip, &token, now).await,
+        // so do the exempt addresses, which still need the password
+        (None, _) if is_in_ip_ranges(&server_state.config.pow.exempt_ips, ip) => {
+            authorize_heartbeat(&server_state, ip, &req.password, None, None, now).await
+        }
+        (None, Some(pow)) => {
+            authorize_heartbeat(
+                &server_state,
+                ip,
+                &req.password,
+                Some(pow),
+                Some(&payload_digest),
+                now,
+            )
+            .await
+        }
+        (None, None) => Err(Problem::from(PowError::Invalid)),
+    };
+    let label: Option<String> = match authorized {
+        Ok(label) => label,
+        Err(problem) => {
+            return problem::uniform(&server_state, started, problem)
+                .await
+                .into_response();
+        }
+    };
+
+    // past this point, we're successfully authenticated + past rate limit checks
+
+    // late heartbeats count from when they were composed (clocks may be off, though)
+    let timestamp: u64 = match req.composed_at {
+        Some(composed_at) if composed_at.saturating_add(MAX_COMPOSED_AGE) < now => {
+            return Problem::new(StatusCode::UNPROCESSABLE_ENTITY, "stale_heartbeat")
+                .detail("The heartbeat was composed too long ago.")
+                .into_response();
+        }
+        Some(composed_at) => composed_at.min(now),
+        None => now,
+    };
+    let (message, updated_note): (String, String) =
+        match clean_heartbeat_text(&req.message, &req.updated_note) {
+            Ok(text) => text,
+            Err(problem) => return problem.into_response(),
+        };
+    let mut media: Option<String> = None;
+
+    if let Some(attachment) = req.attachment.as_ref() {
+        let Some(media_config) = server_state.config.heartbeat_media.as_ref() else {
+            return Problem::new(StatusCode::UNPROCESSABLE_ENTITY, "media_disabled")
+                .detail("Heartbeat media is not enabled.")
+                .into_response();
+        };
+        match save_attachment(media_config, attachment, timestamp).await {
+            Ok(file_name) => media = Some(file_name),
+            Err(reason) => {
+                return Problem::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_attachment")
+                    .detail(reason)
+                    .into_response();
+            }
+        }
+    }
+    let mut locked_note: MutexGuard<'_, Option<String>> = server_state.note.lock().await;
+
+    if req.remove_current_note {
+        let _: Option<String> = locked_note.take();
+    } else if !updated_note.is_empty() {
+        let _: Option<String> = locked_note.replace(updated_note);
+    }
+    drop(locked_note);
+
+    if let Err(reason) = register_heartbeat(
+        &server_state,
+        timestamp,
+        ip.to_string(),
+        message,
+        media,
+        false,
+        label,
+    )
+    .await
+    {
+        return Problem::new(StatusCode::INTERNAL_SERVER_ERROR, "database_error")
+            .detail(reason)
+            .into_response();
+    }
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::default())
+        .unwrap()
+}
+
+/// Check that an address may send heartbeats at all, as configured by the
+/// `heartbeat_allowed_ips` and `heartbeat_denied_ips` lists. This comes before
+/// rate limiting, proofs of work and authentication.
+///
+pub fn check_heartbeat_ip(server_state: &ServerState, ip: IpAddr) -> Result<(), Problem> {
+    let global: &Global = &server_state.config.global;
+
+    let allowed: bool = global.heartbeat_allowed_ips.is_empty()
+        || is_in_ip_ranges(&global.heartbeat_allowed_ips, ip);
+
+    if !allowed || is_in_ip_ranges(&global.heartbeat_denied_ips, ip) {
+        return Err(Problem::new(StatusCode::FORBIDDEN, "ip_not_allowed")
+            .detail("Heartbeats can't be sent from this address."));
+    }
+    Ok(())
+}
+
+/// Check that an address isn't rate limited, then both the proof of work if one
+/// is required and the password, with [`authenticate`], before telling whether
+/// either failed. Failed attempts block the address for longer and longer.
+///
+/// Callers check that the address may send heartbeats first, see [`check_heartbeat_ip`]
+///
+/// Returns the label to record on the heartbeat if authenticated.
+///
+pub async fn authorize_heartbeat(
+    server_state: &ServerState,
+    ip: IpAddr,
+    password: &str,
+    pow: Option<PowSolution>,
+    payload_digest: Option<&str>,
+    now: u64,
+) -> Result<Option<String>, Problem> {
     let mut locked_map: MutexGuard<'_, HashMap<IpAddr, RateLimit>> =
         server_state.rate_limited_ips.lock().await;
     let mut previous_rate_limit_period: Option<u64> = None;
@@ -156,116 +799,313 @@ pub async fn heartbeat_api(
 
         if now < rate_limit.timestamp {
             // return here to enforce rate limit, and send seconds left until retry available
-            return Response::builder()
-                .status(StatusCode::TOO_MANY_REQUESTS)
-                .header("Retry-After", rate_limit.timestamp - now)
-                .body(Body::default())
-                .unwrap();
+            return Err(Problem::new(StatusCode::TOO_MANY_REQUESTS, "rate_limited")
+                .detail("Blocked after too many failed attempts.")
+                .retry_after(rate_limit.timestamp - now));
         }
     }
-    // now verify the PoW challenge. secondary rate limiting
-    if !verify_pow_solution(server_state.pow_state.clone(), ip, req.pow) {
-        // invalid proof of work; allow the client to retry
-        return Response::builder()
-            .status(StatusCode::NOT_ACCEPTABLE)
-            .body(Body::default())
-            .unwrap();
+    // verify the PoW challenge (secondary rate limiting) and authenticate the
+    // heartbeat, both every time, so the response time doesn't tell which failed
+    let pow_checked: Result<(), PowError> = match pow {
+        Some(pow) => {
+            check_pow_solution(server_state.pow_state.clone(), ip, pow, payload_digest).await
+        }
+        None => Ok(()),
+    };
+    let auth: Option<HeartbeatAuth> = authenticate(server_state, password, now).await;
+
+    // invalid proof of work; allow the client to retry
+    if let Err(err) = pow_checked {
+        let problem: Problem = Problem::from(err);
+        auth_log::record(ip, "password", problem.code(), "").await;
+        return Err(problem);
     }
+    let log_label: &str = match auth {
+        Some(HeartbeatAuth::Totp(_)) => "totp",
+        Some(HeartbeatAuth::QrToken) => "qr_code",
+        _ => "",
+    };
 
-    // OK, let's authenticate the heartbeat
-    if Argon2::default()
-        .verify_password(req.password.as_bytes(), &server_state.password_hash)
-        .is_err()
-    {
+    // a TOTP code or QR code token is only used up once the heartbeat is accepted
+    let label: Option<Option<String>> = match auth {
+        Some(HeartbeatAuth::Password) => Some(None),
+        Some(HeartbeatAuth::Credential(name)) => Some(Some(name)),
+        Some(HeartbeatAuth::Totp(step)) if use_totp_step(server_state, step).await => Some(None),
+        Some(HeartbeatAuth::QrToken) if qr_code::use_token(server_state, password, now).await => {
+            Some(None)
+        }
+        Some(HeartbeatAuth::Totp(_)) | Some(HeartbeatAuth::QrToken) | None => None,
+    };
+    let Some(label) = label else {
         // auth failed, let's give them (or extend) a rate limit
         let wait_period: u64 = match previous_rate_limit_period {
             Some(period) => period * RATE_LIMIT_PERIOD_FACTOR,
             None => INITIAL_RATE_LIMIT_PERIOD,
         };
-        locked_map.insert(
-            ip,
-            RateLimit {
-                period: wait_period,
-                timestamp: now + wait_period,
-            },
-        );
+        rate_limit::block(&mut locked_map, ip, wait_period, now);
+        server_state.pow_state.note_auth_failure(ip);
+        auth_log::record(ip, "password", "bad_password", "").await;
 
-        return Response::builder()
-            .status(StatusCode::UNAUTHORIZED)
-            .header("Retry-After", wait_period)
-            .body(Body::default())
-            .unwrap();
-    }
+        return Err(Problem::new(StatusCode::UNAUTHORIZED, "bad_password")
+            .detail("Wrong password or TOTP code.")
+            .retry_after(wait_period));
+    };
     if previous_rate_limit_period.is_some() {
         locked_map.remove(&ip);
     }
-    drop(locked_map);
+    server_state.pow_state.forgive(ip);
 
-    // past this point, we're successfully authenticated + past rate limit checks
-    let mut locked_note: MutexGuard<'_, Option<String>> = server_state.note.lock().await;
+    auth_log::record(
+        ip,
+        "password",
+        "accepted",
+        label.as_deref().unwrap_or(log_label),
+    )
+    .await;
 
-    if req.remove_current_note {
-        let _: Option<String> = locked_note.take();
-    } else if !req.updated_note.is_empty() {
-        let _: Option<String> = locked_note.replace(req.updated_note);
+    Ok(label)
+}
+
+/// Authorize a heartbeat sent with a device token, see [`crate::device_tokens`]
+///
+/// Unknown tokens block the address like wrong passwords do, see [`authorize_heartbeat`].
+/// Returns the name of the device, to label the heartbeat with.
+///
+async fn authorize_device_token(
+    server_state: &ServerState,
+    ip: IpAddr,
+    token: &str,
+    now: u64,
+) -> Result<Option<String>, Problem> {
+    let mut locked_map: MutexGuard<'_, HashMap<IpAddr, RateLimit>> =
+        server_state.rate_limited_ips.lock().await;
+    let mut previous_rate_limit_period: Option<u64> = None;
+
+    if let Some(rate_limit) = locked_map.get(&ip) {
+        previous_rate_limit_period = Some(rate_limit.period);
+
+        if now < rate_limit.timestamp {
+            return Err(Problem::new(StatusCode::TOO_MANY_REQUESTS, "rate_limited")
+                .detail("Blocked after too many failed attempts.")
+                .retry_after(rate_limit.timestamp - now));
+        }
     }
+
+    match device_tokens::authenticate(token).await {
+        Ok(Some(name)) => {
+            if previous_rate_limit_period.is_some() {
+                locked_map.remove(&ip);
+            }
+            server_state.pow_state.forgive(ip);
+            auth_log::record(ip, "device_token", "accepted", &name).await;
+            Ok(Some(name))
+        }
+        Ok(None) => {
+            let wait_period: u64 = match previous_rate_limit_period {
+                Some(period) => period * RATE_LIMIT_PERIOD_FACTOR,
+                None => INITIAL_RATE_LIMIT_PERIOD,
+            };
+            rate_limit::block(&mut locked_map, ip, wait_period, now);
+            server_state.pow_state.note_auth_failure(ip);
+            auth_log::record(ip, "device_token", "invalid_token", "").await;
+
+            Err(Problem::new(StatusCode::UNAUTHORIZED, "invalid_token")
+                .detail("Unknown or revoked device token.")
+                .retry_after(wait_period))
+        }
+        Err(reason) => {
+            Err(Problem::new(StatusCode::INTERNAL_SERVER_ERROR, "database_error").detail(reason))
+        }
+    }
+}
+
+/// Sanitize the message and note of a heartbeat, and check their lengths.
+pub fn clean_heartbeat_text(message: &str, note: &str) -> Result<(String, String), Problem> {
+    let message: String = sanitize(message);
+
+    if message.chars().count() > MAX_MESSAGE_LENGTH {
+        return Err(
+            Problem::new(StatusCode::UNPROCESSABLE_ENTITY, "message_too_long").detail(format!(
+                "The message is longer than {} characters.",
+                MAX_MESSAGE_LENGTH
+            )),
+        );
+    }
+    Ok((message, clean_note(note)?))
+}
+
+/// Sanitize a note, public or private, however it's set, and check its length.
+pub fn clean_note(note: &str) -> Result<String, Problem> {
+    let note: String = sanitize(note);
+
+    if note.chars().count() > MAX_NOTE_LENGTH {
+        return Err(
+            Problem::new(StatusCode::UNPROCESSABLE_ENTITY, "note_too_long").detail(format!(
+                "The note is longer than {} characters.",
+                MAX_NOTE_LENGTH
+            )),
+        );
+    }
+    Ok(note)
+}
+
+/// Largest heartbeat request body accepted, in bytes, making room
+/// for an attachment of the configured maximum size, if enabled.
+pub fn heartbeat_body_limit(server_state: &ServerState) -> usize {
+    // attachments are base64 encoded, taking up a third more
+    let attachment: usize = server_state
+        .config
+        .heartbeat_media
+        .as_ref()
+        .map_or(0, |media| usize::from(media.max_size) * 1024 * 4 / 3);
+
+    MAX_BODY_SIZE + attachment
+}
+
+/// Clean up text sent along with a heartbeat, which is stored on a single
+/// line and shown to anyone, by replacing line breaks and any other
+/// control characters with spaces.
+fn sanitize(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect::<String>()
+        .trim()
+        .to_owned()
+}
+
+/// Register a new heartbeat, from any heartbeat source, and sync it to disk.
+///
+/// `timestamp` is when the person was last heard from, which is the current
+/// time, unless the heartbeat arrives late. Late heartbeats are inserted
+/// into the history in order, and never move the last heartbeat back.
+///
+/// `from_address` identifies where the heartbeat came from, e.g. an IP address.
+/// Heartbeats are `passive` when registered from activity seen elsewhere,
+/// rather than sent by the person themself. A `label` says what registered
+/// it, when the address doesn't tell, e.g. a sensor's name.
+///
+/// Fails with a reason to show the client if the database could not be updated.
+///
+pub async fn register_heartbeat(
+    server_state: &ServerState,
+    timestamp: u64,
+    from_address: String,
+    message: String,
+    media: Option<String>,
+    passive: bool,
+    label: Option<String>,
+) -> Result<(), &'static str> {
+    // heartbeats are stored one per line
+    let message: String = message.replace(['\n', '\r'], " ");
+    let label: Option<String> = label.map(|label| label.replace(['\n', '\r'], " "));
+
     // keep a copy for the write to disk we will do
-    let note_db_copy: String = locked_note.clone().unwrap_or_default();
-    drop(locked_note);
+    let note_db_copy: String = server_state.note.lock().await.clone().unwrap_or_default();
 
     // update the last heartbeat
     let mut locked_heartbeat: MutexGuard<'_, Redundant<u64>> =
         server_state.last_heartbeat.lock().await;
-    *locked_heartbeat = Redundant::new(now);
+    let last_heartbeat: u64 = (**locked_heartbeat).max(timestamp);
+    *locked_heartbeat = Redundant::new(last_heartbeat);
     drop(locked_heartbeat);
 
+    if !passive {
+        let mut locked_active: MutexGuard<'_, Redundant<u64>> =
+            server_state.last_active_heartbeat.lock().await;
+        let last_active: u64 = (**locked_active).max(timestamp);
+        *locked_active = Redundant::new(last_active);
+    }
+
     // create a formatted date string for this heartbeat's Unix timestamp
     let timezone: FixedOffset =
         FixedOffset::east_opt(server_state.config.global.utc_offset * 60 * 60).unwrap();
-    let now_i64: i64 = now.try_into().unwrap(); // who knows how many years out we are from this failing
-    let ts: String = timezone.timestamp_opt(now_i64, 0).unwrap().to_rfc2822();
+    let timestamp_i64: i64 = timestamp.try_into().unwrap(); // who knows how many years out we are from this failing
+    let ts: String = timezone
+        .timestamp_opt(timestamp_i64, 0)
+        .unwrap()
+        .to_rfc2822();
 
     // update the displayed heartbeats
     let mut locked_display: MutexGuard<'_, [HeartbeatDisplay; 5]> =
         server_state.displayed_heartbeats.lock().await;
 
-    // shift top 4 entries 'down' (+1 by index)
-    for i in (0..=(MAX_DISPLAYED_HEARTBEATS - 2)).rev() {
-        locked_display[i + 1] = locked_display[i].clone();
+    // late heartbeats go below newer ones, if they are recent enough to be shown at all
+    if let Some(position) = locked_display
+        .iter()
+        .position(|display| display.unix_timestamp <= timestamp)
+    {
+        // shift the entries below 'down' (+1 by index)
+        for i in (position..=(MAX_DISPLAYED_HEARTBEATS - 2)).rev() {
+            locked_display[i + 1] = locked_display[i].clone();
+        }
+        locked_display[position] = HeartbeatDisplay {
+            unix_timestamp: timestamp,
+            timestamp: ts,
+            message: match message.is_empty() {
+                true => "N/A".into(),
+                false => message.clone(),
+            },
+            media: media.clone(),
+            passive,
+            label: label.clone(),
+        };
     }
-    // set top entry to new heartbeat
-    locked_display[0] = HeartbeatDisplay {
-        timestamp: ts,
-        message: match req.message.is_empty() {
-            true => "N/A".into(),
-            false => req.message.clone(),
-        },
-    };
     drop(locked_display);
 
+    *server_state
+        .heartbeat_days
+        .lock()
+        .await
+        .entry(heatmap::local_date(timezone, timestamp))
+        .or_default() += 1;
+
     // make sure our state is up-to-date & any baked API responses are re-baked
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
     server_state.update(now).await;
+    // the last heartbeat changed, even if the state didn't
+    let _: String = bake_status_api_response(server_state.clone()).await;
 
     // finally, let's sync our results to the database file on disk
+    let _db_guard: MutexGuard<'_, ()> = database::lock().await;
     let mut db: Database = match load_database(crate::DB_PATH) {
         Err(err) => {
             eprintln!("An error ocurred while trying to read from disk: {}", err);
 
-            return Response::builder()
-                .status(StatusCode::INTERNAL_SERVER_ERROR)
-                .body(Body::from("There was an issue reading from the database."))
-                .unwrap();
+            return Err("There was an issue reading from the database.");
         }
         Ok(db) => db,
     };
 
-    db.last_heartbeat = now;
+    db.last_heartbeat = last_heartbeat;
     db.note = note_db_copy;
-    db.heartbeat_history.push(HeartbeatLog {
-        timestamp: now,
-        from_address: ip.to_string(),
-        message: req.message,
-    });
+
+    // keep the history in order, even with late heartbeats
+    let position: usize = db
+        .heartbeat_history
+        .partition_point(|log| log.timestamp <= timestamp);
+    db.heartbeat_history.insert(
+        position,
+        HeartbeatLog {
+            timestamp,
+            from_address,
+            message,
+        },
+    );
+    if let Some(file_name) = media {
+        db.heartbeat_media.push(MediaLog {
+            timestamp,
+            file_name,
+        });
+    }
+    if passive {
+        db.passive_heartbeats.push(timestamp);
+    }
+    if let Some(label) = label {
+        db.heartbeat_labels.push(LabelLog { timestamp, label });
+    }
 
     if let Err(err) = db.write_to_disk().await {
         eprintln!(
@@ -273,23 +1113,181 @@ pub async fn heartbeat_api(
             err
         );
 
-        return Response::builder()
-            .status(StatusCode::INTERNAL_SERVER_ERROR)
-            .body(Body::from("There was an issue writing to the database."))
-            .unwrap();
+        return Err("There was an issue writing to the database.");
     }
-
-    Response::builder()
-        .status(StatusCode::OK)
-        .body(Body::default())
-        .unwrap()
+    Ok(())
 }
 
-/// Return an [`IpAddr`] by extracting the `X-Real-IP` HTTP header.
+/// Address of the client that made a request.
 ///
-pub fn get_proxied_client_ip(headers: &HeaderMap) -> IpAddr {
-    let real_ip: &HeaderValue = headers
-        .get("X-Real-IP")
-        .expect("Missing X-Real-IP header. Fix in NGINX conf.");
-    IpAddr::from_str(str::from_utf8(real_ip.as_bytes()).unwrap()).unwrap()
+/// Requests from one of the configured `trusted_proxies` are taken to be from
+/// the address they were forwarded for, as told by the `Forwarded`,
+/// `X-Forwarded-For` or `X-Real-IP` HTTP header, in that order. Any other
+/// request is taken to be from its peer, as those headers could be forged.
+///
+pub struct ClientIp(pub IpAddr);
+
+#[async_trait]
+impl FromRequestParts<ServerState> for ClientIp {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        server_state: &ServerState,
+    ) -> Result<Self, Self::Rejection> {
+        let peer: IpAddr = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED), |ConnectInfo(addr)| {
+                addr.ip()
+            });
+
+        warn_of_untrusted_proxy(
+            &server_state.config.global.trusted_proxies,
+            peer,
+            &parts.headers,
+        );
+
+        Ok(ClientIp(get_proxied_client_ip(
+            &server_state.config.global.trusted_proxies,
+            peer,
+            &parts.headers,
+        )))
+    }
+}
+
+/// Warn once, loudly, when forwarding HTTP headers come from a peer that isn't one of the
+/// trusted proxies. Behind a proxy that isn't trusted, every client shares its address,
+/// so one wrong password blocks everyone, including the person themself.
+fn warn_of_untrusted_proxy(trusted_proxies: &[String], peer: IpAddr, headers: &HeaderMap) {
+    static WARNED: AtomicBool = AtomicBool::new(false);
+
+    let forwarded: bool = ["Forwarded", "X-Forwarded-For", "X-Real-IP"]
+        .iter()
+        .any(|name| headers.contains_key(*name));
+
+    if forwarded
+        && !is_trusted_proxy(trusted_proxies, peer)
+        && !WARNED.swap(true, Ordering::Relaxed)
+    {
+        eprintln!(
+            "WARNING: Got forwarding headers from {}, which isn't one of `global.trusted_proxies`. \
+             If it's your reverse proxy, add it to them, as every client behind it is now seen \
+             as {}, sharing its rate limits and blocks.",
+            peer, peer
+        );
+    }
+}
+
+/// Whether the given address is in one of the given proxy addresses
+/// or ranges, e.g. "127.0.0.1" or "172.16.0.0/12".
+pub fn is_trusted_proxy(trusted_proxies: &[String], ip: IpAddr) -> bool {
+    is_in_ip_ranges(trusted_proxies, ip)
+}
+
+/// Whether the given address is in one of the given addresses or ranges,
+/// e.g. "127.0.0.1" or "172.16.0.0/12". Invalid entries never match.
+pub fn is_in_ip_ranges(ranges: &[String], ip: IpAddr) -> bool {
+    // an IPv4 peer may be seen as mapped into IPv6 on dual-stack sockets
+    let ip: IpAddr = ip.to_canonical();
+
+    ranges.iter().any(|range| {
+        let (network, prefix) = range.split_once('/').unwrap_or((range, ""));
+
+        let Ok(network) = IpAddr::from_str(network) else {
+            return false;
+        };
+        let bits: u32 = match network {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        let prefix: u32 = match prefix.is_empty() {
+            true => bits,
+            false => match prefix.parse::<u32>() {
+                Ok(prefix) if prefix <= bits => prefix,
+                _ => return false,
+            },
+        };
+        // compare the first `prefix` bits of both addresses
+        match (network, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask: u32 = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask: u128 = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    })
+}
+
+/// Parse a node of a `Forwarded` or `X-Forwarded-For` HTTP header into its address,
+/// e.g. "192.0.2.43", "\"192.0.2.43:47011\"" or "\"[2001:db8:cafe::17]:4711\"".
+fn parse_forwarded_node(node: &str) -> Option<IpAddr> {
+    let node: &str = node.trim().trim_matches('"');
+
+    if let Ok(ip) = IpAddr::from_str(node) {
+        return Some(ip);
+    }
+    if let Some(rest) = node.strip_prefix('[') {
+        return IpAddr::from_str(rest.split(']').next()?).ok();
+    }
+    IpAddr::from_str(node.split(':').next()?).ok()
+}
+
+/// Get the address of the client that made a request through the given peer,
+/// see [`ClientIp`].
+pub fn get_proxied_client_ip(
+    trusted_proxies: &[String],
+    peer: IpAddr,
+    headers: &HeaderMap,
+) -> IpAddr {
+    if !is_trusted_proxy(trusted_proxies, peer) {
+        return peer.to_canonical();
+    }
+    let values = |name: &str| -> Vec<String> {
+        headers
+            .get_all(name)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::to_owned)
+            .collect()
+    };
+    // every proxy along the way appends the address it got the request from
+    let forwarded: Vec<String> = values("Forwarded")
+        .iter()
+        .filter_map(|element| {
+            element.split(';').find_map(|pair| {
+                let (name, value) = pair.split_once('=')?;
+                name.trim()
+                    .eq_ignore_ascii_case("for")
+                    .then(|| value.to_owned())
+            })
+        })
+        .collect();
+    let chain: Vec<String> = match forwarded.is_empty() {
+        false => forwarded,
+        true => match values("X-Forwarded-For") {
+            chain if !chain.is_empty() => chain,
+            _ => values("X-Real-IP"),
+        },
+    };
+    // the client is the last address that isn't one of our own proxies,
+    // as anything before it could have been forged by the client itself
+    let mut client: IpAddr = peer.to_canonical();
+
+    for node in chain.iter().rev() {
+        let Some(ip) = parse_forwarded_node(node) else {
+            break;
+        };
+        client = ip.to_canonical();
+
+        if !is_trusted_proxy(trusted_proxies, client) {
+            break;
+        }
+    }
+    client
 }
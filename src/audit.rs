@@ -0,0 +1,131 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::database::{self, AuditEntry, Database, load_database};
+use crate::trusted::Admin;
+use axum::body::Body;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::MutexGuard;
+use utoipa::ToSchema;
+
+/// Stands in for the previous hash of the very first audit log entry.
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+#[derive(Serialize, ToSchema)]
+struct AuditResponse<'a> {
+    /// Whether every entry's hash matches its contents and the entry before it.
+    intact: bool,
+    /// Index of the first entry that does not match, if any.
+    first_broken_entry: Option<usize>,
+    entries: &'a [AuditEntry],
+}
+
+/// Hash of an audit log entry, chained to the hash of the previous entry.
+pub fn chain_hash(previous_hash: &str, timestamp: u64, actor: &str, action: &str) -> String {
+    let line: String = format!("{} {} {} {}", previous_hash, timestamp, actor, action);
+    hex::encode(Sha256::digest(line.as_bytes()))
+}
+
+/// Walk the hash chain of the audit log, returning
+/// the index of the first entry that was tampered with.
+pub fn verify_chain(audit_log: &[AuditEntry]) -> Option<usize> {
+    let mut previous_hash: &str = GENESIS_HASH;
+
+    for (i, entry) in audit_log.iter().enumerate() {
+        if entry.hash != chain_hash(previous_hash, entry.timestamp, &entry.actor, &entry.action) {
+            return Some(i);
+        }
+        previous_hash = &entry.hash;
+    }
+    None
+}
+
+/// Append an action taken by a trusted user to the audit log.
+pub async fn record(actor: &str, action: String) {
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    // every database record is a single line
+    let action: String = action.replace(['\n', '\r'], " ");
+
+    let result = database::modify(|db| {
+        let previous_hash: &str = db
+            .audit_log
+            .last()
+            .map(|entry| entry.hash.as_str())
+            .unwrap_or(GENESIS_HASH);
+
+        db.audit_log.push(AuditEntry {
+            timestamp: now,
+            hash: chain_hash(previous_hash, now, actor, &action),
+            actor: actor.to_owned(),
+            action,
+        });
+    })
+    .await;
+
+    if let Err(err) = result {
+        eprintln!("Could not sync the audit log to disk: {}", err);
+    }
+}
+
+/// Handles requests on `/api/v1/admin/audit`, which shows the audit log.
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/audit",
+    tag = "admin",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The audit log", body = AuditResponse),
+        (status = 401, description = "Not logged in as the person themself"),
+    )
+)]
+pub async fn audit_api(_admin: Admin) -> impl IntoResponse {
+    let db: Database = {
+        let _db_guard: MutexGuard<'_, ()> = database::lock().await;
+
+        match load_database(crate::DB_PATH) {
+            Err(err) => {
+                eprintln!("Could not read the audit log: {}", err);
+                return Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(Body::default())
+                    .unwrap();
+            }
+            Ok(db) => db,
+        }
+    };
+    let first_broken_entry: Option<usize> = verify_chain(&db.audit_log);
+
+    let resp: AuditResponse = AuditResponse {
+        intact: first_broken_entry.is_none(),
+        first_broken_entry,
+        entries: &db.audit_log,
+    };
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&resp).unwrap()))
+        .unwrap()
+}
@@ -0,0 +1,113 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::database::{self, AuthAttempt, Database, load_database};
+use crate::trusted::Admin;
+use axum::body::Body;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+use std::net::IpAddr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::MutexGuard;
+use utoipa::ToSchema;
+
+/// Amount of authentication attempts kept, the oldest are dropped first.
+const MAX_AUTH_ATTEMPTS: usize = 1000;
+
+#[derive(Serialize, ToSchema)]
+struct AuthAttemptsResponse<'a> {
+    /// Refused attempts among those kept.
+    failed: usize,
+    /// Oldest first.
+    attempts: &'a [AuthAttempt],
+}
+
+/// Append an attempt at authenticating a heartbeat to the authentication log.
+///
+/// Only attempts whose credentials were checked are recorded, not those turned
+/// away beforehand by the rate limit or the address lists.
+///
+pub async fn record(ip: IpAddr, method: &str, outcome: &str, label: &str) {
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let result = database::modify(|db| {
+        db.auth_attempts.push(AuthAttempt {
+            timestamp: now,
+            address: ip.to_string(),
+            method: method.to_owned(),
+            outcome: outcome.to_owned(),
+            label: label.to_owned(),
+        });
+        if db.auth_attempts.len() > MAX_AUTH_ATTEMPTS {
+            let excess: usize = db.auth_attempts.len() - MAX_AUTH_ATTEMPTS;
+            db.auth_attempts.drain(..excess);
+        }
+    })
+    .await;
+
+    if let Err(err) = result {
+        eprintln!("Could not sync the authentication log to disk: {}", err);
+    }
+}
+
+/// Handles requests on `/api/v1/admin/auth-attempts`, which shows the most
+/// recent attempts at authenticating a heartbeat, accepted or not.
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/auth-attempts",
+    tag = "admin",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The authentication log", body = AuthAttemptsResponse),
+        (status = 401, description = "Not logged in as the person themself"),
+    )
+)]
+pub async fn auth_attempts_api(_admin: Admin) -> impl IntoResponse {
+    let db: Database = {
+        let _db_guard: MutexGuard<'_, ()> = database::lock().await;
+
+        match load_database(crate::DB_PATH) {
+            Err(err) => {
+                eprintln!("Could not read the authentication log: {}", err);
+                return Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(Body::default())
+                    .unwrap();
+            }
+            Ok(db) => db,
+        }
+    };
+    let resp: AuthAttemptsResponse = AuthAttemptsResponse {
+        failed: db
+            .auth_attempts
+            .iter()
+            .filter(|attempt| attempt.outcome != "accepted")
+            .count(),
+        attempts: &db.auth_attempts,
+    };
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&resp).unwrap()))
+        .unwrap()
+}
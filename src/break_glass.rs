@@ -0,0 +1,152 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::api::{ClientIp, PowSolution};
+use crate::audit;
+use crate::config::BreakGlass;
+use crate::database::{self, load_database};
+use crate::notify::{self, NotificationEvent};
+use crate::password;
+use crate::state::ServerState;
+use crate::trusted::{admit, start_session};
+use argon2::password_hash::PasswordHash;
+use axum::body::Body;
+use axum::extract::{Json, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde::Deserialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::MutexGuard;
+use utoipa::ToSchema;
+
+/// Username of whoever logged in with the break-glass token.
+/// Reserved, so no trusted user can be mistaken for them.
+pub const BREAK_GLASS_USERNAME: &str = "break-glass";
+
+#[derive(Deserialize, ToSchema)]
+pub struct BreakGlassRequest {
+    token: String,
+    pow: PowSolution,
+}
+
+/// Handles requests on `/api/v1/break-glass`, where an emergency contact trades
+/// the single-use break-glass token for a trusted user session.
+///
+/// Responds like `/api/v1/login` does on success. Every use is logged,
+/// written to the audit log, and sent to every notification target.
+///
+#[utoipa::path(
+    post,
+    path = "/api/v1/break-glass",
+    tag = "trusted users",
+    request_body = BreakGlassRequest,
+    responses(
+        (status = 200, description = "Logged in", body = crate::trusted::LoginResponse),
+        (status = 401, description = "Wrong or already used token"),
+        (status = 404, description = "No break-glass token is configured"),
+        (status = 406, description = "The proof of work is invalid or expired"),
+        (status = 429, description = "Blocked after too many failed attempts"),
+    )
+)]
+pub async fn break_glass_api(
+    ClientIp(ip): ClientIp,
+    State(server_state): State<ServerState>,
+    Json(req): Json<BreakGlassRequest>,
+) -> impl IntoResponse {
+    let Some(break_glass): Option<&BreakGlass> = server_state.config.break_glass.as_ref() else {
+        return Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::default())
+            .unwrap();
+    };
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    // a token that was already used counts as a failed authentication attempt
+    let admitted = admit(ip, &server_state, req.pow, async |_| {
+        // validated on startup
+        let hash: PasswordHash = PasswordHash::new(&break_glass.token_hash).unwrap();
+
+        password::verify(&req.token, &hash).then_some(())?;
+
+        let _db_guard: MutexGuard<'_, ()> = database::lock().await;
+
+        match load_database(crate::DB_PATH).ok()?.break_glass_used {
+            Some(_) => None,
+            None => Some(()),
+        }
+    })
+    .await;
+
+    if let Err(resp) = admitted {
+        return resp;
+    }
+
+    // make sure the token is only ever used once
+    let mut used: bool = false;
+
+    let result = database::modify(|db| {
+        if db.break_glass_used.is_none() {
+            used = true;
+            db.break_glass_used = Some(now);
+        }
+    })
+    .await;
+
+    if let Err(err) = result {
+        eprintln!("Could not sync break-glass token use to disk: {}", err);
+        return Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::default())
+            .unwrap();
+    }
+    if !used {
+        return Response::builder()
+            .status(StatusCode::UNAUTHORIZED)
+            .body(Body::default())
+            .unwrap();
+    }
+    let address: String = ip.to_string();
+
+    eprintln!(
+        "WARNING: The break-glass token was used from {}! Granting emergency {} access.",
+        address,
+        break_glass.role.db_code()
+    );
+    audit::record(
+        BREAK_GLASS_USERNAME,
+        format!(
+            "used the break-glass token from {}, as {}",
+            address,
+            break_glass.role.db_code()
+        ),
+    )
+    .await;
+    tokio::spawn(notify::dispatch(
+        server_state.config.clone(),
+        NotificationEvent::BreakGlassUsed {
+            timestamp: now,
+            address,
+        },
+    ));
+
+    start_session(&server_state, BREAK_GLASS_USERNAME.into(), break_glass.role).await
+}
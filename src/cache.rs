@@ -0,0 +1,104 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::api::baked_status;
+use crate::state::{LifeState, ServerState};
+use axum::http::{HeaderMap, HeaderValue};
+use axum::response::{IntoResponseParts, ResponseParts};
+use chrono::{DateTime, NaiveDateTime};
+use std::convert::Infallible;
+
+/// Longest time shared caches (e.g. CDNs) may serve the status for, in seconds.
+const SHARED_MAX_AGE: u64 = 60;
+/// Format of HTTP dates. See: <https://www.rfc-editor.org/rfc/rfc9110#section-5.6.7>
+const HTTP_DATE: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+/// `Cache-Control` and `Last-Modified` HTTP headers of the routes showing
+/// the status, keyed to when it last changed, so that CDNs and monitoring
+/// probes can revalidate them cheaply.
+///
+pub struct CacheHeaders {
+    cache_control: String,
+    /// Unix timestamp
+    last_modified: u64,
+}
+
+impl CacheHeaders {
+    pub async fn new(server_state: &ServerState, now: u64) -> Self {
+        // make sure the status was baked once, which it changes from
+        let _ = baked_status(server_state).await;
+        let last_modified: u64 = *server_state.status_changed_at.lock().await;
+
+        // browsers always revalidate, so a heartbeat shows right away, while
+        // shared caches may keep the status for a while, but never past a transition
+        let cache_control: String = match server_state.config.global.private {
+            true => "private, no-cache".into(),
+            false => {
+                let state: LifeState = **server_state.state.lock().await;
+                let shared_max_age: u64 = server_state
+                    .next_transition(state, now)
+                    .await
+                    .map_or(SHARED_MAX_AGE, |next| {
+                        next.saturating_sub(now).min(SHARED_MAX_AGE)
+                    });
+                format!("public, max-age=0, s-maxage={}", shared_max_age)
+            }
+        };
+        CacheHeaders {
+            cache_control,
+            last_modified,
+        }
+    }
+
+    /// Whether the client's cached response is still current, as told by the
+    /// `If-Modified-Since` HTTP header. It's ignored when `If-None-Match` is
+    /// also sent, which is more precise.
+    ///
+    pub fn not_modified(&self, headers: &HeaderMap) -> bool {
+        if headers.contains_key("If-None-Match") {
+            return false;
+        }
+        headers
+            .get("If-Modified-Since")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| NaiveDateTime::parse_from_str(value, HTTP_DATE).ok())
+            .is_some_and(|since| since.and_utc().timestamp() >= self.last_modified as i64)
+    }
+}
+
+impl IntoResponseParts for CacheHeaders {
+    type Error = Infallible;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        let last_modified: String = DateTime::from_timestamp(self.last_modified as i64, 0)
+            .unwrap_or_default()
+            .format(HTTP_DATE)
+            .to_string();
+
+        res.headers_mut().insert(
+            "Cache-Control",
+            HeaderValue::from_str(&self.cache_control).unwrap(),
+        );
+        res.headers_mut().insert(
+            "Last-Modified",
+            HeaderValue::from_str(&last_modified).unwrap(),
+        );
+        Ok(res)
+    }
+}
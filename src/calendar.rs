@@ -0,0 +1,206 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::api::bake_status_api_response;
+use crate::config::Calendar;
+use crate::state::ServerState;
+use chrono::{FixedOffset, NaiveDate, NaiveDateTime, TimeZone};
+use std::time::Duration;
+use tokio::time::{self, Interval};
+
+/// A planned absence, during which the time without heartbeats doesn't count.
+#[derive(Debug, Clone, Copy)]
+pub struct Absence {
+    /// Unix timestamp
+    pub start: u64,
+    /// Unix timestamp
+    pub end: u64,
+}
+
+/// Total time spent in the given absences between two points in time, in seconds.
+pub fn absent_seconds(absences: &[Absence], from: u64, to: u64) -> u64 {
+    let mut overlaps: Vec<(u64, u64)> = absences
+        .iter()
+        .map(|absence| (absence.start.max(from), absence.end.min(to)))
+        .filter(|(start, end)| start < end)
+        .collect();
+    overlaps.sort();
+
+    // don't count overlapping absences twice
+    let mut total: u64 = 0;
+    let mut counted_until: u64 = from;
+
+    for (start, end) in overlaps {
+        let start: u64 = start.max(counted_until);
+
+        if end > start {
+            total += end - start;
+            counted_until = end;
+        }
+    }
+    total
+}
+
+/// Parse an iCalendar date or date-time value, e.g. "20261016T080000Z".
+///
+/// Time zones other than UTC are not looked up. Such times, along
+/// with all-day dates, are read in our configured UTC offset.
+///
+fn parse_time(value: &str, timezone: &FixedOffset) -> Option<u64> {
+    let timestamp: i64 = if let Some(utc) = value.strip_suffix('Z') {
+        NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")
+            .ok()?
+            .and_utc()
+            .timestamp()
+    } else if value.contains('T') {
+        let local: NaiveDateTime = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+        timezone.from_local_datetime(&local).single()?.timestamp()
+    } else {
+        let local: NaiveDateTime = NaiveDate::parse_from_str(value, "%Y%m%d")
+            .ok()?
+            .and_hms_opt(0, 0, 0)?;
+        timezone.from_local_datetime(&local).single()?.timestamp()
+    };
+    timestamp.try_into().ok()
+}
+
+/// Find the events tagged as absences in an iCalendar document.
+/// See: <https://www.rfc-editor.org/rfc/rfc5545>
+///
+/// An event is an absence if the tag is one of its categories, or a word
+/// of its summary. Recurring events only count on their first occurrence.
+///
+fn parse_absences(ical: &str, tag: &str, timezone: &FixedOffset) -> Vec<Absence> {
+    // unfold long lines first
+    let unfolded: String = ical
+        .replace("\r\n ", "")
+        .replace("\r\n\t", "")
+        .replace("\n ", "")
+        .replace("\n\t", "");
+
+    let mut absences: Vec<Absence> = vec![];
+    let mut in_event: bool = false;
+    let mut tagged: bool = false;
+    let mut start: Option<u64> = None;
+    let mut end: Option<u64> = None;
+
+    for line in unfolded.lines() {
+        let line: &str = line.trim_end_matches('\r');
+
+        if line == "BEGIN:VEVENT" {
+            (in_event, tagged, start, end) = (true, false, None, None);
+            continue;
+        }
+        if !in_event {
+            continue;
+        }
+        if line == "END:VEVENT" {
+            in_event = false;
+
+            if let (true, Some(start), Some(end)) = (tagged, start, end) {
+                absences.push(Absence { start, end });
+            }
+            continue;
+        }
+        // e.g. 'DTSTART;TZID=Europe/Paris:20261016T080000'
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let (name, params) = name.split_once(';').unwrap_or((name, ""));
+
+        match name {
+            "SUMMARY" => {
+                tagged |= value
+                    .split(|c: char| !c.is_alphanumeric() && c != '-')
+                    .any(|word| word.eq_ignore_ascii_case(tag));
+            }
+            "CATEGORIES" => {
+                tagged |= value
+                    .split(',')
+                    .any(|category| category.trim().eq_ignore_ascii_case(tag));
+            }
+            "DTSTART" => {
+                start = parse_time(value, timezone);
+
+                // all-day events without an end last a day
+                if end.is_none() && params.contains("VALUE=DATE") {
+                    end = start.map(|start| start + 24 * 60 * 60);
+                }
+            }
+            "DTEND" => end = parse_time(value, timezone),
+            _ => (),
+        }
+    }
+    absences
+}
+
+/// Fetch the planned absences from the configured calendar.
+async fn fetch_absences(
+    config: &Calendar,
+    timezone: &FixedOffset,
+) -> Result<Vec<Absence>, reqwest::Error> {
+    let mut request: reqwest::RequestBuilder = reqwest::Client::new().get(&config.url);
+
+    if let Some(username) = config.username.as_ref() {
+        request = request.basic_auth(username, config.password.as_ref());
+    }
+    let ical: String = request.send().await?.error_for_status()?.text().await?;
+
+    Ok(parse_absences(
+        &ical,
+        config.tag.as_deref().unwrap_or("offline"),
+        timezone,
+    ))
+}
+
+/// Fetch the planned absences from the configured calendar,
+/// keeping the ones we know of if the calendar is unreachable.
+pub async fn refresh(server_state: &ServerState) {
+    let Some(config) = server_state.config.calendar.as_ref() else {
+        return;
+    };
+    let timezone: FixedOffset =
+        FixedOffset::east_opt(server_state.config.global.utc_offset * 60 * 60).unwrap();
+
+    match fetch_absences(config, &timezone).await {
+        Err(err) => eprintln!("Could not check the calendar for absences: {}", err),
+        Ok(absences) => {
+            *server_state.planned_absences.lock().await = absences;
+            // absences push the next transition back
+            let _: String = bake_status_api_response(server_state.clone()).await;
+        }
+    }
+}
+
+/// Poll the configured calendar for planned absences, forever.
+pub async fn poll(server_state: ServerState) {
+    let Some(config) = server_state.config.calendar.as_ref() else {
+        return;
+    };
+    let ival: u64 = config.poll_interval.into();
+    let mut interval: Interval = time::interval(Duration::from_secs(ival * 60));
+
+    // the first tick completes immediately, and we refresh on startup already
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+        refresh(&server_state).await;
+    }
+}
@@ -0,0 +1,109 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::api::bake_status_api_response;
+use crate::audit;
+use crate::config::{CheckIns, Role};
+use crate::database::{self, CheckInLog};
+use crate::state::ServerState;
+use crate::trusted::TrustedUser;
+use axum::body::Body;
+use axum::extract::{Json, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde::Deserialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+
+/// Longest check-in message accepted, in characters.
+const MAX_MESSAGE_LENGTH: usize = 256;
+
+#[derive(Deserialize, ToSchema)]
+pub struct CheckInRequest {
+    /// e.g. "Saw them at dinner."
+    message: String,
+}
+
+/// Handles requests on `/api/v1/check-in`, where a trusted user says they saw the
+/// person. Check-ins aren't heartbeats: they are shown apart from the person's
+/// own heartbeats, and only hold back the "Missing or Dead" state for a while.
+///
+#[utoipa::path(
+    post,
+    path = "/api/v1/check-in",
+    tag = "trusted users",
+    request_body = CheckInRequest,
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The check-in was recorded"),
+        (status = 401, description = "Not logged in"),
+        (status = 403, description = "Requires the verifier role"),
+        (status = 404, description = "Check-ins are not enabled"),
+    )
+)]
+pub async fn check_in_api(
+    State(server_state): State<ServerState>,
+    user: TrustedUser,
+    Json(req): Json<CheckInRequest>,
+) -> impl IntoResponse {
+    if let Err(forbidden) = user.require(Role::Verifier) {
+        return forbidden.into_response();
+    }
+    let Some(_): Option<&CheckIns> = server_state.config.check_ins.as_ref() else {
+        return Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::default())
+            .unwrap();
+    };
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let check_in: CheckInLog = CheckInLog {
+        timestamp: now,
+        username: user.session.username.clone(),
+        // check-ins are stored one per line
+        message: req
+            .message
+            .replace(['\n', '\r'], " ")
+            .chars()
+            .take(MAX_MESSAGE_LENGTH)
+            .collect(),
+    };
+    *server_state.last_check_in.lock().await = Some(check_in.clone());
+    // the check-in may push the next transition back
+    let _: String = bake_status_api_response(server_state.clone()).await;
+
+    if let Err(err) = database::modify(|db| db.check_ins.push(check_in)).await {
+        eprintln!("Could not sync check-in to disk: {}", err);
+
+        return Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::default())
+            .unwrap();
+    }
+    println!("Trusted user '{}' checked in.", user.session.username);
+    audit::record(&user.session.username, "checked in".into()).await;
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::default())
+        .unwrap()
+}
@@ -0,0 +1,265 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use futures_util::StreamExt;
+use pow_solver::{Challenge, Solution};
+use reqwest::StatusCode;
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tokio_tungstenite::tungstenite::{self, Message};
+
+#[derive(Serialize)]
+struct HeartbeatRequest<'a> {
+    remove_current_note: bool,
+    updated_note: &'a str,
+    message: &'a str,
+    password: &'a str,
+    pow: Solution,
+}
+
+#[derive(Serialize)]
+struct RateLimitRequest {
+    pow: Solution,
+}
+
+/// Whether our IP address is blocked from sending heartbeats, as served on `/api/v1/ratelimit`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimitStatus {
+    pub blocked: bool,
+    /// Unix timestamp of when the block expires, if blocked.
+    pub blocked_until: Option<u64>,
+    /// Seconds left until the block expires, if blocked.
+    pub retry_after: Option<u64>,
+    /// How long the next wrong password blocks us for, in seconds.
+    pub next_block_period: u64,
+}
+
+/// A heartbeat to send, with its optional message and note update.
+#[derive(Debug, Default, Clone)]
+pub struct Heartbeat {
+    pub message: String,
+    /// Replaces the note shown on the status page, if not empty.
+    pub note: String,
+    /// Removes the note shown on the status page.
+    pub remove_note: bool,
+}
+
+/// Why a heartbeat could not be sent.
+#[derive(Debug)]
+pub enum ClientError {
+    /// The instance could not be reached, or replied with something unexpected.
+    Connection(String),
+    /// The password was wrong. Retrying is blocked for this many seconds.
+    Unauthorized { retry_after: u64 },
+    /// We are rate limited for this many seconds, after a wrong password.
+    RateLimited { retry_after: u64 },
+    /// The proof of work was rejected, e.g. as it took too long to solve.
+    PowRejected,
+    /// The instance could not register the heartbeat.
+    Server { status: u16, reason: String },
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Connection(err) => write!(f, "Could not reach the instance: {}", err),
+            ClientError::Unauthorized { retry_after } => {
+                write!(f, "Unauthorized. Rate limited for {} seconds.", retry_after)
+            }
+            ClientError::RateLimited { retry_after } => {
+                write!(f, "Rate limited. Try again in {} seconds.", retry_after)
+            }
+            ClientError::PowRejected => write!(f, "The proof of work was rejected."),
+            ClientError::Server { status, reason } => {
+                write!(f, "Heartbeat failed with HTTP {}: {}", status, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+/// Get the seconds to wait for out of a `Retry-After` HTTP header.
+fn retry_after(headers: &HeaderMap) -> u64 {
+    headers
+        .get("Retry-After")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_default()
+}
+
+/// Get the explanation out of an error response, which are RFC 7807 problem details.
+async fn problem_detail(resp: reqwest::Response) -> String {
+    let text: String = resp.text().await.unwrap_or_default();
+
+    match serde_json::from_str::<serde_json::Value>(&text) {
+        Ok(problem) => problem["detail"].as_str().unwrap_or_default().to_owned(),
+        Err(_) => text,
+    }
+}
+
+/// Client for sending authenticated heartbeats to an "Am I Alive" instance,
+/// taking care of the proof of work handshake on `/api/v1/pow`.
+///
+/// ```no_run
+/// # async fn example() -> Result<(), am_i_alive::client::ClientError> {
+/// use am_i_alive::client::{Heartbeat, HeartbeatClient};
+///
+/// let client = HeartbeatClient::new("https://alive.example.com", "<PASSWORD>");
+/// client.send(&Heartbeat { message: "Backup done.".into(), ..Default::default() }).await
+/// # }
+/// ```
+///
+pub struct HeartbeatClient {
+    /// e.g. "https://alive.example.com"
+    url: String,
+    password: String,
+    http: reqwest::Client,
+}
+
+impl HeartbeatClient {
+    pub fn new(url: &str, password: &str) -> Self {
+        HeartbeatClient {
+            url: url.trim_end_matches('/').to_string(),
+            password: password.to_string(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Get a fresh PoW challenge from the `/api/v1/pow` WebSocket.
+    async fn challenge(&self) -> Result<Challenge, ClientError> {
+        let ws_url: String = match self.url.split_once("://") {
+            Some(("http", rest)) => format!("ws://{}/api/v1/pow", rest),
+            Some(("https", rest)) => format!("wss://{}/api/v1/pow", rest),
+            _ => return Err(ClientError::Connection("Invalid instance URL.".into())),
+        };
+        let (mut socket, _) = match tokio_tungstenite::connect_async(ws_url).await {
+            // rate limited addresses are refused a challenge too
+            Err(tungstenite::Error::Http(resp))
+                if resp.status() == StatusCode::TOO_MANY_REQUESTS =>
+            {
+                return Err(ClientError::RateLimited {
+                    retry_after: retry_after(resp.headers()),
+                });
+            }
+            Err(err) => return Err(ClientError::Connection(err.to_string())),
+            Ok(connected) => connected,
+        };
+
+        while let Some(msg) = socket.next().await {
+            match msg.map_err(|err| ClientError::Connection(err.to_string()))? {
+                Message::Text(text) => {
+                    let _ = socket.close(None).await;
+
+                    return serde_json::from_str(&text)
+                        .map_err(|err| ClientError::Connection(err.to_string()));
+                }
+                Message::Close(_) => break,
+                _ => (),
+            }
+        }
+        Err(ClientError::Connection(
+            "The PoW WebSocket closed without a challenge.".into(),
+        ))
+    }
+
+    /// Get a fresh PoW challenge and solve it, committing to the given payload digest, if any.
+    async fn pow(&self, payload_digest: Option<String>) -> Result<Solution, ClientError> {
+        let challenge: Challenge = self.challenge().await?;
+
+        // solving can take a while on slow machines, don't block the runtime
+        let solved = tokio::task::spawn_blocking(move || {
+            pow_solver::solve(&challenge, payload_digest.as_deref(), 0..u64::MAX)
+        })
+        .await
+        .map_err(|err| ClientError::Connection(err.to_string()))?;
+
+        match solved {
+            Ok(Some(solution)) => Ok(solution),
+            Ok(None) => Err(ClientError::Connection("Could not solve the PoW.".into())),
+            Err(err) => Err(ClientError::Connection(err.to_string())),
+        }
+    }
+
+    /// Solve a proof of work, then ask `/api/v1/ratelimit` whether we are blocked,
+    /// to back off before sending a heartbeat.
+    ///
+    /// While blocked, this fails with [`ClientError::RateLimited`] already,
+    /// as blocked addresses are refused a PoW challenge.
+    ///
+    pub async fn rate_limit(&self) -> Result<RateLimitStatus, ClientError> {
+        let pow: Solution = self.pow(None).await?;
+
+        let resp: reqwest::Response = self
+            .http
+            .post(format!("{}/api/v1/ratelimit", self.url))
+            .json(&RateLimitRequest { pow })
+            .send()
+            .await
+            .map_err(|err| ClientError::Connection(err.without_url().to_string()))?;
+
+        match resp.status() {
+            StatusCode::OK => resp
+                .json()
+                .await
+                .map_err(|err| ClientError::Connection(err.without_url().to_string())),
+            StatusCode::NOT_ACCEPTABLE => Err(ClientError::PowRejected),
+            status => Err(ClientError::Server {
+                status: status.as_u16(),
+                reason: problem_detail(resp).await,
+            }),
+        }
+    }
+
+    /// Solve a proof of work, committing to the heartbeat so that it can't be
+    /// sent along with another one, then send it to `/api/v1/heartbeat`.
+    pub async fn send(&self, heartbeat: &Heartbeat) -> Result<(), ClientError> {
+        let payload_digest: String =
+            pow_solver::payload_digest(&heartbeat.message, &heartbeat.note, heartbeat.remove_note);
+        let pow: Solution = self.pow(Some(payload_digest)).await?;
+
+        let resp: reqwest::Response = self
+            .http
+            .post(format!("{}/api/v1/heartbeat", self.url))
+            .json(&HeartbeatRequest {
+                remove_current_note: heartbeat.remove_note,
+                updated_note: &heartbeat.note,
+                message: &heartbeat.message,
+                password: &self.password,
+                pow,
+            })
+            .send()
+            .await
+            .map_err(|err| ClientError::Connection(err.without_url().to_string()))?;
+
+        let retry_after: u64 = retry_after(resp.headers());
+
+        match resp.status() {
+            StatusCode::OK => Ok(()),
+            StatusCode::UNAUTHORIZED => Err(ClientError::Unauthorized { retry_after }),
+            StatusCode::TOO_MANY_REQUESTS => Err(ClientError::RateLimited { retry_after }),
+            StatusCode::NOT_ACCEPTABLE => Err(ClientError::PowRejected),
+            status => Err(ClientError::Server {
+                status: status.as_u16(),
+                reason: problem_detail(resp).await,
+            }),
+        }
+    }
+}
@@ -17,9 +17,12 @@
     License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::config::ServerConfig;
-use crate::state::{HeartbeatDisplay, LifeState};
+use crate::MAX_DISPLAYED_HEARTBEATS;
+use crate::config::{Role, ServerConfig, TrustedUserConfig};
+use crate::heatmap::{self, HeartbeatDays};
+use crate::state::{HeartbeatDisplay, LifeState, TransitionCause};
 use chrono::{FixedOffset, TimeZone};
+use serde::Serialize;
 use std::fmt::{Display, Formatter, Write};
 use std::fs::File;
 use std::hash::{Hash, Hasher};
@@ -27,12 +30,36 @@ use std::io::Read;
 use std::sync::Arc;
 use tokio::fs::write as tokio_write;
 use tokio::io::Result as TokioIOResult;
+use tokio::sync::{Mutex, MutexGuard};
+use utoipa::ToSchema;
+
+/// Held by any task that loads, modifies, and writes back the database
+/// file, so concurrent tasks can't overwrite each other's changes.
+static DB_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// Lock the database file for a read-modify-write cycle.
+pub async fn lock() -> MutexGuard<'static, ()> {
+    DB_LOCK.lock().await
+}
 
 pub struct InitialState {
     pub state: LifeState,
     pub last_heartbeat: u64,
+    /// Unix timestamp of the last heartbeat that wasn't registered passively.
+    pub last_active_heartbeat: u64,
     pub note: Option<String>,
-    pub heartbeat_display: [HeartbeatDisplay; 5],
+    pub private_note: Option<String>,
+    pub heartbeat_display: [HeartbeatDisplay; MAX_DISPLAYED_HEARTBEATS],
+    pub heartbeat_days: HeartbeatDays,
+    /// Confirmations of the "Dead" state since the last heartbeat.
+    pub dead_confirmations: Vec<ConfirmationLog>,
+    pub last_check_in: Option<CheckInLog>,
+    /// Unix timestamp of when the person set the current state themself, if they did.
+    pub overridden_at: Option<u64>,
+    pub release_countdown: Option<Countdown>,
+    pub registered_users: Vec<TrustedUserConfig>,
+    /// Guestbook entries approved by a trusted user.
+    pub guestbook: Vec<GuestbookEntry>,
 }
 
 #[derive(Debug, Default)]
@@ -40,7 +67,35 @@ pub struct Database {
     pub state: String,
     pub last_heartbeat: u64,
     pub note: String,
+    /// Note only shown to trusted users, see [`crate::note`]
+    pub private_note: Option<String>,
     pub heartbeat_history: Vec<HeartbeatLog>,
+    pub notification_ledger: Vec<LedgerEntry>,
+    pub push_subscriptions: Vec<PushSubscription>,
+    pub webhooks: Vec<WebhookSubscription>,
+    pub transitions: Vec<TransitionLog>,
+    pub confirmations: Vec<ConfirmationLog>,
+    pub release_countdown: Option<Countdown>,
+    pub audit_log: Vec<AuditEntry>,
+    /// Trusted users that registered through an invitation.
+    pub registered_users: Vec<TrustedUserConfig>,
+    pub invitations: Vec<Invitation>,
+    pub heartbeat_media: Vec<MediaLog>,
+    /// Unix timestamp of when the break-glass token was used, if ever.
+    pub break_glass_used: Option<u64>,
+    pub timestamp_proofs: Vec<TimestampProof>,
+    /// Unix timestamps of the heartbeats that were registered passively,
+    /// from activity seen elsewhere rather than sent by the person.
+    pub passive_heartbeats: Vec<u64>,
+    pub heartbeat_labels: Vec<LabelLog>,
+    /// Times trusted users said they saw the person.
+    pub check_ins: Vec<CheckInLog>,
+    /// Tokens to send heartbeats with, one per device.
+    pub device_tokens: Vec<DeviceToken>,
+    /// Most recent heartbeat authentication attempts, oldest first.
+    pub auth_attempts: Vec<AuthAttempt>,
+    /// Messages left by visitors once the person is dead, approved or not.
+    pub guestbook: Vec<GuestbookEntry>,
 }
 
 impl Database {
@@ -58,6 +113,61 @@ impl Hash for Database {
         for log in self.heartbeat_history.iter() {
             log.hash(state);
         }
+        for entry in self.notification_ledger.iter() {
+            entry.hash(state);
+        }
+        for subscription in self.push_subscriptions.iter() {
+            subscription.hash(state);
+        }
+        for webhook in self.webhooks.iter() {
+            webhook.hash(state);
+        }
+        for transition in self.transitions.iter() {
+            transition.hash(state);
+        }
+        for confirmation in self.confirmations.iter() {
+            confirmation.hash(state);
+        }
+        if let Some(countdown) = self.release_countdown.as_ref() {
+            countdown.hash(state);
+        }
+        for entry in self.audit_log.iter() {
+            entry.hash(state);
+        }
+        for user in self.registered_users.iter() {
+            user.hash(state);
+        }
+        for invitation in self.invitations.iter() {
+            invitation.hash(state);
+        }
+        for media in self.heartbeat_media.iter() {
+            media.hash(state);
+        }
+        if let Some(timestamp) = self.break_glass_used {
+            state.write_u64(timestamp);
+        }
+        for proof in self.timestamp_proofs.iter() {
+            proof.hash(state);
+        }
+        for timestamp in self.passive_heartbeats.iter() {
+            state.write_u64(*timestamp);
+        }
+        for label in self.heartbeat_labels.iter() {
+            label.hash(state);
+        }
+        for check_in in self.check_ins.iter() {
+            check_in.hash(state);
+        }
+        for device_token in self.device_tokens.iter() {
+            device_token.hash(state);
+        }
+        for attempt in self.auth_attempts.iter() {
+            attempt.hash(state);
+        }
+        for entry in self.guestbook.iter() {
+            entry.hash(state);
+        }
+        self.private_note.hash(state);
     }
 }
 
@@ -73,6 +183,69 @@ impl Display for Database {
         for log in self.heartbeat_history.iter() {
             log.fmt(f)?;
         }
+        for entry in self.notification_ledger.iter() {
+            entry.fmt(f)?;
+        }
+        for subscription in self.push_subscriptions.iter() {
+            subscription.fmt(f)?;
+        }
+        for webhook in self.webhooks.iter() {
+            webhook.fmt(f)?;
+        }
+        for transition in self.transitions.iter() {
+            transition.fmt(f)?;
+        }
+        for confirmation in self.confirmations.iter() {
+            confirmation.fmt(f)?;
+        }
+        if let Some(countdown) = self.release_countdown.as_ref() {
+            countdown.fmt(f)?;
+        }
+        for entry in self.audit_log.iter() {
+            entry.fmt(f)?;
+        }
+        for user in self.registered_users.iter() {
+            writeln!(
+                f,
+                "user {} {} {}",
+                user.username,
+                user.role.db_code(),
+                user.password_hash
+            )?;
+        }
+        for invitation in self.invitations.iter() {
+            invitation.fmt(f)?;
+        }
+        for media in self.heartbeat_media.iter() {
+            media.fmt(f)?;
+        }
+        if let Some(timestamp) = self.break_glass_used {
+            writeln!(f, "break-glass {}", timestamp)?;
+        }
+        for proof in self.timestamp_proofs.iter() {
+            proof.fmt(f)?;
+        }
+        for timestamp in self.passive_heartbeats.iter() {
+            writeln!(f, "passive {}", timestamp)?;
+        }
+        for label in self.heartbeat_labels.iter() {
+            label.fmt(f)?;
+        }
+        for check_in in self.check_ins.iter() {
+            check_in.fmt(f)?;
+        }
+        for device_token in self.device_tokens.iter() {
+            device_token.fmt(f)?;
+        }
+        for attempt in self.auth_attempts.iter() {
+            attempt.fmt(f)?;
+        }
+        for entry in self.guestbook.iter() {
+            entry.fmt(f)?;
+        }
+        if let Some(note) = self.private_note.as_ref() {
+            writeln!(f, "private-note {}", note)?;
+        }
         Ok(())
     }
 }
@@ -104,6 +277,703 @@ impl Display for HeartbeatLog {
     }
 }
 
+/// Record of a notification event that was delivered to a notification channel.
+///
+/// Stored in the database as: `notified <timestamp> <channel> <event>`
+///
+#[derive(Debug, Default, Clone, Hash)]
+pub struct LedgerEntry {
+    pub timestamp: u64,
+    /// Non-reversible ID of the notification URL, as URLs may contain credentials.
+    pub channel: String,
+    /// e.g. "state:2@1767225600"
+    pub event: String,
+}
+
+impl Display for LedgerEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "notified {} {} {}",
+            self.timestamp, self.channel, self.event
+        )
+    }
+}
+
+/// Web Push subscription of a visitor of the status page.
+///
+/// Stored in the database as: `subscription <endpoint> <p256dh> <auth>`
+///
+#[derive(Debug, Default, Clone, Hash)]
+pub struct PushSubscription {
+    pub endpoint: String,
+    /// Base64url encoded P-256 public key of the browser.
+    pub p256dh: String,
+    /// Base64url encoded authentication secret of the browser.
+    pub auth: String,
+}
+
+impl Display for PushSubscription {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "subscription {} {} {}",
+            self.endpoint, self.p256dh, self.auth
+        )
+    }
+}
+
+/// Webhook registered at runtime through `/api/v1/admin/webhooks`.
+///
+/// Stored in the database as: `webhook <id> <url> <secret> <events>`
+///
+#[derive(Debug, Default, Clone, Hash)]
+pub struct WebhookSubscription {
+    /// Random hex encoded ID, to unregister the webhook with.
+    pub id: String,
+    pub url: String,
+    /// Shared secret the deliveries are signed with, using HMAC-SHA256.
+    pub secret: String,
+    /// Names of the [`crate::notify::NotificationEvent`]s to deliver, or "*" for all.
+    pub events: Vec<String>,
+}
+
+impl WebhookSubscription {
+    /// Whether the webhook subscribed to the event of the given name.
+    pub fn wants(&self, event: &str) -> bool {
+        self.events.iter().any(|name| name == "*" || name == event)
+    }
+}
+
+impl Display for WebhookSubscription {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "webhook {} {} {} {}",
+            self.id,
+            self.url,
+            self.secret,
+            self.events.join(",")
+        )
+    }
+}
+
+/// Record of a [`LifeState`] transition.
+///
+/// Stored in the database as: `transition <timestamp> <from> <to> <cause>`
+///
+#[derive(Debug, Clone)]
+pub struct TransitionLog {
+    pub timestamp: u64,
+    pub from: LifeState,
+    pub to: LifeState,
+    /// [`std::fmt::Display`] output of [`crate::state::TransitionCause`]
+    pub cause: String,
+}
+
+impl Hash for TransitionLog {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.timestamp);
+        state.write(self.from.db_code().as_bytes());
+        state.write(self.to.db_code().as_bytes());
+        state.write(self.cause.as_bytes());
+    }
+}
+
+impl Display for TransitionLog {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "transition {} {} {} {}",
+            self.timestamp,
+            self.from.db_code(),
+            self.to.db_code(),
+            self.cause
+        )
+    }
+}
+
+/// Trusted user's confirmation of the "Dead" state, counting towards the quorum.
+///
+/// Stored in the database as: `confirmation <timestamp> <username>`
+///
+#[derive(Debug, Clone, Hash)]
+pub struct ConfirmationLog {
+    pub timestamp: u64,
+    pub username: String,
+}
+
+impl Display for ConfirmationLog {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "confirmation {} {}", self.timestamp, self.username)
+    }
+}
+
+/// Countdown before releasing the will and the final notifications, started
+/// when entering the "Missing or Dead" or "Dead" state. A heartbeat or the
+/// recovery code cancels it.
+///
+/// Stored in the database as: `countdown <started> <ends> <from> <status>`
+///
+#[derive(Debug, Clone)]
+pub struct Countdown {
+    pub started: u64,
+    pub ends: u64,
+    /// State we were in before the countdown started.
+    pub from: LifeState,
+    pub status: CountdownStatus,
+}
+
+impl Countdown {
+    /// Whether whatever the countdown holds back may be released.
+    pub fn is_over(&self, now: u64) -> bool {
+        match self.status {
+            CountdownStatus::Pending => now >= self.ends,
+            CountdownStatus::Cancelled => false,
+            CountdownStatus::Released => true,
+        }
+    }
+}
+
+impl Hash for Countdown {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.started);
+        state.write_u64(self.ends);
+        state.write(self.from.db_code().as_bytes());
+        state.write(self.status.db_code().as_bytes());
+    }
+}
+
+impl Display for Countdown {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "countdown {} {} {} {}",
+            self.started,
+            self.ends,
+            self.from.db_code(),
+            self.status.db_code()
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CountdownStatus {
+    Pending,
+    Cancelled,
+    Released,
+}
+
+impl CountdownStatus {
+    pub fn db_code(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Cancelled => "cancelled",
+            Self::Released => "released",
+        }
+    }
+}
+
+/// Entry of the audit log of trusted user actions. Every entry's hash covers the
+/// previous entry's hash, so editing or removing any entry breaks the chain.
+///
+/// Stored in the database as: `audit <timestamp> <hash> <actor> <action>`
+///
+#[derive(Debug, Clone, Hash, Serialize, ToSchema)]
+pub struct AuditEntry {
+    pub timestamp: u64,
+    /// Hex encoded SHA-256 hash, see [`crate::audit::chain_hash`]
+    pub hash: String,
+    /// Username of whoever took the action.
+    pub actor: String,
+    pub action: String,
+}
+
+impl Display for AuditEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "audit {} {} {} {}",
+            self.timestamp, self.hash, self.actor, self.action
+        )
+    }
+}
+
+/// One-time invitation for a contact to register as a trusted user.
+///
+/// Stored in the database as: `invitation <token_hash> <role> <expires>`
+///
+#[derive(Debug, Clone, Hash)]
+pub struct Invitation {
+    /// Hex encoded SHA-256 hash of the invitation token.
+    pub token_hash: String,
+    pub role: Role,
+    /// Unix timestamp of when this invitation expires.
+    pub expires: u64,
+}
+
+impl Display for Invitation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "invitation {} {} {}",
+            self.token_hash,
+            self.role.db_code(),
+            self.expires
+        )
+    }
+}
+
+/// Media attachment that was sent along with a heartbeat, as proof of life.
+///
+/// Stored in the database as: `media <timestamp> <file_name>`
+///
+#[derive(Debug, Clone, Hash)]
+pub struct MediaLog {
+    /// Unix timestamp of the heartbeat this was sent with.
+    pub timestamp: u64,
+    /// Name of the file in the heartbeat media directory.
+    pub file_name: String,
+}
+
+impl Display for MediaLog {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "media {} {}", self.timestamp, self.file_name)
+    }
+}
+
+/// Label of a heartbeat, saying what registered it. (e.g. the sensor that saw you)
+///
+/// Stored in the database as: `label <timestamp> <label>`
+///
+#[derive(Debug, Clone, Hash)]
+pub struct LabelLog {
+    /// Unix timestamp of the labeled heartbeat.
+    pub timestamp: u64,
+    pub label: String,
+}
+
+impl Display for LabelLog {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "label {} {}", self.timestamp, self.label)
+    }
+}
+
+/// A trusted user saying they saw the person, e.g. "Saw them at dinner."
+///
+/// Stored in the database as: `checkin <timestamp> <username> <message>`
+///
+#[derive(Debug, Clone, Hash)]
+pub struct CheckInLog {
+    pub timestamp: u64,
+    pub username: String,
+    pub message: String,
+}
+
+impl Display for CheckInLog {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "checkin {} {} {}",
+            self.timestamp, self.username, self.message
+        )
+    }
+}
+
+/// Token issued through `/api/v1/admin/tokens`, to send heartbeats from a device
+/// without its password or a proof of work, until it is revoked.
+///
+/// Stored in the database as: `device-token <id> <created> <token hash> <name>`
+///
+#[derive(Debug, Clone, Hash)]
+pub struct DeviceToken {
+    /// Random hex encoded ID, to revoke the token with.
+    pub id: String,
+    /// Unix timestamp of when the token was issued.
+    pub created: u64,
+    /// See [`crate::sources::token_hash`]
+    pub token_hash: String,
+    /// e.g. "phone", shown as the label of the heartbeats sent with it.
+    pub name: String,
+}
+
+impl Display for DeviceToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "device-token {} {} {} {}",
+            self.id, self.created, self.token_hash, self.name
+        )
+    }
+}
+
+/// Attempt at authenticating a heartbeat, see [`crate::auth_log`]
+///
+/// Stored in the database as: `auth-attempt <timestamp> <address> <method> <outcome> <label>`
+///
+#[derive(Debug, Clone, Hash, Serialize, ToSchema)]
+pub struct AuthAttempt {
+    pub timestamp: u64,
+    /// IP address the attempt came from.
+    pub address: String,
+    /// What the client authenticated with, i.e. "password", "device_token" or "certificate"
+    pub method: String,
+    /// "accepted", or the error code the attempt was refused with, e.g. "bad_password"
+    pub outcome: String,
+    /// Name of the credential, device token or certificate the attempt was accepted
+    /// with, or "totp". Empty if there is none, or the attempt was refused.
+    pub label: String,
+}
+
+impl Display for AuthAttempt {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "auth-attempt {} {} {} {} {}",
+            self.timestamp, self.address, self.method, self.outcome, self.label
+        )
+    }
+}
+
+/// Message left by a visitor once the person is dead, see [`crate::guestbook`]
+///
+/// Stored in the database as: `guestbook <id> <timestamp> <approved|pending> <name>\t<message>`
+///
+#[derive(Debug, Clone, Hash, Serialize, ToSchema)]
+pub struct GuestbookEntry {
+    /// Random hex encoded ID, to approve or delete the entry with.
+    pub id: String,
+    /// Unix timestamp
+    pub timestamp: u64,
+    /// Whether a trusted user approved it, so it's shown on the status page.
+    pub approved: bool,
+    /// Name the visitor signed with, if any.
+    pub name: String,
+    pub message: String,
+}
+
+impl Display for GuestbookEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "guestbook {} {} {} {}\t{}",
+            self.id,
+            self.timestamp,
+            match self.approved {
+                true => "approved",
+                false => "pending",
+            },
+            self.name,
+            self.message
+        )
+    }
+}
+
+/// Proof from an external timestamping service that a state transition
+/// record existed at some point in time.
+///
+/// Stored in the database as: `timestamp <digest> <calendar> <receipt>`
+///
+#[derive(Debug, Clone, Hash)]
+pub struct TimestampProof {
+    /// Hex encoded SHA-256 hash of the transition record, see [`crate::timestamping`]
+    pub digest: String,
+    /// URL of the calendar server that timestamped the digest.
+    pub calendar: String,
+    /// Base64 encoded (pending) OpenTimestamps proof, as returned by the calendar.
+    pub receipt: String,
+}
+
+impl Display for TimestampProof {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "timestamp {} {} {}",
+            self.digest, self.calendar, self.receipt
+        )
+    }
+}
+
+/// Load the database file, apply the given changes, and write it back to disk.
+pub async fn modify<F>(changes: F) -> TokioIOResult<()>
+where
+    F: FnOnce(&mut Database),
+{
+    let _db_guard: MutexGuard<'_, ()> = lock().await;
+
+    let mut db: Database = load_database(crate::DB_PATH)?;
+    changes(&mut db);
+    db.write_to_disk().await
+}
+
+/// Persist a state transition, the new state itself, and the
+/// resulting release countdown to the database file.
+pub async fn record_transition(
+    transition: TransitionLog,
+    countdown: Option<Countdown>,
+) -> TokioIOResult<()> {
+    modify(|db| {
+        db.state = transition.to.db_code().into();
+        db.transitions.push(transition);
+        db.release_countdown = countdown;
+    })
+    .await
+}
+
+/// Parse a tagged record line (any line after the heartbeat history header
+/// that does not start with a Unix timestamp) into the given [`Database`].
+///
+fn parse_tagged_record(db: &mut Database, line: &str, line_number: usize) {
+    let fields: Vec<&str> = line.split(' ').collect();
+
+    match fields[0] {
+        "notified" => {
+            if fields.len() != 4 {
+                panic!("Corrupted ledger entry on line {}", line_number);
+            }
+            db.notification_ledger.push(LedgerEntry {
+                timestamp: fields[1]
+                    .parse::<u64>()
+                    .unwrap_or_else(|_| panic!("Invalid unix timestamp on line {}", line_number)),
+                channel: fields[2].to_owned(),
+                event: fields[3].to_owned(),
+            });
+        }
+        "subscription" => {
+            if fields.len() != 4 {
+                panic!("Corrupted push subscription on line {}", line_number);
+            }
+            db.push_subscriptions.push(PushSubscription {
+                endpoint: fields[1].to_owned(),
+                p256dh: fields[2].to_owned(),
+                auth: fields[3].to_owned(),
+            });
+        }
+        "webhook" => {
+            if fields.len() != 5 {
+                panic!("Corrupted webhook on line {}", line_number);
+            }
+            db.webhooks.push(WebhookSubscription {
+                id: fields[1].to_owned(),
+                url: fields[2].to_owned(),
+                secret: fields[3].to_owned(),
+                events: fields[4].split(',').map(str::to_owned).collect(),
+            });
+        }
+        "transition" => {
+            // the cause is the rest of the line, and may contain spaces
+            let fields: Vec<&str> = line.splitn(5, ' ').collect();
+
+            if fields.len() != 5 {
+                panic!("Corrupted state transition on line {}", line_number);
+            }
+            db.transitions.push(TransitionLog {
+                timestamp: fields[1]
+                    .parse::<u64>()
+                    .unwrap_or_else(|_| panic!("Invalid unix timestamp on line {}", line_number)),
+                from: LifeState::from(fields[2]),
+                to: LifeState::from(fields[3]),
+                cause: fields[4].to_owned(),
+            });
+        }
+        "confirmation" => {
+            if fields.len() != 3 {
+                panic!("Corrupted confirmation on line {}", line_number);
+            }
+            db.confirmations.push(ConfirmationLog {
+                timestamp: fields[1]
+                    .parse::<u64>()
+                    .unwrap_or_else(|_| panic!("Invalid unix timestamp on line {}", line_number)),
+                username: fields[2].to_owned(),
+            });
+        }
+        "countdown" => {
+            if fields.len() != 5 || db.release_countdown.is_some() {
+                panic!("Corrupted release countdown on line {}", line_number);
+            }
+            let timestamp = |field: &str| -> u64 {
+                field
+                    .parse::<u64>()
+                    .unwrap_or_else(|_| panic!("Invalid unix timestamp on line {}", line_number))
+            };
+            db.release_countdown = Some(Countdown {
+                started: timestamp(fields[1]),
+                ends: timestamp(fields[2]),
+                from: LifeState::from(fields[3]),
+                status: match fields[4] {
+                    "pending" => CountdownStatus::Pending,
+                    "cancelled" => CountdownStatus::Cancelled,
+                    "released" => CountdownStatus::Released,
+                    _ => panic!("Invalid countdown status on line {}", line_number),
+                },
+            });
+        }
+        "audit" => {
+            // the action is the rest of the line, and may contain spaces
+            let fields: Vec<&str> = line.splitn(5, ' ').collect();
+
+            if fields.len() != 5 {
+                panic!("Corrupted audit log entry on line {}", line_number);
+            }
+            db.audit_log.push(AuditEntry {
+                timestamp: fields[1]
+                    .parse::<u64>()
+                    .unwrap_or_else(|_| panic!("Invalid unix timestamp on line {}", line_number)),
+                hash: fields[2].to_owned(),
+                actor: fields[3].to_owned(),
+                action: fields[4].to_owned(),
+            });
+        }
+        "user" => {
+            if fields.len() != 4 {
+                panic!("Corrupted registered user on line {}", line_number);
+            }
+            db.registered_users.push(TrustedUserConfig {
+                username: fields[1].to_owned(),
+                role: Role::from_db_code(fields[2])
+                    .unwrap_or_else(|| panic!("Invalid role on line {}", line_number)),
+                password_hash: fields[3].to_owned(),
+            });
+        }
+        "invitation" => {
+            if fields.len() != 4 {
+                panic!("Corrupted invitation on line {}", line_number);
+            }
+            db.invitations.push(Invitation {
+                token_hash: fields[1].to_owned(),
+                role: Role::from_db_code(fields[2])
+                    .unwrap_or_else(|| panic!("Invalid role on line {}", line_number)),
+                expires: fields[3]
+                    .parse::<u64>()
+                    .unwrap_or_else(|_| panic!("Invalid unix timestamp on line {}", line_number)),
+            });
+        }
+        "media" => {
+            if fields.len() != 3 {
+                panic!("Corrupted heartbeat media on line {}", line_number);
+            }
+            db.heartbeat_media.push(MediaLog {
+                timestamp: fields[1]
+                    .parse::<u64>()
+                    .unwrap_or_else(|_| panic!("Invalid unix timestamp on line {}", line_number)),
+                file_name: fields[2].to_owned(),
+            });
+        }
+        "break-glass" => {
+            if fields.len() != 2 || db.break_glass_used.is_some() {
+                panic!("Corrupted break-glass record on line {}", line_number);
+            }
+            db.break_glass_used = Some(
+                fields[1]
+                    .parse::<u64>()
+                    .unwrap_or_else(|_| panic!("Invalid unix timestamp on line {}", line_number)),
+            );
+        }
+        "timestamp" => {
+            if fields.len() != 4 {
+                panic!("Corrupted timestamp proof on line {}", line_number);
+            }
+            db.timestamp_proofs.push(TimestampProof {
+                digest: fields[1].to_owned(),
+                calendar: fields[2].to_owned(),
+                receipt: fields[3].to_owned(),
+            });
+        }
+        "passive" => {
+            if fields.len() != 2 {
+                panic!("Corrupted passive heartbeat record on line {}", line_number);
+            }
+            db.passive_heartbeats.push(
+                fields[1]
+                    .parse::<u64>()
+                    .unwrap_or_else(|_| panic!("Invalid unix timestamp on line {}", line_number)),
+            );
+        }
+        "label" => {
+            if fields.len() < 3 {
+                panic!("Corrupted heartbeat label on line {}", line_number);
+            }
+            db.heartbeat_labels.push(LabelLog {
+                timestamp: fields[1]
+                    .parse::<u64>()
+                    .unwrap_or_else(|_| panic!("Invalid unix timestamp on line {}", line_number)),
+                // labels may contain spaces
+                label: fields[2..].join(" "),
+            });
+        }
+        "checkin" => {
+            if fields.len() < 3 {
+                panic!("Corrupted check-in on line {}", line_number);
+            }
+            db.check_ins.push(CheckInLog {
+                timestamp: fields[1]
+                    .parse::<u64>()
+                    .unwrap_or_else(|_| panic!("Invalid unix timestamp on line {}", line_number)),
+                username: fields[2].to_owned(),
+                // messages may contain spaces, or be empty
+                message: fields[3..].join(" "),
+            });
+        }
+        "device-token" => {
+            if fields.len() < 5 {
+                panic!("Corrupted device token on line {}", line_number);
+            }
+            db.device_tokens.push(DeviceToken {
+                id: fields[1].to_owned(),
+                created: fields[2]
+                    .parse::<u64>()
+                    .unwrap_or_else(|_| panic!("Invalid unix timestamp on line {}", line_number)),
+                token_hash: fields[3].to_owned(),
+                // names may contain spaces
+                name: fields[4..].join(" "),
+            });
+        }
+        "guestbook" => {
+            if fields.len() < 5 {
+                panic!("Corrupted guestbook entry on line {}", line_number);
+            }
+            // names and messages may contain spaces, so they are separated by a tab
+            let signed: String = fields[4..].join(" ");
+            let Some((name, message)) = signed.split_once('\t') else {
+                panic!("Corrupted guestbook entry on line {}", line_number);
+            };
+            db.guestbook.push(GuestbookEntry {
+                id: fields[1].to_owned(),
+                timestamp: fields[2]
+                    .parse::<u64>()
+                    .unwrap_or_else(|_| panic!("Invalid unix timestamp on line {}", line_number)),
+                approved: match fields[3] {
+                    "approved" => true,
+                    "pending" => false,
+                    _ => panic!("Invalid guestbook entry status on line {}", line_number),
+                },
+                name: name.to_owned(),
+                message: message.to_owned(),
+            });
+        }
+        "private-note" => {
+            // the note is the rest of the line, and may contain spaces
+            db.private_note = Some(fields[1..].join(" "));
+        }
+        "auth-attempt" => {
+            if fields.len() < 6 {
+                panic!("Corrupted authentication attempt on line {}", line_number);
+            }
+            db.auth_attempts.push(AuthAttempt {
+                timestamp: fields[1]
+                    .parse::<u64>()
+                    .unwrap_or_else(|_| panic!("Invalid unix timestamp on line {}", line_number)),
+                address: fields[2].to_owned(),
+                method: fields[3].to_owned(),
+                outcome: fields[4].to_owned(),
+                // labels may contain spaces, or be empty
+                label: fields[5..].join(" "),
+            });
+        }
+        tag => panic!("Unknown record '{}' on line {}", tag, line_number),
+    }
+}
+
 pub fn read_db_file(path: &str) -> Result<String, std::io::Error> {
     let mut db_file: File = File::open(path)?;
     let mut db_contents: String = String::new();
@@ -135,6 +1005,9 @@ pub fn load_database(path: &str) -> Result<Database, std::io::Error> {
             2 => {
                 db.note = line.to_owned();
             }
+            _ if !line.starts_with(|c: char| c.is_ascii_digit()) => {
+                parse_tagged_record(&mut db, line, i + 1);
+            }
             _ => {
                 let line_number: usize = db_contents.lines().count() - i;
 
@@ -177,7 +1050,7 @@ pub fn load_database(path: &str) -> Result<Database, std::io::Error> {
 /// Reads the given file from the disk and returns the parsed [`InitialState`].
 ///
 pub fn get_initial_state_from_disk(path: &str, config: Arc<ServerConfig>) -> InitialState {
-    let db_contents: String = match read_db_file(path) {
+    let db: Database = match load_database(path) {
         Err(err) => {
             eprintln!("Could not load database file.");
             eprintln!("Cannot start without a database file present.");
@@ -186,94 +1059,79 @@ pub fn get_initial_state_from_disk(path: &str, config: Arc<ServerConfig>) -> Ini
         Ok(db) => db,
     };
 
-    // get the initial state from disk
-    let mut state: LifeState = LifeState::default();
-    let mut last_heartbeat: u64 = 0;
-    let mut note: Option<String> = None;
-
-    for (i, line) in db_contents.lines().enumerate() {
-        match i {
-            0 => {
-                if line.is_empty() {
-                    panic!("Invalid db entry on line {}", i + 1);
-                }
-                state = LifeState::from(line);
-            }
-            1 => {
-                last_heartbeat = line
-                    .parse::<u64>()
-                    .unwrap_or_else(|_| panic!("Invalid timestamp in db file; line {}.", i + 1));
-            }
-            2 => {
-                if !line.is_empty() {
-                    note = Some(line.to_owned());
-                }
-            }
-            _ => break,
-        }
-    }
+    let note: Option<String> = match db.note.is_empty() {
+        true => None,
+        false => Some(db.note.clone()),
+    };
 
     // get the latest 5 heartbeats to display
-    let mut heartbeat_display: [HeartbeatDisplay; 5] = [
-        HeartbeatDisplay::default(),
-        HeartbeatDisplay::default(),
-        HeartbeatDisplay::default(),
-        HeartbeatDisplay::default(),
-        HeartbeatDisplay::default(),
-    ];
-
-    for (i, line) in db_contents.lines().rev().enumerate() {
-        if i > 4 {
-            break;
-        }
-        let line_number: usize = db_contents.lines().count() - i;
-
-        // don't read the first 3 lines, which are reserved for other values stored on disk
-        if line_number <= 3 {
-            break;
-        }
-        let split_index: usize = match line.find(" ") {
-            Some(index) => index,
-            None => panic!("Corrupted database entry on line {}", line_number),
-        };
-        let data: (&str, &str) = line.split_at(split_index);
-
-        let mut second_half: String = data.1.to_owned();
-        let _: char = second_half.remove(0);
-
-        let second_split_index: usize = match second_half.find(" ") {
-            Some(index) => index,
-            None => panic!("Corrupted database entry on line {}", line_number),
-        };
-        let address_and_msg: (_, &str) = second_half.split_at(second_split_index);
-
-        let unix_timestamp: i64 = data
-            .0
-            .parse::<i64>()
-            .unwrap_or_else(|_| panic!("Invalid unix timestamp on line {}", line_number));
-
-        let timezone: FixedOffset =
-            FixedOffset::east_opt(config.global.utc_offset * 60 * 60).unwrap();
-
-        let ts: String = timezone
-            .timestamp_opt(unix_timestamp, 0)
-            .unwrap()
-            .to_rfc2822();
+    let mut heartbeat_display: [HeartbeatDisplay; MAX_DISPLAYED_HEARTBEATS] = Default::default();
 
-        heartbeat_display[i].timestamp = ts;
+    let timezone: FixedOffset = FixedOffset::east_opt(config.global.utc_offset * 60 * 60).unwrap();
 
-        let mut message: String = address_and_msg.1.to_owned();
-        let _: char = message.remove(0);
+    for (i, log) in db
+        .heartbeat_history
+        .iter()
+        .rev()
+        .take(MAX_DISPLAYED_HEARTBEATS)
+        .enumerate()
+    {
+        heartbeat_display[i].unix_timestamp = log.timestamp;
+        heartbeat_display[i].timestamp = timezone
+            .timestamp_opt(log.timestamp as i64, 0)
+            .unwrap()
+            .to_rfc2822();
 
-        if !message.is_empty() {
-            heartbeat_display[i].message = message;
+        if !log.message.is_empty() {
+            heartbeat_display[i].message = log.message.clone();
         }
+        heartbeat_display[i].media = db
+            .heartbeat_media
+            .iter()
+            .find(|media| media.timestamp == log.timestamp)
+            .map(|media| media.file_name.clone());
+        heartbeat_display[i].passive = db.passive_heartbeats.contains(&log.timestamp);
+        heartbeat_display[i].label = db
+            .heartbeat_labels
+            .iter()
+            .find(|label| label.timestamp == log.timestamp)
+            .map(|label| label.label.clone());
     }
 
+    // older confirmations were made before the person was heard from again
+    let dead_confirmations: Vec<ConfirmationLog> = db
+        .confirmations
+        .into_iter()
+        .filter(|confirmation| confirmation.timestamp > db.last_heartbeat)
+        .collect();
+
     InitialState {
-        state,
-        last_heartbeat,
+        state: LifeState::from(db.state.as_str()),
+        last_heartbeat: db.last_heartbeat,
+        last_active_heartbeat: db
+            .heartbeat_history
+            .iter()
+            .map(|log| log.timestamp)
+            .filter(|timestamp| !db.passive_heartbeats.contains(timestamp))
+            .max()
+            .unwrap_or_default(),
         note,
+        private_note: db.private_note,
         heartbeat_display,
+        heartbeat_days: heatmap::count_days(&db.heartbeat_history, timezone),
+        dead_confirmations,
+        last_check_in: db.check_ins.last().cloned(),
+        overridden_at: db
+            .transitions
+            .last()
+            .filter(|transition| transition.cause == TransitionCause::Override.to_string())
+            .map(|transition| transition.timestamp),
+        release_countdown: db.release_countdown,
+        registered_users: db.registered_users,
+        guestbook: db
+            .guestbook
+            .into_iter()
+            .filter(|entry| entry.approved)
+            .collect(),
     }
 }
@@ -0,0 +1,252 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::audit;
+use crate::database::{self, Database, DeviceToken, load_database};
+use crate::sources::token_hash;
+use crate::trusted::{Admin, OWNER_USERNAME, generate_token};
+use axum::body::Body;
+use axum::extract::{Json, Path as UrlPath};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use rand::rand_core::{OsRng, TryRngCore};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::MutexGuard;
+use utoipa::ToSchema;
+
+/// Maximum amount of device tokens issued at once.
+const MAX_DEVICE_TOKENS: usize = 100;
+/// Longest device name accepted, in characters.
+const MAX_NAME_LENGTH: usize = 64;
+
+#[derive(Deserialize, ToSchema)]
+pub struct DeviceTokenRequest {
+    /// e.g. "phone", shown as the label of the heartbeats sent with the token.
+    name: String,
+}
+
+/// An issued device token, without the token itself.
+#[derive(Serialize, ToSchema)]
+struct DeviceTokenResponse<'a> {
+    id: &'a str,
+    name: &'a str,
+    /// Unix timestamp
+    created: u64,
+}
+
+impl<'a> From<&'a DeviceToken> for DeviceTokenResponse<'a> {
+    fn from(device_token: &'a DeviceToken) -> Self {
+        Self {
+            id: &device_token.id,
+            name: &device_token.name,
+            created: device_token.created,
+        }
+    }
+}
+
+/// A newly issued device token. The token is only ever shown here.
+#[derive(Serialize, ToSchema)]
+struct IssuedDeviceTokenResponse<'a> {
+    #[serde(flatten)]
+    device_token: DeviceTokenResponse<'a>,
+    /// Sent as `Authorization: Bearer <token>` along with heartbeats.
+    token: String,
+}
+
+/// Get the name of the device the given token was issued to, unless it was revoked.
+pub async fn authenticate(token: &str) -> Result<Option<String>, String> {
+    let hash: String = token_hash(token);
+
+    let _db_guard: MutexGuard<'_, ()> = database::lock().await;
+    let db: Database = load_database(crate::DB_PATH).map_err(|err| err.to_string())?;
+
+    Ok(db
+        .device_tokens
+        .into_iter()
+        .find(|device_token| device_token.token_hash == hash)
+        .map(|device_token| device_token.name))
+}
+
+/// Handles `GET` requests on `/api/v1/admin/tokens`, which lists the issued device tokens.
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/tokens",
+    tag = "admin",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The issued device tokens", body = [DeviceTokenResponse]),
+        (status = 401, description = "Not logged in as the person themself"),
+    )
+)]
+pub async fn list_api(_admin: Admin) -> impl IntoResponse {
+    let db: Database = {
+        let _db_guard: MutexGuard<'_, ()> = database::lock().await;
+
+        match load_database(crate::DB_PATH) {
+            Err(err) => {
+                eprintln!("Could not read the device tokens: {}", err);
+                return Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(Body::default())
+                    .unwrap();
+            }
+            Ok(db) => db,
+        }
+    };
+    let device_tokens: Vec<DeviceTokenResponse> = db.device_tokens.iter().map(Into::into).collect();
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&device_tokens).unwrap()))
+        .unwrap()
+}
+
+/// Handles `POST` requests on `/api/v1/admin/tokens`, which issues a token for a device
+/// to send heartbeats with, as `Authorization: Bearer <token>`, without the password
+/// or a proof of work. Revoking it doesn't affect the password or the other devices.
+///
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/tokens",
+    tag = "admin",
+    request_body = DeviceTokenRequest,
+    security(("bearer" = [])),
+    responses(
+        (status = 201, description = "The token was issued", body = IssuedDeviceTokenResponse),
+        (status = 401, description = "Not logged in as the person themself"),
+        (status = 422, description = "Invalid device name"),
+        (status = 507, description = "Too many device tokens"),
+    )
+)]
+pub async fn create_api(_admin: Admin, Json(req): Json<DeviceTokenRequest>) -> impl IntoResponse {
+    let name: &str = req.name.trim();
+
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH || name.contains(char::is_control)
+    {
+        return Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(Body::from(format!(
+                "Device names must be 1 to {} characters long, on a single line.",
+                MAX_NAME_LENGTH
+            )))
+            .unwrap();
+    }
+    let mut id: [u8; 8] = [0; 8];
+    OsRng.try_fill_bytes(&mut id).expect("OS RNG error.");
+
+    let token: String = generate_token();
+    let device_token: DeviceToken = DeviceToken {
+        id: hex::encode(id),
+        created: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+        token_hash: token_hash(&token),
+        name: name.to_owned(),
+    };
+    let mut full: bool = false;
+
+    let result = database::modify(|db| match db.device_tokens.len() >= MAX_DEVICE_TOKENS {
+        true => full = true,
+        false => db.device_tokens.push(device_token.clone()),
+    })
+    .await;
+
+    if let Err(err) = result {
+        eprintln!("Could not sync the device token to disk: {}", err);
+        return Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::default())
+            .unwrap();
+    }
+    if full {
+        return Response::builder()
+            .status(StatusCode::INSUFFICIENT_STORAGE)
+            .body(Body::default())
+            .unwrap();
+    }
+    audit::record(
+        OWNER_USERNAME,
+        format!(
+            "issued device token {} for '{}'",
+            device_token.id, device_token.name
+        ),
+    )
+    .await;
+
+    let resp: IssuedDeviceTokenResponse = IssuedDeviceTokenResponse {
+        device_token: DeviceTokenResponse::from(&device_token),
+        token,
+    };
+    Response::builder()
+        .status(StatusCode::CREATED)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&resp).unwrap()))
+        .unwrap()
+}
+
+/// Handles `DELETE` requests on `/api/v1/admin/tokens/:id`, which revokes a device token.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/tokens/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "ID of the device token")),
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The token was revoked"),
+        (status = 401, description = "Not logged in as the person themself"),
+        (status = 404, description = "No such device token"),
+    )
+)]
+pub async fn delete_api(_admin: Admin, UrlPath(id): UrlPath<String>) -> impl IntoResponse {
+    let mut revoked: Option<String> = None;
+
+    let result = database::modify(|db| {
+        if let Some(i) = db.device_tokens.iter().position(|token| token.id == id) {
+            revoked = Some(db.device_tokens.remove(i).name);
+        }
+    })
+    .await;
+
+    if let Err(err) = result {
+        eprintln!("Could not sync the device tokens to disk: {}", err);
+        return Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::default())
+            .unwrap();
+    }
+    let Some(name) = revoked else {
+        return Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::default())
+            .unwrap();
+    };
+    audit::record(
+        OWNER_USERNAME,
+        format!("revoked device token {} for '{}'", id, name),
+    )
+    .await;
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::default())
+        .unwrap()
+}
@@ -0,0 +1,202 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::api::{StatusApiResponse, current_status};
+use crate::config::Role;
+use crate::database::{self, Database, load_database};
+use crate::state::ServerState;
+use crate::trusted::{
+    DEFAULT_PAGE_SIZE, HistoryEntry, MAX_PAGE_SIZE, Reader, TrustedUser, history_entry,
+};
+use async_graphql::{Context, EmptyMutation, EmptySubscription, Object, Schema, SimpleObject};
+use axum::body::Body;
+use axum::extract::{Json, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use std::sync::LazyLock;
+use tokio::sync::MutexGuard;
+
+type AmIAliveSchema = Schema<Query, EmptyMutation, EmptySubscription>;
+
+static SCHEMA: LazyLock<AmIAliveSchema> =
+    LazyLock::new(|| Schema::new(Query, EmptyMutation, EmptySubscription));
+
+#[derive(SimpleObject)]
+struct Heartbeat {
+    /// Unix timestamp
+    timestamp: u64,
+    message: String,
+    /// Path of the media sent along with the heartbeat, if any.
+    media: Option<String>,
+    /// Whether the heartbeat was registered from activity seen elsewhere.
+    passive: bool,
+    /// What registered the heartbeat, if labeled. (e.g. a sensor's name)
+    label: Option<String>,
+    /// Where the heartbeat came from, unless it was sent from an IP address.
+    source: Option<String>,
+}
+
+impl From<HistoryEntry<'_>> for Heartbeat {
+    fn from(entry: HistoryEntry<'_>) -> Self {
+        Self {
+            timestamp: entry.timestamp,
+            message: entry.message.to_owned(),
+            media: entry.media,
+            passive: entry.passive,
+            label: entry.label.map(str::to_owned),
+            source: entry.source.map(str::to_owned),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+struct Transition {
+    /// Unix timestamp
+    timestamp: u64,
+    /// Name of the state, e.g. "ProbablyAlive"
+    from: String,
+    /// Name of the state, e.g. "ProbablyAlive"
+    to: String,
+    /// What caused the transition, e.g. "timeout" or "heartbeat"
+    cause: String,
+}
+
+/// Make sure the request was made by a trusted user whose role
+/// grants the permissions of the `required` role.
+fn require(ctx: &Context<'_>, required: Role) -> async_graphql::Result<()> {
+    match ctx.data_opt::<Role>() {
+        None => Err("Not logged in".into()),
+        Some(role) if !role.permits(required) => {
+            Err(format!("Requires the {:?} role", required).into())
+        }
+        Some(_) => Ok(()),
+    }
+}
+
+async fn read_database() -> async_graphql::Result<Database> {
+    let _db_guard: MutexGuard<'_, ()> = database::lock().await;
+
+    load_database(crate::DB_PATH).map_err(|err| {
+        eprintln!("Could not read the database for a GraphQL query: {}", err);
+        "Could not read the database".into()
+    })
+}
+
+pub struct Query;
+
+#[Object]
+impl Query {
+    /// The current state, as served on `/api/v1/status`.
+    async fn status(&self, ctx: &Context<'_>) -> StatusApiResponse {
+        current_status(ctx.data_unchecked::<ServerState>()).await
+    }
+
+    /// The note currently shown along with the state, if any.
+    async fn note(&self, ctx: &Context<'_>) -> Option<String> {
+        ctx.data_unchecked::<ServerState>()
+            .note
+            .lock()
+            .await
+            .clone()
+    }
+
+    /// A page of the heartbeat history, newest first.
+    /// Requires the viewer role, as on `/api/v1/heartbeats`.
+    async fn heartbeats(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(desc = "Starting from 1, the newest heartbeats.")] page: Option<usize>,
+        #[graphql(desc = "Up to 100 heartbeats, 20 by default.")] per_page: Option<usize>,
+    ) -> async_graphql::Result<Vec<Heartbeat>> {
+        require(ctx, Role::Viewer)?;
+
+        let page: usize = page.unwrap_or(1).max(1);
+        let per_page: usize = per_page
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE);
+        let db: Database = read_database().await?;
+
+        Ok(db
+            .heartbeat_history
+            .iter()
+            .rev()
+            .skip((page - 1).saturating_mul(per_page))
+            .take(per_page)
+            .map(|log| history_entry(&db, log).into())
+            .collect())
+    }
+
+    /// Every state transition, newest first. Requires the viewer role.
+    async fn transitions(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Transition>> {
+        require(ctx, Role::Viewer)?;
+
+        let db: Database = read_database().await?;
+
+        Ok(db
+            .transitions
+            .iter()
+            .rev()
+            .map(|transition| Transition {
+                timestamp: transition.timestamp,
+                from: format!("{:?}", transition.from),
+                to: format!("{:?}", transition.to),
+                cause: transition.cause.clone(),
+            })
+            .collect())
+    }
+}
+
+/// Handles `POST` requests on `/api/v1/graphql`, which runs a GraphQL query,
+/// so that dashboards can get the status along with the heartbeat history
+/// and the state transitions in a single request.
+///
+/// Anyone may query the status, unless the instance is private, while the
+/// heartbeat history and the transitions require a trusted user's session token
+/// in the `Authorization: Bearer <token>` HTTP header, as they do on the rest of the API.
+///
+pub async fn graphql_api(
+    _reader: Reader,
+    State(server_state): State<ServerState>,
+    user: Option<TrustedUser>,
+    Json(req): Json<async_graphql::Request>,
+) -> impl IntoResponse {
+    let mut req: async_graphql::Request = req.data(server_state);
+
+    if let Some(user) = user {
+        req = req.data(user.session.role);
+    }
+    let resp: async_graphql::Response = SCHEMA.execute(req).await;
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&resp).unwrap()))
+        .unwrap()
+}
+
+/// Handles `GET` requests on `/api/v1/graphql`, which
+/// serves the GraphQL schema in its definition language.
+///
+pub async fn schema_api() -> impl IntoResponse {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "text/plain; charset=utf-8")
+        .body(Body::from(SCHEMA.sdl()))
+        .unwrap()
+}
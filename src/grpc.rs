@@ -0,0 +1,315 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::api::{
+    StatusApiResponse, authorize_heartbeat, check_heartbeat_ip, clean_heartbeat_text,
+    current_status, register_heartbeat,
+};
+use crate::config::Role;
+use crate::database::{self, Database, load_database};
+use crate::problem;
+use crate::state::ServerState;
+use crate::trusted::{Session, active_session, history_entry, is_reader_key};
+use futures_util::stream::{self, Stream, StreamExt};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::pin::Pin;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{MutexGuard, broadcast};
+use tonic::metadata::MetadataMap;
+use tonic::transport::Server;
+use tonic::{Request, Response};
+
+// the service itself, generated by `build.rs`
+include!(concat!(env!("OUT_DIR"), "/amialive.v1.AmIAlive.rs"));
+
+use am_i_alive_server::{AmIAlive, AmIAliveServer};
+
+// messages of `proto/amialive.proto`, which clients generate their code from
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct StatusRequest {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct StatusResponse {
+    #[prost(string, tag = "1")]
+    pub status: String,
+    #[prost(string, tag = "2")]
+    pub state: String,
+    #[prost(uint32, tag = "3")]
+    pub state_code: u32,
+    #[prost(uint64, tag = "4")]
+    pub last_heartbeat: u64,
+    #[prost(uint64, optional, tag = "5")]
+    pub next_transition: Option<u64>,
+    #[prost(uint64, optional, tag = "6")]
+    pub seconds_until_next_transition: Option<u64>,
+    #[prost(string, tag = "7")]
+    pub active_note: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct HeartbeatRequest {
+    #[prost(string, tag = "1")]
+    pub password: String,
+    #[prost(string, tag = "2")]
+    pub message: String,
+    #[prost(string, optional, tag = "3")]
+    pub note: Option<String>,
+    #[prost(bool, tag = "4")]
+    pub remove_note: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct HeartbeatResponse {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct HistoryRequest {
+    #[prost(bool, tag = "1")]
+    pub follow: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Heartbeat {
+    #[prost(uint64, tag = "1")]
+    pub timestamp: u64,
+    #[prost(string, tag = "2")]
+    pub message: String,
+    #[prost(string, optional, tag = "3")]
+    pub media: Option<String>,
+    #[prost(bool, tag = "4")]
+    pub passive: bool,
+    #[prost(string, optional, tag = "5")]
+    pub label: Option<String>,
+    #[prost(string, optional, tag = "6")]
+    pub source: Option<String>,
+}
+
+impl From<StatusApiResponse> for StatusResponse {
+    fn from(resp: StatusApiResponse) -> Self {
+        StatusResponse {
+            status: resp.status,
+            state: resp.state,
+            state_code: resp.state_code.into(),
+            last_heartbeat: resp.last_heartbeat,
+            next_transition: resp.next_transition,
+            seconds_until_next_transition: resp.seconds_until_next_transition,
+            active_note: resp.active_note,
+        }
+    }
+}
+
+/// Get the token from the `authorization: Bearer <token>` metadata of a call.
+fn bearer_token(metadata: &MetadataMap) -> Option<&str> {
+    let value: &str = metadata.get("authorization")?.to_str().ok()?;
+    let token: &str = value.strip_prefix("Bearer ")?.trim();
+
+    match token.is_empty() {
+        true => None,
+        false => Some(token),
+    }
+}
+
+/// Read the heartbeat history, oldest first, keeping the heartbeats sent after `since`.
+async fn read_history(since: u64) -> Result<Vec<Heartbeat>, tonic::Status> {
+    let _db_guard: MutexGuard<'_, ()> = database::lock().await;
+
+    let db: Database = load_database(crate::DB_PATH).map_err(|err| {
+        eprintln!("Could not read the heartbeat history: {}", err);
+        tonic::Status::internal("Could not read the database")
+    })?;
+
+    Ok(db
+        .heartbeat_history
+        .iter()
+        .filter(|log| log.timestamp > since)
+        .map(|log| {
+            let entry = history_entry(&db, log);
+
+            Heartbeat {
+                timestamp: entry.timestamp,
+                message: entry.message.into(),
+                media: entry.media,
+                passive: entry.passive,
+                label: entry.label.map(str::to_owned),
+                source: entry.source.map(str::to_owned),
+            }
+        })
+        .collect())
+}
+
+struct AmIAliveService {
+    server_state: ServerState,
+}
+
+#[tonic::async_trait]
+impl AmIAlive for AmIAliveService {
+    async fn get_status(
+        &self,
+        request: Request<StatusRequest>,
+    ) -> Result<Response<StatusResponse>, tonic::Status> {
+        if self.server_state.config.global.private {
+            let may_read: bool = match bearer_token(request.metadata()) {
+                Some(key) => is_reader_key(&self.server_state, key).await,
+                None => false,
+            };
+            if !may_read {
+                return Err(tonic::Status::unauthenticated("This instance is private."));
+            }
+        }
+        let status: StatusApiResponse = current_status(&self.server_state).await;
+
+        Ok(Response::new(status.into()))
+    }
+
+    async fn send_heartbeat(
+        &self,
+        request: Request<HeartbeatRequest>,
+    ) -> Result<Response<HeartbeatResponse>, tonic::Status> {
+        let started: Instant = Instant::now();
+
+        // the service is served directly, not behind the reverse proxy
+        let ip: IpAddr = request
+            .remote_addr()
+            .map_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED), |addr| addr.ip());
+        let req: HeartbeatRequest = request.into_inner();
+        let now: u64 = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        if let Err(problem) = check_heartbeat_ip(&self.server_state, ip) {
+            return Err(problem::uniform(&self.server_state, started, problem)
+                .await
+                .into());
+        }
+        // there are no challenges to solve over gRPC, so only the rate limit applies
+        let label: Option<String> =
+            match authorize_heartbeat(&self.server_state, ip, &req.password, None, None, now).await
+            {
+                Ok(label) => label,
+                Err(problem) => {
+                    return Err(problem::uniform(&self.server_state, started, problem)
+                        .await
+                        .into());
+                }
+            };
+        let (message, updated_note): (String, String) =
+            clean_heartbeat_text(&req.message, req.note.as_deref().unwrap_or_default())?;
+
+        let mut locked_note: MutexGuard<'_, Option<String>> = self.server_state.note.lock().await;
+
+        if req.remove_note {
+            let _: Option<String> = locked_note.take();
+        } else if !updated_note.is_empty() {
+            let _: Option<String> = locked_note.replace(updated_note);
+        }
+        drop(locked_note);
+
+        register_heartbeat(
+            &self.server_state,
+            now,
+            ip.to_string(),
+            message,
+            None,
+            false,
+            label,
+        )
+        .await
+        .map_err(tonic::Status::internal)?;
+
+        println!("Heartbeat received over gRPC.");
+        Ok(Response::new(HeartbeatResponse {}))
+    }
+
+    type StreamHistoryStream =
+        Pin<Box<dyn Stream<Item = Result<Heartbeat, tonic::Status>> + Send + 'static>>;
+
+    async fn stream_history(
+        &self,
+        request: Request<HistoryRequest>,
+    ) -> Result<Response<Self::StreamHistoryStream>, tonic::Status> {
+        let session: Option<Session> = match bearer_token(request.metadata()) {
+            Some(token) => active_session(&self.server_state, token).await,
+            None => None,
+        };
+        let Some(session) = session else {
+            return Err(tonic::Status::unauthenticated("Not logged in"));
+        };
+        if !session.role.permits(Role::Viewer) {
+            return Err(tonic::Status::permission_denied("Requires the viewer role"));
+        }
+        // subscribe first, so no heartbeat between reading and subscribing is missed
+        let events: broadcast::Receiver<String> = self.server_state.status_events.subscribe();
+        let history: Vec<Heartbeat> = read_history(0).await?;
+        let last_sent: u64 = history.last().map_or(0, |heartbeat| heartbeat.timestamp);
+
+        let history = stream::iter(history.into_iter().map(Ok));
+
+        if !request.get_ref().follow {
+            return Ok(Response::new(Box::pin(history)));
+        }
+        // every heartbeat changes the status, so read the new ones whenever it does.
+        // late heartbeats, older than the last one sent, aren't sent again.
+        let new_heartbeats =
+            stream::unfold((events, last_sent), |(mut events, last_sent)| async move {
+                loop {
+                    match events.recv().await {
+                        Err(broadcast::error::RecvError::Closed) => return None,
+                        // missed changes are caught up on all the same
+                        Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => (),
+                    }
+                    let heartbeats: Vec<Heartbeat> = match read_history(last_sent).await {
+                        Err(status) => return Some((vec![Err(status)], (events, last_sent))),
+                        Ok(heartbeats) => heartbeats,
+                    };
+                    if let Some(last) = heartbeats.last() {
+                        let last_sent: u64 = last.timestamp;
+                        let heartbeats = heartbeats.into_iter().map(Ok).collect();
+
+                        return Some((heartbeats, (events, last_sent)));
+                    }
+                }
+            })
+            .flat_map(stream::iter);
+
+        Ok(Response::new(Box::pin(history.chain(new_heartbeats))))
+    }
+}
+
+/// Serve the gRPC service on its configured address, forever.
+pub async fn serve(server_state: ServerState) {
+    let Some(config) = server_state.config.grpc.clone() else {
+        return;
+    };
+    let address: SocketAddr = config
+        .address
+        .parse()
+        .expect("The gRPC address must be an IP address and port.");
+    let service: AmIAliveService = AmIAliveService { server_state };
+
+    println!("Serving gRPC on {}.", address);
+
+    if let Err(err) = Server::builder()
+        .add_service(AmIAliveServer::new(service))
+        .serve(address)
+        .await
+    {
+        eprintln!("Could not serve gRPC: {}", err);
+    }
+}
@@ -0,0 +1,370 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::api::{ClientIp, PowSolution};
+use crate::audit;
+use crate::config::Role;
+use crate::database::{self, Database, GuestbookEntry, load_database};
+use crate::i18n::Catalog;
+use crate::pow::check_pow_solution;
+use crate::problem::Problem;
+use crate::state::{LifeState, ServerState};
+use crate::templating::datetime;
+use crate::trusted::{Reader, TrustedUser};
+use axum::body::Body;
+use axum::extract::{Json, Path as UrlPath, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use chrono::{FixedOffset, TimeZone};
+use rand::rand_core::{OsRng, TryRngCore};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::MutexGuard;
+use utoipa::ToSchema;
+
+/// Longest name accepted, in characters.
+pub const MAX_NAME_LENGTH: usize = 64;
+/// Longest message accepted, in characters.
+pub const MAX_MESSAGE_LENGTH: usize = 500;
+/// Most entries awaiting review at once, so that spam can't fill the database.
+const MAX_PENDING_ENTRIES: usize = 100;
+
+/// Approved guestbook entry, as shown on the status page.
+#[derive(Serialize)]
+pub struct GuestbookDisplay {
+    pub name: String,
+    pub message: String,
+    pub timestamp: String,
+    /// RFC 3339, see [`datetime`]
+    pub datetime: String,
+}
+
+/// The approved guestbook entries, newest first, in the owner's time zone.
+pub fn display(
+    entries: &[GuestbookEntry],
+    timezone: FixedOffset,
+    t: &Catalog,
+) -> Vec<GuestbookDisplay> {
+    entries
+        .iter()
+        .rev()
+        .map(|entry| GuestbookDisplay {
+            name: match entry.name.is_empty() {
+                true => t.anonymous.into(),
+                false => entry.name.clone(),
+            },
+            message: entry.message.clone(),
+            timestamp: timezone
+                .timestamp_opt(entry.timestamp as i64, 0)
+                .unwrap()
+                .to_rfc2822(),
+            datetime: datetime(timezone, entry.timestamp),
+        })
+        .collect()
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct GuestbookRequest {
+    /// Name to sign the message with, may be left empty.
+    #[serde(default)]
+    name: String,
+    /// e.g. "I'll miss our walks."
+    message: String,
+    pow: PowSolution,
+}
+
+/// Handles `POST` requests on `/api/v1/guestbook`, where visitors leave a message once
+/// the person is dead. Messages are only shown on the status page once a trusted user
+/// approved them, and require a solved PoW challenge, like a heartbeat.
+///
+#[utoipa::path(
+    post,
+    path = "/api/v1/guestbook",
+    tag = "guestbook",
+    request_body = GuestbookRequest,
+    responses(
+        (status = 202, description = "The message awaits review by a trusted user"),
+        (status = 404, description = "The guestbook is only open once the person is dead", body = Problem, content_type = "application/problem+json"),
+        (status = 406, description = "The proof of work is invalid or expired", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid name or message", body = Problem, content_type = "application/problem+json"),
+        (status = 503, description = "Too many messages await review", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn sign_api(
+    _reader: Reader,
+    ClientIp(ip): ClientIp,
+    State(server_state): State<ServerState>,
+    Json(req): Json<GuestbookRequest>,
+) -> impl IntoResponse {
+    if **server_state.state.lock().await != LifeState::Dead {
+        return Problem::new(StatusCode::NOT_FOUND, "guestbook_closed")
+            .detail("The guestbook is only open once the person is dead.")
+            .into_response();
+    }
+    if let Err(err) = check_pow_solution(server_state.pow_state.clone(), ip, req.pow, None).await {
+        return Problem::from(err).into_response();
+    }
+    // entries are stored one per line, with a tab between the name and the message
+    let name: String = req.name.replace(char::is_control, " ").trim().to_owned();
+    let message: String = req.message.replace(char::is_control, " ").trim().to_owned();
+
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return Problem::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_name")
+            .detail(format!(
+                "Names must be at most {} characters long.",
+                MAX_NAME_LENGTH
+            ))
+            .into_response();
+    }
+    if message.is_empty() || message.chars().count() > MAX_MESSAGE_LENGTH {
+        return Problem::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_message")
+            .detail(format!(
+                "Messages must be 1 to {} characters long.",
+                MAX_MESSAGE_LENGTH
+            ))
+            .into_response();
+    }
+    let mut id: [u8; 8] = [0; 8];
+    OsRng.try_fill_bytes(&mut id).expect("OS RNG error.");
+
+    let entry: GuestbookEntry = GuestbookEntry {
+        id: hex::encode(id),
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+        approved: false,
+        name,
+        message,
+    };
+    let mut full: bool = false;
+
+    let result = database::modify(|db| {
+        let pending: usize = db.guestbook.iter().filter(|entry| !entry.approved).count();
+
+        match pending >= MAX_PENDING_ENTRIES {
+            true => full = true,
+            false => db.guestbook.push(entry),
+        }
+    })
+    .await;
+
+    if let Err(err) = result {
+        eprintln!("Could not sync the guestbook entry to disk: {}", err);
+        return Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::default())
+            .unwrap();
+    }
+    if full {
+        return Problem::new(StatusCode::SERVICE_UNAVAILABLE, "guestbook_full")
+            .detail("Too many messages await review, try again later.")
+            .into_response();
+    }
+    println!("A visitor signed the guestbook.");
+
+    Response::builder()
+        .status(StatusCode::ACCEPTED)
+        .body(Body::default())
+        .unwrap()
+}
+
+/// Handles `GET` requests on `/api/v1/guestbook`, which lists every guestbook entry,
+/// including the ones awaiting review, oldest first.
+///
+#[utoipa::path(
+    get,
+    path = "/api/v1/guestbook",
+    tag = "guestbook",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The guestbook entries", body = [GuestbookEntry]),
+        (status = 401, description = "Not logged in"),
+        (status = 403, description = "Requires the viewer role"),
+    )
+)]
+pub async fn list_api(user: TrustedUser) -> impl IntoResponse {
+    if let Err(forbidden) = user.require(Role::Viewer) {
+        return forbidden.into_response();
+    }
+    let db: Database = {
+        let _db_guard: MutexGuard<'_, ()> = database::lock().await;
+
+        match load_database(crate::DB_PATH) {
+            Err(err) => {
+                eprintln!("Could not read the guestbook: {}", err);
+                return Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(Body::default())
+                    .unwrap();
+            }
+            Ok(db) => db,
+        }
+    };
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&db.guestbook).unwrap()))
+        .unwrap()
+}
+
+/// Handles `POST` requests on `/api/v1/guestbook/:id/approve`, which shows
+/// a guestbook entry on the status page.
+///
+#[utoipa::path(
+    post,
+    path = "/api/v1/guestbook/{id}/approve",
+    tag = "guestbook",
+    params(("id" = String, Path, description = "ID of the guestbook entry")),
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The entry was approved"),
+        (status = 401, description = "Not logged in"),
+        (status = 403, description = "Requires the verifier role"),
+        (status = 404, description = "No such guestbook entry"),
+    )
+)]
+pub async fn approve_api(
+    State(server_state): State<ServerState>,
+    user: TrustedUser,
+    UrlPath(id): UrlPath<String>,
+) -> impl IntoResponse {
+    if let Err(forbidden) = user.require(Role::Verifier) {
+        return forbidden.into_response();
+    }
+    let mut approved: Option<GuestbookEntry> = None;
+
+    let result = database::modify(|db| {
+        if let Some(entry) = db.guestbook.iter_mut().find(|entry| entry.id == id) {
+            entry.approved = true;
+            approved = Some(entry.clone());
+        }
+    })
+    .await;
+
+    if let Err(err) = result {
+        eprintln!("Could not sync the guestbook to disk: {}", err);
+        return Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::default())
+            .unwrap();
+    }
+    let Some(entry) = approved else {
+        return Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::default())
+            .unwrap();
+    };
+    let mut guestbook: MutexGuard<'_, Vec<GuestbookEntry>> = server_state.guestbook.lock().await;
+
+    // approving an entry twice doesn't show it twice
+    if !guestbook.iter().any(|shown| shown.id == entry.id) {
+        guestbook.push(entry);
+        guestbook.sort_by_key(|entry| entry.timestamp);
+        changed(&server_state).await;
+    }
+    drop(guestbook);
+
+    audit::record(
+        &user.session.username,
+        format!("approved guestbook entry {}", id),
+    )
+    .await;
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::default())
+        .unwrap()
+}
+
+/// Handles `DELETE` requests on `/api/v1/guestbook/:id`, which deletes a guestbook
+/// entry, whether it was approved or not.
+///
+#[utoipa::path(
+    delete,
+    path = "/api/v1/guestbook/{id}",
+    tag = "guestbook",
+    params(("id" = String, Path, description = "ID of the guestbook entry")),
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The entry was deleted"),
+        (status = 401, description = "Not logged in"),
+        (status = 403, description = "Requires the verifier role"),
+        (status = 404, description = "No such guestbook entry"),
+    )
+)]
+pub async fn delete_api(
+    State(server_state): State<ServerState>,
+    user: TrustedUser,
+    UrlPath(id): UrlPath<String>,
+) -> impl IntoResponse {
+    if let Err(forbidden) = user.require(Role::Verifier) {
+        return forbidden.into_response();
+    }
+    let mut deleted: bool = false;
+
+    let result = database::modify(|db| {
+        if let Some(i) = db.guestbook.iter().position(|entry| entry.id == id) {
+            db.guestbook.remove(i);
+            deleted = true;
+        }
+    })
+    .await;
+
+    if let Err(err) = result {
+        eprintln!("Could not sync the guestbook to disk: {}", err);
+        return Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::default())
+            .unwrap();
+    }
+    if !deleted {
+        return Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::default())
+            .unwrap();
+    }
+    let mut guestbook: MutexGuard<'_, Vec<GuestbookEntry>> = server_state.guestbook.lock().await;
+
+    if let Some(i) = guestbook.iter().position(|shown| shown.id == id) {
+        guestbook.remove(i);
+        changed(&server_state).await;
+    }
+    drop(guestbook);
+
+    audit::record(
+        &user.session.username,
+        format!("deleted guestbook entry {}", id),
+    )
+    .await;
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::default())
+        .unwrap()
+}
+
+/// The status page changed along with the guestbook, so caches must revalidate it.
+async fn changed(server_state: &ServerState) {
+    *server_state.status_changed_at.lock().await = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+}
@@ -0,0 +1,174 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::api::{
+    ClientIp, authorize_heartbeat, check_heartbeat_ip, clean_heartbeat_text, register_heartbeat,
+};
+use crate::problem::{self, Problem};
+use crate::state::ServerState;
+use crate::templating::heartbeat_page;
+use crate::web_session::{WebSession, sign, verify};
+use axum::extract::{Form, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::Response;
+use serde::Deserialize;
+use std::net::IpAddr;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::MutexGuard;
+
+/// Least time between loading the page and sending its form, in seconds,
+/// standing in for the time a proof of work would have taken.
+const FORM_MIN_AGE: u64 = 3;
+/// Longest time between loading the page and sending its form, in seconds.
+const FORM_MAX_AGE: u64 = 10 * 60;
+
+/// How a heartbeat sent from the form went, shown on the page.
+#[derive(Clone, Copy)]
+pub struct Feedback {
+    pub sent: bool,
+    pub text: &'static str,
+}
+
+#[derive(Deserialize)]
+pub struct HeartbeatForm {
+    form_token: String,
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    updated_note: String,
+    /// A checkbox, only sent when checked.
+    #[serde(default)]
+    remove_current_note: Option<String>,
+    password: String,
+}
+
+/// Token of the `/heartbeat` form, bound to the address that loaded the page and to
+/// when it did, so that a heartbeat sent without a proof of work still takes loading
+/// the page first, and waiting for a bit, see [`FORM_MIN_AGE`]
+///
+pub fn form_token(server_state: &ServerState, ip: IpAddr, now: u64) -> String {
+    let value: String = format!("{} {}", ip, now);
+
+    format!("{}.{}", now, sign(server_state, "heartbeat form", &value))
+}
+
+/// Whether the given form token was issued to the given address, long enough ago, but not too long.
+fn check_form_token(server_state: &ServerState, ip: IpAddr, token: &str, now: u64) -> bool {
+    let Some((issued_at, signature)) = token.split_once('.') else {
+        return false;
+    };
+    let Ok(issued_at) = issued_at.parse::<u64>() else {
+        return false;
+    };
+    let value: String = format!("{} {}", ip, issued_at);
+
+    verify(server_state, "heartbeat form", &value, signature)
+        && issued_at + FORM_MIN_AGE <= now
+        && now <= issued_at + FORM_MAX_AGE
+}
+
+/// What to tell the person when their heartbeat was refused with the given problem.
+fn refusal(problem: &Problem) -> &'static str {
+    match problem.code() {
+        "bad_password" => "Wrong password or TOTP code.",
+        "rate_limited" => "Blocked after too many failed attempts, try again later.",
+        "ip_not_allowed" => "Heartbeats can't be sent from this address.",
+        "message_too_long" => "The message is too long.",
+        "note_too_long" => "The note is too long.",
+        "refused" => "The heartbeat was refused.",
+        _ => "The heartbeat could not be sent.",
+    }
+}
+
+/// Handles `POST` requests on `/heartbeat`, where the page's form is sent by browsers
+/// without JavaScript, which can't solve a proof of work. Only if `no_js_fallback` is
+/// enabled under `[pow]`. The password is still needed, and wrong ones still block the
+/// address, but the proof of work is replaced by the form token, see [`form_token`]
+///
+pub async fn submit_handler(
+    web_session: Option<WebSession>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+    State(server_state): State<ServerState>,
+    Form(form): Form<HeartbeatForm>,
+) -> Response {
+    let started: Instant = Instant::now();
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let refused = |text: &'static str| Some(Feedback { sent: false, text });
+
+    let feedback: Option<Feedback> = if !server_state.config.pow.no_js_fallback {
+        refused("Sending heartbeats without JavaScript is not enabled.")
+    } else if !check_form_token(&server_state, ip, &form.form_token, now) {
+        refused("The form expired, or was sent too quickly. Reload the page and try again.")
+    } else {
+        match send(&server_state, ip, &form, now).await {
+            Ok(()) => Some(Feedback {
+                sent: true,
+                text: "Your heartbeat was sent!",
+            }),
+            Err(problem) => refused(refusal(
+                &problem::uniform(&server_state, started, problem).await,
+            )),
+        }
+    };
+    heartbeat_page(&server_state, web_session, &headers, ip, feedback).await
+}
+
+/// Authenticate the heartbeat sent from the form, without a proof of work, and register it.
+async fn send(
+    server_state: &ServerState,
+    ip: IpAddr,
+    form: &HeartbeatForm,
+    now: u64,
+) -> Result<(), Problem> {
+    check_heartbeat_ip(server_state, ip)?;
+
+    let label: Option<String> =
+        authorize_heartbeat(server_state, ip, &form.password, None, None, now).await?;
+
+    let (message, updated_note): (String, String) =
+        clean_heartbeat_text(&form.message, &form.updated_note)?;
+
+    let mut locked_note: MutexGuard<'_, Option<String>> = server_state.note.lock().await;
+
+    if form.remove_current_note.is_some() {
+        let _: Option<String> = locked_note.take();
+    } else if !updated_note.is_empty() {
+        let _: Option<String> = locked_note.replace(updated_note);
+    }
+    drop(locked_note);
+
+    register_heartbeat(
+        server_state,
+        now,
+        ip.to_string(),
+        message,
+        None,
+        false,
+        label,
+    )
+    .await
+    .map_err(|reason| {
+        Problem::new(StatusCode::INTERNAL_SERVER_ERROR, "database_error").detail(reason)
+    })
+}
@@ -0,0 +1,97 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::database::HeartbeatLog;
+use crate::i18n::Catalog;
+use chrono::{Datelike, Days, FixedOffset, NaiveDate, TimeZone};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Weeks shown on the calendar, a year's worth.
+const CALENDAR_WEEKS: u64 = 53;
+/// Shades of the days with heartbeats, the busiest being the darkest.
+const LEVELS: u32 = 4;
+
+/// Number of heartbeats of each day, in the configured time zone.
+pub type HeartbeatDays = BTreeMap<NaiveDate, u32>;
+
+/// Day a Unix timestamp falls on in the given time zone.
+pub fn local_date(timezone: FixedOffset, timestamp: u64) -> NaiveDate {
+    timezone
+        .timestamp_opt(timestamp as i64, 0)
+        .unwrap()
+        .date_naive()
+}
+
+/// Count the heartbeats of each day of the heartbeat history.
+pub fn count_days(heartbeat_history: &[HeartbeatLog], timezone: FixedOffset) -> HeartbeatDays {
+    let mut days: HeartbeatDays = BTreeMap::new();
+
+    for log in heartbeat_history.iter() {
+        *days.entry(local_date(timezone, log.timestamp)).or_default() += 1;
+    }
+    days
+}
+
+#[derive(Serialize)]
+pub struct CalendarDay {
+    /// e.g. "2026-10-16"
+    pub date: String,
+    pub heartbeats: u32,
+    /// 0 without heartbeats, up to [`LEVELS`] on the busiest days.
+    pub level: u32,
+    /// e.g. "3 heartbeats on 2026-10-16"
+    pub title: String,
+}
+
+/// Weeks of the heartbeat calendar, from Sunday to Saturday, the
+/// last one being the current week, which ends with `today`.
+///
+pub fn calendar(days: &HeartbeatDays, today: NaiveDate, t: &Catalog) -> Vec<Vec<CalendarDay>> {
+    let this_sunday: NaiveDate = today - Days::new(today.weekday().num_days_from_sunday().into());
+    let first_day: NaiveDate = this_sunday - Days::new((CALENDAR_WEEKS - 1) * 7);
+
+    // shades are relative to the busiest day shown
+    let busiest: u32 = days
+        .range(first_day..=today)
+        .map(|(_, heartbeats)| *heartbeats)
+        .max()
+        .unwrap_or(0);
+
+    let mut weeks: Vec<Vec<CalendarDay>> = vec![];
+
+    for date in first_day.iter_days().take_while(|date| *date <= today) {
+        if date.weekday().num_days_from_sunday() == 0 {
+            weeks.push(vec![]);
+        }
+        let heartbeats: u32 = days.get(&date).copied().unwrap_or(0);
+        let date: String = date.format("%Y-%m-%d").to_string();
+
+        weeks.last_mut().unwrap().push(CalendarDay {
+            title: t.heartbeats_on(heartbeats, &date),
+            date,
+            heartbeats,
+            level: match heartbeats {
+                0 => 0,
+                _ => (heartbeats * LEVELS).div_ceil(busiest),
+            },
+        });
+    }
+    weeks
+}
@@ -0,0 +1,292 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::state::LifeState;
+use axum::http::HeaderMap;
+use serde::Serialize;
+
+/// Strings of the status page in one language. `{0}` stands for the name
+/// of the person, or the number of hours or heartbeats, and `{1}` for a date.
+///
+#[derive(Serialize)]
+pub struct Catalog {
+    /// Primary language subtag, e.g. "en"
+    pub lang: &'static str,
+    pub title: &'static str,
+    pub alive: &'static str,
+    pub probably_alive: &'static str,
+    pub missing_or_dead: &'static str,
+    pub incapacitated: &'static str,
+    pub dead: &'static str,
+    pub heartbeat_history: &'static str,
+    pub timestamp: &'static str,
+    pub message: &'static str,
+    pub passive: &'static str,
+    pub via: &'static str,
+    pub proof_of_life: &'static str,
+    pub note_from: &'static str,
+    pub seen_by: &'static str,
+    /// Put before the date of a check-in.
+    pub on: &'static str,
+    pub hour_ago: &'static str,
+    pub hours_ago: &'static str,
+    pub calendar: &'static str,
+    pub heartbeat_on: &'static str,
+    pub heartbeats_on: &'static str,
+    /// HTML
+    pub source_code: &'static str,
+    /// HTML
+    pub send_heartbeat: &'static str,
+    pub notify_me: &'static str,
+    pub guestbook: &'static str,
+    pub guestbook_prompt: &'static str,
+    pub your_name: &'static str,
+    pub sign_guestbook: &'static str,
+    /// Shown once a message was sent, until it's approved.
+    pub guestbook_thanks: &'static str,
+    pub anonymous: &'static str,
+    pub in_memory_of: &'static str,
+    /// Put before the time the status was last loaded, when shown without a connection.
+    pub offline_since: &'static str,
+    /// `{0}` is the state the current one degrades to, and `{1}` the time left, e.g. "7h 12m".
+    pub degrades_in: &'static str,
+}
+
+impl Catalog {
+    pub fn state_name(&self, state: LifeState) -> &'static str {
+        match state {
+            LifeState::Alive => self.alive,
+            LifeState::ProbablyAlive => self.probably_alive,
+            LifeState::MissingOrDead => self.missing_or_dead,
+            LifeState::Incapacitated => self.incapacitated,
+            LifeState::Dead => self.dead,
+        }
+    }
+
+    /// e.g. "5 hours ago"
+    pub fn hours_ago(&self, hours: u32) -> String {
+        match hours {
+            1 => self.hour_ago,
+            _ => self.hours_ago,
+        }
+        .replace("{0}", &hours.to_string())
+    }
+
+    /// e.g. "3 heartbeats on 2026-10-16"
+    pub fn heartbeats_on(&self, heartbeats: u32, date: &str) -> String {
+        match heartbeats {
+            1 => self.heartbeat_on,
+            _ => self.heartbeats_on,
+        }
+        .replace("{0}", &heartbeats.to_string())
+        .replace("{1}", date)
+    }
+}
+
+/// Languages the status page is available in.
+pub static CATALOGS: [Catalog; 4] = [
+    Catalog {
+        lang: "en",
+        title: "Is {0} Alive?",
+        alive: "ALIVE",
+        probably_alive: "PROBABLY ALIVE",
+        missing_or_dead: "MISSING OR DEAD",
+        incapacitated: "ALIVE BUT INCAPACITATED",
+        dead: "DEAD",
+        heartbeat_history: "Heartbeat History",
+        timestamp: "Timestamp",
+        message: "Message",
+        passive: "(passive)",
+        via: "via",
+        proof_of_life: "(proof of life)",
+        note_from: "Note from {0}:",
+        seen_by: "Seen by a trusted contact",
+        on: "on",
+        hour_ago: "{0} hour ago",
+        hours_ago: "{0} hours ago",
+        calendar: "Heartbeats in the Past Year",
+        heartbeat_on: "{0} heartbeat on {1}",
+        heartbeats_on: "{0} heartbeats on {1}",
+        source_code: "\"Am I Alive?\" is free and open source software available on \
+            <a href=\"https://gitlab.com/maxrdz/am-i-alive\">GitLab</a>.",
+        send_heartbeat: "Looking for the link to <a href=\"/heartbeat\">send a heartbeat</a>?",
+        notify_me: "Notify me when {0}'s status changes.",
+        guestbook: "Guestbook",
+        guestbook_prompt: "Leave a message in memory of {0}.",
+        your_name: "Your name (optional)",
+        sign_guestbook: "Sign the Guestbook",
+        guestbook_thanks: "Thank you. Your message will appear once it has been reviewed.",
+        anonymous: "Anonymous",
+        in_memory_of: "In Loving Memory of {0}",
+        offline_since: "You are offline. This is the status as of",
+        degrades_in: "Becomes {0} in {1}, unless a heartbeat arrives.",
+    },
+    Catalog {
+        lang: "es",
+        title: "¿{0} sigue con vida?",
+        alive: "CON VIDA",
+        probably_alive: "PROBABLEMENTE CON VIDA",
+        missing_or_dead: "EN PARADERO DESCONOCIDO O SIN VIDA",
+        incapacitated: "CON VIDA PERO SIN PODER RESPONDER",
+        dead: "SIN VIDA",
+        heartbeat_history: "Historial de latidos",
+        timestamp: "Fecha",
+        message: "Mensaje",
+        passive: "(pasivo)",
+        via: "vía",
+        proof_of_life: "(prueba de vida)",
+        note_from: "Nota de {0}:",
+        seen_by: "Un contacto de confianza tuvo noticias",
+        on: "el",
+        hour_ago: "hace {0} hora",
+        hours_ago: "hace {0} horas",
+        calendar: "Latidos del último año",
+        heartbeat_on: "{0} latido el {1}",
+        heartbeats_on: "{0} latidos el {1}",
+        source_code: "«Am I Alive?» es software libre y de código abierto disponible en \
+            <a href=\"https://gitlab.com/maxrdz/am-i-alive\">GitLab</a>.",
+        send_heartbeat: "¿Buscas el enlace para <a href=\"/heartbeat\">enviar un latido</a>?",
+        notify_me: "Avísame cuando cambie el estado de {0}.",
+        guestbook: "Libro de condolencias",
+        guestbook_prompt: "Deja un mensaje en memoria de {0}.",
+        your_name: "Tu nombre (opcional)",
+        sign_guestbook: "Firmar el libro",
+        guestbook_thanks: "Gracias. Tu mensaje aparecerá una vez revisado.",
+        anonymous: "Anónimo",
+        in_memory_of: "En memoria de {0}",
+        offline_since: "Sin conexión. Este es el estado del",
+        degrades_in: "Pasa a {0} en {1}, a menos que llegue un latido.",
+    },
+    Catalog {
+        lang: "fr",
+        title: "{0} : toujours en vie ?",
+        alive: "EN VIE",
+        probably_alive: "PROBABLEMENT EN VIE",
+        missing_or_dead: "DISPARITION OU DÉCÈS",
+        incapacitated: "EN VIE MAIS DANS L'INCAPACITÉ",
+        dead: "DÉCÈS",
+        heartbeat_history: "Historique des battements",
+        timestamp: "Date",
+        message: "Message",
+        passive: "(passif)",
+        via: "via",
+        proof_of_life: "(preuve de vie)",
+        note_from: "Note de {0} :",
+        seen_by: "Un contact de confiance a eu des nouvelles",
+        on: "le",
+        hour_ago: "il y a {0} heure",
+        hours_ago: "il y a {0} heures",
+        calendar: "Battements de l'année écoulée",
+        heartbeat_on: "{0} battement le {1}",
+        heartbeats_on: "{0} battements le {1}",
+        source_code: "« Am I Alive? » est un logiciel libre et open source disponible sur \
+            <a href=\"https://gitlab.com/maxrdz/am-i-alive\">GitLab</a>.",
+        send_heartbeat: "Vous cherchez le lien pour <a href=\"/heartbeat\">envoyer un battement</a> ?",
+        notify_me: "Me prévenir quand le statut de {0} change.",
+        guestbook: "Livre de condoléances",
+        guestbook_prompt: "Laissez un message en mémoire de {0}.",
+        your_name: "Votre nom (facultatif)",
+        sign_guestbook: "Signer le livre",
+        guestbook_thanks: "Merci. Votre message apparaîtra une fois relu.",
+        anonymous: "Anonyme",
+        in_memory_of: "À la mémoire de {0}",
+        offline_since: "Hors ligne. Voici le statut du",
+        degrades_in: "Passe à {0} dans {1}, sauf si un battement arrive.",
+    },
+    Catalog {
+        lang: "de",
+        title: "Lebt {0} noch?",
+        alive: "LEBT",
+        probably_alive: "LEBT WAHRSCHEINLICH",
+        missing_or_dead: "VERMISST ODER TOT",
+        incapacitated: "LEBT, ABER HANDLUNGSUNFÄHIG",
+        dead: "TOT",
+        heartbeat_history: "Herzschlag-Verlauf",
+        timestamp: "Zeitpunkt",
+        message: "Nachricht",
+        passive: "(passiv)",
+        via: "über",
+        proof_of_life: "(Lebenszeichen)",
+        note_from: "Notiz von {0}:",
+        seen_by: "Von einer Vertrauensperson gesehen",
+        on: "am",
+        hour_ago: "vor {0} Stunde",
+        hours_ago: "vor {0} Stunden",
+        calendar: "Herzschläge im letzten Jahr",
+        heartbeat_on: "{0} Herzschlag am {1}",
+        heartbeats_on: "{0} Herzschläge am {1}",
+        source_code: "„Am I Alive?“ ist freie und quelloffene Software, verfügbar auf \
+            <a href=\"https://gitlab.com/maxrdz/am-i-alive\">GitLab</a>.",
+        send_heartbeat: "Suchst du den Link, um <a href=\"/heartbeat\">einen Herzschlag zu senden</a>?",
+        notify_me: "Benachrichtige mich, wenn sich der Status von {0} ändert.",
+        guestbook: "Kondolenzbuch",
+        guestbook_prompt: "Hinterlasse eine Nachricht im Gedenken an {0}.",
+        your_name: "Dein Name (optional)",
+        sign_guestbook: "Ins Kondolenzbuch eintragen",
+        guestbook_thanks: "Danke. Deine Nachricht erscheint, sobald sie geprüft wurde.",
+        anonymous: "Anonym",
+        in_memory_of: "In liebevoller Erinnerung an {0}",
+        offline_since: "Keine Verbindung. Das ist der Status vom",
+        degrades_in: "Wird in {1} zu {0}, außer ein Herzschlag kommt an.",
+    },
+];
+
+/// The catalog of the given language, e.g. "en", if there's one.
+pub fn catalog(lang: &str) -> Option<&'static Catalog> {
+    CATALOGS
+        .iter()
+        .find(|catalog| catalog.lang.eq_ignore_ascii_case(lang))
+}
+
+/// Picks the language the visitor prefers most out of those there's a
+/// catalog for, as told by the `Accept-Language` HTTP header, e.g.
+/// "fr-CH, fr;q=0.9, en;q=0.8". Only the primary language subtag is
+/// matched. Falls back to the `default` language.
+///
+pub fn negotiate(headers: &HeaderMap, default: &'static Catalog) -> &'static Catalog {
+    let Some(accept_language) = headers
+        .get("Accept-Language")
+        .and_then(|value| value.to_str().ok())
+    else {
+        return default;
+    };
+
+    let mut ranges: Vec<(&str, f32)> = accept_language
+        .split(',')
+        .filter_map(|range| {
+            let mut parts = range.split(';').map(str::trim);
+            let tag: &str = parts.next()?;
+            let quality: f32 = parts
+                .find_map(|param| param.strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.parse().ok())?;
+            Some((tag, quality))
+        })
+        .filter(|(tag, quality)| !tag.is_empty() && *quality > 0.0)
+        .collect();
+    // stable, so that equally preferred languages keep their order
+    ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    ranges
+        .into_iter()
+        .find_map(|(tag, _)| match tag {
+            "*" => Some(default),
+            _ => catalog(tag.split('-').next().unwrap_or(tag)),
+        })
+        .unwrap_or(default)
+}
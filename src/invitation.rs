@@ -0,0 +1,275 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::api::{ClientIp, PowSolution};
+use crate::audit;
+use crate::break_glass::BREAK_GLASS_USERNAME;
+use crate::config::{Role, TrustedUserConfig};
+use crate::database::{self, Invitation, load_database};
+use crate::password;
+use crate::state::ServerState;
+use crate::trusted::{Admin, OWNER_USERNAME, admit, generate_token, start_session};
+use axum::body::Body;
+use axum::extract::{Json, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::MutexGuard;
+use utoipa::ToSchema;
+
+/// Time period, in seconds, for which an invitation can be accepted.
+pub const INVITATION_LIFETIME: u64 = 7 * 24 * 60 * 60;
+
+const MAX_USERNAME_LENGTH: usize = 32;
+const MIN_PASSWORD_LENGTH: usize = 12;
+
+#[derive(Deserialize, ToSchema)]
+pub struct InvitationRequest {
+    role: Role,
+}
+
+#[derive(Serialize, ToSchema)]
+struct InvitationResponse {
+    token: String,
+    /// Unix timestamp
+    expires: u64,
+    /// Path of the page where the invitation is accepted, to send to the contact.
+    link: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AcceptRequest {
+    token: String,
+    username: String,
+    password: String,
+    pow: PowSolution,
+}
+
+/// Invitation tokens are stored hashed, so reading the
+/// database file isn't enough to accept an invitation.
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+fn unprocessable(reason: &'static str) -> Response {
+    Response::builder()
+        .status(StatusCode::UNPROCESSABLE_ENTITY)
+        .body(Body::from(reason))
+        .unwrap()
+}
+
+/// Handles requests on `/api/v1/admin/invitations`, which creates a new
+/// one-time invitation for a contact to register as a trusted user.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/invitations",
+    tag = "admin",
+    request_body = InvitationRequest,
+    security(("bearer" = [])),
+    responses(
+        (status = 201, description = "The invitation was created", body = InvitationResponse),
+        (status = 401, description = "Not logged in as the person themself"),
+    )
+)]
+pub async fn create_api(_admin: Admin, Json(req): Json<InvitationRequest>) -> impl IntoResponse {
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let token: String = generate_token();
+    let invitation: Invitation = Invitation {
+        token_hash: hash_token(&token),
+        role: req.role,
+        expires: now + INVITATION_LIFETIME,
+    };
+
+    let result = database::modify(|db| {
+        // forget about any expired invitations while we're here
+        db.invitations.retain(|invitation| now < invitation.expires);
+        db.invitations.push(invitation);
+    })
+    .await;
+
+    if let Err(err) = result {
+        eprintln!("Could not sync invitation to disk: {}", err);
+        return Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::default())
+            .unwrap();
+    }
+    audit::record(
+        OWNER_USERNAME,
+        format!("invited a new {}", req.role.db_code()),
+    )
+    .await;
+
+    let resp: InvitationResponse = InvitationResponse {
+        link: format!("/invite?token={}", token),
+        token,
+        expires: now + INVITATION_LIFETIME,
+    };
+    Response::builder()
+        .status(StatusCode::CREATED)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&resp).unwrap()))
+        .unwrap()
+}
+
+/// Handles requests on `/api/v1/invitations/accept`, where an invited contact
+/// picks their username and password, and becomes a trusted user.
+///
+/// Responds like `/api/v1/login` does on success, so they're logged in right away.
+///
+#[utoipa::path(
+    post,
+    path = "/api/v1/invitations/accept",
+    tag = "trusted users",
+    request_body = AcceptRequest,
+    responses(
+        (status = 200, description = "Registered and logged in", body = crate::trusted::LoginResponse),
+        (status = 401, description = "Unknown or expired invitation"),
+        (status = 406, description = "The proof of work is invalid or expired"),
+        (status = 409, description = "The username is taken"),
+        (status = 422, description = "Invalid username or password"),
+        (status = 429, description = "Blocked after too many failed attempts"),
+    )
+)]
+pub async fn accept_api(
+    ClientIp(ip): ClientIp,
+    State(server_state): State<ServerState>,
+    Json(req): Json<AcceptRequest>,
+) -> impl IntoResponse {
+    // usernames are stored in space separated database records
+    if req.username.is_empty()
+        || req.username.len() > MAX_USERNAME_LENGTH
+        || !req
+            .username
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+    {
+        return unprocessable("Usernames may only contain letters, digits, '-', '_', and '.'");
+    }
+    if req.username == OWNER_USERNAME || req.username == BREAK_GLASS_USERNAME {
+        return unprocessable("This username is reserved.");
+    }
+    if req.password.chars().count() < MIN_PASSWORD_LENGTH {
+        return unprocessable("Passwords must be at least 12 characters long.");
+    }
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let token_hash: String = hash_token(&req.token);
+
+    // an invalid invitation counts as a failed authentication attempt
+    let admitted = admit(ip, &server_state, req.pow, async |_| {
+        let _db_guard: MutexGuard<'_, ()> = database::lock().await;
+
+        load_database(crate::DB_PATH)
+            .ok()?
+            .invitations
+            .into_iter()
+            .find(|invitation| invitation.token_hash == token_hash && now < invitation.expires)
+            .map(|invitation| invitation.role)
+    })
+    .await;
+
+    let role: Role = match admitted {
+        Ok(role) => role,
+        Err(resp) => return resp,
+    };
+
+    let mut locked_users: MutexGuard<'_, Vec<TrustedUserConfig>> =
+        server_state.registered_users.lock().await;
+
+    if server_state
+        .config
+        .trusted_users
+        .iter()
+        .chain(locked_users.iter())
+        .any(|user| user.username == req.username)
+    {
+        return Response::builder()
+            .status(StatusCode::CONFLICT)
+            .body(Body::from("This username is already taken."))
+            .unwrap();
+    }
+    let password_hash: String =
+        match password::hash(&server_state.config.password_hashing, &req.password) {
+            Ok(hash) => hash,
+            Err(err) => {
+                eprintln!("Could not hash a trusted user's password: {}", err);
+                return Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(Body::default())
+                    .unwrap();
+            }
+        };
+    let user: TrustedUserConfig = TrustedUserConfig {
+        username: req.username.clone(),
+        password_hash,
+        role,
+    };
+
+    // make sure the invitation is only ever used once
+    let mut accepted: bool = false;
+
+    let result = database::modify(|db| {
+        let count: usize = db.invitations.len();
+        db.invitations
+            .retain(|invitation| invitation.token_hash != token_hash);
+
+        if db.invitations.len() < count {
+            accepted = true;
+            db.registered_users.push(user.clone());
+        }
+    })
+    .await;
+
+    if let Err(err) = result {
+        eprintln!("Could not sync registered user to disk: {}", err);
+        return Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::default())
+            .unwrap();
+    }
+    if !accepted {
+        return Response::builder()
+            .status(StatusCode::UNAUTHORIZED)
+            .body(Body::default())
+            .unwrap();
+    }
+    locked_users.push(user);
+    drop(locked_users);
+
+    println!(
+        "Trusted user '{}' registered through an invitation.",
+        req.username
+    );
+    audit::record(
+        &req.username,
+        format!("accepted an invitation as {}", role.db_code()),
+    )
+    .await;
+
+    start_session(&server_state, req.username, role).await
+}
@@ -17,27 +17,75 @@
     License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
 */
 
+mod admin_page;
+mod affidavit;
 mod api;
+mod audit;
+mod auth_log;
+mod break_glass;
+mod cache;
+mod calendar;
+mod check_in;
 mod config;
 mod database;
+mod device_tokens;
+#[cfg(feature = "graphql")]
+mod graphql;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod guestbook;
+mod heartbeat_form;
+mod heatmap;
+mod i18n;
+mod invitation;
+mod manifest;
+mod media;
+mod note;
+mod notify;
+mod openapi;
+mod password;
 mod pow;
+mod pow_metrics;
+mod problem;
+mod push;
+mod qr_code;
+mod rate_limit;
+mod release;
+mod server_info;
+mod sources;
 mod state;
+mod state_override;
+mod stats;
+mod status_signing;
+mod summary;
 mod templating;
+mod theme;
+mod timestamping;
+mod transitions;
+mod trusted;
+mod web_session;
+mod webhooks;
+mod will;
 
+use crate::rate_limit::RequestLimiter;
 use crate::state::{Redundant, ServerState};
 use argon2::password_hash::PasswordHash;
 use axum::{
     Router,
-    routing::{get, post},
+    extract::DefaultBodyLimit,
+    middleware,
+    routing::{delete, get, post},
 };
-use std::collections::HashMap;
+use chrono::NaiveDate;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::Read;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::net::TcpListener;
-use tokio::sync::{Mutex, broadcast};
+use tokio::sync::{Mutex, Semaphore, broadcast};
 use tokio::time::{self, Duration, Interval};
 
 const BIND_ADDRESS: &str = "0.0.0.0:3000";
@@ -75,9 +123,9 @@ async fn main() {
         .expect("Failed to read file contents to string.");
     drop(conf_file); // we're in the main scope, so lets drop manually here
 
-    // deserialize the TOML config file to our [`config::ServerConfig`] struct.
-    let daemon_config: Arc<config::ServerConfig> = match toml::from_str(contents.as_str()) {
-        Ok(config) => Arc::new(config),
+    // parse the TOML config file, and fill in the secrets kept out of it
+    let mut config_value: toml::Value = match toml::from_str(contents.as_str()) {
+        Ok(value) => value,
         Err(err) => {
             println!("An error occurred while parsing the TOML configuration.");
             panic!("{}", err)
@@ -85,9 +133,405 @@ async fn main() {
     };
     drop(contents);
 
+    if let Err(err) = config::resolve_secrets(&mut config_value) {
+        println!("Could not resolve the secrets of the TOML configuration.");
+        panic!("{}", err)
+    }
+
+    // deserialize it to our [`config::ServerConfig`] struct.
+    let daemon_config: Arc<config::ServerConfig> = match config_value.try_into() {
+        Ok(config) => Arc::new(config),
+        Err(err) => {
+            println!("An error occurred while parsing the TOML configuration.");
+            panic!("{}", err)
+        }
+    };
+
+    if !password::is_supported(&daemon_config.global.heartbeat_auth_hash) {
+        panic!("Invalid or unsupported hash for `heartbeat_auth_hash`.");
+    }
+    if i18n::catalog(&daemon_config.global.locale).is_none() {
+        panic!(
+            "Unsupported `locale` '{}', it must be one of: {}.",
+            daemon_config.global.locale,
+            i18n::CATALOGS
+                .iter()
+                .map(|catalog| catalog.lang)
+                .collect::<Vec<&str>>()
+                .join(", ")
+        );
+    }
+    for (name, value) in daemon_config.theme.colors.iter() {
+        if !theme::is_css_name(name) {
+            panic!("Invalid theme color name '{}'.", name);
+        }
+        if !theme::is_css_value(value) {
+            panic!("Invalid value for theme color '{}'.", name);
+        }
+    }
+    let state_colors: &config::StateColors = &daemon_config.theme.state_colors;
+    for color in [
+        daemon_config.theme.accent.as_ref(),
+        state_colors.alive.as_ref(),
+        state_colors.uncertain.as_ref(),
+        state_colors.missing.as_ref(),
+        state_colors.incapacitated.as_ref(),
+        state_colors.dead.as_ref(),
+    ]
+    .into_iter()
+    .flatten()
+    {
+        if !theme::is_css_value(color) {
+            panic!("Invalid theme color '{}'.", color);
+        }
+    }
+    let memorial: Option<&config::Memorial> = daemon_config.memorial.as_ref();
+    let memorial_links: &[config::Link] = memorial.map_or(&[], |memorial| &memorial.links);
+
+    for link in daemon_config.profile.links.iter().chain(memorial_links) {
+        if !["https://", "http://", "mailto:"]
+            .iter()
+            .any(|scheme| link.url.starts_with(scheme))
+        {
+            panic!(
+                "Invalid URL for link '{}', it must be an http(s) or mailto URL.",
+                link.name
+            );
+        }
+    }
+    for date in memorial
+        .into_iter()
+        .flat_map(|memorial| [memorial.born.as_ref(), memorial.died.as_ref()])
+        .flatten()
+    {
+        if NaiveDate::parse_from_str(date, "%Y-%m-%d").is_err() {
+            panic!(
+                "Invalid memorial date '{}', it must be like 1970-04-01.",
+                date
+            );
+        }
+    }
+    if let Some(mtls) = daemon_config.mtls.as_ref()
+        && let Err(err) = sources::mtls::tls_config(mtls)
+    {
+        panic!("Invalid mutual TLS configuration: {}", err);
+    }
+    if let Err(err) = password::hasher(&daemon_config.password_hashing) {
+        panic!("Invalid `password_hashing` configuration: {}", err);
+    }
+    // make sure we can encrypt to every configured PGP key before we need to
+    for target in daemon_config.notifications.urls.iter() {
+        if let Some(armored_key) = target.pgp_key()
+            && let Err(err) = notify::parse_pgp_key(armored_key)
+        {
+            panic!("Invalid PGP key for notification target: {}", err);
+        }
+    }
+
+    for (i, credential) in daemon_config
+        .global
+        .heartbeat_credentials
+        .iter()
+        .enumerate()
+    {
+        if credential.name.is_empty() {
+            panic!("Heartbeat credentials must have a name.");
+        }
+        if daemon_config.global.heartbeat_credentials[..i]
+            .iter()
+            .any(|other| other.name == credential.name)
+        {
+            panic!("Duplicate heartbeat credential '{}'.", credential.name);
+        }
+        if !password::is_supported(&credential.password_hash) {
+            panic!(
+                "Invalid or unsupported hash for heartbeat credential '{}'.",
+                credential.name
+            );
+        }
+    }
+    for user in daemon_config.trusted_users.iter() {
+        // usernames are stored in space separated database records
+        if user.username.is_empty()
+            || user.username.contains(char::is_whitespace)
+            || user.username == trusted::OWNER_USERNAME
+            || user.username == break_glass::BREAK_GLASS_USERNAME
+        {
+            panic!("Invalid trusted user username '{}'.", user.username);
+        }
+        if !password::is_supported(&user.password_hash) {
+            panic!(
+                "Invalid or unsupported hash for trusted user '{}'.",
+                user.username
+            );
+        }
+    }
+    for (i, message) in daemon_config.posthumous_messages.iter().enumerate() {
+        // recipients are stored in space separated database records
+        if message.recipient.is_empty() || message.recipient.contains(char::is_whitespace) {
+            panic!(
+                "Invalid posthumous message recipient '{}'.",
+                message.recipient
+            );
+        }
+        if daemon_config.posthumous_messages[..i]
+            .iter()
+            .any(|other| other.recipient == message.recipient)
+        {
+            panic!(
+                "Duplicate posthumous message recipient '{}'.",
+                message.recipient
+            );
+        }
+    }
+    if !daemon_config.posthumous_messages.is_empty()
+        && daemon_config.notifications.apprise_api.is_none()
+    {
+        panic!("Posthumous messages are delivered through Apprise, but `apprise_api` is not set.");
+    }
+    for (i, post) in daemon_config.scheduled_posts.iter().enumerate() {
+        // IDs are stored in space separated database records
+        if post.id.is_empty() || post.id.contains(char::is_whitespace) {
+            panic!("Invalid scheduled post ID '{}'.", post.id);
+        }
+        if daemon_config.scheduled_posts[..i]
+            .iter()
+            .any(|other| other.id == post.id)
+        {
+            panic!("Duplicate scheduled post ID '{}'.", post.id);
+        }
+    }
+    if !daemon_config.scheduled_posts.is_empty()
+        && daemon_config.notifications.apprise_api.is_none()
+    {
+        panic!("Scheduled posts are published through Apprise, but `apprise_api` is not set.");
+    }
+    if daemon_config.verification.auto_dead_after.is_some()
+        && daemon_config.verification.propose_dead_after.is_none()
+    {
+        panic!(
+            "`auto_dead_after` is counted from the proposal, so `propose_dead_after` must be set."
+        );
+    }
+    if let Some(timestamping) = daemon_config.timestamping.as_ref()
+        && let Some(calendar) = timestamping
+            .calendars
+            .iter()
+            .find(|calendar| calendar.is_empty() || calendar.contains(char::is_whitespace))
+    {
+        // calendars are stored in space separated database records
+        panic!("Invalid timestamping calendar URL '{}'.", calendar);
+    }
+    if daemon_config
+        .imap
+        .as_ref()
+        .is_some_and(|imap| imap.poll_interval == 0)
+    {
+        panic!("The heartbeat mailbox `poll_interval` must be at least 1 minute.");
+    }
+    if daemon_config
+        .fediverse
+        .as_ref()
+        .is_some_and(|fediverse| fediverse.poll_interval == 0)
+    {
+        panic!("The fediverse outbox `poll_interval` must be at least 1 minute.");
+    }
+    if daemon_config
+        .calendar
+        .as_ref()
+        .is_some_and(|calendar| calendar.poll_interval == 0)
+    {
+        panic!("The calendar `poll_interval` must be at least 1 minute.");
+    }
+    if daemon_config
+        .feeds
+        .as_ref()
+        .is_some_and(|feeds| feeds.poll_interval == 0)
+    {
+        panic!("The feeds `poll_interval` must be at least 1 minute.");
+    }
+    validate_tokens(
+        "read key",
+        daemon_config
+            .read_keys
+            .iter()
+            .map(|read_key| (read_key.name.as_str(), read_key.key_hash.as_str())),
+    );
+    if !daemon_config.read_keys.is_empty() && !daemon_config.global.private {
+        println!("Read keys are configured, but the instance is public. They are not needed.");
+    }
+    validate_tokens(
+        "ping token",
+        daemon_config
+            .ping_tokens
+            .iter()
+            .map(|ping_token| (ping_token.name.as_str(), ping_token.token_hash.as_str())),
+    );
+    validate_tokens(
+        "ingest source",
+        daemon_config
+            .ingest_sources
+            .iter()
+            .map(|source| (source.name.as_str(), source.token_hash.as_str())),
+    );
+    validate_tokens(
+        "SSH host",
+        daemon_config
+            .ssh_hosts
+            .iter()
+            .map(|host| (host.name.as_str(), host.token_hash.as_str())),
+    );
+    for source in daemon_config.ingest_sources.iter() {
+        if let Some(field) = source.message_field.as_ref()
+            && !field.starts_with('/')
+        {
+            panic!(
+                "The `message_field` of ingest source '{}' must be a JSON pointer, e.g. \"/message\".",
+                source.name
+            );
+        }
+    }
+    for (i, key) in daemon_config.batch_keys.iter().enumerate() {
+        if key.name.is_empty() {
+            panic!("Batch keys must have a name.");
+        }
+        if daemon_config.batch_keys[..i]
+            .iter()
+            .any(|other| other.name == key.name)
+        {
+            panic!("Duplicate batch key '{}'.", key.name);
+        }
+        // panics on an invalid key
+        let _ = sources::batch::verifying_key(key);
+    }
+    if let Some(home_assistant) = daemon_config.home_assistant.as_ref()
+        && !sources::is_token_hash(&home_assistant.token_hash)
+    {
+        panic!("Invalid SHA-256 hash for the Home Assistant token, it must be lowercase hex.");
+    }
+    if let Some(scrobbles) = daemon_config.scrobbles.as_ref() {
+        if scrobbles.poll_interval == 0 {
+            panic!("The scrobbles `poll_interval` must be at least 1 minute.");
+        }
+        if scrobbles.service == config::ScrobbleService::LastFm && scrobbles.api_key.is_none() {
+            panic!("The Last.fm `api_key` to check the scrobbles with is not set.");
+        }
+    }
+    if let Some(git_activity) = daemon_config.git_activity.as_ref() {
+        if git_activity.poll_interval == 0 {
+            panic!("The git activity `poll_interval` must be at least 1 minute.");
+        }
+        if git_activity.forge == config::Forge::Gitea && git_activity.url.is_none() {
+            panic!("The `url` of the Gitea instance to watch for git activity is not set.");
+        }
+    }
+    if let Some(break_glass) = daemon_config.break_glass.as_ref()
+        && !password::is_supported(&break_glass.token_hash)
+    {
+        panic!("Invalid or unsupported hash for the break-glass token.");
+    }
+    if let Some(hash) = daemon_config.release.recovery_code_hash.as_ref()
+        && !password::is_supported(hash)
+    {
+        panic!("Invalid or unsupported hash for the release recovery code.");
+    }
+    if let Some(twilio) = daemon_config.twilio.as_ref()
+        && !password::is_supported(&twilio.pin_hash)
+    {
+        panic!("Invalid or unsupported hash for the SMS PIN.");
+    }
+    if let Some(secret) = daemon_config.global.heartbeat_totp_secret.as_ref() {
+        // panics on an invalid secret
+        let _ = api::heartbeat_totp(secret);
+    }
+    let max_difficulty: u8 = daemon_config
+        .pow
+        .max_difficulty
+        .unwrap_or(daemon_config.pow.difficulty);
+
+    if !(1..=pow::DIFFICULTIES.len() as u8).contains(&daemon_config.pow.difficulty)
+        || !(daemon_config.pow.difficulty..=pow::DIFFICULTIES.len() as u8).contains(&max_difficulty)
+    {
+        panic!("PoW difficulties must be 1 to 5, and `max_difficulty` at least `difficulty`.");
+    }
+    if let Some(affidavit) = daemon_config.affidavit.as_ref() {
+        // panics on an invalid key
+        let _ = affidavit::signing_key(affidavit);
+    }
+    if let Some(status_signing) = daemon_config.status_signing.as_ref() {
+        // panics on an invalid key
+        let _ = status_signing::signing_key(status_signing);
+    }
+    if let Some(media) = daemon_config.heartbeat_media.as_ref() {
+        if !std::path::Path::new(&media.directory).is_dir() {
+            panic!(
+                "Heartbeat media directory is missing or not accessible at: {}",
+                media.directory
+            );
+        }
+        // attachments are sent base64 encoded, in a JSON body of at most 2 MB
+        if media.max_size == 0 || media.max_size > 1024 {
+            panic!("Heartbeat media `max_size` must be between 1 and 1024 KiB.");
+        }
+    }
+    if let Some(attachments) = daemon_config.attachments.as_ref()
+        && !std::path::Path::new(&attachments.directory).is_dir()
+    {
+        panic!(
+            "Will attachments directory is missing or not accessible at: {}",
+            attachments.directory
+        );
+    }
+    if let Some(will) = daemon_config.will.as_ref() {
+        match (
+            will.path.as_ref(),
+            will.inline.as_ref(),
+            will.location.as_ref(),
+        ) {
+            (Some(path), None, None) => {
+                if !std::path::Path::new(path).exists() {
+                    panic!("Will payload is missing or not accessible at: {}", path);
+                }
+            }
+            (None, Some(_), None) | (None, None, Some(_)) => (),
+            _ => panic!("Exactly one of `path`, `inline`, or `location` must be set for the will."),
+        }
+    }
+
+    // the audit log is only tamper-evident if someone looks
+    match database::load_database(DB_PATH) {
+        Err(err) => panic!("{}", err),
+        Ok(db) => {
+            if let Some(i) = audit::verify_chain(&db.audit_log) {
+                eprintln!("The audit log was tampered with, starting at entry {}!", i);
+            }
+        }
+    }
+
     let initial_state: database::InitialState =
         database::get_initial_state_from_disk(DB_PATH, daemon_config.clone());
 
+    for user in initial_state.registered_users.iter() {
+        if daemon_config
+            .trusted_users
+            .iter()
+            .any(|configured| configured.username == user.username)
+        {
+            panic!(
+                "Trusted user '{}' is both configured and registered.",
+                user.username
+            );
+        }
+    }
+    let trusted_user_count: usize =
+        daemon_config.trusted_users.len() + initial_state.registered_users.len();
+
+    if daemon_config.verification.dead_quorum > 1
+        && usize::from(daemon_config.verification.dead_quorum) > trusted_user_count
+    {
+        panic!("The dead quorum is larger than the number of trusted users.");
+    }
+
     // get the unix timestamp of this instant, so we can record the time at which
     // the server was started. useful for avoiding immediately switching to a missing/dead
     // state if the server was down for longer than the maximum silence period.
@@ -102,29 +546,70 @@ async fn main() {
     let pwd_hash_str: &mut str = daemon_config.global.heartbeat_auth_hash.clone().leak();
 
     // broadcast channel for PoW challenges
-    let (tx, _) = broadcast::channel::<String>(100);
+    let (tx, _) = broadcast::channel::<pow::Challenge>(100);
 
     let pow_state: pow::PoWState = pow::PoWState {
         secret: daemon_config.pow.secret.clone().leak(), // leak string so it has static lifetime (read-only)
-        difficulty: pow::DIFFICULTIES[daemon_config.pow.difficulty as usize - 1].0,
-        difficulty_index: daemon_config.pow.difficulty as usize - 1,
+        algorithm: daemon_config.pow.algorithm,
+        difficulty: Arc::new(std::sync::Mutex::new(pow::Difficulty::new(
+            daemon_config.pow.difficulty as usize - 1,
+        ))),
+        failures: Arc::new(AtomicU64::new(0)),
+        penalties: Arc::new(std::sync::Mutex::new(HashMap::new())),
+        metrics: Arc::new(std::sync::Mutex::new(pow_metrics::PowMetrics::new())),
         tx: Arc::new(tx),
+        used_solutions: Arc::new(std::sync::Mutex::new(HashSet::new())),
+        verifications: Arc::new(Semaphore::new(pow::MAX_ARGON2_VERIFICATIONS)),
     };
 
     // build our state struct
     let server_state: ServerState = ServerState {
         state: Arc::new(Mutex::new(Redundant::new(initial_state.state))),
         last_heartbeat: Arc::new(Mutex::new(Redundant::new(initial_state.last_heartbeat))),
+        last_active_heartbeat: Arc::new(Mutex::new(Redundant::new(
+            initial_state.last_active_heartbeat,
+        ))),
         server_start_time: Redundant::new(boot_time),
         config: daemon_config.clone(),
         password_hash: PasswordHash::new(pwd_hash_str).expect("Invalid Argon2id hash."),
         displayed_heartbeats: Arc::new(Mutex::new(initial_state.heartbeat_display)),
+        heartbeat_days: Arc::new(Mutex::new(initial_state.heartbeat_days)),
         note: Arc::new(Mutex::new(initial_state.note)),
+        private_note: Arc::new(Mutex::new(initial_state.private_note)),
         baked_status_api_resp: Arc::new(Mutex::new(String::default())),
+        baked_status_etag: Arc::new(Mutex::new(String::default())),
+        status_changed_at: Arc::new(Mutex::new(boot_time)),
+        status_events: Arc::new(broadcast::channel::<String>(16).0),
         rate_limited_ips: Arc::new(Mutex::new(HashMap::default())),
+        request_limiter: Arc::new(std::sync::Mutex::new(RequestLimiter::default())),
         pow_state,
+        qr_tokens: Arc::new(Mutex::new(HashMap::default())),
+        sessions: Arc::new(Mutex::new(HashMap::default())),
+        dead_confirmations: Arc::new(Mutex::new(initial_state.dead_confirmations)),
+        last_check_in: Arc::new(Mutex::new(initial_state.last_check_in)),
+        guestbook: Arc::new(Mutex::new(initial_state.guestbook)),
+        release_countdown: Arc::new(Mutex::new(initial_state.release_countdown)),
+        registered_users: Arc::new(Mutex::new(initial_state.registered_users)),
+        dead_proposed: Arc::new(Mutex::new(None)),
+        last_totp_step: Arc::new(Mutex::new(0)),
+        planned_absences: Arc::new(Mutex::new(vec![])),
+        overridden_at: Arc::new(Mutex::new(initial_state.overridden_at)),
+        template_overrides: daemon_config
+            .global
+            .templates_directory
+            .as_ref()
+            .map(|directory| Arc::new(templating::TemplateOverrides::new(directory))),
     };
 
+    // forget about addresses that haven't made requests or failed to log in for a while
+    tokio::spawn(rate_limit::evict_expired(server_state.clone()));
+
+    // know of any planned absences before the first state update
+    if server_state.config.calendar.is_some() {
+        calendar::refresh(&server_state).await;
+        tokio::spawn(calendar::poll(server_state.clone()));
+    }
+
     // start a tokio job that updates our state every tick interval.
     //
     // this is useful for the digital will to take effect even if
@@ -145,10 +630,54 @@ async fn main() {
                     .unwrap()
                     .as_secs();
                 state.update(now).await;
+                state.release_if_due(now).await;
+
+                // retry timestamping any transitions that failed to be published
+                if state.config.timestamping.is_some() {
+                    tokio::spawn(timestamping::publish_pending(state.config.clone()));
+                }
             }
         }
     });
 
+    // serve the gRPC service on its own port, if it's configured
+    #[cfg(feature = "grpc")]
+    if server_state.config.grpc.is_some() {
+        tokio::spawn(grpc::serve(server_state.clone()));
+    }
+    #[cfg(not(feature = "grpc"))]
+    if server_state.config.grpc.is_some() {
+        eprintln!("gRPC is configured, but this build doesn't include it.");
+    }
+    // listen for heartbeats authenticated by client certificates, if configured
+    if server_state.config.mtls.is_some() {
+        tokio::spawn(sources::mtls::serve(server_state.clone()));
+    }
+    // poll the heartbeat mailbox, if e-mailed heartbeats are enabled
+    if server_state.config.imap.is_some() {
+        tokio::spawn(sources::imap::poll(server_state.clone()));
+    }
+    // listen for heartbeats sent to the Telegram bot, if one is configured
+    if server_state.config.telegram.is_some() {
+        tokio::spawn(sources::telegram::poll(server_state.clone()));
+    }
+    // poll the git activity feed, if passive heartbeats from it are enabled
+    if server_state.config.git_activity.is_some() {
+        tokio::spawn(sources::git::poll(server_state.clone()));
+    }
+    // poll the fediverse outbox, if passive heartbeats from it are enabled
+    if server_state.config.fediverse.is_some() {
+        tokio::spawn(sources::fediverse::poll(server_state.clone()));
+    }
+    // poll the RSS and Atom feeds, if passive heartbeats from them are enabled
+    if server_state.config.feeds.is_some() {
+        tokio::spawn(sources::feeds::poll(server_state.clone()));
+    }
+    // poll the Last.fm or ListenBrainz scrobbles, if passive heartbeats from them are enabled
+    if server_state.config.scrobbles.is_some() {
+        tokio::spawn(sources::scrobbles::poll(server_state.clone()));
+    }
+
     // start another tokio job that handles broadcasting PoW challenges
     tokio::spawn({
         let state: pow::PoWState = server_state.pow_state.clone();
@@ -156,14 +685,128 @@ async fn main() {
             pow::generate_pow_challenges(state).await;
         }
     });
+    tokio::spawn(pow::adapt_difficulty(server_state.clone()));
+
+    // the versioned API, also served on unversioned paths for existing clients
+    let api: Router<ServerState> = Router::new()
+        .route("/status", get(api::status_api))
+        .route("/status/key", get(status_signing::key_api))
+        .route("/events", get(api::events_api))
+        .route("/server", get(server_info::server_info_api))
+        .route(
+            "/heartbeat",
+            post(api::heartbeat_api).layer(DefaultBodyLimit::max(api::heartbeat_body_limit(
+                &server_state,
+            ))),
+        )
+        .route("/heartbeat/media/:file", get(media::media_api))
+        .route("/ingest/:token", post(sources::ingest::ingest_api))
+        .route("/heartbeat/batch", post(sources::batch::batch_api))
+        .route("/sms", post(sources::sms::sms_api))
+        .route(
+            "/home-assistant",
+            post(sources::home_assistant::home_assistant_api),
+        )
+        .route("/ssh-login", post(sources::ssh::ssh_login_api))
+        .route("/pow", get(pow::ws_handler))
+        .route("/pow/challenge", get(pow::challenge_api))
+        .route("/ratelimit", post(rate_limit::rate_limit_api))
+        .route("/login", post(trusted::login_api))
+        .route("/logout", post(trusted::logout_api))
+        .route("/session", get(trusted::session_api))
+        .route("/verify", post(trusted::verify_api))
+        .route("/check-in", post(check_in::check_in_api))
+        .route(
+            "/guestbook",
+            get(guestbook::list_api).post(guestbook::sign_api),
+        )
+        .route("/guestbook/:id", delete(guestbook::delete_api))
+        .route("/guestbook/:id/approve", post(guestbook::approve_api))
+        .route("/history", get(trusted::history_api))
+        .route("/heartbeats", get(trusted::heartbeats_api))
+        .route("/stats", get(stats::stats_api))
+        .route("/affidavit", get(affidavit::affidavit_api))
+        .route("/timestamps", get(timestamping::timestamps_api))
+        .route("/transitions", get(transitions::transitions_api))
+        .route("/summary", get(summary::summary_api))
+        .route("/break-glass", post(break_glass::break_glass_api))
+        .route("/admin/login", post(trusted::admin_login_api))
+        .route("/admin/audit", get(audit::audit_api))
+        .route("/admin/auth-attempts", get(auth_log::auth_attempts_api))
+        .route("/admin/pow", get(pow_metrics::pow_metrics_api))
+        .route("/admin/state", post(state_override::state_override_api))
+        .route(
+            "/admin/webhooks",
+            get(webhooks::list_api).post(webhooks::create_api),
+        )
+        .route("/admin/webhooks/:id", delete(webhooks::delete_api))
+        .route(
+            "/admin/tokens",
+            get(device_tokens::list_api).post(device_tokens::create_api),
+        )
+        .route("/admin/tokens/:id", delete(device_tokens::delete_api))
+        .route(
+            "/note",
+            get(note::get_note_api)
+                .put(note::put_note_api)
+                .delete(note::delete_note_api),
+        )
+        .route(
+            "/private-note",
+            get(note::get_private_note_api)
+                .put(note::put_private_note_api)
+                .delete(note::delete_private_note_api),
+        )
+        .route("/admin/invitations", post(invitation::create_api))
+        .route("/invitations/accept", post(invitation::accept_api))
+        .route("/will", get(will::payload_api))
+        .route("/will/key", get(will::key_api))
+        .route("/release/cancel", post(release::cancel_api))
+        .route("/attachments", get(will::attachments_api))
+        .route("/attachments/*path", get(will::attachment_api))
+        .route("/push/subscribe", post(push::subscribe_api))
+        .route("/push/unsubscribe", post(push::unsubscribe_api));
+    #[cfg(feature = "graphql")]
+    let api: Router<ServerState> = api.route(
+        "/graphql",
+        get(graphql::schema_api).post(graphql::graphql_api),
+    );
 
     // start the web server (with initial state)
     let app: Router = Router::new()
         .route("/", get(templating::index))
-        .route("/heartbeat", get(templating::heartbeat))
-        .route("/api/status", get(api::status_api))
-        .route("/api/heartbeat", post(api::heartbeat_api))
-        .route("/api/pow", get(pow::ws_handler))
+        .route(
+            "/heartbeat",
+            get(templating::heartbeat).post(heartbeat_form::submit_handler),
+        )
+        .route("/invite", get(templating::invite))
+        .route(
+            "/login",
+            get(web_session::login_page_handler).post(web_session::login_handler),
+        )
+        .route("/account", get(web_session::account_handler))
+        .route("/history", get(web_session::history_handler))
+        .route("/admin", get(admin_page::admin_handler))
+        .route("/admin/note", post(admin_page::note_handler))
+        .route("/admin/state", post(admin_page::state_handler))
+        .route(
+            "/admin/test-notification",
+            post(admin_page::test_notification_handler),
+        )
+        .route("/logout", post(web_session::logout_handler))
+        .route("/badge.svg", get(templating::badge))
+        .route("/favicon.svg", get(templating::favicon))
+        .route("/theme.css", get(theme::theme_css))
+        .route("/manifest.webmanifest", get(manifest::manifest))
+        .route("/status.txt", get(api::status_txt))
+        .route("/ping/:token", get(sources::ping::ping_api))
+        .route("/api/openapi.json", get(openapi::openapi_api))
+        .nest("/api/v1", api.clone())
+        .nest("/api", api)
+        .layer(middleware::from_fn_with_state(
+            server_state.clone(),
+            rate_limit::limit_requests,
+        ))
         .with_state(server_state);
 
     let listener: TcpListener = tokio::net::TcpListener::bind(BIND_ADDRESS).await.unwrap();
@@ -174,3 +817,28 @@ async fn main() {
     .await
     .unwrap();
 }
+
+/// Check that every credential of a kind, given as its name and token hash,
+/// has a unique name and token, and a valid SHA-256 hash of the token.
+fn validate_tokens<'a>(label: &str, tokens: impl Iterator<Item = (&'a str, &'a str)>) {
+    let mut seen: Vec<(&str, &str)> = vec![];
+
+    for (name, hash) in tokens {
+        if name.is_empty() {
+            panic!("Every {} must have a name.", label);
+        }
+        if seen
+            .iter()
+            .any(|(other_name, other_hash)| *other_name == name || *other_hash == hash)
+        {
+            panic!("Duplicate {} '{}'.", label, name);
+        }
+        if !sources::is_token_hash(hash) {
+            panic!(
+                "Invalid SHA-256 hash for {} '{}', it must be lowercase hex.",
+                label, name
+            );
+        }
+        seen.push((name, hash));
+    }
+}
@@ -0,0 +1,78 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+use crate::i18n::{self, Catalog};
+use crate::state::ServerState;
+use crate::theme;
+use crate::trusted;
+use axum::extract::{Query, RawQuery, State};
+use axum::http::HeaderMap;
+use axum::response::IntoResponse;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+
+/// Handles requests on `/manifest.webmanifest`, the web app manifest that makes the
+/// status page installable, e.g. on the phones of your contacts, where `status_worker.js`
+/// keeps the last status they saw for when they have no connection.
+///
+/// On a private instance, the status page links it with its read key, which the
+/// installed app then opens the page with.
+///
+pub async fn manifest(
+    headers: HeaderMap,
+    Query(params): Query<HashMap<String, String>>,
+    RawQuery(query): RawQuery,
+    State(server_state): State<ServerState>,
+) -> impl IntoResponse {
+    let t: &'static Catalog = i18n::negotiate(
+        &headers,
+        i18n::catalog(&server_state.config.global.locale).expect("Unsupported locale."),
+    );
+    let name: &str = &server_state.config.global.name;
+
+    let start_url: String = match params.get("key") {
+        Some(key) if trusted::is_reader_key(&server_state, key).await => {
+            format!("/?{}", query.unwrap_or_default())
+        }
+        _ => "/".into(),
+    };
+    let background: String = theme::background_color(&server_state.config.theme);
+
+    let manifest: Value = json!({
+        "name": t.title.replace("{0}", name),
+        "short_name": name,
+        "lang": t.lang,
+        "start_url": start_url,
+        "scope": "/",
+        "display": "standalone",
+        "theme_color": background,
+        "background_color": background,
+        "icons": [
+            { "src": "/favicon/android-chrome-192x192.png", "sizes": "192x192", "type": "image/png" },
+            { "src": "/favicon/android-chrome-512x512.png", "sizes": "512x512", "type": "image/png" },
+        ],
+    });
+    (
+        [
+            ("Content-Type", "application/manifest+json"),
+            ("Cache-Control", "no-cache"),
+            ("Vary", "Accept-Language"),
+        ],
+        manifest.to_string(),
+    )
+}
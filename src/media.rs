@@ -0,0 +1,152 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::config::{HeartbeatMedia, Role};
+use crate::state::ServerState;
+use crate::trusted::{Reader, TrustedUser};
+use axum::body::Body;
+use axum::extract::{Path as UrlPath, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::Deserialize;
+use std::path::Path;
+use utoipa::ToSchema;
+
+/// Media types accepted as heartbeat attachments, and the file extensions they are saved with.
+const MEDIA_TYPES: [(&str, &str); 8] = [
+    ("image/png", "png"),
+    ("image/jpeg", "jpg"),
+    ("image/webp", "webp"),
+    ("image/gif", "gif"),
+    ("audio/mpeg", "mp3"),
+    ("audio/ogg", "ogg"),
+    ("audio/webm", "weba"),
+    ("audio/mp4", "m4a"),
+];
+
+/// Media attached to a heartbeat request.
+#[derive(Deserialize, ToSchema)]
+pub struct HeartbeatAttachment {
+    /// e.g. "image/jpeg", see [`MEDIA_TYPES`]
+    pub content_type: String,
+    /// Base64 encoded contents of the file.
+    pub data: String,
+}
+
+/// Path on which the given heartbeat media file is served.
+pub fn media_url(file_name: &str) -> String {
+    format!("/api/v1/heartbeat/media/{}", file_name)
+}
+
+/// Decode and save a heartbeat attachment to the media directory,
+/// returning the name of the saved file.
+///
+/// Fails with a reason to show the client if the attachment is not acceptable.
+///
+pub async fn save_attachment(
+    config: &HeartbeatMedia,
+    attachment: &HeartbeatAttachment,
+    timestamp: u64,
+) -> Result<String, &'static str> {
+    let Some((_, extension)) = MEDIA_TYPES
+        .iter()
+        .find(|(content_type, _)| *content_type == attachment.content_type)
+    else {
+        return Err("Only PNG, JPEG, WebP, GIF, MP3, Ogg, WebM and MP4 media is accepted.");
+    };
+    let Ok(contents) = STANDARD.decode(&attachment.data) else {
+        return Err("The attachment is not valid base64.");
+    };
+    if contents.len() > usize::from(config.max_size) * 1024 {
+        return Err("The attachment is too large.");
+    }
+    let file_name: String = format!("{}.{}", timestamp, extension);
+
+    if let Err(err) =
+        tokio::fs::write(Path::new(&config.directory).join(&file_name), contents).await
+    {
+        eprintln!("Could not save heartbeat media: {}", err);
+        return Err("There was an issue saving the attachment.");
+    }
+    Ok(file_name)
+}
+
+/// Handles requests on `/api/v1/heartbeat/media/:file`, which serves the
+/// media sent along with a heartbeat.
+///
+/// Responds with `404 Not Found` to anyone but trusted users,
+/// unless the media is configured to be public. Like the status page,
+/// it's only public to those allowed to read a private instance.
+///
+#[utoipa::path(
+    get,
+    path = "/api/v1/heartbeat/media/{file}",
+    tag = "heartbeats",
+    params(("file" = String, Path, description = "Name of the media file")),
+    responses(
+        (status = 200, description = "The media file"),
+        (status = 401, description = "The instance is private"),
+        (status = 404, description = "No such media, or it is private"),
+    ),
+    security((), ("bearer" = [])),
+)]
+pub async fn media_api(
+    _reader: Reader,
+    State(server_state): State<ServerState>,
+    user: Option<TrustedUser>,
+    UrlPath(file_name): UrlPath<String>,
+) -> impl IntoResponse {
+    let not_found: Response = Response::builder()
+        .status(StatusCode::NOT_FOUND)
+        .body(Body::default())
+        .unwrap();
+
+    let Some(media) = server_state.config.heartbeat_media.as_ref() else {
+        return not_found;
+    };
+    if !media.public
+        && !user
+            .as_ref()
+            .is_some_and(|user| user.session.role.permits(Role::Viewer))
+    {
+        return not_found;
+    }
+    // we only ever save files named '<timestamp>.<extension>'
+    let Some((timestamp, extension)) = file_name.split_once('.') else {
+        return not_found;
+    };
+    let Some((content_type, _)) = MEDIA_TYPES.iter().find(|(_, ext)| *ext == extension) else {
+        return not_found;
+    };
+    if timestamp.is_empty() || !timestamp.chars().all(|c| c.is_ascii_digit()) {
+        return not_found;
+    }
+
+    match tokio::fs::read(Path::new(&media.directory).join(&file_name)).await {
+        Err(_) => not_found,
+        Ok(contents) => Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", *content_type)
+            .header("X-Content-Type-Options", "nosniff")
+            .body(Body::from(contents))
+            .unwrap(),
+    }
+}
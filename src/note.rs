@@ -0,0 +1,248 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::api::{bake_status_api_response, clean_note};
+use crate::audit;
+use crate::database;
+use crate::problem::Problem;
+use crate::state::ServerState;
+use crate::trusted::{Admin, OWNER_USERNAME, TrustedUser};
+use axum::body::Body;
+use axum::extract::{Json, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct Note {
+    /// The note shown on the status page, if any.
+    note: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct PrivateNote {
+    /// The note only shown to trusted users, if any, e.g. "I'm at the cabin, key under the mat"
+    note: Option<String>,
+}
+
+/// Replace or remove the note, and sync it to disk.
+pub async fn set_note(server_state: &ServerState, note: Option<String>) -> Result<(), Problem> {
+    let note: Option<String> = match note.as_deref().map(clean_note) {
+        None => None,
+        Some(result) => Some(result?).filter(|note| !note.is_empty()),
+    };
+
+    *server_state.note.lock().await = note.clone();
+    let _: String = bake_status_api_response(server_state.clone()).await;
+
+    if let Err(err) = database::modify(|db| db.note = note.unwrap_or_default()).await {
+        eprintln!("Could not sync the note to disk: {}", err);
+
+        return Err(
+            Problem::new(StatusCode::INTERNAL_SERVER_ERROR, "database_error")
+                .detail("There was an issue writing to the database."),
+        );
+    }
+    Ok(())
+}
+
+/// Handles `GET` requests on `/api/v1/note`, which shows the current note.
+#[utoipa::path(
+    get,
+    path = "/api/v1/note",
+    tag = "admin",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The current note", body = Note),
+        (status = 401, description = "Not logged in as the person themself"),
+    )
+)]
+pub async fn get_note_api(
+    _admin: Admin,
+    State(server_state): State<ServerState>,
+) -> impl IntoResponse {
+    let resp: Note = Note {
+        note: server_state.note.lock().await.clone(),
+    };
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&resp).unwrap()))
+        .unwrap()
+}
+
+/// Handles `PUT` requests on `/api/v1/note`, which replaces the note
+/// without registering a heartbeat.
+#[utoipa::path(
+    put,
+    path = "/api/v1/note",
+    tag = "admin",
+    request_body = Note,
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The note was replaced"),
+        (status = 401, description = "Not logged in as the person themself"),
+        (status = 422, description = "The note is empty or too long"),
+    )
+)]
+pub async fn put_note_api(
+    _admin: Admin,
+    State(server_state): State<ServerState>,
+    Json(req): Json<Note>,
+) -> impl IntoResponse {
+    let Some(note) = req.note.filter(|note| !note.trim().is_empty()) else {
+        return Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(Body::from("The note is empty, remove it instead."))
+            .unwrap();
+    };
+    audit::record(OWNER_USERNAME, "updated the note".into()).await;
+
+    match set_note(&server_state, Some(note)).await {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(problem) => problem.into_response(),
+    }
+}
+
+/// Handles `DELETE` requests on `/api/v1/note`, which removes the note
+/// without registering a heartbeat.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/note",
+    tag = "admin",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The note was removed"),
+        (status = 401, description = "Not logged in as the person themself"),
+    )
+)]
+pub async fn delete_note_api(
+    _admin: Admin,
+    State(server_state): State<ServerState>,
+) -> impl IntoResponse {
+    audit::record(OWNER_USERNAME, "removed the note".into()).await;
+
+    match set_note(&server_state, None).await {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(problem) => problem.into_response(),
+    }
+}
+
+/// Replace or remove the private note, and sync it to disk.
+async fn set_private_note(server_state: &ServerState, note: Option<String>) -> Response {
+    let note: Option<String> = match note.as_deref().map(clean_note) {
+        None => None,
+        Some(Err(problem)) => return problem.into_response(),
+        Some(Ok(note)) => Some(note).filter(|note| !note.is_empty()),
+    };
+
+    *server_state.private_note.lock().await = note.clone();
+
+    if let Err(err) = database::modify(|db| db.private_note = note).await {
+        eprintln!("Could not sync the private note to disk: {}", err);
+
+        return Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::from("There was an issue writing to the database."))
+            .unwrap();
+    }
+    Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::default())
+        .unwrap()
+}
+
+/// Handles `GET` requests on `/api/v1/private-note`, which shows the private note
+/// to any trusted user. It is never part of the status page or the status API.
+#[utoipa::path(
+    get,
+    path = "/api/v1/private-note",
+    tag = "trusted users",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The current private note", body = PrivateNote),
+        (status = 401, description = "Not logged in"),
+    )
+)]
+pub async fn get_private_note_api(
+    user: TrustedUser,
+    State(server_state): State<ServerState>,
+) -> impl IntoResponse {
+    let resp: PrivateNote = PrivateNote {
+        note: server_state.private_note.lock().await.clone(),
+    };
+    if user.session.username != OWNER_USERNAME && resp.note.is_some() {
+        audit::record(&user.session.username, "read the private note".into()).await;
+    }
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&resp).unwrap()))
+        .unwrap()
+}
+
+/// Handles `PUT` requests on `/api/v1/private-note`, which replaces the private note.
+#[utoipa::path(
+    put,
+    path = "/api/v1/private-note",
+    tag = "admin",
+    request_body = PrivateNote,
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The private note was replaced"),
+        (status = 401, description = "Not logged in as the person themself"),
+        (status = 422, description = "The private note is empty or too long"),
+    )
+)]
+pub async fn put_private_note_api(
+    _admin: Admin,
+    State(server_state): State<ServerState>,
+    Json(req): Json<PrivateNote>,
+) -> impl IntoResponse {
+    let Some(note) = req.note.filter(|note| !note.trim().is_empty()) else {
+        return Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(Body::from("The private note is empty, remove it instead."))
+            .unwrap();
+    };
+    audit::record(OWNER_USERNAME, "updated the private note".into()).await;
+
+    set_private_note(&server_state, Some(note)).await
+}
+
+/// Handles `DELETE` requests on `/api/v1/private-note`, which removes the private note.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/private-note",
+    tag = "admin",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The private note was removed"),
+        (status = 401, description = "Not logged in as the person themself"),
+    )
+)]
+pub async fn delete_private_note_api(
+    _admin: Admin,
+    State(server_state): State<ServerState>,
+) -> impl IntoResponse {
+    audit::record(OWNER_USERNAME, "removed the private note".into()).await;
+
+    set_private_note(&server_state, None).await
+}
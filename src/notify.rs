@@ -0,0 +1,696 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::config::ServerConfig;
+use crate::database::{self, Database, LedgerEntry, load_database};
+use crate::push::{self, Delivery};
+use crate::state::LifeState;
+use crate::webhooks;
+use chrono::{FixedOffset, TimeZone};
+use pgp::composed::{ArmorOptions, Deserializable, MessageBuilder, SignedPublicKey};
+use pgp::crypto::sym::SymmetricKeyAlgorithm;
+use pgp::types::KeyDetails;
+use rand_core::OsRng;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::{Mutex, MutexGuard};
+
+/// Ledger channel ID used for all Web Push subscriptions.
+const WEB_PUSH_CHANNEL: &str = "webpush";
+
+/// Held while delivering posthumous messages or scheduled posts, so
+/// two deliveries running at once can't both send the same message.
+static POSTHUMOUS_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// Any event that is worth letting the configured notification targets know about.
+#[derive(Debug, Clone)]
+pub enum NotificationEvent {
+    StateChanged {
+        from: LifeState,
+        to: LifeState,
+        /// Unix timestamp of the last heartbeat at the time of the transition.
+        last_heartbeat: u64,
+        /// Message of the last heartbeat at the time of the transition.
+        last_message: String,
+        note: Option<String>,
+    },
+    /// The "Dead" state was proposed to the trusted users, after
+    /// a long enough time in the "Missing or Dead" state.
+    DeadProposed {
+        /// Unix timestamp of the last heartbeat.
+        last_heartbeat: u64,
+        /// Unix timestamp of when the "Dead" state is entered without confirmation, if ever.
+        deadline: Option<u64>,
+    },
+    /// The break-glass token was used to gain emergency access.
+    BreakGlassUsed {
+        /// Unix timestamp of when the token was used.
+        timestamp: u64,
+        /// Address the token was used from.
+        address: String,
+    },
+}
+
+/// Names of every kind of [`NotificationEvent`], which webhooks can subscribe to.
+pub const EVENT_NAMES: [&str; 3] = ["state_changed", "dead_proposed", "break_glass_used"];
+
+impl NotificationEvent {
+    /// Name of the kind of event, as given to webhooks. See [`EVENT_NAMES`].
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::StateChanged { .. } => EVENT_NAMES[0],
+            Self::DeadProposed { .. } => EVENT_NAMES[1],
+            Self::BreakGlassUsed { .. } => EVENT_NAMES[2],
+        }
+    }
+
+    /// Identifies this event in the notification ledger. Two events with the
+    /// same key are the same event, e.g. when re-entering a state after a restart.
+    fn ledger_key(&self) -> String {
+        match self {
+            Self::StateChanged {
+                to, last_heartbeat, ..
+            } => format!("state:{}@{}", to.db_code(), last_heartbeat),
+            Self::DeadProposed { last_heartbeat, .. } => {
+                format!("proposal:dead@{}", last_heartbeat)
+            }
+            Self::BreakGlassUsed { timestamp, .. } => format!("break-glass@{}", timestamp),
+        }
+    }
+
+    fn title(&self, config: &ServerConfig) -> String {
+        match self {
+            Self::StateChanged { to, .. } => {
+                format!("{} is now: {}", config.global.full_name, to)
+            }
+            Self::DeadProposed { .. } => {
+                format!("Is {} dead?", config.global.full_name)
+            }
+            Self::BreakGlassUsed { .. } => {
+                format!(
+                    "Emergency access to {}'s status was used",
+                    config.global.full_name
+                )
+            }
+        }
+    }
+
+    fn body(&self, config: &ServerConfig) -> String {
+        match self {
+            Self::StateChanged {
+                from,
+                to,
+                last_heartbeat,
+                ..
+            } => {
+                let timezone: FixedOffset =
+                    FixedOffset::east_opt(config.global.utc_offset * 60 * 60).unwrap();
+                let last_seen: String = timezone
+                    .timestamp_opt(*last_heartbeat as i64, 0)
+                    .unwrap()
+                    .to_rfc2822();
+
+                format!(
+                    "The status of {} changed from \"{}\" to \"{}\".\n\
+                     The last heartbeat was received on {}.",
+                    config.global.full_name, from, to, last_seen
+                )
+            }
+            Self::DeadProposed {
+                last_heartbeat,
+                deadline,
+            } => {
+                let timezone: FixedOffset =
+                    FixedOffset::east_opt(config.global.utc_offset * 60 * 60).unwrap();
+                let format_time = |timestamp: u64| -> String {
+                    timezone
+                        .timestamp_opt(timestamp as i64, 0)
+                        .unwrap()
+                        .to_rfc2822()
+                };
+                let mut body: String = format!(
+                    "{} has been missing since {}. Trusted users can now confirm \
+                     the \"Dead\" state on their own.",
+                    config.global.full_name,
+                    format_time(*last_heartbeat)
+                );
+                if let Some(deadline) = deadline {
+                    body.push_str(&format!(
+                        "\nUnless a heartbeat is received, it will be assumed on {}.",
+                        format_time(*deadline)
+                    ));
+                }
+                body
+            }
+            Self::BreakGlassUsed { timestamp, .. } => {
+                let timezone: FixedOffset =
+                    FixedOffset::east_opt(config.global.utc_offset * 60 * 60).unwrap();
+                let used: String = timezone
+                    .timestamp_opt(*timestamp as i64, 0)
+                    .unwrap()
+                    .to_rfc2822();
+
+                format!(
+                    "The break-glass token was used on {}, granting someone emergency \
+                     access as a trusted user. If this wasn't expected, take action now.",
+                    used
+                )
+            }
+        }
+    }
+
+    /// Details that are only sent to targets with a PGP key, as they
+    /// would otherwise pass through (e-mail) relays in plaintext.
+    fn details(&self) -> String {
+        match self {
+            Self::StateChanged {
+                last_message, note, ..
+            } => format!(
+                "Last heartbeat message: {}\nActive note: {}",
+                last_message,
+                note.as_deref().unwrap_or("N/A")
+            ),
+            Self::DeadProposed { .. } => {
+                "Trusted users confirm the state by logging in, and posting to /api/v1/verify."
+                    .into()
+            }
+            Self::BreakGlassUsed { address, .. } => format!("Used from address: {}", address),
+        }
+    }
+
+    /// Apprise notification type, which some services use to pick an icon/color.
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::StateChanged { to, .. } => match to {
+                LifeState::Alive => "success",
+                LifeState::ProbablyAlive | LifeState::Incapacitated => "warning",
+                LifeState::MissingOrDead | LifeState::Dead => "failure",
+            },
+            Self::DeadProposed { .. } => "failure",
+            Self::BreakGlassUsed { .. } => "warning",
+        }
+    }
+}
+
+/// Request body understood by the stateless `/notify/` endpoint of the Apprise API.
+/// See: <https://github.com/caronc/apprise-api#stateless-solution>
+#[derive(Serialize)]
+struct AppriseRequest<'a> {
+    urls: &'a str,
+    title: &'a str,
+    body: &'a str,
+    #[serde(rename = "type")]
+    kind: &'a str,
+}
+
+/// Parse an armored PGP public key, and check that it can be encrypted to.
+pub fn parse_pgp_key(armored_key: &str) -> Result<SignedPublicKey, String> {
+    let (key, _) = SignedPublicKey::from_string(armored_key).map_err(|err| err.to_string())?;
+
+    if !key.primary_key.algorithm().can_encrypt()
+        && !key
+            .public_subkeys
+            .iter()
+            .any(|subkey| subkey.key.algorithm().can_encrypt())
+    {
+        return Err("PGP key has no encryption capable (sub)key.".into());
+    }
+    Ok(key)
+}
+
+/// Encrypt a notification body to the given armored PGP public key,
+/// returning an ASCII armored PGP message.
+fn encrypt_body(armored_key: &str, body: &str) -> Result<String, String> {
+    let key: SignedPublicKey = parse_pgp_key(armored_key)?;
+
+    let mut builder = MessageBuilder::from_bytes("", body.as_bytes().to_vec())
+        .seipd_v1(OsRng, SymmetricKeyAlgorithm::AES256);
+
+    // prefer a dedicated encryption subkey over the primary key
+    match key
+        .public_subkeys
+        .iter()
+        .find(|subkey| subkey.key.algorithm().can_encrypt())
+    {
+        Some(subkey) => builder.encrypt_to_key(OsRng, &subkey.key),
+        None => builder.encrypt_to_key(OsRng, &key.primary_key),
+    }
+    .map_err(|err| err.to_string())?;
+
+    builder
+        .to_armored_string(OsRng, ArmorOptions::default())
+        .map_err(|err| err.to_string())
+}
+
+/// Derive a stable ID for a notification URL that can be stored in the database
+/// without leaking any credentials embedded in the URL.
+fn channel_id(url: &str) -> String {
+    hex::encode(&Sha256::digest(url.as_bytes())[..8])
+}
+
+/// Send a notification through the Apprise API, returning whether it was accepted.
+async fn post_to_apprise(
+    client: &reqwest::Client,
+    endpoint: &str,
+    req: &AppriseRequest<'_>,
+) -> bool {
+    match client.post(endpoint).json(req).send().await {
+        Err(err) => {
+            eprintln!("Failed to reach the Apprise API: {}", err);
+            false
+        }
+        Ok(resp) => {
+            if !resp.status().is_success() {
+                eprintln!(
+                    "Apprise API refused a notification with HTTP status {}.",
+                    resp.status()
+                );
+                return false;
+            }
+            true
+        }
+    }
+}
+
+/// Kind of the event behind a ledger key, e.g. "state:2" for "state:2@1767225600".
+fn event_kind(event_key: &str) -> &str {
+    event_key
+        .split_once('@')
+        .map_or(event_key, |(kind, _)| kind)
+}
+
+/// Check the notification ledger on whether an event should be delivered to a channel.
+fn should_deliver(
+    ledger: &[LedgerEntry],
+    channel: &str,
+    event_key: &str,
+    now: u64,
+    cooldown: u64,
+) -> bool {
+    if ledger
+        .iter()
+        .any(|entry| entry.channel == channel && entry.event == event_key)
+    {
+        println!("Notification '{}' already sent to {}.", event_key, channel);
+        return false;
+    }
+    // only repeats of the same kind of event (e.g. the same state) are held
+    // back, so a change to another state is never lost to the cooldown
+    let kind: &str = event_kind(event_key);
+    let last_sent: Option<u64> = ledger
+        .iter()
+        .filter(|entry| entry.channel == channel && event_kind(&entry.event) == kind)
+        .map(|entry| entry.timestamp)
+        .max();
+
+    if let Some(timestamp) = last_sent
+        && now < timestamp.saturating_add(cooldown)
+    {
+        println!(
+            "Notification '{}' to {} suppressed by cooldown.",
+            event_key, channel
+        );
+        return false;
+    }
+    true
+}
+
+/// Send the given event to every configured notification URL, to every
+/// visitor subscribed to Web Push notifications, and to every webhook
+/// registered for it.
+///
+/// We do not implement any notification service natively; Every URL is
+/// handed to an Apprise API server, which knows how to talk to dozens of
+/// services (e-mail, Matrix, Signal, ntfy, Discord, Telegram, etc.)
+///
+/// Deliveries are recorded in the notification ledger, so the same event
+/// is never sent twice to a channel, and no channel is sent the same kind
+/// of event (e.g. the same state) more than once per configured cooldown
+/// period. A change to another state is always delivered.
+///
+pub async fn dispatch(config: Arc<ServerConfig>, event: NotificationEvent) {
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    // security alerts are never held back by the cooldown
+    let cooldown: u64 = match event {
+        NotificationEvent::BreakGlassUsed { .. } => 0,
+        _ => u64::from(config.notifications.cooldown) * 60,
+    };
+    let event_key: String = event.ledger_key();
+
+    let db: Database = {
+        let _db_guard: MutexGuard<'_, ()> = database::lock().await;
+
+        match load_database(crate::DB_PATH) {
+            Err(err) => {
+                eprintln!("Could not read the notification ledger: {}", err);
+                return;
+            }
+            Ok(db) => db,
+        }
+    };
+    let apprise_enabled: bool =
+        config.notifications.apprise_api.is_some() && !config.notifications.urls.is_empty();
+
+    if !apprise_enabled && config.web_push.is_none() && db.webhooks.is_empty() {
+        return;
+    }
+
+    let title: String = event.title(&config);
+    let body: String = event.body(&config);
+    let client: reqwest::Client = reqwest::Client::new();
+    let mut delivered: Vec<LedgerEntry> = vec![];
+    let mut expired_subscriptions: Vec<String> = vec![];
+
+    if let Some(apprise_api) = config.notifications.apprise_api.as_ref() {
+        let endpoint: String = format!("{}/notify/", apprise_api.trim_end_matches('/'));
+
+        // send one request per URL, so one broken target doesn't stop the rest
+        for target in config.notifications.urls.iter() {
+            let channel: String = channel_id(target.url());
+
+            if !should_deliver(&db.notification_ledger, &channel, &event_key, now, cooldown) {
+                continue;
+            }
+            let target_body: String = match target.pgp_key() {
+                None => body.clone(),
+                Some(armored_key) => {
+                    let full_body: String = format!("{}\n\n{}", body, event.details());
+
+                    match encrypt_body(armored_key, &full_body) {
+                        Ok(encrypted) => encrypted,
+                        Err(err) => {
+                            // never fall back to plaintext for this target
+                            eprintln!("Could not encrypt notification for {}: {}", channel, err);
+                            continue;
+                        }
+                    }
+                }
+            };
+            let req: AppriseRequest = AppriseRequest {
+                urls: target.url(),
+                title: &title,
+                body: &target_body,
+                kind: event.kind(),
+            };
+
+            if post_to_apprise(&client, &endpoint, &req).await {
+                delivered.push(LedgerEntry {
+                    timestamp: now,
+                    channel,
+                    event: event_key.clone(),
+                });
+            }
+        }
+    }
+
+    // all visitor subscriptions share a single ledger channel
+    if let Some(web_push) = config.web_push.as_ref()
+        && should_deliver(
+            &db.notification_ledger,
+            WEB_PUSH_CHANNEL,
+            &event_key,
+            now,
+            cooldown,
+        )
+    {
+        for subscription in db.push_subscriptions.iter() {
+            match push::send(&client, web_push, subscription, &title, &body).await {
+                Delivery::Expired => expired_subscriptions.push(subscription.endpoint.clone()),
+                Delivery::Delivered | Delivery::Failed => (),
+            }
+        }
+        delivered.push(LedgerEntry {
+            timestamp: now,
+            channel: WEB_PUSH_CHANNEL.into(),
+            event: event_key.clone(),
+        });
+    }
+
+    let payload: String = webhooks::payload(&event, &title, &body, now);
+
+    for webhook in db.webhooks.iter() {
+        let channel: String = format!("webhook:{}", webhook.id);
+
+        if !webhook.wants(event.name())
+            || !should_deliver(&db.notification_ledger, &channel, &event_key, now, cooldown)
+        {
+            continue;
+        }
+        if webhooks::send(&client, webhook, &payload).await {
+            delivered.push(LedgerEntry {
+                timestamp: now,
+                channel,
+                event: event_key.clone(),
+            });
+        }
+    }
+
+    if delivered.is_empty() && expired_subscriptions.is_empty() {
+        return;
+    }
+    // record our deliveries in the ledger on disk
+    let _db_guard: MutexGuard<'_, ()> = database::lock().await;
+
+    let mut db: Database = match load_database(crate::DB_PATH) {
+        Err(err) => {
+            eprintln!("Could not read the notification ledger: {}", err);
+            return;
+        }
+        Ok(db) => db,
+    };
+    db.notification_ledger.append(&mut delivered);
+    db.push_subscriptions
+        .retain(|subscription| !expired_subscriptions.contains(&subscription.endpoint));
+
+    if let Err(err) = db.write_to_disk().await {
+        eprintln!("Could not sync the notification ledger to disk: {}", err);
+    }
+}
+
+/// Send a test notification to every notification target, so that the person can
+/// check their setup. It's kept out of the ledger, so that it doesn't hold back a
+/// real notification for the cooldown, and visitors subscribed to web push don't get it.
+///
+/// Returns how many targets it was delivered to, out of how many there are.
+///
+pub async fn send_test(config: Arc<ServerConfig>) -> (usize, usize) {
+    let Some(apprise_api) = config.notifications.apprise_api.as_ref() else {
+        return (0, 0);
+    };
+    let endpoint: String = format!("{}/notify/", apprise_api.trim_end_matches('/'));
+    let client: reqwest::Client = reqwest::Client::new();
+
+    let title: String = format!("Test notification for {}", config.global.full_name);
+    let body: String = format!(
+        "This is a test of the notifications about {}'s status. Nothing has changed.",
+        config.global.full_name
+    );
+    let mut delivered: usize = 0;
+
+    for target in config.notifications.urls.iter() {
+        // targets with a key must be able to decrypt what they get
+        let target_body: String = match target.pgp_key() {
+            None => body.clone(),
+            Some(armored_key) => match encrypt_body(armored_key, &body) {
+                Ok(encrypted) => encrypted,
+                Err(err) => {
+                    eprintln!(
+                        "Could not encrypt notification for {}: {}",
+                        channel_id(target.url()),
+                        err
+                    );
+                    continue;
+                }
+            },
+        };
+        let req: AppriseRequest = AppriseRequest {
+            urls: target.url(),
+            title: &title,
+            body: &target_body,
+            kind: "info",
+        };
+
+        if post_to_apprise(&client, &endpoint, &req).await {
+            delivered += 1;
+        }
+    }
+    (delivered, config.notifications.urls.len())
+}
+
+/// Send every configured posthumous message that has not been delivered yet.
+///
+/// Each delivery is recorded in the notification ledger, so calling this
+/// again (e.g. on every tick) only retries the messages that failed.
+///
+pub async fn deliver_posthumous(config: Arc<ServerConfig>) {
+    let Some(apprise_api) = config.notifications.apprise_api.as_ref() else {
+        return;
+    };
+    let _posthumous_guard: MutexGuard<'_, ()> = POSTHUMOUS_LOCK.lock().await;
+
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let db: Database = {
+        let _db_guard: MutexGuard<'_, ()> = database::lock().await;
+
+        match load_database(crate::DB_PATH) {
+            Err(err) => {
+                eprintln!("Could not read the notification ledger: {}", err);
+                return;
+            }
+            Ok(db) => db,
+        }
+    };
+
+    let endpoint: String = format!("{}/notify/", apprise_api.trim_end_matches('/'));
+    let title: String = format!("A message from {}", config.global.full_name);
+    let client: reqwest::Client = reqwest::Client::new();
+    let mut delivered: Vec<LedgerEntry> = vec![];
+
+    for message in config.posthumous_messages.iter() {
+        let channel: String = channel_id(&message.url);
+        let event_key: String = format!("posthumous:{}", message.recipient);
+
+        if db
+            .notification_ledger
+            .iter()
+            .any(|entry| entry.channel == channel && entry.event == event_key)
+        {
+            continue;
+        }
+        let req: AppriseRequest = AppriseRequest {
+            urls: &message.url,
+            title: &title,
+            body: &message.body,
+            kind: "info",
+        };
+
+        if post_to_apprise(&client, &endpoint, &req).await {
+            println!("Posthumous message delivered to '{}'.", message.recipient);
+
+            delivered.push(LedgerEntry {
+                timestamp: now,
+                channel,
+                event: event_key,
+            });
+        }
+    }
+
+    if delivered.is_empty() {
+        return;
+    }
+    if let Err(err) = database::modify(|db| db.notification_ledger.append(&mut delivered)).await {
+        eprintln!("Could not sync the notification ledger to disk: {}", err);
+    }
+}
+
+/// Publish every configured scheduled post that is due, and was not published yet.
+///
+/// Posts are due a number of days after the "Dead" state is confirmed, which is when
+/// the last transition to "Dead" happened, or when the release countdown ended.
+/// As everything is read back from the database, the schedule survives restarts.
+///
+pub async fn publish_scheduled(config: Arc<ServerConfig>) {
+    let Some(apprise_api) = config.notifications.apprise_api.as_ref() else {
+        return;
+    };
+    let _posthumous_guard: MutexGuard<'_, ()> = POSTHUMOUS_LOCK.lock().await;
+
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let db: Database = {
+        let _db_guard: MutexGuard<'_, ()> = database::lock().await;
+
+        match load_database(crate::DB_PATH) {
+            Err(err) => {
+                eprintln!("Could not read the notification ledger: {}", err);
+                return;
+            }
+            Ok(db) => db,
+        }
+    };
+
+    let Some(died) = db
+        .transitions
+        .iter()
+        .rev()
+        .find(|transition| transition.to == LifeState::Dead)
+        .map(|transition| transition.timestamp)
+    else {
+        return;
+    };
+    let confirmed: u64 = match db.release_countdown.as_ref() {
+        Some(countdown) => died.max(countdown.ends),
+        None => died,
+    };
+
+    let endpoint: String = format!("{}/notify/", apprise_api.trim_end_matches('/'));
+    let client: reqwest::Client = reqwest::Client::new();
+    let mut delivered: Vec<LedgerEntry> = vec![];
+
+    for post in config.scheduled_posts.iter() {
+        if now < confirmed + u64::from(post.after) * 24 * 60 * 60 {
+            continue;
+        }
+        let channel: String = channel_id(&post.url);
+        let event_key: String = format!("scheduled:{}", post.id);
+
+        if db
+            .notification_ledger
+            .iter()
+            .any(|entry| entry.channel == channel && entry.event == event_key)
+        {
+            continue;
+        }
+        let req: AppriseRequest = AppriseRequest {
+            urls: &post.url,
+            title: &post.title,
+            body: &post.body,
+            kind: "info",
+        };
+
+        if post_to_apprise(&client, &endpoint, &req).await {
+            println!("Scheduled post '{}' published.", post.id);
+
+            delivered.push(LedgerEntry {
+                timestamp: now,
+                channel,
+                event: event_key,
+            });
+        }
+    }
+
+    if delivered.is_empty() {
+        return;
+    }
+    if let Err(err) = database::modify(|db| db.notification_ledger.append(&mut delivered)).await {
+        eprintln!("Could not sync the notification ledger to disk: {}", err);
+    }
+}
@@ -0,0 +1,133 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::{
+    affidavit, api, audit, auth_log, break_glass, check_in, device_tokens, guestbook, invitation,
+    media, note, pow, pow_metrics, push, rate_limit, release, server_info, sources, state_override,
+    stats, status_signing, summary, timestamping, transitions, trusted, webhooks, will,
+};
+use axum::body::Body;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use utoipa::openapi::security::{Http, HttpAuthScheme, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+/// OpenAPI document of the versioned API, generated from the
+/// `#[utoipa::path]` annotations of every handler listed here.
+///
+/// New endpoints must be added to `paths(...)`, or they won't be documented.
+///
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Am I Alive",
+        description = "API of an \"Am I Alive\" instance, a dead man's switch.",
+        license(name = "AGPL-3.0-or-later"),
+    ),
+    paths(
+        api::status_api,
+        api::status_txt,
+        status_signing::key_api,
+        api::events_api,
+        transitions::transitions_api,
+        summary::summary_api,
+        server_info::server_info_api,
+        api::heartbeat_api,
+        pow::ws_handler,
+        pow::challenge_api,
+        rate_limit::rate_limit_api,
+        media::media_api,
+        sources::ping::ping_api,
+        sources::ingest::ingest_api,
+        sources::batch::batch_api,
+        sources::sms::sms_api,
+        sources::home_assistant::home_assistant_api,
+        sources::ssh::ssh_login_api,
+        trusted::login_api,
+        trusted::logout_api,
+        trusted::session_api,
+        trusted::verify_api,
+        trusted::history_api,
+        trusted::heartbeats_api,
+        stats::stats_api,
+        check_in::check_in_api,
+        guestbook::sign_api,
+        guestbook::list_api,
+        guestbook::approve_api,
+        guestbook::delete_api,
+        affidavit::affidavit_api,
+        timestamping::timestamps_api,
+        break_glass::break_glass_api,
+        invitation::accept_api,
+        trusted::admin_login_api,
+        audit::audit_api,
+        auth_log::auth_attempts_api,
+        pow_metrics::pow_metrics_api,
+        state_override::state_override_api,
+        webhooks::list_api,
+        webhooks::create_api,
+        webhooks::delete_api,
+        device_tokens::list_api,
+        device_tokens::create_api,
+        device_tokens::delete_api,
+        note::get_note_api,
+        note::put_note_api,
+        note::delete_note_api,
+        note::get_private_note_api,
+        note::put_private_note_api,
+        note::delete_private_note_api,
+        invitation::create_api,
+        will::payload_api,
+        will::key_api,
+        will::attachments_api,
+        will::attachment_api,
+        release::cancel_api,
+        push::subscribe_api,
+        push::unsubscribe_api,
+    ),
+    modifiers(&BearerAuth),
+)]
+struct ApiDoc;
+
+/// Session tokens of trusted users, and the tokens of heartbeat sources,
+/// are all sent in the `Authorization: Bearer <token>` HTTP header.
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        openapi
+            .components
+            .get_or_insert_default()
+            .add_security_scheme(
+                "bearer",
+                SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)),
+            );
+    }
+}
+
+/// Handles requests on `/api/openapi.json`, which serves the OpenAPI
+/// document of the versioned API, for client authors.
+///
+pub async fn openapi_api() -> impl IntoResponse {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(ApiDoc::openapi().to_json().unwrap()))
+        .unwrap()
+}
@@ -0,0 +1,74 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+use rand_core::OsRng;
+
+use crate::config::PasswordHashing;
+
+/// Whether the given password matches the given hash.
+///
+/// The hash is verified with the algorithm and parameters encoded in it, e.g.
+/// `$argon2id$v=19$m=19456,t=2,p=1$...`, rather than with the configured ones,
+/// so that hashes made before the parameters were changed keep working.
+///
+pub fn verify(password: &str, hash: &PasswordHash) -> bool {
+    // each verifier only accepts the algorithms it implements.
+    // support for other algorithms is added by adding their verifier here
+    let argon2: Argon2 = Argon2::default();
+    let verifiers: [&dyn PasswordVerifier; 1] = [&argon2];
+
+    hash.verify_password(&verifiers, password).is_ok()
+}
+
+/// Whether the given hash can be verified, i.e. is well formed
+/// and made with one of the supported algorithms.
+pub fn is_supported(hash: &str) -> bool {
+    match PasswordHash::new(hash) {
+        Ok(hash) => Algorithm::try_from(hash.algorithm).is_ok() && Params::try_from(&hash).is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Build the hasher for the passwords hashed by the server,
+/// e.g. those of trusted users accepting an invitation.
+pub fn hasher(config: &PasswordHashing) -> Result<Argon2<'static>, String> {
+    let algorithm: Algorithm = Algorithm::new(&config.algorithm)
+        .map_err(|_| format!("Unsupported hash algorithm '{}'.", config.algorithm))?;
+    let params: Params = Params::new(
+        config.memory_cost,
+        config.time_cost,
+        config.parallelism,
+        None,
+    )
+    .map_err(|err| format!("Invalid hash parameters: {}", err))?;
+
+    Ok(Argon2::new(algorithm, Version::V0x13, params))
+}
+
+/// Hash a password with the configured algorithm and parameters, and a random salt.
+pub fn hash(config: &PasswordHashing, password: &str) -> Result<String, String> {
+    let salt: SaltString = SaltString::generate(&mut OsRng);
+
+    hasher(config)?
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|err| err.to_string())
+}
@@ -17,26 +17,52 @@
     License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::api::{PowSolution, get_proxied_client_ip};
+use crate::api::{ClientIp, PowSolution, check_heartbeat_ip};
+use crate::config::{Pow, PowAlgorithm};
+use crate::pow_metrics::PowMetrics;
+use crate::problem::{self, Problem};
 use crate::state::{RateLimit, ServerState};
 use axum::body::Body;
 use axum::extract::State;
 use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
-use axum::http::{HeaderMap, StatusCode};
+use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
-use serde_json::json;
+use pow_solver::Hasher;
+use serde::Serialize;
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::net::IpAddr;
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
-use tokio::sync::{MutexGuard, broadcast};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{MutexGuard, Semaphore, broadcast};
 use tokio::time::{Duration, Interval, interval};
+use utoipa::ToSchema;
 
 /// Interval, in milliseconds, for sending new PoW challenges over WS.
 pub static CHALLENGE_INTERVAL: u64 = 500;
 /// Time period, in milliseconds, for which a PoW challenge is valid for.
 pub static CHALLENGE_VALID_PERIOD: u128 = 10000;
+/// Memory used to hash each attempt at an Argon2id challenge. (in KiB)
+pub const ARGON2_MEMORY_COST: u32 = 8192;
+/// Iterations of each Argon2id hash.
+pub const ARGON2_TIME_COST: u32 = 1;
+/// Lanes of each Argon2id hash.
+pub const ARGON2_PARALLELISM: u32 = 1;
+/// Most Argon2id hashes of submitted solutions that are reconstructed at once.
+pub const MAX_ARGON2_VERIFICATIONS: usize = 2;
+
+/// Time between two adjustments of an adaptive difficulty. (in seconds)
+const ADAPT_INTERVAL: u64 = 60;
+/// Failed authentications from an address that raise the difficulty of its challenges by one.
+const FAILURES_PER_PENALTY: u32 = 3;
+/// Time after its last failed authentication for which an address is penalized. (in milliseconds)
+const PENALTY_MEMORY: u128 = 24 * 60 * 60 * 1000;
+/// Most penalized addresses that are remembered at once.
+const MAX_PENALIZED_ADDRESSES: usize = 10_000;
+/// Solutions with a forged hash from an address after which its solutions are refused
+/// without reconstructing their hash, until it authenticates or its penalty is lifted.
+const MAX_FORGED_SOLUTIONS: u32 = 3;
 
 /// Hardcoded difficulties 1-5 (as per PoW concept article)
 /// with their respective expected leading zero hex bytes.
@@ -48,16 +74,136 @@ pub static DIFFICULTIES: [(u128, &str); 5] = [
     (0x00000fffffffffffffffffffffffffff, "00000"),
 ];
 
+/// Current difficulty of the PoW challenges, see [`adapt_difficulty`]
+pub struct Difficulty {
+    /// Range 0-4, inclusive.
+    pub index: usize,
+    /// Index before the last change, still accepted for the challenges issued before it.
+    previous_index: usize,
+    /// Unix timestamp, in milliseconds, of the last change.
+    changed_at_ms: u128,
+}
+
+impl Difficulty {
+    pub fn new(index: usize) -> Self {
+        Self {
+            index,
+            previous_index: index,
+            changed_at_ms: 0,
+        }
+    }
+
+    /// Index of the difficulty a challenge issued at the given time must be solved at.
+    /// A solution of a challenge issued before the last change may meet either one.
+    fn index_at(&self, timestamp_ms: u128) -> usize {
+        match timestamp_ms < self.changed_at_ms {
+            true => self.index.min(self.previous_index),
+            false => self.index,
+        }
+    }
+}
+
+/// Failed authentications from an address, see [`PoWState::note_auth_failure`]
+pub struct Penalty {
+    failures: u32,
+    /// Unix timestamp, in milliseconds, of the last one.
+    last_failure_ms: u128,
+    /// Solutions whose hash didn't match the one we reconstructed, see [`MAX_FORGED_SOLUTIONS`]
+    forged_solutions: u32,
+}
+
+impl Penalty {
+    /// Difficulty levels added to a challenge issued to the address at the given time.
+    /// A solution of a challenge issued before the last failure may meet the level before it.
+    fn levels_at(&self, timestamp_ms: u128) -> usize {
+        let failures: u32 = match timestamp_ms < self.last_failure_ms {
+            true => self.failures - 1,
+            false => self.failures,
+        };
+        (failures / FAILURES_PER_PENALTY) as usize
+    }
+}
+
 /// State used by the PoW challenge generator Tokio task.
 #[derive(Clone)]
 pub struct PoWState {
     /// Secret used to generate challenges that can't be predicted.
     pub secret: &'static str,
-    pub difficulty: u128,
-    /// Range 0-4, inclusive.
-    pub difficulty_index: usize,
+    pub algorithm: PowAlgorithm,
+    pub difficulty: Arc<std::sync::Mutex<Difficulty>>,
+    /// Failed PoW solutions and authentications since the difficulty was last adapted.
+    pub failures: Arc<AtomicU64>,
+    /// Failed authentications of the addresses that recently had any, raising
+    /// the difficulty of their own challenges on top of the global one.
+    pub penalties: Arc<std::sync::Mutex<HashMap<IpAddr, Penalty>>>,
+    pub metrics: Arc<std::sync::Mutex<PowMetrics>>,
     /// Tokio async channel for broadcasted PoW challenges for auth rate limiting.
-    pub tx: Arc<broadcast::Sender<String>>,
+    pub tx: Arc<broadcast::Sender<Challenge>>,
+    /// Solutions accepted while their challenge is still valid, by address, nonce
+    /// and challenge timestamp, so that a captured request can't be replayed.
+    pub used_solutions: Arc<std::sync::Mutex<HashSet<(IpAddr, u64, u128)>>>,
+    /// Permits to reconstruct the hash of a solution, see [`MAX_ARGON2_VERIFICATIONS`]
+    pub verifications: Arc<Semaphore>,
+}
+
+/// A PoW challenge: find a nonce for which the first 128 bits of
+/// SHA256(user address + seed + nonce) are below the difficulty target.
+#[derive(Clone, Serialize, ToSchema)]
+pub struct Challenge {
+    /// The address the server sees the client's requests come from.
+    user_address: String,
+    seed: String,
+    /// Hex encoded 128-bit target.
+    difficulty: String,
+    /// Unix timestamp, in milliseconds, of when the challenge was issued.
+    #[schema(value_type = u64)]
+    timestamp: u128,
+    algorithm: PowAlgorithm,
+    /// Parameters of the hash, if it is Argon2id.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    argon2: Option<Argon2Parameters>,
+}
+
+/// Parameters of the Argon2id hash of a challenge, whose salt is the seed,
+/// and whose output is 32 bytes long.
+#[derive(Clone, Serialize, ToSchema)]
+struct Argon2Parameters {
+    /// In KiB
+    memory_cost: u32,
+    time_cost: u32,
+    parallelism: u32,
+}
+
+impl Challenge {
+    /// Issue a new challenge, for no address yet, see [`Challenge::for_address`]
+    fn new(pow_state: &PoWState) -> Self {
+        let timestamp_ms: u128 = current_timestamp_ms();
+
+        Self {
+            user_address: String::new(),
+            seed: generate_seed(pow_state.secret, timestamp_ms),
+            difficulty: String::new(),
+            timestamp: timestamp_ms,
+            algorithm: pow_state.algorithm,
+            argon2: match pow_state.algorithm {
+                PowAlgorithm::Sha256 => None,
+                PowAlgorithm::Argon2id => Some(Argon2Parameters {
+                    memory_cost: ARGON2_MEMORY_COST,
+                    time_cost: ARGON2_TIME_COST,
+                    parallelism: ARGON2_PARALLELISM,
+                }),
+            },
+        }
+    }
+
+    /// The challenge as sent to the given address, at the difficulty it must be solved at.
+    fn for_address(mut self, pow_state: &PoWState, ip: IpAddr) -> Self {
+        let difficulty_index: usize = pow_state.difficulty_index(ip, self.timestamp);
+
+        self.user_address = ip.to_string();
+        self.difficulty = format!("{:032x}", DIFFICULTIES[difficulty_index].0);
+        self
+    }
 }
 
 /// Generate PoW challenges every 50ms.
@@ -67,51 +213,258 @@ pub async fn generate_pow_challenges(pow_state: PoWState) {
     loop {
         interval.tick().await;
 
-        let timestamp_ms: u128 = current_timestamp_ms();
-        let seed: String = generate_seed(pow_state.secret, timestamp_ms);
+        // the address and difficulty are filled in per web socket connection
+        let _ = pow_state.tx.send(Challenge::new(&pow_state));
+    }
+}
 
-        let challenge = json!({
-            "user_address": "{USER_ADDRESS}", // replaced per web socket connection
-            "seed": seed,
-            "difficulty": format!("{:032x}", pow_state.difficulty),
-            "timestamp": timestamp_ms
-        });
+/// Raise the difficulty by one every minute with at least `raise_after` failed PoW
+/// solutions or authentications, up to `max_difficulty`, and lower it back by one
+/// every quiet minute without any, down to the configured `difficulty`.
+pub async fn adapt_difficulty(server_state: ServerState) {
+    let pow_state: &PoWState = &server_state.pow_state;
+    let config: &Pow = &server_state.config.pow;
 
-        let _ = pow_state.tx.send(challenge.to_string());
+    let base_index: usize = config.difficulty as usize - 1;
+    let max_index: usize = config.max_difficulty.unwrap_or(config.difficulty) as usize - 1;
+
+    if max_index <= base_index {
+        // the difficulty is static
+        return;
+    }
+    let mut interval: Interval = interval(Duration::from_secs(ADAPT_INTERVAL));
+
+    loop {
+        interval.tick().await;
+
+        let failures: u64 = pow_state.failures.swap(0, Ordering::Relaxed);
+        let mut difficulty = pow_state.difficulty.lock().unwrap();
+
+        let index: usize = match failures {
+            0 => difficulty.index.saturating_sub(1).max(base_index),
+            _ if failures >= config.raise_after => (difficulty.index + 1).min(max_index),
+            _ => difficulty.index,
+        };
+        if index != difficulty.index {
+            println!(
+                "PoW difficulty changed from {} to {}, after {} failed attempts in a minute.",
+                difficulty.index + 1,
+                index + 1,
+                failures
+            );
+            difficulty.previous_index = difficulty.index;
+            difficulty.index = index;
+            difficulty.changed_at_ms = current_timestamp_ms();
+        }
     }
 }
 
-pub fn verify_pow_solution(state: PoWState, ip: IpAddr, pow: PowSolution) -> bool {
-    let now_ms: u128 = current_timestamp_ms();
+/// Why a PoW solution was rejected.
+pub enum PowError {
+    /// The challenge it solves is no longer valid.
+    Stale,
+    /// It doesn't solve the challenge, or the challenge isn't one of ours.
+    Invalid,
+    /// It was already used, e.g. by a replayed request.
+    Reused,
+}
 
-    if (now_ms - pow.timestamp_ms) > CHALLENGE_VALID_PERIOD {
-        // submitted solution too late
-        return false;
+/// Verify a PoW solution submitted from the given address.
+///
+/// Each solution is only accepted once: it is remembered, by address, nonce and
+/// challenge timestamp (which together determine its hash), until its challenge
+/// expires, so one solved challenge can't be replayed for a burst of password guesses.
+///
+pub async fn verify_pow_solution(state: PoWState, ip: IpAddr, pow: PowSolution) -> bool {
+    check_pow_solution(state, ip, pow, None).await.is_ok()
+}
+
+/// Like [`verify_pow_solution`], but telling why the solution was rejected.
+///
+/// A solution that commits to a payload is only accepted along with the given
+/// payload digest, e.g. that of the heartbeat it was sent with.
+///
+/// Argon2id hashes are reconstructed off the async runtime, a few at a time, and
+/// an address that sent [`MAX_FORGED_SOLUTIONS`] solutions with a made up hash gets
+/// the rest refused without hashing them, so that it can't make us work for free.
+///
+pub async fn check_pow_solution(
+    state: PoWState,
+    ip: IpAddr,
+    pow: PowSolution,
+    payload_digest: Option<&str>,
+) -> Result<(), PowError> {
+    let checked: Result<(), PowError> = check_solution(&state, ip, pow, payload_digest).await;
+
+    if let Err(err) = &checked {
+        state.metrics.lock().unwrap().record_failure(err);
+        state.note_failure();
     }
-    // re-generate seed using the solution's timestamp and our secret
-    let seed: String = generate_seed(state.secret, pow.timestamp_ms);
-    // reconstruct their hash (address + seed + nonce)
-    let message: String = format!("{}{}{}", &ip.to_string(), &seed, pow.nonce);
-    let hash: String = hex::encode(Sha256::digest(message.as_bytes()));
+    checked
+}
 
-    if pow.hash != hash {
-        // SHA256(address + seed + nonce) does not output the hash they submitted
-        return false;
+async fn check_solution(
+    state: &PoWState,
+    ip: IpAddr,
+    pow: PowSolution,
+    payload_digest: Option<&str>,
+) -> Result<(), PowError> {
+    let now_ms: u128 = current_timestamp_ms();
+
+    if pow.timestamp_ms > now_ms || now_ms - pow.timestamp_ms > CHALLENGE_VALID_PERIOD {
+        // submitted solution too late
+        return Err(PowError::Stale);
     }
+    let difficulty_index: usize = state.difficulty_index(ip, pow.timestamp_ms);
 
-    match pow.hash.find(DIFFICULTIES[state.difficulty_index].1) {
+    // check the hash they submitted first, as an Argon2id hash is costly to reconstruct
+    match pow.hash.find(DIFFICULTIES[difficulty_index].1) {
         None => {
             // no continuous n zero bits found in hash
-            return false;
+            return Err(PowError::Invalid);
         }
         Some(i) => {
             if i != 0 {
                 // no leading n zero bits found
-                return false;
+                return Err(PowError::Invalid);
             }
         }
     }
-    true
+    if pow.payload_digest.is_some() && pow.payload_digest.as_deref() != payload_digest {
+        // the solution was made for another request
+        return Err(PowError::Invalid);
+    }
+    if state.is_forging(ip) {
+        // don't reconstruct hashes for an address that keeps making them up
+        return Err(PowError::Invalid);
+    }
+    // re-generate seed using the solution's timestamp and our secret
+    let seed: String = generate_seed(state.secret, pow.timestamp_ms);
+    let algorithm: PowAlgorithm = state.algorithm;
+    let payload: String = pow.payload_digest.clone().unwrap_or_default();
+
+    // reconstruct their hash (address + seed + nonce + payload digest)
+    let hash: String = match algorithm {
+        PowAlgorithm::Sha256 => {
+            solution_hash(algorithm, &ip.to_string(), &seed, pow.nonce, &payload)
+        }
+        PowAlgorithm::Argon2id => {
+            let _permit = state.verifications.acquire().await.unwrap();
+
+            tokio::task::spawn_blocking(move || {
+                solution_hash(algorithm, &ip.to_string(), &seed, pow.nonce, &payload)
+            })
+            .await
+            .unwrap()
+        }
+    };
+
+    if pow.hash != hash {
+        // the hash of address + seed + nonce + payload digest is not the hash they submitted
+        state.note_forged_solution(ip);
+        return Err(PowError::Invalid);
+    }
+    let mut used_solutions = state.used_solutions.lock().unwrap();
+
+    // solutions of stale challenges are rejected anyway, no need to remember them
+    used_solutions.retain(|(_, _, timestamp_ms)| {
+        now_ms.saturating_sub(*timestamp_ms) <= CHALLENGE_VALID_PERIOD
+    });
+    if !used_solutions.insert((ip, pow.nonce, pow.timestamp_ms)) {
+        return Err(PowError::Reused);
+    }
+    state
+        .metrics
+        .lock()
+        .unwrap()
+        .record_solution(difficulty_index, now_ms.saturating_sub(pow.timestamp_ms));
+    Ok(())
+}
+
+impl PoWState {
+    /// Index of the difficulty a challenge issued to the given address at the given time
+    /// must be solved at: the global one, raised by the address's penalty, if any.
+    fn difficulty_index(&self, ip: IpAddr, timestamp_ms: u128) -> usize {
+        let index: usize = self.difficulty.lock().unwrap().index_at(timestamp_ms);
+        let penalty: usize = match self.penalties.lock().unwrap().get(&ip) {
+            Some(penalty) => penalty.levels_at(timestamp_ms),
+            None => 0,
+        };
+        (index + penalty).min(DIFFICULTIES.len() - 1)
+    }
+
+    /// Count a failed PoW solution or authentication towards raising the difficulty.
+    pub fn note_failure(&self) {
+        self.failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a failed authentication from the given address towards raising the global
+    /// difficulty, and that of the address's own challenges, every [`FAILURES_PER_PENALTY`]
+    /// failures, until it authenticates or stops failing for [`PENALTY_MEMORY`].
+    ///
+    /// When too many addresses are penalized, the one that failed the longest
+    /// ago is forgiven, so that an attacker with many addresses can't exhaust
+    /// the server's memory.
+    ///
+    pub fn note_auth_failure(&self, ip: IpAddr) {
+        self.note_failure();
+
+        let now_ms: u128 = current_timestamp_ms();
+        let mut penalties = self.penalties.lock().unwrap();
+
+        if penalties.len() >= MAX_PENALIZED_ADDRESSES && !penalties.contains_key(&ip) {
+            forget_expired_penalties(&mut penalties, now_ms);
+
+            if penalties.len() >= MAX_PENALIZED_ADDRESSES
+                && let Some(oldest) = penalties
+                    .iter()
+                    .min_by_key(|(_, penalty)| penalty.last_failure_ms)
+                    .map(|(ip, _)| *ip)
+            {
+                penalties.remove(&oldest);
+            }
+        }
+        let penalty: &mut Penalty = penalties.entry(ip).or_insert(Penalty {
+            failures: 0,
+            last_failure_ms: 0,
+            forged_solutions: 0,
+        });
+        penalty.failures += 1;
+        penalty.last_failure_ms = now_ms;
+    }
+
+    /// Count a solution with a made up hash from the given address, as a failed
+    /// authentication, and towards refusing its solutions, see [`MAX_FORGED_SOLUTIONS`]
+    fn note_forged_solution(&self, ip: IpAddr) {
+        self.note_auth_failure(ip);
+
+        if let Some(penalty) = self.penalties.lock().unwrap().get_mut(&ip) {
+            penalty.forged_solutions += 1;
+        }
+    }
+
+    /// Whether the given address sent too many solutions with a made up hash.
+    fn is_forging(&self, ip: IpAddr) -> bool {
+        self.penalties
+            .lock()
+            .unwrap()
+            .get(&ip)
+            .is_some_and(|penalty| penalty.forged_solutions >= MAX_FORGED_SOLUTIONS)
+    }
+
+    /// Lift the penalty of an address that authenticated successfully.
+    pub fn forgive(&self, ip: IpAddr) {
+        self.penalties.lock().unwrap().remove(&ip);
+    }
+
+    /// Lift the penalties of the addresses that haven't failed for [`PENALTY_MEMORY`].
+    pub fn forget_expired_penalties(&self) {
+        forget_expired_penalties(&mut self.penalties.lock().unwrap(), current_timestamp_ms());
+    }
+}
+
+fn forget_expired_penalties(penalties: &mut HashMap<IpAddr, Penalty>, now_ms: u128) {
+    penalties.retain(|_, penalty| now_ms < penalty.last_failure_ms + PENALTY_MEMORY);
 }
 
 fn current_timestamp_ms() -> u128 {
@@ -121,6 +474,32 @@ fn current_timestamp_ms() -> u128 {
         .as_millis()
 }
 
+/// Hash an attempt at a challenge with the given algorithm, hex encoded,
+/// the same way clients do with the [`pow_solver`] crate.
+pub fn solution_hash(
+    algorithm: PowAlgorithm,
+    user_address: &str,
+    seed: &str,
+    nonce: u64,
+    payload_digest: &str,
+) -> String {
+    let hasher: Hasher = match algorithm {
+        PowAlgorithm::Sha256 => Hasher::Sha256,
+        PowAlgorithm::Argon2id => Hasher::argon2id(&pow_solver::Argon2Parameters {
+            memory_cost: ARGON2_MEMORY_COST,
+            time_cost: ARGON2_TIME_COST,
+            parallelism: ARGON2_PARALLELISM,
+        })
+        .unwrap(),
+    };
+    // our seeds are long enough to salt Argon2id with
+    hex::encode(
+        hasher
+            .hash(user_address, seed, nonce, payload_digest)
+            .unwrap(),
+    )
+}
+
 /// Generate SHA256(seed + timestamp)
 pub fn generate_seed(secret: &'static str, timestamp_ms: u128) -> String {
     let message: String = format!("{}{}", secret, timestamp_ms);
@@ -128,14 +507,85 @@ pub fn generate_seed(secret: &'static str, timestamp_ms: u128) -> String {
     hex::encode(hash)
 }
 
-/// WebSocket handler for `/api/pow`, which serves PoW challenges at an interval.
+/// WebSocket handler for `/api/v1/pow`, which serves PoW challenges at an interval.
+#[utoipa::path(
+    get,
+    path = "/api/v1/pow",
+    tag = "heartbeats",
+    description = "WebSocket, on which PoW challenges are sent at an interval.",
+    responses(
+        (status = 101, description = "Switching to the WebSocket protocol"),
+        (status = 403, description = "Heartbeats can't be sent from this address", body = Problem, content_type = "application/problem+json"),
+        (status = 429, description = "Blocked after too many failed attempts", body = Problem, content_type = "application/problem+json"),
+        (status = 401, description = "Any refusal, with uniform errors", body = Problem, content_type = "application/problem+json"),
+    )
+)]
 pub async fn ws_handler(
     ws: WebSocketUpgrade,
-    headers: HeaderMap,
+    ClientIp(ip): ClientIp,
     State(server_state): State<ServerState>,
 ) -> impl IntoResponse {
-    // we will also enforce the IP-based rate limit block on this WebSocket endpoint
-    let ip: IpAddr = get_proxied_client_ip(&headers);
+    let started: Instant = Instant::now();
+
+    if let Err(problem) = may_get_challenges(&server_state, ip).await {
+        return problem::uniform(&server_state, started, problem)
+            .await
+            .into_response();
+    }
+
+    // finalize the upgrade process by returning upgrade callback.
+    // we can customize the callback by sending additional info such as address.
+    ws.on_upgrade(move |ws| handle_websocket(ws, ip, server_state.pow_state))
+}
+
+/// Handles requests on `/api/v1/pow/challenge`, which issues a single PoW challenge,
+/// for clients that would rather not keep a WebSocket open, e.g. scripts and cron jobs.
+#[utoipa::path(
+    get,
+    path = "/api/v1/pow/challenge",
+    tag = "heartbeats",
+    responses(
+        (status = 200, description = "A fresh challenge, valid for 10 seconds", body = Challenge),
+        (status = 403, description = "Heartbeats can't be sent from this address", body = Problem, content_type = "application/problem+json"),
+        (status = 429, description = "Blocked after too many failed attempts", body = Problem, content_type = "application/problem+json"),
+        (status = 401, description = "Any refusal, with uniform errors", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn challenge_api(
+    ClientIp(ip): ClientIp,
+    State(server_state): State<ServerState>,
+) -> impl IntoResponse {
+    let started: Instant = Instant::now();
+
+    if let Err(problem) = may_get_challenges(&server_state, ip).await {
+        return problem::uniform(&server_state, started, problem)
+            .await
+            .into_response();
+    }
+    let challenge: Challenge =
+        Challenge::new(&server_state.pow_state).for_address(&server_state.pow_state, ip);
+
+    server_state
+        .pow_state
+        .metrics
+        .lock()
+        .unwrap()
+        .record_challenge(false);
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .header("Cache-Control", "no-store")
+        .body(Body::from(serde_json::to_string(&challenge).unwrap()))
+        .unwrap()
+}
+
+/// Check whether the given address may get PoW challenges, on either endpoint.
+async fn may_get_challenges(server_state: &ServerState, ip: IpAddr) -> Result<(), Problem> {
+    // addresses that can't send heartbeats have no use for challenges either
+    check_heartbeat_ip(server_state, ip)?;
+
+    // we will also enforce the IP-based rate limit block on the challenge endpoints
 
     let locked_map: MutexGuard<'_, HashMap<IpAddr, RateLimit>> =
         server_state.rate_limited_ips.lock().await;
@@ -149,30 +599,27 @@ pub async fn ws_handler(
 
         if now < rate_limit.timestamp {
             // return here to enforce rate limit, and send seconds left until retry available
-            return Response::builder()
-                .status(StatusCode::TOO_MANY_REQUESTS)
-                .header("Retry-After", rate_limit.timestamp - now)
-                .body(Body::default())
-                .unwrap();
+            return Err(Problem::new(StatusCode::TOO_MANY_REQUESTS, "rate_limited")
+                .detail("Blocked after too many failed attempts.")
+                .retry_after(rate_limit.timestamp - now));
         }
     }
-
-    // finalize the upgrade process by returning upgrade callback.
-    // we can customize the callback by sending additional info such as address.
-    ws.on_upgrade(move |ws| handle_websocket(ws, ip, server_state.pow_state.tx))
+    Ok(())
 }
 
-async fn handle_websocket(mut socket: WebSocket, ip: IpAddr, tx: Arc<broadcast::Sender<String>>) {
-    let mut rx: broadcast::Receiver<String> = tx.subscribe();
+async fn handle_websocket(mut socket: WebSocket, ip: IpAddr, pow_state: PoWState) {
+    let mut rx: broadcast::Receiver<Challenge> = pow_state.tx.subscribe();
 
     // spawn a task to forward messages from broadcast to websocket
-    while let Ok(mut msg) = rx.recv().await {
+    while let Ok(challenge) = rx.recv().await {
         // inject user address based on the IP address the server sees they're from
-        msg = msg.replace("{USER_ADDRESS}", &ip.to_string());
+        let challenge: Challenge = challenge.for_address(&pow_state, ip);
+        let msg: String = serde_json::to_string(&challenge).unwrap();
 
         if socket.send(Message::Text(msg)).await.is_err() {
             // client disconnected
             break;
         }
+        pow_state.metrics.lock().unwrap().record_challenge(true);
     }
 }
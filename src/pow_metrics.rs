@@ -0,0 +1,212 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::config::Pow;
+use crate::pow::{DIFFICULTIES, PowError};
+use crate::state::ServerState;
+use crate::trusted::Admin;
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+
+/// Upper bounds of the buckets of the solve time histograms, up to
+/// [`crate::pow::CHALLENGE_VALID_PERIOD`], after which solutions are stale. (in milliseconds)
+const SOLVE_TIME_BUCKETS: [u128; 8] = [250, 500, 1000, 2000, 3000, 5000, 7500, 10000];
+
+/// Counts kept on the PoW challenges and solutions since the daemon started,
+/// for operators to pick a difficulty that people on phones can meet.
+pub struct PowMetrics {
+    /// Unix timestamp of when counting started.
+    since: u64,
+    websocket_challenges: u64,
+    http_challenges: u64,
+    accepted: u64,
+    stale: u64,
+    invalid: u64,
+    reused: u64,
+    /// Accepted solutions by difficulty index, then by solve time bucket.
+    solve_times: [[u64; SOLVE_TIME_BUCKETS.len()]; DIFFICULTIES.len()],
+}
+
+impl PowMetrics {
+    pub fn new() -> Self {
+        Self {
+            since: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            websocket_challenges: 0,
+            http_challenges: 0,
+            accepted: 0,
+            stale: 0,
+            invalid: 0,
+            reused: 0,
+            solve_times: [[0; SOLVE_TIME_BUCKETS.len()]; DIFFICULTIES.len()],
+        }
+    }
+
+    /// Count a challenge sent over the `/api/v1/pow` WebSocket, or else `/api/v1/pow/challenge`.
+    pub fn record_challenge(&mut self, websocket: bool) {
+        match websocket {
+            true => self.websocket_challenges += 1,
+            false => self.http_challenges += 1,
+        }
+    }
+
+    /// Count an accepted solution of the given difficulty, solved and sent the
+    /// given time after its challenge was issued. (in milliseconds)
+    pub fn record_solution(&mut self, difficulty_index: usize, solve_time: u128) {
+        let bucket: usize = SOLVE_TIME_BUCKETS
+            .iter()
+            .position(|bound| solve_time <= *bound)
+            .unwrap_or(SOLVE_TIME_BUCKETS.len() - 1);
+
+        self.accepted += 1;
+        self.solve_times[difficulty_index][bucket] += 1;
+    }
+
+    pub fn record_failure(&mut self, err: &PowError) {
+        match err {
+            PowError::Stale => self.stale += 1,
+            PowError::Invalid => self.invalid += 1,
+            PowError::Reused => self.reused += 1,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+struct PowMetricsResponse {
+    /// Unix timestamp of when counting started, with the daemon.
+    since: u64,
+    /// Current global difficulty, 1-5.
+    difficulty: usize,
+    /// Configured difficulty, which an adaptive one falls back to.
+    base_difficulty: u8,
+    /// Highest adaptive difficulty, if any.
+    max_difficulty: Option<u8>,
+    /// Addresses whose challenges are harder after failed authentications.
+    penalized_addresses: usize,
+    challenges: ChallengeCounts,
+    solutions: SolutionCounts,
+    /// Time between issuing a challenge and accepting its solution, by difficulty.
+    solve_times: Vec<SolveTimes>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct ChallengeCounts {
+    /// Sent over the `/api/v1/pow` WebSocket, every 500ms to each connection.
+    websocket: u64,
+    /// Issued on `/api/v1/pow/challenge`
+    http: u64,
+}
+
+/// Solutions checked, by outcome.
+#[derive(Serialize, ToSchema)]
+struct SolutionCounts {
+    accepted: u64,
+    /// Solved too late, see `stale_challenge`
+    stale: u64,
+    /// Not solving their challenge, see `invalid_pow`
+    invalid: u64,
+    /// Already used, see `reused_pow`
+    reused: u64,
+}
+
+#[derive(Serialize, ToSchema)]
+struct SolveTimes {
+    /// 1-5
+    difficulty: usize,
+    accepted: u64,
+    buckets: Vec<SolveTimeBucket>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct SolveTimeBucket {
+    /// Upper bound of the solve times counted, in milliseconds.
+    le_ms: u64,
+    /// Solutions accepted within it, but not the previous bucket.
+    count: u64,
+}
+
+/// Handles requests on `/api/v1/admin/pow`, which shows how the PoW challenges
+/// are being solved, to tune their difficulty with.
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/pow",
+    tag = "admin",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "PoW metrics since the daemon started", body = PowMetricsResponse),
+        (status = 401, description = "Not logged in as the person themself"),
+    )
+)]
+pub async fn pow_metrics_api(
+    _admin: Admin,
+    State(server_state): State<ServerState>,
+) -> impl IntoResponse {
+    let config: &Pow = &server_state.config.pow;
+    let difficulty: usize = server_state.pow_state.difficulty.lock().unwrap().index + 1;
+    let penalized_addresses: usize = server_state.pow_state.penalties.lock().unwrap().len();
+    let metrics = server_state.pow_state.metrics.lock().unwrap();
+
+    let resp: PowMetricsResponse = PowMetricsResponse {
+        since: metrics.since,
+        difficulty,
+        base_difficulty: config.difficulty,
+        max_difficulty: config.max_difficulty,
+        penalized_addresses,
+        challenges: ChallengeCounts {
+            websocket: metrics.websocket_challenges,
+            http: metrics.http_challenges,
+        },
+        solutions: SolutionCounts {
+            accepted: metrics.accepted,
+            stale: metrics.stale,
+            invalid: metrics.invalid,
+            reused: metrics.reused,
+        },
+        solve_times: metrics
+            .solve_times
+            .iter()
+            .enumerate()
+            .map(|(index, counts)| SolveTimes {
+                difficulty: index + 1,
+                accepted: counts.iter().sum(),
+                buckets: SOLVE_TIME_BUCKETS
+                    .iter()
+                    .zip(counts)
+                    .map(|(bound, count)| SolveTimeBucket {
+                        le_ms: *bound as u64,
+                        count: *count,
+                    })
+                    .collect(),
+            })
+            .collect(),
+    };
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .header("Cache-Control", "no-store")
+        .body(Body::from(serde_json::to_string(&resp).unwrap()))
+        .unwrap()
+}
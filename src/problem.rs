@@ -0,0 +1,153 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::pow::PowError;
+use crate::state::ServerState;
+use axum::body::Body;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use rand::Rng;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+/// Least time taken to answer a refused request with uniform errors. (in milliseconds)
+const UNIFORM_DELAY: u64 = 500;
+/// Most random time added on top of [`UNIFORM_DELAY`]. (in milliseconds)
+const UNIFORM_JITTER: u64 = 250;
+
+/// Problem details of an error response, served as `application/problem+json`.
+/// See: <https://www.rfc-editor.org/rfc/rfc7807>
+///
+/// Its `type` is left out, meaning "about:blank", so `code` tells problems apart.
+///
+#[derive(Serialize, ToSchema)]
+pub struct Problem {
+    /// Reason phrase of the HTTP status, e.g. "Too Many Requests"
+    title: &'static str,
+    status: u16,
+    /// Machine-readable error code, e.g. "rate_limited"
+    code: &'static str,
+    /// Human-readable explanation of this occurrence of the problem.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+    /// Seconds to wait for before retrying, if retrying later may succeed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry_after: Option<u64>,
+}
+
+impl Problem {
+    pub fn new(status: StatusCode, code: &'static str) -> Self {
+        Problem {
+            title: status.canonical_reason().unwrap_or_default(),
+            status: status.as_u16(),
+            code,
+            detail: None,
+            retry_after: None,
+        }
+    }
+
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.code
+    }
+
+    /// Human-readable explanation, if any, e.g. to show on a page.
+    pub fn reason(&self) -> Option<&str> {
+        self.detail.as_deref()
+    }
+
+    /// Also sent as the `Retry-After` HTTP header.
+    pub fn retry_after(mut self, seconds: u64) -> Self {
+        self.retry_after = Some(seconds);
+        self
+    }
+}
+
+/// Hide which check refused a heartbeat or login, if `uniform_errors` is enabled.
+///
+/// Every refusal then gets the same `401 Unauthorized`, without a `Retry-After` header,
+/// no sooner than [`UNIFORM_DELAY`] plus some jitter after the request `started`, so
+/// that neither the response nor its timing tells a failed proof of work, a wrong
+/// password, a rate limit or a denied address apart. Server errors are left as is.
+///
+pub async fn uniform(server_state: &ServerState, started: Instant, problem: Problem) -> Problem {
+    match server_state.config.global.uniform_errors && problem.status < 500 {
+        true => refused(started).await,
+        false => problem,
+    }
+}
+
+/// The refusal given out with uniform errors, see [`uniform`]
+pub async fn refused(started: Instant) -> Problem {
+    let jitter: u64 = rand::rng().random_range(0..=UNIFORM_JITTER);
+    let respond_at: Instant = started + Duration::from_millis(UNIFORM_DELAY + jitter);
+
+    tokio::time::sleep_until(respond_at.into()).await;
+
+    Problem::new(StatusCode::UNAUTHORIZED, "refused").detail("The request was refused.")
+}
+
+impl IntoResponse for Problem {
+    fn into_response(self) -> Response {
+        let mut builder = Response::builder()
+            .status(self.status)
+            .header("Content-Type", "application/problem+json");
+
+        if let Some(seconds) = self.retry_after {
+            builder = builder.header("Retry-After", seconds);
+        }
+        builder
+            .body(Body::from(serde_json::to_string(&self).unwrap()))
+            .unwrap()
+    }
+}
+
+impl From<PowError> for Problem {
+    fn from(err: PowError) -> Self {
+        match err {
+            PowError::Stale => Problem::new(StatusCode::NOT_ACCEPTABLE, "stale_challenge")
+                .detail("The PoW challenge expired, solve a fresh one.")
+                .retry_after(0),
+            PowError::Invalid => Problem::new(StatusCode::NOT_ACCEPTABLE, "invalid_pow")
+                .detail("The PoW solution doesn't solve the challenge."),
+            PowError::Reused => Problem::new(StatusCode::NOT_ACCEPTABLE, "reused_pow")
+                .detail("The PoW solution was already used, solve a fresh one.")
+                .retry_after(0),
+        }
+    }
+}
+
+#[cfg(feature = "grpc")]
+impl From<Problem> for tonic::Status {
+    fn from(problem: Problem) -> Self {
+        let code: tonic::Code = match StatusCode::from_u16(problem.status) {
+            Ok(StatusCode::UNAUTHORIZED) => tonic::Code::Unauthenticated,
+            Ok(StatusCode::FORBIDDEN) => tonic::Code::PermissionDenied,
+            Ok(StatusCode::TOO_MANY_REQUESTS) => tonic::Code::ResourceExhausted,
+            Ok(StatusCode::UNPROCESSABLE_ENTITY) => tonic::Code::InvalidArgument,
+            _ => tonic::Code::Internal,
+        };
+        tonic::Status::new(code, problem.detail.unwrap_or(problem.code.into()))
+    }
+}
@@ -0,0 +1,454 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::api::{ClientIp, PowSolution};
+use crate::config::WebPush;
+use crate::database::{self, Database, PushSubscription, load_database};
+use crate::pow::check_pow_solution;
+use crate::problem::Problem;
+use crate::state::ServerState;
+use crate::trusted::Reader;
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes128Gcm, Nonce};
+use axum::body::Body;
+use axum::extract::{Json, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hkdf::Hkdf;
+use p256::ecdsa::signature::Signer;
+use p256::ecdsa::{Signature, SigningKey};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use p256::{PublicKey, SecretKey};
+use rand::rand_core::{OsRng, TryRngCore};
+use serde::Deserialize;
+use serde_json::json;
+use sha2::Sha256;
+use std::net::IpAddr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::MutexGuard;
+use utoipa::ToSchema;
+
+/// Upper bound of stored subscriptions, so visitors can't fill up our disk.
+const MAX_SUBSCRIPTIONS: usize = 1000;
+/// Time period, in seconds, a push service should keep trying to deliver a message.
+const MESSAGE_TTL: u64 = 24 * 60 * 60;
+/// Record size used for the single aes128gcm record we send.
+const RECORD_SIZE: u32 = 4096;
+
+/// The `PushSubscription` JSON object, as given by the browser,
+/// along with a solved PoW challenge.
+#[derive(Deserialize, ToSchema)]
+pub struct SubscriptionRequest {
+    pub endpoint: String,
+    pub keys: SubscriptionKeys,
+    pub pow: PowSolution,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SubscriptionKeys {
+    pub p256dh: String,
+    pub auth: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UnsubscribeRequest {
+    pub endpoint: String,
+}
+
+/// Parse the VAPID private key from our configuration.
+fn vapid_key(config: &WebPush) -> SecretKey {
+    let bytes: Vec<u8> = URL_SAFE_NO_PAD
+        .decode(config.vapid_private_key.trim_end_matches('='))
+        .expect("VAPID private key is not valid base64url.");
+    SecretKey::from_slice(&bytes).expect("Invalid VAPID private key.")
+}
+
+/// Base64url encoded public VAPID key, which browsers need when subscribing.
+pub fn vapid_public_key(config: &WebPush) -> String {
+    let point = vapid_key(config).public_key().to_encoded_point(false);
+    URL_SAFE_NO_PAD.encode(point.as_bytes())
+}
+
+/// Generate a random P-256 secret key using the OS RNG.
+fn random_secret_key() -> SecretKey {
+    loop {
+        let mut bytes: [u8; 32] = [0; 32];
+        OsRng.try_fill_bytes(&mut bytes).expect("OS RNG error.");
+
+        // a random 256-bit value is above the curve order extremely rarely
+        if let Ok(key) = SecretKey::from_slice(&bytes) {
+            return key;
+        }
+    }
+}
+
+/// Encrypt the payload for the given subscription. (RFC 8291, `aes128gcm`)
+///
+/// Implemented by hand on top of RustCrypto primitives, since the existing
+/// Web Push crates all depend on OpenSSL, which our slim images do not ship.
+///
+fn encrypt(subscription: &PushSubscription, payload: &[u8]) -> Option<Vec<u8>> {
+    let ua_public_bytes: Vec<u8> = URL_SAFE_NO_PAD.decode(&subscription.p256dh).ok()?;
+    let auth_secret: Vec<u8> = URL_SAFE_NO_PAD.decode(&subscription.auth).ok()?;
+    let ua_public: PublicKey = PublicKey::from_sec1_bytes(&ua_public_bytes).ok()?;
+
+    let as_secret: SecretKey = random_secret_key();
+    let as_public = as_secret.public_key().to_encoded_point(false);
+    let shared = p256::ecdh::diffie_hellman(as_secret.to_nonzero_scalar(), ua_public.as_affine());
+
+    // combine the ECDH secret with the subscription's authentication secret
+    let mut key_info: Vec<u8> = b"WebPush: info\0".to_vec();
+    key_info.extend_from_slice(&ua_public_bytes);
+    key_info.extend_from_slice(as_public.as_bytes());
+
+    let mut ikm: [u8; 32] = [0; 32];
+    Hkdf::<Sha256>::new(Some(&auth_secret), shared.raw_secret_bytes())
+        .expand(&key_info, &mut ikm)
+        .ok()?;
+
+    // derive the content encryption key and nonce
+    let mut salt: [u8; 16] = [0; 16];
+    OsRng.try_fill_bytes(&mut salt).expect("OS RNG error.");
+
+    let prk: Hkdf<Sha256> = Hkdf::<Sha256>::new(Some(&salt), &ikm);
+    let mut cek: [u8; 16] = [0; 16];
+    let mut nonce: [u8; 12] = [0; 12];
+    prk.expand(b"Content-Encoding: aes128gcm\0", &mut cek)
+        .ok()?;
+    prk.expand(b"Content-Encoding: nonce\0", &mut nonce).ok()?;
+
+    // single record, so pad with the last record delimiter
+    let mut plaintext: Vec<u8> = payload.to_vec();
+    plaintext.push(0x02);
+
+    let ciphertext: Vec<u8> = Aes128Gcm::new_from_slice(&cek)
+        .ok()?
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+        .ok()?;
+
+    // header: salt (16) | record size (4) | key id length (1) | key id (65)
+    let mut body: Vec<u8> = salt.to_vec();
+    body.extend_from_slice(&RECORD_SIZE.to_be_bytes());
+    body.push(as_public.as_bytes().len() as u8);
+    body.extend_from_slice(as_public.as_bytes());
+    body.extend_from_slice(&ciphertext);
+    Some(body)
+}
+
+/// Build the VAPID `Authorization` header value for the given endpoint. (RFC 8292)
+fn vapid_authorization(config: &WebPush, endpoint: &str, now: u64) -> Option<String> {
+    let audience: String = reqwest::Url::parse(endpoint)
+        .ok()?
+        .origin()
+        .ascii_serialization();
+
+    let header: String = URL_SAFE_NO_PAD.encode(json!({"typ": "JWT", "alg": "ES256"}).to_string());
+    let claims: String = URL_SAFE_NO_PAD.encode(
+        json!({
+            "aud": audience,
+            "exp": now + 12 * 60 * 60,
+            "sub": config.subject,
+        })
+        .to_string(),
+    );
+    let signing_input: String = format!("{}.{}", header, claims);
+    let signature: Signature = SigningKey::from(vapid_key(config)).sign(signing_input.as_bytes());
+
+    Some(format!(
+        "vapid t={}.{}, k={}",
+        signing_input,
+        URL_SAFE_NO_PAD.encode(signature.to_bytes()),
+        vapid_public_key(config)
+    ))
+}
+
+/// Outcome of delivering a push message to a single subscription.
+pub enum Delivery {
+    Delivered,
+    Failed,
+    /// The push service told us this subscription no longer exists.
+    Expired,
+}
+
+/// Send a push message with the given title and body to a subscription.
+pub async fn send(
+    client: &reqwest::Client,
+    config: &WebPush,
+    subscription: &PushSubscription,
+    title: &str,
+    body: &str,
+) -> Delivery {
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let payload: String = json!({"title": title, "body": body}).to_string();
+
+    let Some(encrypted) = encrypt(subscription, payload.as_bytes()) else {
+        return Delivery::Expired; // keys are unusable, so forget about it
+    };
+    let Some(authorization) = vapid_authorization(config, &subscription.endpoint, now) else {
+        return Delivery::Expired;
+    };
+
+    let resp = client
+        .post(&subscription.endpoint)
+        .header("Authorization", authorization)
+        .header("Content-Encoding", "aes128gcm")
+        .header("Content-Type", "application/octet-stream")
+        .header("TTL", MESSAGE_TTL)
+        .header("Urgency", "high")
+        .body(encrypted)
+        .send()
+        .await;
+
+    match resp {
+        Err(err) => {
+            eprintln!("Failed to reach push service: {}", err);
+            Delivery::Failed
+        }
+        Ok(resp) => match resp.status() {
+            StatusCode::NOT_FOUND | StatusCode::GONE => Delivery::Expired,
+            status if status.is_success() => Delivery::Delivered,
+            status => {
+                eprintln!(
+                    "Push service refused a message with HTTP status {}.",
+                    status
+                );
+                Delivery::Failed
+            }
+        },
+    }
+}
+
+/// Whether a subscription can be stored and pushed to. Its fields are written to
+/// the database separated by spaces, one per line, so none of them may contain
+/// whitespace or control characters, which would corrupt it.
+///
+fn is_valid_subscription(req: &SubscriptionRequest) -> bool {
+    let is_field = |field: &str| {
+        !field.is_empty() && !field.contains(|c: char| c.is_whitespace() || c.is_control())
+    };
+    if ![&req.endpoint, &req.keys.p256dh, &req.keys.auth]
+        .iter()
+        .all(|field| is_field(field))
+    {
+        return false;
+    }
+    let valid_endpoint: bool =
+        reqwest::Url::parse(&req.endpoint).is_ok_and(|url| url.scheme() == "https");
+
+    let valid_keys: bool = URL_SAFE_NO_PAD
+        .decode(&req.keys.p256dh)
+        .ok()
+        .and_then(|key| PublicKey::from_sec1_bytes(&key).ok())
+        .is_some()
+        && URL_SAFE_NO_PAD
+            .decode(&req.keys.auth)
+            .is_ok_and(|auth| auth.len() == 16);
+
+    valid_endpoint && valid_keys
+}
+
+/// Whether the host of a push endpoint is a public one. Push services are run by browser
+/// vendors under their domain names, so an address, or a name that resolves to a loopback,
+/// private or link-local address, could only make us send requests to internal hosts.
+///
+async fn is_public_endpoint(endpoint: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(endpoint) else {
+        return false;
+    };
+    // not an address, which has no domain
+    let Some(domain) = url.domain() else {
+        return false;
+    };
+    let port: u16 = url.port_or_known_default().unwrap_or(443);
+
+    match tokio::net::lookup_host((domain, port)).await {
+        Err(_) => false,
+        Ok(addresses) => {
+            let addresses: Vec<IpAddr> = addresses.map(|address| address.ip()).collect();
+            !addresses.is_empty() && addresses.into_iter().all(is_public_address)
+        }
+    }
+}
+
+fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            !(ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified())
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_address(IpAddr::V4(ip)),
+            None => {
+                !(ip.is_loopback()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local()
+                    || ip.is_unspecified())
+            }
+        },
+    }
+}
+
+/// Handles requests on `/api/v1/push/subscribe`.
+#[utoipa::path(
+    post,
+    path = "/api/v1/push/subscribe",
+    tag = "push",
+    request_body = SubscriptionRequest,
+    responses(
+        (status = 201, description = "Subscribed"),
+        (status = 401, description = "The instance is private"),
+        (status = 404, description = "Web push notifications are not enabled"),
+        (status = 406, description = "The proof of work is invalid or expired", body = Problem, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid subscription, or its endpoint isn't a public host"),
+        (status = 507, description = "Too many subscriptions"),
+    ),
+    security((), ("bearer" = [])),
+)]
+pub async fn subscribe_api(
+    _reader: Reader,
+    ClientIp(ip): ClientIp,
+    State(server_state): State<ServerState>,
+    Json(req): Json<SubscriptionRequest>,
+) -> impl IntoResponse {
+    if server_state.config.web_push.is_none() {
+        return Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::default())
+            .unwrap();
+    }
+
+    // validate the subscription before we store it
+    if !is_valid_subscription(&req) {
+        return Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(Body::default())
+            .unwrap();
+    }
+    if let Err(err) = check_pow_solution(server_state.pow_state.clone(), ip, req.pow, None).await {
+        return Problem::from(err).into_response();
+    }
+    if !is_public_endpoint(&req.endpoint).await {
+        return Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(Body::default())
+            .unwrap();
+    }
+
+    let _db_guard: MutexGuard<'_, ()> = database::lock().await;
+
+    let mut db: Database = match load_database(crate::DB_PATH) {
+        Err(err) => {
+            eprintln!("An error ocurred while trying to read from disk: {}", err);
+
+            return Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Body::from("There was an issue reading from the database."))
+                .unwrap();
+        }
+        Ok(db) => db,
+    };
+
+    // re-subscribing replaces the old keys of the same endpoint
+    db.push_subscriptions
+        .retain(|subscription| subscription.endpoint != req.endpoint);
+
+    if db.push_subscriptions.len() >= MAX_SUBSCRIPTIONS {
+        return Response::builder()
+            .status(StatusCode::INSUFFICIENT_STORAGE)
+            .body(Body::default())
+            .unwrap();
+    }
+    db.push_subscriptions.push(PushSubscription {
+        endpoint: req.endpoint,
+        p256dh: req.keys.p256dh,
+        auth: req.keys.auth,
+    });
+
+    if let Err(err) = db.write_to_disk().await {
+        eprintln!(
+            "An error ocurred while trying to sync state to disk: {}",
+            err
+        );
+
+        return Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::from("There was an issue writing to the database."))
+            .unwrap();
+    }
+
+    Response::builder()
+        .status(StatusCode::CREATED)
+        .body(Body::default())
+        .unwrap()
+}
+
+/// Handles requests on `/api/v1/push/unsubscribe`.
+#[utoipa::path(
+    post,
+    path = "/api/v1/push/unsubscribe",
+    tag = "push",
+    request_body = UnsubscribeRequest,
+    responses(
+        (status = 200, description = "Unsubscribed"),
+        (status = 401, description = "The instance is private"),
+    ),
+    security((), ("bearer" = [])),
+)]
+pub async fn unsubscribe_api(
+    _reader: Reader,
+    Json(req): Json<UnsubscribeRequest>,
+) -> impl IntoResponse {
+    let _db_guard: MutexGuard<'_, ()> = database::lock().await;
+
+    let mut db: Database = match load_database(crate::DB_PATH) {
+        Err(err) => {
+            eprintln!("An error ocurred while trying to read from disk: {}", err);
+
+            return Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Body::from("There was an issue reading from the database."))
+                .unwrap();
+        }
+        Ok(db) => db,
+    };
+
+    db.push_subscriptions
+        .retain(|subscription| subscription.endpoint != req.endpoint);
+
+    if let Err(err) = db.write_to_disk().await {
+        eprintln!(
+            "An error ocurred while trying to sync state to disk: {}",
+            err
+        );
+
+        return Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::from("There was an issue writing to the database."))
+            .unwrap();
+    }
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::default())
+        .unwrap()
+}
@@ -0,0 +1,94 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::sources::token_hash;
+use crate::state::ServerState;
+use crate::trusted::generate_token;
+use axum::http::HeaderMap;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use qrcode::QrCode;
+use qrcode::render::svg;
+use std::collections::HashMap;
+use tokio::sync::MutexGuard;
+
+/// How long the token of a heartbeat QR code can be used for, in seconds.
+pub const TOKEN_LIFETIME: u64 = 5 * 60;
+
+/// Issue a single-use token to send a heartbeat with, in place of the password, for
+/// the QR code shown to the person on `/heartbeat`, and forget about the expired ones.
+///
+pub async fn issue_token(server_state: &ServerState, now: u64) -> String {
+    let token: String = generate_token();
+
+    let mut tokens: MutexGuard<'_, HashMap<String, u64>> = server_state.qr_tokens.lock().await;
+    tokens.retain(|_, expires_at| *expires_at > now);
+    tokens.insert(token_hash(&token), now + TOKEN_LIFETIME);
+
+    token
+}
+
+/// Whether the given password is the token of a heartbeat QR code that didn't expire.
+pub async fn is_token(server_state: &ServerState, password: &str, now: u64) -> bool {
+    server_state
+        .qr_tokens
+        .lock()
+        .await
+        .get(&token_hash(password))
+        .is_some_and(|expires_at| *expires_at > now)
+}
+
+/// Use up the token of a heartbeat QR code, unless it expired or already was.
+pub async fn use_token(server_state: &ServerState, password: &str, now: u64) -> bool {
+    server_state
+        .qr_tokens
+        .lock()
+        .await
+        .remove(&token_hash(password))
+        .is_some_and(|expires_at| expires_at > now)
+}
+
+/// Link to `/heartbeat` with the given token, on the host the page was requested from.
+///
+/// The token is in the fragment, which browsers don't send, so it doesn't end up in logs.
+///
+pub fn heartbeat_link(headers: &HeaderMap, token: &str) -> String {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+
+    format!(
+        "{}://{}/heartbeat#token={}",
+        header("X-Forwarded-Proto").unwrap_or("https"),
+        header("Host").unwrap_or_default(),
+        token
+    )
+}
+
+/// Render the given text as a QR code, in a `data:` URL of an SVG image.
+pub fn data_url(text: &str) -> String {
+    let code: QrCode = QrCode::new(text.as_bytes()).expect("Text too long for a QR code.");
+
+    let image: String = code
+        .render::<svg::Color>()
+        .min_dimensions(240, 240)
+        .dark_color(svg::Color("#000000"))
+        .light_color(svg::Color("#ffffff"))
+        .build();
+
+    format!("data:image/svg+xml;base64,{}", BASE64.encode(image))
+}
@@ -0,0 +1,355 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::api::{ClientIp, PowSolution};
+use crate::config::RequestLimits;
+use crate::pow::check_pow_solution;
+use crate::problem::{self, Problem};
+use crate::state::{RateLimit, ServerState};
+use crate::trusted::{active_session, bearer_token};
+use crate::web_session::cookie_session;
+use crate::{INITIAL_RATE_LIMIT_PERIOD, RATE_LIMIT_PERIOD_FACTOR};
+use axum::body::Body;
+use axum::extract::{Json, Request, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::MutexGuard;
+use utoipa::ToSchema;
+
+/// Most addresses blocked after failed attempts that are remembered at once.
+const MAX_BLOCKED_ADDRESSES: usize = 10_000;
+/// Time after a block expires for which it is remembered, so that the next failed
+/// attempt blocks the address for longer, unless its period is longer. (in seconds)
+const BLOCK_MEMORY: u64 = 24 * 60 * 60;
+/// Paths of the endpoints the person sends heartbeats through, which don't count towards
+/// the global budget, so that a flood of requests can't keep their heartbeats out. They
+/// are already gated by a proof of work and authentication.
+const HEARTBEAT_PATHS: [&str; 9] = [
+    "/heartbeat",
+    "/api/heartbeat",
+    "/api/v1/heartbeat",
+    "/api/heartbeat/batch",
+    "/api/v1/heartbeat/batch",
+    "/api/pow",
+    "/api/v1/pow",
+    "/api/pow/challenge",
+    "/api/v1/pow/challenge",
+];
+/// Most addresses whose requests are counted at once. Requests of any other
+/// address only count towards the global budget, until some are evicted.
+const MAX_TRACKED_ADDRESSES: usize = 100_000;
+
+/// Block an address after a failed attempt, for the given period.
+///
+/// When too many addresses are blocked, the blocks that expired long enough ago
+/// are forgotten, and then those expiring the soonest, so that an attacker with
+/// many addresses can't exhaust the server's memory.
+///
+pub fn block(blocked: &mut HashMap<IpAddr, RateLimit>, ip: IpAddr, period: u64, now: u64) {
+    if blocked.len() >= MAX_BLOCKED_ADDRESSES && !blocked.contains_key(&ip) {
+        forget_expired_blocks(blocked, now);
+
+        if blocked.len() >= MAX_BLOCKED_ADDRESSES
+            && let Some(soonest) = blocked
+                .iter()
+                .min_by_key(|(_, rate_limit)| rate_limit.timestamp)
+                .map(|(ip, _)| *ip)
+        {
+            blocked.remove(&soonest);
+        }
+    }
+    blocked.insert(
+        ip,
+        RateLimit {
+            period,
+            timestamp: now + period,
+        },
+    );
+}
+
+/// Forget the blocks that expired longer than [`BLOCK_MEMORY`], or their own period, ago.
+fn forget_expired_blocks(blocked: &mut HashMap<IpAddr, RateLimit>, now: u64) {
+    blocked
+        .retain(|_, rate_limit| now < rate_limit.timestamp + rate_limit.period.max(BLOCK_MEMORY));
+}
+
+/// Requests counted over a sliding window, approximated by the counts of the current
+/// and previous fixed windows, the latter weighted by how much of it the sliding
+/// window still covers. This takes constant memory, however many requests are made.
+#[derive(Default, Clone, Copy)]
+struct SlidingWindow {
+    /// Start of the current fixed window. (unix timestamp in milliseconds)
+    start: u64,
+    current: u64,
+    previous: u64,
+}
+
+impl SlidingWindow {
+    /// Move on to the fixed window `now` is in, then get the
+    /// amount of requests made in the sliding window ending `now`.
+    fn count(&mut self, now: u64, window: u64) -> u64 {
+        let start: u64 = now - now % window;
+
+        if start != self.start {
+            // the clock may have stepped back, to before the window we're in
+            self.previous = match start.checked_sub(self.start) == Some(window) {
+                true => self.current,
+                false => 0,
+            };
+            self.current = 0;
+            self.start = start;
+        }
+        self.previous * (window - (now - start)) / window + self.current
+    }
+
+    /// Whether no requests were made in the sliding window ending `now`.
+    fn is_stale(&self, now: u64, window: u64) -> bool {
+        self.start + 2 * window <= now
+    }
+}
+
+/// Request counts of every recently seen address and of all of them,
+/// checked against the configured [`RequestLimits`] on every request.
+#[derive(Default)]
+pub struct RequestLimiter {
+    addresses: HashMap<IpAddr, SlidingWindow>,
+    global: SlidingWindow,
+}
+
+impl RequestLimiter {
+    /// Count a request from the given address, unless it's over either budget,
+    /// in which case the seconds until it should be retried are returned.
+    /// Only the address's own budget applies to `exempt` requests.
+    fn admit(
+        &mut self,
+        ip: IpAddr,
+        now: u64,
+        limits: &RequestLimits,
+        exempt: bool,
+    ) -> Result<(), u64> {
+        let window: u64 = limits.window.max(1) * 1000;
+        let retry_after: u64 = (window - now % window).div_ceil(1000);
+
+        if self.addresses.len() >= MAX_TRACKED_ADDRESSES && !self.addresses.contains_key(&ip) {
+            self.evict(now, window);
+        }
+        let tracked: bool =
+            self.addresses.len() < MAX_TRACKED_ADDRESSES || self.addresses.contains_key(&ip);
+
+        if tracked {
+            let address: &mut SlidingWindow = self.addresses.entry(ip).or_default();
+
+            if address.count(now, window) >= u64::from(limits.per_address) {
+                return Err(retry_after);
+            }
+        }
+        if !exempt {
+            if self.global.count(now, window) >= u64::from(limits.global) {
+                return Err(retry_after);
+            }
+            self.global.current += 1;
+        }
+
+        if let Some(address) = self.addresses.get_mut(&ip) {
+            address.current += 1;
+        }
+        Ok(())
+    }
+
+    /// Forget about the addresses that made no requests in the last window.
+    fn evict(&mut self, now: u64, window: u64) {
+        self.addresses
+            .retain(|_, address| !address.is_stale(now, window));
+    }
+}
+
+/// Middleware refusing requests beyond the configured [`RequestLimits`],
+/// with `429 Too Many Requests`, on every endpoint.
+///
+/// Heartbeats, and requests of logged in trusted users or the person themself,
+/// are only held to the per-address budget, see [`HEARTBEAT_PATHS`].
+///
+/// With `uniform_errors`, refused requests get the same response as any other
+/// refusal instead, see [`problem::uniform`]
+///
+pub async fn limit_requests(
+    ClientIp(ip): ClientIp,
+    State(server_state): State<ServerState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let started: Instant = Instant::now();
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64;
+
+    let exempt: bool = HEARTBEAT_PATHS.contains(&request.uri().path())
+        || has_session(&server_state, request.headers()).await;
+
+    let admitted: Result<(), u64> = server_state.request_limiter.lock().unwrap().admit(
+        ip,
+        now,
+        &server_state.config.request_limits,
+        exempt,
+    );
+
+    match admitted {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => {
+            let problem: Problem = Problem::new(StatusCode::TOO_MANY_REQUESTS, "too_many_requests")
+                .detail("Too many requests. Slow down.")
+                .retry_after(retry_after);
+
+            problem::uniform(&server_state, started, problem)
+                .await
+                .into_response()
+        }
+    }
+}
+
+/// Whether a request carries the token of an active session, as a bearer
+/// token or in a validly signed session cookie.
+async fn has_session(server_state: &ServerState, headers: &HeaderMap) -> bool {
+    if let Some(token) = bearer_token(headers)
+        && active_session(server_state, &token).await.is_some()
+    {
+        return true;
+    }
+    cookie_session(server_state, headers).await.is_some()
+}
+
+/// Periodically forget the addresses that stopped making requests,
+/// and the blocks that expired long enough ago, forever.
+pub async fn evict_expired(server_state: ServerState) {
+    let window: u64 = server_state.config.request_limits.window.max(1);
+    let mut interval: tokio::time::Interval = tokio::time::interval(Duration::from_secs(window));
+
+    loop {
+        interval.tick().await;
+
+        let now: SystemTime = SystemTime::now();
+        let now_secs: u64 = now.duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let now_ms: u64 = now.duration_since(UNIX_EPOCH).unwrap().as_millis() as u64;
+
+        server_state
+            .request_limiter
+            .lock()
+            .unwrap()
+            .evict(now_ms, window * 1000);
+
+        forget_expired_blocks(&mut *server_state.rate_limited_ips.lock().await, now_secs);
+        server_state.pow_state.forget_expired_penalties();
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RateLimitRequest {
+    pow: PowSolution,
+}
+
+#[derive(Serialize, ToSchema)]
+struct RateLimitResponse {
+    /// Whether heartbeats from the caller's IP address are currently refused.
+    blocked: bool,
+    /// Unix timestamp of when the block expires, if blocked.
+    blocked_until: Option<u64>,
+    /// Seconds left until the block expires, if blocked.
+    retry_after: Option<u64>,
+    /// How long the next wrong password blocks the address for, in seconds.
+    /// It grows with every wrong password, until a heartbeat is accepted.
+    next_block_period: u64,
+}
+
+/// Handles requests on `/api/v1/ratelimit`, which tells a client whether its
+/// IP address is blocked after wrong passwords, and until when, so that it
+/// can back off before submitting a heartbeat.
+///
+/// Requires a solved PoW challenge, like a heartbeat. Note that blocked
+/// addresses are already refused a challenge on `/api/v1/pow`, with the
+/// same `Retry-After` HTTP header.
+///
+/// With `uniform_errors`, which this would defeat, every request is refused
+/// like a heartbeat would be, see [`crate::problem::uniform`]
+///
+#[utoipa::path(
+    post,
+    path = "/api/v1/ratelimit",
+    tag = "heartbeats",
+    request_body = RateLimitRequest,
+    responses(
+        (status = 200, description = "The caller's rate limit", body = RateLimitResponse),
+        (status = 401, description = "Always, with uniform errors", body = Problem, content_type = "application/problem+json"),
+        (status = 406, description = "The proof of work is invalid or expired", body = Problem, content_type = "application/problem+json"),
+    )
+)]
+pub async fn rate_limit_api(
+    ClientIp(ip): ClientIp,
+    State(server_state): State<ServerState>,
+    Json(req): Json<RateLimitRequest>,
+) -> impl IntoResponse {
+    let started: Instant = Instant::now();
+
+    // telling whether the address is blocked is exactly what uniform errors hide
+    if server_state.config.global.uniform_errors {
+        return problem::refused(started).await.into_response();
+    }
+    if let Err(err) = check_pow_solution(server_state.pow_state.clone(), ip, req.pow, None).await {
+        return Problem::from(err).into_response();
+    }
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let locked_map: MutexGuard<'_, HashMap<IpAddr, RateLimit>> =
+        server_state.rate_limited_ips.lock().await;
+
+    // expired blocks are kept until a heartbeat is accepted (or for a while), to extend the next one
+    let resp: RateLimitResponse = match locked_map.get(&ip) {
+        None => RateLimitResponse {
+            blocked: false,
+            blocked_until: None,
+            retry_after: None,
+            next_block_period: INITIAL_RATE_LIMIT_PERIOD,
+        },
+        Some(rate_limit) => {
+            let blocked: bool = now < rate_limit.timestamp;
+
+            RateLimitResponse {
+                blocked,
+                blocked_until: blocked.then_some(rate_limit.timestamp),
+                retry_after: blocked.then(|| rate_limit.timestamp - now),
+                next_block_period: rate_limit.period * RATE_LIMIT_PERIOD_FACTOR,
+            }
+        }
+    };
+    drop(locked_map);
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&resp).unwrap()))
+        .unwrap()
+}
@@ -0,0 +1,122 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::api::{ClientIp, PowSolution};
+use crate::database::{self, Countdown, CountdownStatus};
+use crate::password;
+use crate::state::ServerState;
+use crate::trusted;
+use argon2::password_hash::PasswordHash;
+use axum::body::Body;
+use axum::extract::{Json, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde::Deserialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::MutexGuard;
+use utoipa::ToSchema;
+
+#[derive(Deserialize, ToSchema)]
+pub struct CancelRequest {
+    recovery_code: String,
+    pow: PowSolution,
+}
+
+/// Handles requests on `/api/v1/release/cancel`, which cancels a pending
+/// release countdown given the recovery code.
+///
+/// For when you can't send a heartbeat, but want to stop your will
+/// and final notifications from being released.
+///
+#[utoipa::path(
+    post,
+    path = "/api/v1/release/cancel",
+    tag = "will",
+    request_body = CancelRequest,
+    responses(
+        (status = 200, description = "The release countdown was cancelled"),
+        (status = 401, description = "Wrong recovery code. Every refusal, with uniform errors"),
+        (status = 404, description = "No recovery code is configured"),
+        (status = 406, description = "The proof of work is invalid or expired"),
+        (status = 409, description = "No release countdown is pending"),
+        (status = 429, description = "Blocked after too many failed attempts"),
+    )
+)]
+pub async fn cancel_api(
+    ClientIp(ip): ClientIp,
+    State(server_state): State<ServerState>,
+    Json(req): Json<CancelRequest>,
+) -> impl IntoResponse {
+    let Some(recovery_code_hash) = server_state.config.release.recovery_code_hash.as_ref() else {
+        return Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::default())
+            .unwrap();
+    };
+    // validated on startup
+    let hash: PasswordHash = PasswordHash::new(recovery_code_hash).unwrap();
+
+    let admitted = trusted::admit(ip, &server_state, req.pow, async |_| {
+        password::verify(&req.recovery_code, &hash).then_some(())
+    })
+    .await;
+
+    if let Err(resp) = admitted {
+        return resp;
+    }
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let mut locked_countdown: MutexGuard<'_, Option<Countdown>> =
+        server_state.release_countdown.lock().await;
+
+    let Some(countdown) = locked_countdown.as_mut() else {
+        return Response::builder()
+            .status(StatusCode::CONFLICT)
+            .body(Body::from("There is no release countdown to cancel."))
+            .unwrap();
+    };
+    if countdown.status == CountdownStatus::Cancelled {
+        return Response::builder()
+            .status(StatusCode::OK)
+            .body(Body::default())
+            .unwrap();
+    }
+    if countdown.is_over(now) {
+        return Response::builder()
+            .status(StatusCode::CONFLICT)
+            .body(Body::from("The release countdown is already over."))
+            .unwrap();
+    }
+    println!("Release countdown cancelled with the recovery code.");
+
+    countdown.status = CountdownStatus::Cancelled;
+    let cancelled: Countdown = countdown.clone();
+
+    if let Err(err) = database::modify(|db| db.release_countdown = Some(cancelled)).await {
+        eprintln!("Could not sync the release countdown to disk: {}", err);
+    }
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::default())
+        .unwrap()
+}
@@ -0,0 +1,135 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::database::{self, Database, load_database};
+use crate::state::ServerState;
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+use std::fs::Metadata;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::MutexGuard;
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema)]
+struct ServerInfoResponse {
+    /// Version of the `amialived` daemon.
+    version: &'static str,
+    build: BuildInfo,
+    /// Unix timestamp of when the daemon started.
+    started: u64,
+    /// In seconds
+    uptime: u64,
+    /// Time between two periodic state updates, in minutes.
+    tick_interval: u16,
+    database: DatabaseHealth,
+}
+
+#[derive(Serialize, ToSchema)]
+struct BuildInfo {
+    /// "debug" or "release"
+    profile: &'static str,
+    /// e.g. "linux"
+    os: &'static str,
+    /// e.g. "x86_64"
+    arch: &'static str,
+}
+
+#[derive(Serialize, ToSchema)]
+struct DatabaseHealth {
+    /// Whether the database file could be read and parsed.
+    readable: bool,
+    /// Whether the database file isn't read-only. Heartbeats can't be saved otherwise.
+    writable: bool,
+    /// In bytes
+    size: Option<u64>,
+    /// Unix timestamp of the last write to the database file.
+    modified: Option<u64>,
+    /// Number of heartbeats in the history.
+    heartbeats: Option<usize>,
+}
+
+/// Check on the database file, without telling anything about its contents
+/// but the number of heartbeats in it.
+async fn database_health() -> DatabaseHealth {
+    let _db_guard: MutexGuard<'_, ()> = database::lock().await;
+
+    let metadata: Option<Metadata> = std::fs::metadata(crate::DB_PATH).ok();
+    let db: Option<Database> = match load_database(crate::DB_PATH) {
+        Err(err) => {
+            eprintln!("Could not read the database for a health check: {}", err);
+            None
+        }
+        Ok(db) => Some(db),
+    };
+
+    DatabaseHealth {
+        readable: db.is_some(),
+        writable: metadata
+            .as_ref()
+            .is_some_and(|metadata| !metadata.permissions().readonly()),
+        size: metadata.as_ref().map(|metadata| metadata.len()),
+        modified: metadata
+            .as_ref()
+            .and_then(|metadata| metadata.modified().ok())
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|modified| modified.as_secs()),
+        heartbeats: db.map(|db| db.heartbeat_history.len()),
+    }
+}
+
+/// Handles requests on `/api/v1/server`, which describes this instance
+/// of the daemon rather than the person, for debugging a deployment.
+#[utoipa::path(
+    get,
+    path = "/api/v1/server",
+    tag = "status",
+    responses((status = 200, description = "About this instance", body = ServerInfoResponse))
+)]
+pub async fn server_info_api(State(server_state): State<ServerState>) -> impl IntoResponse {
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let started: u64 = *server_state.server_start_time;
+
+    let resp: ServerInfoResponse = ServerInfoResponse {
+        version: env!("CARGO_PKG_VERSION"),
+        build: BuildInfo {
+            profile: match cfg!(debug_assertions) {
+                true => "debug",
+                false => "release",
+            },
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+        },
+        started,
+        uptime: now.saturating_sub(started),
+        tick_interval: server_state.config.state.tick_interval,
+        database: database_health().await,
+    };
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&resp).unwrap()))
+        .unwrap()
+}
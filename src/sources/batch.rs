@@ -0,0 +1,219 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::api::{ClientIp, MAX_COMPOSED_AGE, clean_heartbeat_text, register_heartbeat};
+use crate::config::BatchKey;
+use crate::database::{self, Database, load_database};
+use crate::state::ServerState;
+use axum::body::Body;
+use axum::extract::{Json, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::Deserialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::MutexGuard;
+use utoipa::ToSchema;
+
+/// Most heartbeats accepted in a single batch.
+const MAX_BATCH_SIZE: usize = 100;
+/// How far ahead of our clock a heartbeat's timestamp may be, in seconds.
+const MAX_CLOCK_SKEW: u64 = 60;
+
+#[derive(Deserialize, ToSchema)]
+pub struct BatchRequest {
+    /// Name of the batch key that signed the heartbeats.
+    key: String,
+    heartbeats: Vec<SignedHeartbeat>,
+}
+
+#[derive(Deserialize, ToSchema)]
+struct SignedHeartbeat {
+    /// Unix timestamp of when the heartbeat was composed.
+    timestamp: u64,
+    #[serde(default)]
+    message: String,
+    /// Base64 encoded Ed25519 signature of "<timestamp> <message>".
+    signature: String,
+}
+
+/// Decode the public key of a batch key.
+pub fn verifying_key(key: &BatchKey) -> VerifyingKey {
+    let bytes: Vec<u8> = STANDARD.decode(key.public_key.trim()).unwrap_or_else(|_| {
+        panic!(
+            "Public key of batch key '{}' is not valid base64.",
+            key.name
+        )
+    });
+    let bytes: [u8; 32] = bytes.try_into().unwrap_or_else(|_| {
+        panic!(
+            "Public key of batch key '{}' must be 32 bytes long.",
+            key.name
+        )
+    });
+    VerifyingKey::from_bytes(&bytes)
+        .unwrap_or_else(|_| panic!("Invalid Ed25519 public key for batch key '{}'.", key.name))
+}
+
+fn verify_signature(key: &VerifyingKey, heartbeat: &SignedHeartbeat) -> bool {
+    let Some(signature) = STANDARD
+        .decode(&heartbeat.signature)
+        .ok()
+        .and_then(|bytes| Signature::from_slice(&bytes).ok())
+    else {
+        return false;
+    };
+    let signed: String = format!("{} {}", heartbeat.timestamp, heartbeat.message);
+
+    key.verify(signed.as_bytes(), &signature).is_ok()
+}
+
+fn reject(status: StatusCode, reason: &'static str) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(Body::from(reason))
+        .unwrap()
+}
+
+/// Handles requests on `/api/v1/heartbeat/batch`, where a device that was offline
+/// submits the heartbeats it queued, each signed with the device's batch key.
+///
+/// The batch is rejected as a whole if any signature or timestamp is invalid.
+/// Heartbeats that were already registered are skipped, so that a batch can
+/// safely be sent again, e.g. after a timeout.
+///
+#[utoipa::path(
+    post,
+    path = "/api/v1/heartbeat/batch",
+    tag = "sources",
+    request_body = BatchRequest,
+    responses(
+        (status = 200, description = "The heartbeats were registered"),
+        (status = 401, description = "Unknown key, or a signature doesn't match"),
+        (status = 422, description = "The batch is too large, or a heartbeat is too old"),
+    )
+)]
+pub async fn batch_api(
+    ClientIp(ip): ClientIp,
+    State(server_state): State<ServerState>,
+    Json(mut req): Json<BatchRequest>,
+) -> impl IntoResponse {
+    let Some(batch_key): Option<&BatchKey> = server_state
+        .config
+        .batch_keys
+        .iter()
+        .find(|batch_key| batch_key.name == req.key)
+    else {
+        return reject(StatusCode::UNAUTHORIZED, "Unknown batch key.");
+    };
+    if req.heartbeats.is_empty() || req.heartbeats.len() > MAX_BATCH_SIZE {
+        return reject(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "A batch holds 1 to 100 heartbeats.",
+        );
+    }
+    let key: VerifyingKey = verifying_key(batch_key);
+
+    if !req
+        .heartbeats
+        .iter()
+        .all(|heartbeat| verify_signature(&key, heartbeat))
+    {
+        return reject(StatusCode::UNAUTHORIZED, "Invalid heartbeat signature.");
+    }
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    if req.heartbeats.iter().any(|heartbeat| {
+        heartbeat.timestamp > now + MAX_CLOCK_SKEW
+            || heartbeat.timestamp.saturating_add(MAX_COMPOSED_AGE) < now
+    }) {
+        return reject(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "A heartbeat was composed too long ago, or in the future.",
+        );
+    }
+    // clean the messages as any other heartbeat's, after checking their signatures
+    for heartbeat in req.heartbeats.iter_mut() {
+        match clean_heartbeat_text(&heartbeat.message, "") {
+            Err(_) => {
+                return reject(
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    "A heartbeat message is too long.",
+                );
+            }
+            Ok((message, _)) => heartbeat.message = message,
+        }
+    }
+    // skip the heartbeats we already have
+    let db: Database = {
+        let _db_guard: MutexGuard<'_, ()> = database::lock().await;
+
+        match load_database(crate::DB_PATH) {
+            Err(err) => {
+                eprintln!("An error ocurred while trying to read from disk: {}", err);
+                return reject(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "There was an issue reading from the database.",
+                );
+            }
+            Ok(db) => db,
+        }
+    };
+    req.heartbeats.retain(|heartbeat| {
+        !db.heartbeat_history.iter().any(|log| {
+            log.timestamp == heartbeat.timestamp.min(now) && log.message == heartbeat.message
+        })
+    });
+    // registering them oldest first leaves the state computed from the newest one
+    req.heartbeats.sort_by_key(|heartbeat| heartbeat.timestamp);
+    req.heartbeats
+        .dedup_by(|a, b| a.timestamp == b.timestamp && a.message == b.message);
+    let address: String = ip.to_string();
+
+    for heartbeat in req.heartbeats.iter() {
+        if let Err(reason) = register_heartbeat(
+            &server_state,
+            heartbeat.timestamp.min(now),
+            address.clone(),
+            heartbeat.message.clone(),
+            None,
+            false,
+            Some(batch_key.name.clone()),
+        )
+        .await
+        {
+            return reject(StatusCode::INTERNAL_SERVER_ERROR, reason);
+        }
+    }
+    println!(
+        "Registered a batch of {} heartbeats signed by '{}'.",
+        req.heartbeats.len(),
+        batch_key.name
+    );
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::default())
+        .unwrap()
+}
@@ -0,0 +1,171 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::config::Fediverse;
+use crate::sources::{Activity, parse_rfc3339, register_activity};
+use crate::state::ServerState;
+use serde::Deserialize;
+use serde::de::IgnoredAny;
+use std::time::Duration;
+use tokio::time::{self, Interval};
+
+/// Longest heartbeat message made from a post, in characters.
+const MAX_SUMMARY_LENGTH: usize = 140;
+
+/// An ActivityPub outbox, or a page of it.
+/// See: <https://www.w3.org/TR/activitypub/#outbox>
+#[derive(Deserialize)]
+struct OutboxCollection {
+    /// The first page, as a link or embedded. Only set on the outbox itself.
+    first: Option<OutboxPage>,
+    #[serde(rename = "orderedItems", default)]
+    ordered_items: Vec<OutboxActivity>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OutboxPage {
+    Link(String),
+    Embedded(Box<OutboxCollection>),
+}
+
+#[derive(Deserialize)]
+struct OutboxActivity {
+    #[serde(rename = "type")]
+    kind: String,
+    published: Option<String>,
+    object: Option<OutboxObject>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OutboxObject {
+    Post(Post),
+    /// e.g. a link to the object, rather than the object itself
+    Other(IgnoredAny),
+}
+
+#[derive(Deserialize)]
+struct Post {
+    /// Content warning, or summary of the post.
+    summary: Option<String>,
+    /// HTML content of the post.
+    content: Option<String>,
+}
+
+/// Turn a post's HTML content into a short plain text summary.
+fn summarize(html: &str) -> String {
+    let mut text: String = String::new();
+    let mut tag: Option<String> = None;
+
+    for c in html.chars() {
+        match (c, tag.as_mut()) {
+            ('<', None) => tag = Some(String::new()),
+            ('>', Some(name)) => {
+                // keep paragraphs and line breaks apart
+                if name.starts_with("br") || name == "/p" {
+                    text.push(' ');
+                }
+                tag = None;
+            }
+            (c, Some(name)) => name.push(c),
+            (c, None) => text.push(c),
+        }
+    }
+    let text: String = text
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    let text: String = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+    match text.char_indices().nth(MAX_SUMMARY_LENGTH) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text,
+    }
+}
+
+/// Describe an outbox activity, if it is a new post.
+fn describe(activity: OutboxActivity) -> Option<Activity> {
+    if activity.kind != "Create" {
+        return None;
+    }
+    let timestamp: u64 = parse_rfc3339(activity.published.as_deref()?)?;
+
+    let description: String = match activity.object {
+        Some(OutboxObject::Post(post)) => match (post.summary, post.content) {
+            (Some(summary), _) if !summary.is_empty() => summarize(&summary),
+            (_, Some(content)) => summarize(&content),
+            _ => String::default(),
+        },
+        _ => String::default(),
+    };
+    Some(Activity {
+        timestamp,
+        description,
+    })
+}
+
+/// Fetch the recent posts from the outbox.
+async fn fetch_posts(config: &Fediverse) -> Result<Vec<Activity>, reqwest::Error> {
+    let client: reqwest::Client = reqwest::Client::new();
+
+    let fetch = async |url: &str| -> Result<OutboxCollection, reqwest::Error> {
+        client
+            .get(url)
+            .header("Accept", "application/activity+json")
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<OutboxCollection>()
+            .await
+    };
+    let outbox: OutboxCollection = fetch(&config.outbox).await?;
+
+    // the latest posts are on the first page
+    let page: OutboxCollection = match outbox.first {
+        Some(OutboxPage::Link(url)) => fetch(&url).await?,
+        Some(OutboxPage::Embedded(page)) => *page,
+        None => outbox,
+    };
+    Ok(page
+        .ordered_items
+        .into_iter()
+        .filter_map(describe)
+        .collect())
+}
+
+/// Poll the configured fediverse outbox for new posts, forever.
+pub async fn poll(server_state: ServerState) {
+    let Some(config) = server_state.config.fediverse.clone() else {
+        return;
+    };
+    let ival: u64 = config.poll_interval.into();
+    let mut interval: Interval = time::interval(Duration::from_secs(ival * 60));
+
+    loop {
+        interval.tick().await;
+
+        match fetch_posts(&config).await {
+            Err(err) => eprintln!("Could not check the fediverse outbox: {}", err),
+            Ok(posts) => register_activity(&server_state, "fediverse", &posts).await,
+        }
+    }
+}
@@ -0,0 +1,86 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::config::Feeds;
+use crate::sources::{Activity, register_activity};
+use crate::state::ServerState;
+use feed_rs::model::Feed;
+use std::time::Duration;
+use tokio::time::{self, Interval};
+
+/// Fetch the items of an RSS or Atom feed.
+async fn fetch_items(client: &reqwest::Client, url: &str) -> Result<Vec<Activity>, String> {
+    let contents: Vec<u8> = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|resp| resp.error_for_status())
+        .map_err(|err| err.to_string())?
+        .bytes()
+        .await
+        .map_err(|err| err.to_string())?
+        .to_vec();
+
+    let feed: Feed = feed_rs::parser::parse(contents.as_slice()).map_err(|err| err.to_string())?;
+
+    Ok(feed
+        .entries
+        .into_iter()
+        .filter_map(|entry| {
+            let timestamp: u64 = entry
+                .published
+                .or(entry.updated)?
+                .timestamp()
+                .try_into()
+                .ok()?;
+
+            Some(Activity {
+                timestamp,
+                description: match entry.title {
+                    Some(title) => format!("Published \"{}\"", title.content.trim()),
+                    None => "Published a new post".into(),
+                },
+            })
+        })
+        .collect())
+}
+
+/// Poll the configured RSS and Atom feeds for new items, forever.
+pub async fn poll(server_state: ServerState) {
+    let Some(config): Option<Feeds> = server_state.config.feeds.clone() else {
+        return;
+    };
+    let ival: u64 = config.poll_interval.into();
+    let mut interval: Interval = time::interval(Duration::from_secs(ival * 60));
+    let client: reqwest::Client = reqwest::Client::new();
+
+    loop {
+        interval.tick().await;
+
+        let mut items: Vec<Activity> = vec![];
+
+        for url in config.urls.iter() {
+            match fetch_items(&client, url).await {
+                Err(err) => eprintln!("Could not check the feed at {}: {}", url, err),
+                Ok(mut feed_items) => items.append(&mut feed_items),
+            }
+        }
+        register_activity(&server_state, "feed", &items).await;
+    }
+}
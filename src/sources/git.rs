@@ -0,0 +1,167 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::config::{Forge, GitActivity};
+use crate::sources::{Activity, parse_rfc3339, register_activity};
+use crate::state::ServerState;
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::time::{self, Interval};
+
+const GITHUB_API: &str = "https://api.github.com";
+
+/// An event of a GitHub user's public activity feed.
+/// See: <https://docs.github.com/en/rest/activity/events>
+#[derive(Deserialize)]
+struct GitHubEvent {
+    #[serde(rename = "type")]
+    kind: String,
+    created_at: String,
+    repo: GitHubRepo,
+}
+
+#[derive(Deserialize)]
+struct GitHubRepo {
+    /// e.g. "maxrdz/Am-I-Alive"
+    name: String,
+}
+
+/// An activity of a Gitea (or Forgejo) user's feed.
+/// See: <https://gitea.com/api/swagger#/user/userListActivityFeeds>
+#[derive(Deserialize)]
+struct GiteaActivity {
+    op_type: String,
+    created: String,
+    repo: Option<GiteaRepo>,
+}
+
+#[derive(Deserialize)]
+struct GiteaRepo {
+    full_name: String,
+}
+
+/// Describe a forge event, if it is a commit or a comment.
+fn describe(forge: Forge, kind: &str, repo: &str) -> Option<String> {
+    let action: &str = match (forge, kind) {
+        (Forge::GitHub, "PushEvent") | (Forge::Gitea, "commit_repo") => "Pushed to",
+        (
+            Forge::GitHub,
+            "CommitCommentEvent" | "IssueCommentEvent" | "PullRequestReviewCommentEvent",
+        )
+        | (Forge::Gitea, "comment_issue" | "comment_pull") => "Commented on",
+        _ => return None,
+    };
+    Some(format!("{} {}", action, repo))
+}
+
+/// Fetch the recent commits and comments from the activity feed.
+async fn fetch_activity(config: &GitActivity) -> Result<Vec<Activity>, reqwest::Error> {
+    let client: reqwest::Client = reqwest::Client::new();
+
+    let activity: Vec<Activity> = match config.forge {
+        Forge::GitHub => {
+            let url: String = format!(
+                "{}/users/{}/events/public",
+                config
+                    .url
+                    .as_deref()
+                    .unwrap_or(GITHUB_API)
+                    .trim_end_matches('/'),
+                config.username
+            );
+            client
+                .get(url)
+                .header("Accept", "application/vnd.github+json")
+                // required by GitHub's API
+                .header("User-Agent", "am-i-alive")
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<Vec<GitHubEvent>>()
+                .await?
+                .into_iter()
+                .filter_map(|event| {
+                    Some(Activity {
+                        timestamp: parse_rfc3339(&event.created_at)?,
+                        description: describe(config.forge, &event.kind, &event.repo.name)?,
+                    })
+                })
+                .collect()
+        }
+        Forge::Gitea => {
+            // validated on startup
+            let url: String = format!(
+                "{}/api/v1/users/{}/activities/feeds?only-performed-by=true",
+                config.url.as_deref().unwrap().trim_end_matches('/'),
+                config.username
+            );
+            client
+                .get(url)
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<Vec<GiteaActivity>>()
+                .await?
+                .into_iter()
+                .filter_map(|activity| {
+                    Some(Activity {
+                        timestamp: parse_rfc3339(&activity.created)?,
+                        description: describe(
+                            config.forge,
+                            &activity.op_type,
+                            &activity.repo?.full_name,
+                        )?,
+                    })
+                })
+                .collect()
+        }
+    };
+    Ok(activity)
+}
+
+/// Check the activity feed once, registering a passive heartbeat
+/// if there was any activity since the last heartbeat.
+async fn check_activity(server_state: &ServerState, config: &GitActivity) {
+    let activity: Vec<Activity> = match fetch_activity(config).await {
+        Err(err) => {
+            eprintln!("Could not check the git activity feed: {}", err);
+            return;
+        }
+        Ok(activity) => activity,
+    };
+    let source: &str = match config.forge {
+        Forge::GitHub => "github",
+        Forge::Gitea => "gitea",
+    };
+    register_activity(server_state, source, &activity).await;
+}
+
+/// Poll the configured git forge activity feed for commits and comments, forever.
+pub async fn poll(server_state: ServerState) {
+    let Some(config) = server_state.config.git_activity.clone() else {
+        return;
+    };
+    let ival: u64 = config.poll_interval.into();
+    let mut interval: Interval = time::interval(Duration::from_secs(ival * 60));
+
+    loop {
+        interval.tick().await;
+        check_activity(&server_state, &config).await;
+    }
+}
@@ -0,0 +1,139 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::api::{ClientIp, register_heartbeat};
+use crate::config::{HomeAssistant, Sensor};
+use crate::sources::token_hash;
+use crate::state::ServerState;
+use crate::trusted::bearer_token;
+use axum::body::Body;
+use axum::extract::{Json, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde::Deserialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+
+/// Sensor states that mean you are there. Binary presence, motion and occupancy
+/// sensors are "on", and people or device trackers are "home".
+const PRESENCE_STATES: [&str; 2] = ["on", "home"];
+
+/// A sensor's state change, as sent by a Home Assistant `rest_command`.
+#[derive(Deserialize, ToSchema)]
+pub struct SensorEvent {
+    entity_id: String,
+    state: String,
+}
+
+/// Handles requests on `/api/v1/home-assistant`, where a Home Assistant automation
+/// reports state changes of the configured presence and occupancy sensors.
+///
+/// Requires the long-lived token in the `Authorization: Bearer <token>` HTTP header.
+/// A sensor seeing you registers a passive heartbeat, labeled with the sensor's label,
+/// unless there has been a heartbeat within the configured minimum interval.
+///
+#[utoipa::path(
+    post,
+    path = "/api/v1/home-assistant",
+    tag = "sources",
+    request_body = SensorEvent,
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The state change was handled"),
+        (status = 401, description = "Wrong token"),
+        (status = 404, description = "Unknown sensor, or Home Assistant is not enabled"),
+    )
+)]
+pub async fn home_assistant_api(
+    headers: HeaderMap,
+    ClientIp(ip): ClientIp,
+    State(server_state): State<ServerState>,
+    Json(event): Json<SensorEvent>,
+) -> impl IntoResponse {
+    let Some(config): Option<&HomeAssistant> = server_state.config.home_assistant.as_ref() else {
+        return Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::default())
+            .unwrap();
+    };
+    if bearer_token(&headers).is_none_or(|token| token_hash(&token) != config.token_hash) {
+        return Response::builder()
+            .status(StatusCode::UNAUTHORIZED)
+            .header("WWW-Authenticate", "Bearer")
+            .body(Body::default())
+            .unwrap();
+    }
+    let Some(sensor): Option<&Sensor> = config
+        .sensors
+        .iter()
+        .find(|sensor| sensor.entity_id == event.entity_id)
+    else {
+        return Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::from("Unknown sensor."))
+            .unwrap();
+    };
+    // e.g. motion clearing up
+    if !PRESENCE_STATES.contains(&event.state.as_str()) {
+        return Response::builder()
+            .status(StatusCode::OK)
+            .body(Body::default())
+            .unwrap();
+    }
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let min_interval: u64 = u64::from(config.min_interval.unwrap_or(60)) * 60;
+
+    // motion sensors go off all the time, don't flood the history
+    if **server_state.last_heartbeat.lock().await + min_interval > now {
+        return Response::builder()
+            .status(StatusCode::OK)
+            .body(Body::default())
+            .unwrap();
+    }
+    let address: String = ip.to_string();
+
+    if let Err(reason) = register_heartbeat(
+        &server_state,
+        now,
+        address,
+        String::default(),
+        None,
+        true,
+        Some(sensor.label.clone()),
+    )
+    .await
+    {
+        return Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::from(reason))
+            .unwrap();
+    }
+    println!(
+        "Passive heartbeat registered from Home Assistant sensor '{}'.",
+        sensor.entity_id
+    );
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::default())
+        .unwrap()
+}
@@ -0,0 +1,313 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::api::register_heartbeat;
+use crate::config::Imap;
+use crate::state::ServerState;
+use std::io;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::time::{self, Interval};
+use tokio_rustls::TlsConnector;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+
+/// Address recorded in the heartbeat history for e-mailed heartbeats.
+const EMAIL_ADDRESS: &str = "email";
+
+/// An untagged server response, e.g. `* SEARCH 4 8 15`.
+struct UntaggedResponse {
+    line: String,
+    /// Contents of the `{N}` literals sent along with the line, in order.
+    literals: Vec<String>,
+}
+
+/// A heartbeat e-mail, found in the mailbox.
+struct HeartbeatMail {
+    uid: u32,
+    subject: String,
+}
+
+/// A minimal IMAP client connection, just enough to find and flag heartbeat e-mails.
+/// See: <https://www.rfc-editor.org/rfc/rfc9051>
+struct Connection {
+    stream: BufReader<TlsStream<TcpStream>>,
+    next_tag: u32,
+}
+
+fn protocol_error(reason: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason)
+}
+
+/// Quote a string argument of an IMAP command.
+fn quote(arg: &str) -> String {
+    format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl Connection {
+    async fn open(config: &Imap) -> io::Result<Self> {
+        let mut roots: RootCertStore = RootCertStore::empty();
+        roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+
+        let tls_config: ClientConfig = ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let server_name: ServerName<'static> = ServerName::try_from(config.host.clone())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+
+        let tcp: TcpStream = TcpStream::connect((config.host.as_str(), config.port)).await?;
+        let tls: TlsStream<TcpStream> = TlsConnector::from(Arc::new(tls_config))
+            .connect(server_name, tcp)
+            .await?;
+
+        let mut conn: Connection = Connection {
+            stream: BufReader::new(tls),
+            next_tag: 1,
+        };
+        // wait for the server greeting
+        let (greeting, _) = conn.read_response().await?;
+
+        if !greeting.starts_with("* OK") {
+            return Err(protocol_error(format!("Unexpected greeting: {}", greeting)));
+        }
+        Ok(conn)
+    }
+
+    /// Read a full response line, along with any literals it contains.
+    async fn read_response(&mut self) -> io::Result<(String, Vec<String>)> {
+        let mut line: String = String::new();
+        let mut literals: Vec<String> = vec![];
+
+        loop {
+            let mut part: String = String::new();
+
+            if self.stream.read_line(&mut part).await? == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            let part: &str = part.trim_end_matches(['\r', '\n']);
+            line.push_str(part);
+
+            // a line ending with '{N}' is followed by N bytes of literal data
+            let Some(size) = part
+                .strip_suffix('}')
+                .and_then(|part| part.rsplit_once('{'))
+                .and_then(|(_, size)| size.parse::<usize>().ok())
+            else {
+                return Ok((line, literals));
+            };
+            let mut literal: Vec<u8> = vec![0; size];
+            self.stream.read_exact(&mut literal).await?;
+            literals.push(String::from_utf8_lossy(&literal).into_owned());
+        }
+    }
+
+    /// Send a command, returning the untagged responses to it.
+    /// Fails if the server does not complete the command successfully.
+    async fn command(&mut self, command: &str) -> io::Result<Vec<UntaggedResponse>> {
+        let tag: String = format!("a{}", self.next_tag);
+        self.next_tag += 1;
+
+        let stream = self.stream.get_mut();
+        stream
+            .write_all(format!("{} {}\r\n", tag, command).as_bytes())
+            .await?;
+        stream.flush().await?;
+
+        let mut responses: Vec<UntaggedResponse> = vec![];
+
+        loop {
+            let (line, literals) = self.read_response().await?;
+
+            if let Some(status) = line.strip_prefix(&format!("{} ", tag)) {
+                if !status.starts_with("OK") {
+                    // don't leak the password into our logs
+                    let verb: &str = command.split(' ').next().unwrap_or_default();
+                    return Err(protocol_error(format!("{} failed: {}", verb, status)));
+                }
+                return Ok(responses);
+            }
+            responses.push(UntaggedResponse { line, literals });
+        }
+    }
+}
+
+/// Get the value of a header field, unfolding it if it spans multiple lines.
+fn header_field(headers: &str, name: &str) -> Option<String> {
+    let mut value: Option<String> = None;
+
+    for line in headers.lines() {
+        match value.as_mut() {
+            Some(value) if line.starts_with([' ', '\t']) => value.push_str(line),
+            Some(_) => break,
+            None => {
+                if let Some((field, rest)) = line.split_once(':')
+                    && field.eq_ignore_ascii_case(name)
+                {
+                    value = Some(rest.to_string());
+                }
+            }
+        }
+    }
+    value.map(|value| value.trim().to_string())
+}
+
+/// Get the bare address out of a `From` header field, e.g. "Me <me@example.com>".
+fn sender_address(from: &str) -> &str {
+    match from.rsplit_once('<') {
+        Some((_, address)) => address.trim_end_matches('>').trim(),
+        None => from.trim(),
+    }
+}
+
+/// Search the mailbox for unseen heartbeat e-mails, flagging them as seen.
+async fn fetch_heartbeats(config: &Imap) -> io::Result<Vec<HeartbeatMail>> {
+    let mut conn: Connection = Connection::open(config).await?;
+
+    conn.command(&format!(
+        "LOGIN {} {}",
+        quote(&config.username),
+        quote(&config.password)
+    ))
+    .await?;
+    conn.command(&format!(
+        "SELECT {}",
+        quote(config.mailbox.as_deref().unwrap_or("INBOX"))
+    ))
+    .await?;
+
+    let uids: Vec<String> = conn
+        .command(&format!("UID SEARCH UNSEEN FROM {}", quote(&config.from)))
+        .await?
+        .iter()
+        .filter_map(|resp| resp.line.strip_prefix("* SEARCH"))
+        .flat_map(|uids| uids.split_whitespace().map(String::from))
+        .collect();
+
+    let mut mails: Vec<HeartbeatMail> = vec![];
+
+    if !uids.is_empty() {
+        let fetched: Vec<UntaggedResponse> = conn
+            .command(&format!(
+                "UID FETCH {} (UID BODY.PEEK[HEADER.FIELDS (FROM SUBJECT)])",
+                uids.join(",")
+            ))
+            .await?;
+
+        for resp in fetched.iter() {
+            // e.g. '* 12 FETCH (UID 345 BODY[HEADER.FIELDS (FROM SUBJECT)] {87}'
+            let Some(uid) = resp
+                .line
+                .split_once("UID ")
+                .and_then(|(_, rest)| rest.split([' ', ')']).next())
+                .and_then(|uid| uid.parse::<u32>().ok())
+            else {
+                continue;
+            };
+            let Some(headers) = resp.literals.first() else {
+                continue;
+            };
+            // the server's search is only a substring match
+            let Some(from) = header_field(headers, "From") else {
+                continue;
+            };
+            if !sender_address(&from).eq_ignore_ascii_case(&config.from) {
+                continue;
+            }
+            let mut subject: String = header_field(headers, "Subject").unwrap_or_default();
+
+            if let Some(passphrase) = config.passphrase.as_ref() {
+                if !subject.contains(passphrase.as_str()) {
+                    continue;
+                }
+                subject = subject.replace(passphrase.as_str(), "");
+            }
+            mails.push(HeartbeatMail { uid, subject });
+        }
+    }
+
+    if !mails.is_empty() {
+        let flagged: Vec<String> = mails.iter().map(|mail| mail.uid.to_string()).collect();
+
+        conn.command(&format!(
+            "UID STORE {} +FLAGS.SILENT (\\Seen)",
+            flagged.join(",")
+        ))
+        .await?;
+    }
+    conn.command("LOGOUT").await?;
+
+    Ok(mails)
+}
+
+/// Check the mailbox once, registering a heartbeat if any heartbeat e-mail arrived.
+async fn check_mailbox(server_state: &ServerState, config: &Imap) {
+    let mails: Vec<HeartbeatMail> = match fetch_heartbeats(config).await {
+        Err(err) => {
+            eprintln!("Could not check the heartbeat mailbox: {}", err);
+            return;
+        }
+        Ok(mails) => mails,
+    };
+    // a single heartbeat is enough, with the message of the latest e-mail
+    let Some(latest) = mails.iter().max_by_key(|mail| mail.uid) else {
+        return;
+    };
+    let message: String = latest
+        .subject
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ");
+
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    match register_heartbeat(
+        server_state,
+        now,
+        EMAIL_ADDRESS.into(),
+        message,
+        None,
+        false,
+        None,
+    )
+    .await
+    {
+        Err(reason) => eprintln!("Could not register an e-mailed heartbeat: {}", reason),
+        Ok(()) => println!("Heartbeat received by e-mail."),
+    }
+}
+
+/// Poll the configured mailbox for heartbeat e-mails, forever.
+pub async fn poll(server_state: ServerState) {
+    let Some(config) = server_state.config.imap.clone() else {
+        return;
+    };
+    let ival: u64 = config.poll_interval.into();
+    let mut interval: Interval = time::interval(Duration::from_secs(ival * 60));
+
+    loop {
+        interval.tick().await;
+        check_mailbox(&server_state, &config).await;
+    }
+}
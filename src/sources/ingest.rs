@@ -0,0 +1,114 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::api::{ClientIp, register_heartbeat};
+use crate::config::IngestSource;
+use crate::sources::token_hash;
+use crate::state::ServerState;
+use axum::body::{Body, Bytes};
+use axum::extract::{Path as UrlPath, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde_json::Value;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Longest heartbeat message taken from a webhook, in characters.
+const MAX_MESSAGE_LENGTH: usize = 256;
+
+/// Get the heartbeat message out of a webhook's JSON body, given
+/// the JSON pointer to its message field. (e.g. "/data/message")
+fn webhook_message(body: &[u8], message_field: Option<&str>) -> String {
+    let Some(pointer) = message_field else {
+        return String::default();
+    };
+    let Ok(json) = serde_json::from_slice::<Value>(body) else {
+        return String::default();
+    };
+    let message: String = match json.pointer(pointer) {
+        None | Some(Value::Null) => String::default(),
+        Some(Value::String(message)) => message.clone(),
+        Some(value) => value.to_string(),
+    };
+    message.chars().take(MAX_MESSAGE_LENGTH).collect()
+}
+
+/// Handles requests on `/api/v1/ingest/:token`, where third-party automations
+/// (e.g. IFTTT, Home Assistant, or fitness trackers) register heartbeats
+/// with an arbitrary JSON body, given one of the configured source tokens.
+///
+#[utoipa::path(
+    post,
+    path = "/api/v1/ingest/{token}",
+    tag = "sources",
+    params(("token" = String, Path, description = "One of the configured source tokens")),
+    request_body(content = Object, description = "Any JSON"),
+    responses(
+        (status = 200, description = "The heartbeat was registered"),
+        (status = 404, description = "Unknown source token"),
+    )
+)]
+pub async fn ingest_api(
+    ClientIp(ip): ClientIp,
+    State(server_state): State<ServerState>,
+    UrlPath(token): UrlPath<String>,
+    body: Bytes,
+) -> impl IntoResponse {
+    let hash: String = token_hash(&token);
+
+    let Some(source): Option<&IngestSource> = server_state
+        .config
+        .ingest_sources
+        .iter()
+        .find(|source| source.token_hash == hash)
+    else {
+        return Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::default())
+            .unwrap();
+    };
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let address: String = ip.to_string();
+    let message: String = webhook_message(&body, source.message_field.as_deref());
+
+    if let Err(reason) = register_heartbeat(
+        &server_state,
+        now,
+        address,
+        message,
+        None,
+        source.passive,
+        None,
+    )
+    .await
+    {
+        return Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::from(reason))
+            .unwrap();
+    }
+    println!("Heartbeat received from ingest source '{}'.", source.name);
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::default())
+        .unwrap()
+}
@@ -0,0 +1,106 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+pub mod batch;
+pub mod fediverse;
+pub mod feeds;
+pub mod git;
+pub mod home_assistant;
+pub mod imap;
+pub mod ingest;
+pub mod mtls;
+pub mod ping;
+pub mod scrobbles;
+pub mod sms;
+pub mod ssh;
+pub mod telegram;
+
+use crate::api::register_heartbeat;
+use crate::state::ServerState;
+use chrono::DateTime;
+use sha2::{Digest, Sha256};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Activity of yours seen elsewhere, e.g. a commit or a post.
+pub struct Activity {
+    pub timestamp: u64,
+    pub description: String,
+}
+
+/// Register a passive heartbeat for the latest of the given activity,
+/// if any of it happened since the last heartbeat.
+///
+/// `source` is recorded as the heartbeat's address, e.g. "github".
+///
+pub async fn register_activity(server_state: &ServerState, source: &str, activity: &[Activity]) {
+    let last_heartbeat: u64 = **server_state.last_heartbeat.lock().await;
+
+    let Some(latest) = activity
+        .iter()
+        .filter(|activity| activity.timestamp > last_heartbeat)
+        .max_by_key(|activity| activity.timestamp)
+    else {
+        return;
+    };
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    // the person was last seen when the activity happened, not when we noticed it
+    match register_heartbeat(
+        server_state,
+        latest.timestamp.min(now),
+        source.into(),
+        latest.description.clone(),
+        None,
+        true,
+        None,
+    )
+    .await
+    {
+        Err(reason) => eprintln!("Could not register a {} heartbeat: {}", source, reason),
+        Ok(()) => println!("Passive heartbeat registered from {} activity.", source),
+    }
+}
+
+/// Hash of a heartbeat source token, as it is set in our configuration.
+///
+/// Source tokens are long random strings, so they are
+/// only hashed with SHA-256 rather than Argon2.
+///
+pub fn token_hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Whether the given string is a valid hash of a source token,
+/// i.e. a lowercase hex encoded SHA-256 hash.
+pub fn is_token_hash(hash: &str) -> bool {
+    hash.len() == 64
+        && hash
+            .chars()
+            .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+}
+
+/// Parse an RFC 3339 date and time, as used by most web APIs, to a Unix timestamp.
+pub fn parse_rfc3339(time: &str) -> Option<u64> {
+    DateTime::parse_from_rfc3339(time)
+        .ok()
+        .and_then(|time| time.timestamp().try_into().ok())
+}
@@ -0,0 +1,225 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::api::{ClientIp, check_heartbeat_ip, clean_heartbeat_text, register_heartbeat};
+use crate::auth_log;
+use crate::config::Mtls;
+use crate::problem::Problem;
+use crate::state::ServerState;
+use axum::body::{Body, Bytes};
+use axum::extract::{ConnectInfo, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Extension, Router};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use hyper_util::service::TowerToHyperService;
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::MutexGuard;
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::{RootCertStore, ServerConfig};
+
+/// Time a client has to complete the TLS handshake. (in seconds)
+const HANDSHAKE_TIMEOUT: u64 = 10;
+
+/// Same as [`crate::api::HeartbeatRequest`], without the password and proof of work,
+/// which the client certificate stands in for. Every field is optional, so that
+/// a script can check in with an empty body.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct MtlsHeartbeatRequest {
+    remove_current_note: bool,
+    updated_note: String,
+    message: String,
+}
+
+/// Build the TLS configuration of the listener, which requires every client
+/// to present a certificate issued by one of the configured CAs.
+pub fn tls_config(config: &Mtls) -> Result<ServerConfig, String> {
+    let certificates: Vec<CertificateDer<'static>> =
+        CertificateDer::pem_file_iter(&config.certificate)
+            .and_then(|certificates| certificates.collect())
+            .map_err(|err| format!("Could not read {}: {}", config.certificate, err))?;
+    let private_key: PrivateKeyDer<'static> = PrivateKeyDer::from_pem_file(&config.private_key)
+        .map_err(|err| format!("Could not read {}: {}", config.private_key, err))?;
+
+    let mut roots: RootCertStore = RootCertStore::empty();
+
+    for ca in CertificateDer::pem_file_iter(&config.client_ca)
+        .map_err(|err| format!("Could not read {}: {}", config.client_ca, err))?
+    {
+        let ca: CertificateDer<'static> =
+            ca.map_err(|err| format!("Could not read {}: {}", config.client_ca, err))?;
+        roots.add(ca).map_err(|err| err.to_string())?;
+    }
+    let verifier = WebPkiClientVerifier::builder(Arc::new(roots))
+        .build()
+        .map_err(|err| err.to_string())?;
+
+    ServerConfig::builder()
+        .with_client_cert_verifier(verifier)
+        .with_single_cert(certificates, private_key)
+        .map_err(|err| err.to_string())
+}
+
+/// Handles requests on `/api/v1/heartbeat` of the mutual TLS listener.
+///
+/// The TLS handshake already verified the client's certificate, so the heartbeat
+/// is registered without a password or proof of work, and isn't rate limited.
+///
+pub async fn mtls_heartbeat_api(
+    ClientIp(ip): ClientIp,
+    State(server_state): State<ServerState>,
+    body: Bytes,
+) -> impl IntoResponse {
+    if let Err(problem) = check_heartbeat_ip(&server_state, ip) {
+        return problem.into_response();
+    }
+    let label: Option<String> = server_state
+        .config
+        .mtls
+        .as_ref()
+        .and_then(|config| config.label.clone());
+
+    // clients without a valid certificate never get past the handshake
+    auth_log::record(
+        ip,
+        "certificate",
+        "accepted",
+        label.as_deref().unwrap_or_default(),
+    )
+    .await;
+
+    let req: MtlsHeartbeatRequest = match body.is_empty() {
+        true => MtlsHeartbeatRequest::default(),
+        false => match serde_json::from_slice(&body) {
+            Ok(req) => req,
+            Err(err) => {
+                return Problem::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_body")
+                    .detail(err.to_string())
+                    .into_response();
+            }
+        },
+    };
+    let (message, updated_note): (String, String) =
+        match clean_heartbeat_text(&req.message, &req.updated_note) {
+            Ok(text) => text,
+            Err(problem) => return problem.into_response(),
+        };
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let mut locked_note: MutexGuard<'_, Option<String>> = server_state.note.lock().await;
+
+    if req.remove_current_note {
+        let _: Option<String> = locked_note.take();
+    } else if !updated_note.is_empty() {
+        let _: Option<String> = locked_note.replace(updated_note);
+    }
+    drop(locked_note);
+
+    if let Err(reason) = register_heartbeat(
+        &server_state,
+        now,
+        ip.to_string(),
+        message,
+        None,
+        false,
+        label,
+    )
+    .await
+    {
+        return Problem::new(StatusCode::INTERNAL_SERVER_ERROR, "database_error")
+            .detail(reason)
+            .into_response();
+    }
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::default())
+        .unwrap()
+}
+
+/// Serve the mutual TLS listener on its configured address, forever.
+pub async fn serve(server_state: ServerState) {
+    let Some(config) = server_state.config.mtls.clone() else {
+        return;
+    };
+    // validated on startup
+    let acceptor: TlsAcceptor = TlsAcceptor::from(Arc::new(tls_config(&config).unwrap()));
+
+    let listener: TcpListener = match TcpListener::bind(&config.address).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            eprintln!(
+                "Could not listen for mutual TLS on {}: {}",
+                config.address, err
+            );
+            return;
+        }
+    };
+    let app: Router = Router::new()
+        .route("/api/v1/heartbeat", post(mtls_heartbeat_api))
+        .route("/api/heartbeat", post(mtls_heartbeat_api))
+        .with_state(server_state);
+
+    println!("Listening for mutual TLS heartbeats on {}.", config.address);
+
+    loop {
+        let (tcp, peer): (TcpStream, SocketAddr) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(err) => {
+                eprintln!("Could not accept a mutual TLS connection: {}", err);
+                continue;
+            }
+        };
+        let acceptor: TlsAcceptor = acceptor.clone();
+        let app: Router = app.clone().layer(Extension(ConnectInfo(peer)));
+
+        tokio::spawn(async move {
+            // clients without a valid certificate are turned away here
+            let tls = match tokio::time::timeout(
+                Duration::from_secs(HANDSHAKE_TIMEOUT),
+                acceptor.accept(tcp),
+            )
+            .await
+            {
+                Ok(Ok(tls)) => tls,
+                Ok(Err(err)) => {
+                    eprintln!("Mutual TLS handshake with {} failed: {}", peer, err);
+                    return;
+                }
+                Err(_) => return,
+            };
+            let _ = auto::Builder::new(TokioExecutor::new())
+                .serve_connection(TokioIo::new(tls), TowerToHyperService::new(app))
+                .await;
+        });
+    }
+}
@@ -0,0 +1,92 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::api::{ClientIp, register_heartbeat};
+use crate::config::PingToken;
+use crate::sources::token_hash;
+use crate::state::ServerState;
+use axum::body::Body;
+use axum::extract::{Path as UrlPath, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Handles requests on `/ping/:token`, which registers a heartbeat
+/// for anyone who knows one of the configured ping tokens.
+///
+/// There is no proof of work or password to go with it, so that a cron job
+/// or a cheap device can check in with a plain `curl`.
+///
+#[utoipa::path(
+    get,
+    path = "/ping/{token}",
+    tag = "sources",
+    params(("token" = String, Path, description = "One of the configured ping tokens")),
+    responses(
+        (status = 200, description = "The heartbeat was registered", body = String),
+        (status = 404, description = "Unknown ping token"),
+    )
+)]
+pub async fn ping_api(
+    ClientIp(ip): ClientIp,
+    State(server_state): State<ServerState>,
+    UrlPath(token): UrlPath<String>,
+) -> impl IntoResponse {
+    let hash: String = token_hash(&token);
+
+    let Some(ping_token): Option<&PingToken> = server_state
+        .config
+        .ping_tokens
+        .iter()
+        .find(|ping_token| ping_token.token_hash == hash)
+    else {
+        return Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::default())
+            .unwrap();
+    };
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let address: String = ip.to_string();
+
+    if let Err(reason) = register_heartbeat(
+        &server_state,
+        now,
+        address,
+        String::default(),
+        None,
+        false,
+        None,
+    )
+    .await
+    {
+        return Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::from(reason))
+            .unwrap();
+    }
+    println!("Heartbeat received from ping token '{}'.", ping_token.name);
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::from("OK"))
+        .unwrap()
+}
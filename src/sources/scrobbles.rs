@@ -0,0 +1,182 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::config::{ScrobbleService, Scrobbles};
+use crate::sources::{Activity, register_activity};
+use crate::state::ServerState;
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::time::{self, Interval};
+
+const LASTFM_API: &str = "https://ws.audioscrobbler.com/2.0/";
+const LISTENBRAINZ_API: &str = "https://api.listenbrainz.org";
+/// How many of the latest scrobbles to fetch on every check.
+const RECENT_SCROBBLES: u8 = 10;
+
+/// Response of Last.fm's `user.getRecentTracks` method.
+/// See: <https://www.last.fm/api/show/user.getRecentTracks>
+#[derive(Deserialize)]
+struct LastFmResponse {
+    recenttracks: LastFmTracks,
+}
+
+#[derive(Deserialize)]
+struct LastFmTracks {
+    track: Vec<LastFmTrack>,
+}
+
+#[derive(Deserialize)]
+struct LastFmTrack {
+    name: String,
+    artist: LastFmText,
+    /// Missing for the track that is playing right now.
+    date: Option<LastFmDate>,
+}
+
+#[derive(Deserialize)]
+struct LastFmText {
+    #[serde(rename = "#text")]
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct LastFmDate {
+    /// Unix timestamp, as a string
+    uts: String,
+}
+
+/// Response of ListenBrainz's listens endpoint.
+/// See: <https://listenbrainz.readthedocs.io/en/latest/users/api/core.html>
+#[derive(Deserialize)]
+struct ListenBrainzResponse {
+    payload: ListenBrainzPayload,
+}
+
+#[derive(Deserialize)]
+struct ListenBrainzPayload {
+    listens: Vec<ListenBrainzListen>,
+}
+
+#[derive(Deserialize)]
+struct ListenBrainzListen {
+    /// Unix timestamp
+    listened_at: u64,
+    track_metadata: ListenBrainzTrack,
+}
+
+#[derive(Deserialize)]
+struct ListenBrainzTrack {
+    artist_name: String,
+    track_name: String,
+}
+
+/// Fetch the latest scrobbles of the configured account.
+async fn fetch_scrobbles(config: &Scrobbles) -> Result<Vec<Activity>, reqwest::Error> {
+    let client: reqwest::Client = reqwest::Client::new();
+
+    let scrobbles: Vec<Activity> = match config.service {
+        ScrobbleService::LastFm => client
+            .get(config.url.as_deref().unwrap_or(LASTFM_API))
+            .query(&[
+                ("method", "user.getrecenttracks"),
+                ("user", &config.username),
+                // validated on startup
+                ("api_key", config.api_key.as_deref().unwrap()),
+                ("limit", &RECENT_SCROBBLES.to_string()),
+                ("format", "json"),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<LastFmResponse>()
+            .await?
+            .recenttracks
+            .track
+            .into_iter()
+            .filter_map(|track| {
+                Some(Activity {
+                    timestamp: track.date?.uts.parse().ok()?,
+                    description: format!("Listened to {} by {}", track.name, track.artist.text),
+                })
+            })
+            .collect(),
+        ScrobbleService::ListenBrainz => {
+            let url: String = format!(
+                "{}/1/user/{}/listens",
+                config
+                    .url
+                    .as_deref()
+                    .unwrap_or(LISTENBRAINZ_API)
+                    .trim_end_matches('/'),
+                config.username
+            );
+            client
+                .get(url)
+                .query(&[("count", RECENT_SCROBBLES)])
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<ListenBrainzResponse>()
+                .await?
+                .payload
+                .listens
+                .into_iter()
+                .map(|listen| Activity {
+                    timestamp: listen.listened_at,
+                    description: format!(
+                        "Listened to {} by {}",
+                        listen.track_metadata.track_name, listen.track_metadata.artist_name
+                    ),
+                })
+                .collect()
+        }
+    };
+    Ok(scrobbles)
+}
+
+/// Check the scrobbles once, registering a passive heartbeat
+/// if anything was listened to since the last heartbeat.
+async fn check_scrobbles(server_state: &ServerState, config: &Scrobbles) {
+    let scrobbles: Vec<Activity> = match fetch_scrobbles(config).await {
+        Err(err) => {
+            eprintln!("Could not check the scrobbles: {}", err.without_url());
+            return;
+        }
+        Ok(scrobbles) => scrobbles,
+    };
+    let source: &str = match config.service {
+        ScrobbleService::LastFm => "lastfm",
+        ScrobbleService::ListenBrainz => "listenbrainz",
+    };
+    register_activity(server_state, source, &scrobbles).await;
+}
+
+/// Poll the configured Last.fm or ListenBrainz account for scrobbles, forever.
+pub async fn poll(server_state: ServerState) {
+    let Some(config) = server_state.config.scrobbles.clone() else {
+        return;
+    };
+    let ival: u64 = config.poll_interval.into();
+    let mut interval: Interval = time::interval(Duration::from_secs(ival * 60));
+
+    loop {
+        interval.tick().await;
+        check_scrobbles(&server_state, &config).await;
+    }
+}
@@ -0,0 +1,171 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::api::register_heartbeat;
+use crate::config::Twilio;
+use crate::password;
+use crate::state::ServerState;
+use argon2::PasswordHash;
+use axum::body::Body;
+use axum::extract::{Form, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Check the `X-Twilio-Signature` HTTP header of an inbound SMS webhook.
+/// See: <https://www.twilio.com/docs/usage/security#validating-requests>
+///
+/// The signature is an HMAC-SHA1 of the webhook's URL, followed by
+/// every form parameter's name and value, sorted by name.
+///
+fn verify_signature(config: &Twilio, headers: &HeaderMap, params: &[(String, String)]) -> bool {
+    let Some(signature) = headers
+        .get("X-Twilio-Signature")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| BASE64.decode(value).ok())
+    else {
+        return false;
+    };
+    let mut sorted: Vec<&(String, String)> = params.iter().collect();
+    sorted.sort();
+
+    let mut mac: Hmac<Sha1> = Hmac::new_from_slice(config.auth_token.as_bytes()).unwrap();
+    mac.update(config.webhook_url.as_bytes());
+
+    for (name, value) in sorted {
+        mac.update(name.as_bytes());
+        mac.update(value.as_bytes());
+    }
+    mac.verify_slice(&signature).is_ok()
+}
+
+/// Get the heartbeat message out of an SMS, e.g. "ALIVE 1234 at the airport",
+/// if it starts with the configured keyword followed by the right PIN.
+fn parse_sms<'a>(config: &Twilio, text: &'a str) -> Option<&'a str> {
+    let mut words = text.trim().splitn(3, char::is_whitespace);
+
+    let keyword: &str = config.keyword.as_deref().unwrap_or("alive");
+    if !words.next()?.eq_ignore_ascii_case(keyword) {
+        return None;
+    }
+    let pin_hash: PasswordHash = PasswordHash::new(&config.pin_hash).unwrap();
+    password::verify(words.next()?, &pin_hash).then_some(())?;
+
+    Some(words.next().unwrap_or_default().trim())
+}
+
+/// Reply to an inbound SMS, with TwiML.
+/// See: <https://www.twilio.com/docs/messaging/twiml>
+fn twiml_reply(text: Option<&str>) -> Response<Body> {
+    let twiml: String = match text {
+        Some(text) => format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?><Response><Message>{}</Message></Response>",
+            text
+        ),
+        None => "<?xml version=\"1.0\" encoding=\"UTF-8\"?><Response/>".into(),
+    };
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "text/xml")
+        .body(Body::from(twiml))
+        .unwrap()
+}
+
+/// Handles requests on `/api/v1/sms`, Twilio's webhook for inbound SMS.
+///
+/// A text with the keyword and PIN from one of the configured phone numbers
+/// registers a heartbeat, e.g. "ALIVE 1234 landed in Lima". Anything else
+/// is ignored without a reply, so that the number gives nothing away.
+///
+#[utoipa::path(
+    post,
+    path = "/api/v1/sms",
+    tag = "sources",
+    description = "Twilio's webhook for inbound SMS.",
+    request_body(content_type = "application/x-www-form-urlencoded", content = Object),
+    responses(
+        (status = 200, description = "TwiML reply to the SMS", content_type = "application/xml", body = String),
+        (status = 403, description = "The Twilio signature doesn't match"),
+        (status = 404, description = "SMS heartbeats are not enabled"),
+    )
+)]
+pub async fn sms_api(
+    headers: HeaderMap,
+    State(server_state): State<ServerState>,
+    Form(params): Form<Vec<(String, String)>>,
+) -> impl IntoResponse {
+    let Some(config) = server_state.config.twilio.as_ref() else {
+        return Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::default())
+            .unwrap();
+    };
+    if !verify_signature(config, &headers, &params) {
+        return Response::builder()
+            .status(StatusCode::FORBIDDEN)
+            .body(Body::default())
+            .unwrap();
+    }
+    let param = |name: &str| -> &str {
+        params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map_or("", |(_, value)| value.as_str())
+    };
+    if !config
+        .phone_numbers
+        .iter()
+        .any(|number| number == param("From"))
+    {
+        return twiml_reply(None);
+    }
+    let Some(message) = parse_sms(config, param("Body")) else {
+        eprintln!("Ignored an SMS without the keyword and PIN.");
+        return twiml_reply(None);
+    };
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    match register_heartbeat(
+        &server_state,
+        now,
+        "sms".into(),
+        message.into(),
+        None,
+        false,
+        None,
+    )
+    .await
+    {
+        Err(reason) => {
+            eprintln!("Could not register an SMS heartbeat: {}", reason);
+            twiml_reply(Some(reason))
+        }
+        Ok(()) => {
+            println!("Heartbeat received by SMS.");
+            twiml_reply(Some("Heartbeat registered."))
+        }
+    }
+}
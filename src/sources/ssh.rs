@@ -0,0 +1,115 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::api::{ClientIp, register_heartbeat};
+use crate::config::SshHost;
+use crate::sources::token_hash;
+use crate::state::ServerState;
+use crate::trusted::bearer_token;
+use axum::body::Body;
+use axum::extract::{Json, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde::Deserialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+
+/// Longest host or user name accepted from a login hook, in characters.
+const MAX_NAME_LENGTH: usize = 64;
+
+#[derive(Deserialize, ToSchema)]
+pub struct SshLoginRequest {
+    hostname: String,
+    #[serde(default)]
+    user: String,
+}
+
+/// Handles requests on `/api/v1/ssh-login`, where the login hook of one of the
+/// configured hosts registers a passive heartbeat for every SSH login.
+///
+/// Requires the host's token in the `Authorization: Bearer <token>` HTTP header.
+/// See `config/ssh-login-heartbeat.sh` for the hook itself.
+///
+#[utoipa::path(
+    post,
+    path = "/api/v1/ssh-login",
+    tag = "sources",
+    request_body = SshLoginRequest,
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The heartbeat was registered"),
+        (status = 401, description = "Unknown host token"),
+    )
+)]
+pub async fn ssh_login_api(
+    headers: HeaderMap,
+    ClientIp(ip): ClientIp,
+    State(server_state): State<ServerState>,
+    Json(req): Json<SshLoginRequest>,
+) -> impl IntoResponse {
+    let host: Option<&SshHost> = bearer_token(&headers).and_then(|token| {
+        let hash: String = token_hash(&token);
+
+        server_state
+            .config
+            .ssh_hosts
+            .iter()
+            .find(|host| host.token_hash == hash)
+    });
+    let Some(host) = host else {
+        return Response::builder()
+            .status(StatusCode::UNAUTHORIZED)
+            .header("WWW-Authenticate", "Bearer")
+            .body(Body::default())
+            .unwrap();
+    };
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let address: String = ip.to_string();
+    let hostname: String = req.hostname.chars().take(MAX_NAME_LENGTH).collect();
+    let message: String = match req.user.is_empty() {
+        true => format!("Logged into {}", hostname),
+        false => format!(
+            "Logged into {} as {}",
+            hostname,
+            req.user.chars().take(MAX_NAME_LENGTH).collect::<String>()
+        ),
+    };
+
+    if let Err(reason) =
+        register_heartbeat(&server_state, now, address, message, None, true, None).await
+    {
+        return Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::from(reason))
+            .unwrap();
+    }
+    println!(
+        "Passive heartbeat registered from an SSH login to '{}'.",
+        host.name
+    );
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::default())
+        .unwrap()
+}
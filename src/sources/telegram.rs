@@ -0,0 +1,211 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::api::register_heartbeat;
+use crate::config::Telegram;
+use crate::state::ServerState;
+use serde::Deserialize;
+use serde_json::json;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How long a single long-polling request waits for new messages, in seconds.
+const LONG_POLL_TIMEOUT: u64 = 50;
+/// How long to wait before polling again after an error, in seconds.
+const RETRY_DELAY: u64 = 30;
+
+/// Response of the Telegram Bot API.
+/// See: <https://core.telegram.org/bots/api#making-requests>
+#[derive(Deserialize)]
+struct BotResponse<T> {
+    ok: bool,
+    result: Option<T>,
+    description: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Update {
+    update_id: i64,
+    message: Option<Message>,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    /// Unix timestamp
+    date: u64,
+    chat: Chat,
+    from: Option<User>,
+    text: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Chat {
+    id: i64,
+}
+
+#[derive(Deserialize)]
+struct User {
+    id: i64,
+}
+
+/// Get the heartbeat message out of a `/beat` command, e.g. "/beat I'm fine".
+fn beat_command(text: &str) -> Option<&str> {
+    let (command, message) = text.split_once(' ').unwrap_or((text, ""));
+
+    // commands may be addressed to a bot in particular, e.g. "/beat@MyBot"
+    match command.split('@').next() {
+        Some("/beat") => Some(message.trim()),
+        _ => None,
+    }
+}
+
+/// A Telegram bot, long-polling for `/beat` commands.
+struct Bot {
+    client: reqwest::Client,
+    api: String,
+}
+
+impl Bot {
+    async fn call<T: for<'de> Deserialize<'de>>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<T, String> {
+        let resp: BotResponse<T> = self
+            .client
+            .post(format!("{}/{}", self.api, method))
+            .json(&params)
+            .send()
+            .await
+            .map_err(|err| err.without_url().to_string())?
+            .json()
+            .await
+            .map_err(|err| err.without_url().to_string())?;
+
+        match (resp.ok, resp.result) {
+            (true, Some(result)) => Ok(result),
+            _ => Err(resp.description.unwrap_or_default()),
+        }
+    }
+
+    async fn reply(&self, chat_id: i64, text: &str) {
+        let sent: Result<serde_json::Value, String> = self
+            .call("sendMessage", json!({ "chat_id": chat_id, "text": text }))
+            .await;
+
+        if let Err(err) = sent {
+            eprintln!("Could not reply on Telegram: {}", err);
+        }
+    }
+}
+
+/// Handle a message sent to the bot, registering a heartbeat
+/// if it is a `/beat` command from the configured user.
+async fn handle_message(server_state: &ServerState, config: &Telegram, bot: &Bot, msg: Message) {
+    // ignore anyone but the person themself
+    if msg.from.is_none_or(|user| user.id != config.user_id) {
+        return;
+    }
+    // updates since our last run are delivered again on startup
+    if msg.date <= **server_state.last_heartbeat.lock().await {
+        return;
+    }
+    let Some(message) = msg.text.as_deref().and_then(beat_command) else {
+        bot.reply(msg.chat.id, "Send /beat, followed by an optional message.")
+            .await;
+        return;
+    };
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    match register_heartbeat(
+        server_state,
+        now,
+        "telegram".into(),
+        message.into(),
+        None,
+        false,
+        None,
+    )
+    .await
+    {
+        Err(reason) => {
+            eprintln!("Could not register a Telegram heartbeat: {}", reason);
+            bot.reply(msg.chat.id, reason).await;
+        }
+        Ok(()) => {
+            println!("Heartbeat received on Telegram.");
+            bot.reply(msg.chat.id, "Heartbeat registered.").await;
+        }
+    }
+}
+
+/// Long-poll the configured Telegram bot for `/beat` commands, forever.
+pub async fn poll(server_state: ServerState) {
+    let Some(config) = server_state.config.telegram.clone() else {
+        return;
+    };
+    let bot: Bot = Bot {
+        client: reqwest::Client::builder()
+            .timeout(Duration::from_secs(LONG_POLL_TIMEOUT + 10))
+            .build()
+            .unwrap(),
+        api: format!(
+            "{}/bot{}",
+            config
+                .api_url
+                .as_deref()
+                .unwrap_or("https://api.telegram.org")
+                .trim_end_matches('/'),
+            config.bot_token
+        ),
+    };
+    let mut offset: i64 = 0;
+
+    loop {
+        let updates: Result<Vec<Update>, String> = bot
+            .call(
+                "getUpdates",
+                json!({
+                    "offset": offset,
+                    "timeout": LONG_POLL_TIMEOUT,
+                    "allowed_updates": ["message"],
+                }),
+            )
+            .await;
+
+        let updates: Vec<Update> = match updates {
+            Err(err) => {
+                eprintln!("Could not get the Telegram bot's messages: {}", err);
+                tokio::time::sleep(Duration::from_secs(RETRY_DELAY)).await;
+                continue;
+            }
+            Ok(updates) => updates,
+        };
+        for update in updates {
+            // acknowledges the update on the next request
+            offset = offset.max(update.update_id + 1);
+
+            if let Some(msg) = update.message {
+                handle_message(&server_state, &config, &bot, msg).await;
+            }
+        }
+    }
+}
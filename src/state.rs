@@ -19,13 +19,24 @@
 
 use crate::MAX_DISPLAYED_HEARTBEATS;
 use crate::api::bake_status_api_response;
-use crate::config::ServerConfig;
+use crate::calendar::{self, Absence};
+use crate::config::{ServerConfig, TrustedUserConfig};
+use crate::database::{
+    self, CheckInLog, ConfirmationLog, Countdown, CountdownStatus, GuestbookEntry, TransitionLog,
+};
+use crate::heatmap::HeartbeatDays;
+use crate::notify::{self, NotificationEvent};
 use crate::pow::PoWState;
+use crate::rate_limit::RequestLimiter;
+use crate::templating::TemplateOverrides;
+use crate::timestamping;
+use crate::trusted::Session;
 use argon2::password_hash::PasswordHash;
 use std::ops::Deref;
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 use std::{collections::HashMap, net::IpAddr};
-use tokio::sync::{Mutex, MutexGuard};
+use tokio::sync::{Mutex, MutexGuard, broadcast};
 
 /// Store multiple copies of a value in memory in case they
 /// are somehow corrupted by a cosmic ray or something.
@@ -67,22 +78,70 @@ pub struct ServerState {
     /// Unix time. We don't use an atomic u64 data type because
     /// we want to make use of our custom anti-memory-corruption data type.
     pub last_heartbeat: Arc<Mutex<Redundant<u64>>>,
+    /// Unix time of the last heartbeat the person sent themself, rather than one registered
+    /// passively, which alone can restore the "Incapacitated" or "Dead" state.
+    pub last_active_heartbeat: Arc<Mutex<Redundant<u64>>>,
     pub server_start_time: Redundant<u64>,
     pub config: Arc<ServerConfig>,
     /// The parsed Argon2id password hash from our configuration file.
     /// Used to authenticate new heartbeat requests.
     pub password_hash: PasswordHash<'static>,
     pub displayed_heartbeats: Arc<Mutex<[HeartbeatDisplay; MAX_DISPLAYED_HEARTBEATS]>>,
+    /// Shown as the heartbeat calendar, see [`crate::heatmap::calendar`]
+    pub heartbeat_days: Arc<Mutex<HeartbeatDays>>,
     pub note: Arc<Mutex<Option<String>>>,
+    /// Only shown to trusted users, and never baked into the status API response.
+    pub private_note: Arc<Mutex<Option<String>>>,
     /// Instead of borrowing locks for the server state on every
     /// API call, just bake a response every time the state is updated.
     ///
     /// This way, every API call is simply a [`String`] clone.
     pub baked_status_api_resp: Arc<Mutex<String>>,
+    /// Entity tag of the baked status API response, for conditional requests.
+    pub baked_status_etag: Arc<Mutex<String>>,
+    /// Unix timestamp of when the baked status API response or the guestbook last
+    /// changed, or of when the server started, served as the `Last-Modified` HTTP header.
+    pub status_changed_at: Arc<Mutex<u64>>,
+    /// Every newly baked status API response that differs from
+    /// the previous one, for the `/api/v1/events` streams.
+    pub status_events: Arc<broadcast::Sender<String>>,
     /// Store rate limiting expiration timestamps per IPv4/IPv6 address.
+    /// Bounded, see [`crate::rate_limit::block`].
     pub rate_limited_ips: Arc<Mutex<HashMap<IpAddr, RateLimit>>>,
+    /// Requests made recently, per address and in total, for the request limits.
+    pub request_limiter: Arc<std::sync::Mutex<RequestLimiter>>,
     /// State used by the PoW challenge generator Tokio task.
     pub pow_state: PoWState,
+    /// Tokens of the heartbeat QR codes, by hash, with the Unix timestamp they expire at.
+    /// See [`crate::qr_code`]
+    pub qr_tokens: Arc<Mutex<HashMap<String, u64>>>,
+    /// Sessions of logged in trusted users, by session token.
+    pub sessions: Arc<Mutex<HashMap<String, Session>>>,
+    /// Confirmations of the "Dead" state by trusted users. Only the ones
+    /// made after the last heartbeat count towards the quorum.
+    pub dead_confirmations: Arc<Mutex<Vec<ConfirmationLog>>>,
+    /// Latest time a trusted user said they saw the person, if ever.
+    pub last_check_in: Arc<Mutex<Option<CheckInLog>>>,
+    /// Guestbook entries approved by a trusted user, shown on the status page once dead.
+    pub guestbook: Arc<Mutex<Vec<GuestbookEntry>>>,
+    /// Countdown before releasing the will and the final notifications.
+    pub release_countdown: Arc<Mutex<Option<Countdown>>>,
+    /// Trusted users that registered through an invitation,
+    /// on top of the ones in our configuration file.
+    pub registered_users: Arc<Mutex<Vec<TrustedUserConfig>>>,
+    /// Last heartbeat at the time the "Dead" state was last proposed,
+    /// so the proposal is only sent out once per disappearance.
+    pub dead_proposed: Arc<Mutex<Option<u64>>>,
+    /// Time step of the last TOTP code accepted for a heartbeat,
+    /// so every code can only be used once.
+    pub last_totp_step: Arc<Mutex<u64>>,
+    /// Planned absences from the configured calendar.
+    pub planned_absences: Arc<Mutex<Vec<Absence>>>,
+    /// Unix timestamp of when the person last set the state themself,
+    /// unless the state changed in any other way since.
+    pub overridden_at: Arc<Mutex<Option<u64>>>,
+    /// Templates overriding the compiled-in ones, if a directory of them is configured.
+    pub template_overrides: Option<Arc<TemplateOverrides>>,
 }
 
 pub struct RateLimit {
@@ -94,7 +153,7 @@ pub struct RateLimit {
 
 impl ServerState {
     /// Called at every point in the program where the latest state
-    /// should be returned. (e.g. front page, /api/status)
+    /// should be returned. (e.g. front page, /api/v1/status)
     ///
     /// Refreshes the shared application state based on current Unix timestamp.
     ///
@@ -107,17 +166,20 @@ impl ServerState {
         );
 
         let seconds_since_last_seen: u64 = now_unix_timestamp - last_seen;
+        // planned absences extend the grace periods, but don't restore anything
+        let seconds_missing: u64 =
+            seconds_since_last_seen - self.absent_seconds(last_seen, now_unix_timestamp).await;
 
         // config variable is in hours, so translate to seconds by * 60 * 60.
         let seconds_until_uncertain: u64 =
             u64::from(self.config.state.time_until_uncertain) * 60 * 60;
 
-        let mut locked_state: MutexGuard<'_, Redundant<LifeState>> = self.state.lock().await;
+        let locked_state: MutexGuard<'_, Redundant<LifeState>> = self.state.lock().await;
         let mut new_state: Option<LifeState> = None;
 
         match **locked_state {
             LifeState::Alive => {
-                if seconds_since_last_seen > seconds_until_uncertain {
+                if seconds_missing > seconds_until_uncertain {
                     new_state = Some(LifeState::ProbablyAlive);
                     println!("Entering \"Probably Alive\" state.");
                 }
@@ -126,7 +188,11 @@ impl ServerState {
                 let seconds_until_missing: u64 =
                     u64::from(self.config.state.time_until_missing) * 60 * 60;
 
-                if seconds_since_last_seen > seconds_until_missing {
+                if seconds_missing > seconds_until_missing
+                    && !self
+                        .checked_in_recently(last_seen, now_unix_timestamp)
+                        .await
+                {
                     new_state = Some(LifeState::MissingOrDead);
                     println!("Assuming Missing or Dead.");
                 }
@@ -136,11 +202,44 @@ impl ServerState {
                     println!("Restoring state to \"Alive\".");
                 }
             }
+            LifeState::MissingOrDead => {
+                if let Some(proposed) = self.dead_proposal(last_seen, now_unix_timestamp).await
+                    && now_unix_timestamp >= proposed
+                {
+                    match self.config.verification.auto_dead_after {
+                        Some(days)
+                            if now_unix_timestamp >= proposed + u64::from(days) * 24 * 60 * 60 =>
+                        {
+                            new_state = Some(LifeState::Dead);
+                            println!("Nobody answered the \"Dead\" proposal. Assuming \"Dead\".");
+                        }
+                        _ => self.propose_dead(last_seen, proposed).await,
+                    }
+                }
+                // check if the latest heartbeat maybe restores our state back to "Alive"
+                if seconds_since_last_seen < seconds_until_uncertain {
+                    new_state = Some(LifeState::Alive);
+                    println!("Restoring state to \"Alive\".");
+                }
+            }
             // other states can only be reached by manual interaction
             // (e.g. trusted user verifying the state of the person, or the person sending a new heartbeat)
             _ => {
+                // only a heartbeat the person sent themself restores these, not activity seen
+                // elsewhere, which may as well be e.g. one of their scheduled posthumous posts
+                let last_active: u64 = **self.last_active_heartbeat.lock().await;
+
+                // a state set by the person themself is held until their next heartbeat
+                let overridden: bool = self
+                    .overridden_at
+                    .lock()
+                    .await
+                    .is_some_and(|overridden_at| overridden_at >= last_active);
+
                 // check if the latest heartbeat maybe restores our state back to "Alive"
-                if seconds_since_last_seen < seconds_until_uncertain {
+                if now_unix_timestamp.saturating_sub(last_active) < seconds_until_uncertain
+                    && !overridden
+                {
                     new_state = Some(LifeState::Alive);
                     println!("Restoring state to \"Alive\".");
                 }
@@ -149,7 +248,7 @@ impl ServerState {
 
         if let Some(state) = new_state {
             match state {
-                LifeState::MissingOrDead | LifeState::ProbablyAlive => {
+                LifeState::MissingOrDead | LifeState::ProbablyAlive | LifeState::Dead => {
                     let uptime: u64 = now_unix_timestamp - *self.server_start_time;
 
                     if uptime < (self.config.state.minimum_uptime as u64 * 60) {
@@ -161,16 +260,315 @@ impl ServerState {
                 // (user sent a heartbeat), so don't hold back
                 _ => (),
             }
-            *locked_state = Redundant::new(state);
-            drop(locked_state);
+            // timer based transitions go downhill, the rest is a heartbeat restoring us
+            let cause: TransitionCause = match state {
+                LifeState::Alive => TransitionCause::Heartbeat,
+                _ => TransitionCause::Timer,
+            };
+            self.transition(locked_state, state, cause, now_unix_timestamp)
+                .await;
+        }
+    }
+
+    /// Whether a trusted user saw the person since their last heartbeat,
+    /// recently enough to hold back the "Missing or Dead" state.
+    async fn checked_in_recently(&self, last_seen: u64, now: u64) -> bool {
+        let Some(check_ins) = self.config.check_ins.as_ref() else {
+            return false;
+        };
+        let deferral: u64 = u64::from(check_ins.deferral) * 60 * 60;
+
+        self.last_check_in
+            .lock()
+            .await
+            .as_ref()
+            .is_some_and(|check_in| {
+                check_in.timestamp > last_seen && check_in.timestamp + deferral > now
+            })
+    }
+
+    /// Switch to a new state, given the locked current state. Re-bakes our
+    /// baked responses, records the transition in the database, and notifies
+    /// the configured notification targets.
+    ///
+    /// Notifications for entering the "Missing or Dead" or "Dead" state are
+    /// held back until the release countdown is over, if one is configured.
+    ///
+    pub async fn transition(
+        &self,
+        mut locked_state: MutexGuard<'_, Redundant<LifeState>>,
+        state: LifeState,
+        cause: TransitionCause,
+        now_unix_timestamp: u64,
+    ) {
+        let previous_state: LifeState = **locked_state;
+        *locked_state = Redundant::new(state);
+        drop(locked_state);
+
+        *self.overridden_at.lock().await = match cause {
+            TransitionCause::Override => Some(now_unix_timestamp),
+            _ => None,
+        };
+
+        let mut locked_countdown: MutexGuard<'_, Option<Countdown>> =
+            self.release_countdown.lock().await;
+        let release_delay: u64 = u64::from(self.config.release.delay) * 60 * 60;
+
+        match state {
+            LifeState::MissingOrDead | LifeState::Dead => {
+                if locked_countdown.is_none() && release_delay > 0 {
+                    println!("Starting the release countdown.");
+
+                    *locked_countdown = Some(Countdown {
+                        started: now_unix_timestamp,
+                        ends: now_unix_timestamp + release_delay,
+                        from: previous_state,
+                        status: CountdownStatus::Pending,
+                    });
+                }
+            }
+            _ => {
+                if locked_countdown.take().is_some() {
+                    println!("Release countdown cancelled by state change.");
+                }
+            }
+        }
+        // only notify right away if nothing is being held back
+        let notify: bool = match locked_countdown.as_ref() {
+            Some(countdown) => countdown.status == CountdownStatus::Released,
+            None => true,
+        };
+
+        // persist the transition, so the state survives restarts
+        let record: TransitionLog = TransitionLog {
+            timestamp: now_unix_timestamp,
+            from: previous_state,
+            to: state,
+            cause: cause.to_string(),
+        };
+        if let Err(err) = database::record_transition(record, locked_countdown.clone()).await {
+            eprintln!("Could not record state transition to disk: {}", err);
+        }
+        drop(locked_countdown);
+
+        // re-bake any baked stuff
+        let _: String = bake_status_api_response(self.clone()).await;
+
+        if self.config.timestamping.is_some() {
+            tokio::spawn(timestamping::publish_pending(self.config.clone()));
+        }
+
+        if notify {
+            // let the configured notification targets know (without blocking our caller)
+            tokio::spawn(notify::dispatch(
+                self.config.clone(),
+                self.state_changed_event(previous_state, state).await,
+            ));
+
+            if state == LifeState::Dead && !self.config.posthumous_messages.is_empty() {
+                tokio::spawn(notify::deliver_posthumous(self.config.clone()));
+            }
+        }
+    }
+
+    /// Release whatever the release countdown held back, once it is over,
+    /// and (re)try delivering posthumous messages and due scheduled posts once we are dead.
+    /// Called on every tick.
+    ///
+    pub async fn release_if_due(&self, now_unix_timestamp: u64) {
+        let mut locked_countdown: MutexGuard<'_, Option<Countdown>> =
+            self.release_countdown.lock().await;
+        let state: LifeState = **self.state.lock().await;
+
+        let released: bool = match locked_countdown.as_mut() {
+            None => true,
+            Some(countdown)
+                if countdown.status == CountdownStatus::Pending
+                    && now_unix_timestamp >= countdown.ends =>
+            {
+                println!("Release countdown is over. Releasing.");
+
+                countdown.status = CountdownStatus::Released;
+                let from: LifeState = countdown.from;
+                let released: Countdown = countdown.clone();
+
+                if let Err(err) = database::modify(|db| db.release_countdown = Some(released)).await
+                {
+                    eprintln!("Could not sync the release countdown to disk: {}", err);
+                }
+                // the final statement may be shown now
+                tokio::spawn(bake_status_api_response(self.clone()));
+                tokio::spawn(notify::dispatch(
+                    self.config.clone(),
+                    self.state_changed_event(from, state).await,
+                ));
+                true
+            }
+            Some(countdown) => countdown.is_over(now_unix_timestamp),
+        };
+        drop(locked_countdown);
+
+        if !released || state != LifeState::Dead {
+            return;
+        }
+        if !self.config.posthumous_messages.is_empty() {
+            tokio::spawn(notify::deliver_posthumous(self.config.clone()));
+        }
+        if !self.config.scheduled_posts.is_empty() {
+            tokio::spawn(notify::publish_scheduled(self.config.clone()));
+        }
+    }
+
+    /// Unix timestamp of when the "Dead" state is proposed to the trusted users,
+    /// given the last heartbeat, if configured. Counted from when the
+    /// "Missing or Dead" state was reached, so it survives restarts.
+    ///
+    pub async fn dead_proposal(&self, last_seen: u64, now_unix_timestamp: u64) -> Option<u64> {
+        let days: u64 = self.config.verification.propose_dead_after?.into();
+        let seconds_until_missing: u64 = u64::from(self.config.state.time_until_missing) * 60 * 60;
+
+        Some(
+            last_seen
+                + seconds_until_missing
+                + days * 24 * 60 * 60
+                + self.absent_seconds(last_seen, now_unix_timestamp).await,
+        )
+    }
+
+    /// When the given state changes next on its own, unless a heartbeat comes in first,
+    /// as of now. States that are only left through a heartbeat, or by trusted users
+    /// verifying them, don't change on their own.
+    ///
+    pub async fn next_transition(&self, state: LifeState, now: u64) -> Option<u64> {
+        let last_seen: u64 = **self.last_heartbeat.lock().await;
+        let absent: u64 = self.absent_seconds(last_seen, now).await;
+
+        match state {
+            LifeState::Alive => {
+                let seconds_until_uncertain: u64 =
+                    u64::from(self.config.state.time_until_uncertain) * 60 * 60;
+
+                Some(last_seen + absent + seconds_until_uncertain)
+            }
+            LifeState::ProbablyAlive => {
+                let seconds_until_missing: u64 =
+                    u64::from(self.config.state.time_until_missing) * 60 * 60;
+                let missing: u64 = last_seen + absent + seconds_until_missing;
+
+                // a recent check-in holds the state back for a while longer
+                let deferred: Option<u64> = match self.config.check_ins.as_ref() {
+                    Some(check_ins) => self
+                        .last_check_in
+                        .lock()
+                        .await
+                        .as_ref()
+                        .filter(|check_in| check_in.timestamp > last_seen)
+                        .map(|check_in| {
+                            check_in.timestamp + u64::from(check_ins.deferral) * 60 * 60
+                        }),
+                    None => None,
+                };
+                Some(missing.max(deferred.unwrap_or_default()))
+            }
+            LifeState::MissingOrDead => {
+                let days: u64 = self.config.verification.auto_dead_after?.into();
+
+                Some(self.dead_proposal(last_seen, now).await? + days * 24 * 60 * 60)
+            }
+            LifeState::Incapacitated | LifeState::Dead => None,
+        }
+    }
+
+    /// Time spent in planned absences between two points in time, in seconds.
+    pub async fn absent_seconds(&self, from: u64, to: u64) -> u64 {
+        calendar::absent_seconds(&self.planned_absences.lock().await, from, to)
+    }
+
+    /// Let the notification targets know that trusted users
+    /// can now confirm the "Dead" state, once per disappearance.
+    async fn propose_dead(&self, last_seen: u64, proposed: u64) {
+        let mut locked_proposal: MutexGuard<'_, Option<u64>> = self.dead_proposed.lock().await;
+
+        if *locked_proposal == Some(last_seen) {
+            return;
+        }
+        *locked_proposal = Some(last_seen);
+        println!("Proposing the \"Dead\" state to trusted users.");
+
+        tokio::spawn(notify::dispatch(
+            self.config.clone(),
+            NotificationEvent::DeadProposed {
+                last_heartbeat: last_seen,
+                deadline: self
+                    .config
+                    .verification
+                    .auto_dead_after
+                    .map(|days| proposed + u64::from(days) * 24 * 60 * 60),
+            },
+        ));
+    }
 
-            // re-bake any baked stuff
-            let _: String = bake_status_api_response(self.clone()).await;
+    /// The final statement, but only once the "Dead" state is confirmed, see [`Self::dead_confirmed`]
+    pub async fn final_statement(&self) -> Option<&str> {
+        let statement: &str = &self.config.final_statement.as_ref()?.message;
+
+        match self.dead_confirmed().await {
+            true => Some(statement),
+            false => None,
+        }
+    }
+
+    /// Whether the state is "Dead", and the release countdown is over.
+    pub async fn dead_confirmed(&self) -> bool {
+        if **self.state.lock().await != LifeState::Dead {
+            return false;
+        }
+        let now: u64 = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        match self.release_countdown.lock().await.as_ref() {
+            Some(countdown) => countdown.is_over(now),
+            None => true,
+        }
+    }
+
+    async fn state_changed_event(&self, from: LifeState, to: LifeState) -> NotificationEvent {
+        NotificationEvent::StateChanged {
+            from,
+            to,
+            last_heartbeat: **self.last_heartbeat.lock().await,
+            last_message: self.displayed_heartbeats.lock().await[0].message.clone(),
+            note: self.note.lock().await.clone(),
+        }
+    }
+}
+
+/// What caused a state transition. Recorded alongside every transition.
+pub enum TransitionCause {
+    /// The grace period or maximum silence period ran out.
+    Timer,
+    /// A new heartbeat restored the state back to "Alive".
+    Heartbeat,
+    /// Trusted user(s) verified the state, by username.
+    Verified(Vec<String>),
+    /// The person set the state themself.
+    Override,
+}
+
+impl std::fmt::Display for TransitionCause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Timer => write!(f, "timer"),
+            Self::Heartbeat => write!(f, "heartbeat"),
+            Self::Verified(usernames) => write!(f, "verified by {}", usernames.join(", ")),
+            Self::Override => write!(f, "override"),
         }
     }
 }
 
-#[derive(Default, Clone, Copy, PartialEq, Eq)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LifeState {
     #[default]
     Alive,
@@ -218,6 +616,29 @@ impl std::fmt::Display for LifeState {
     }
 }
 
+impl LifeState {
+    /// The state this one degrades to on its own, see [`ServerState::next_transition`].
+    pub fn degrades_to(&self) -> Option<LifeState> {
+        match self {
+            Self::Alive => Some(Self::ProbablyAlive),
+            Self::ProbablyAlive => Some(Self::MissingOrDead),
+            Self::MissingOrDead => Some(Self::Dead),
+            Self::Incapacitated | Self::Dead => None,
+        }
+    }
+
+    /// Representation of this state in the database file. Inverse of `From<&str>`.
+    pub fn db_code(&self) -> &'static str {
+        match self {
+            Self::Alive => "0",
+            Self::ProbablyAlive => "1",
+            Self::MissingOrDead => "2",
+            Self::Incapacitated => "3",
+            Self::Dead => "4",
+        }
+    }
+}
+
 impl From<&str> for LifeState {
     fn from(value: &str) -> Self {
         match value {
@@ -233,15 +654,28 @@ impl From<&str> for LifeState {
 
 #[derive(Clone)]
 pub struct HeartbeatDisplay {
+    /// Unix timestamp
+    pub unix_timestamp: u64,
+    /// Formatted timestamp, in our configured UTC offset.
     pub timestamp: String,
     pub message: String,
+    /// File name of the media sent along with the heartbeat, if any.
+    pub media: Option<String>,
+    /// Whether the heartbeat was registered from activity seen elsewhere.
+    pub passive: bool,
+    /// What registered the heartbeat, if labeled. (e.g. the credential used)
+    pub label: Option<String>,
 }
 
 impl Default for HeartbeatDisplay {
     fn default() -> Self {
         HeartbeatDisplay {
+            unix_timestamp: 0,
             timestamp: String::from("N/A"),
             message: String::from("N/A"),
+            media: None,
+            passive: false,
+            label: None,
         }
     }
 }
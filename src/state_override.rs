@@ -0,0 +1,107 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::audit;
+use crate::state::{LifeState, Redundant, ServerState, TransitionCause};
+use crate::trusted::{Admin, OWNER_USERNAME};
+use axum::body::Body;
+use axum::extract::{Json, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde::Deserialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::MutexGuard;
+use utoipa::ToSchema;
+
+#[derive(Deserialize, ToSchema)]
+pub struct OverrideRequest {
+    state: OverrideState,
+}
+
+/// States the person can set themself.
+#[derive(Deserialize, Clone, Copy, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OverrideState {
+    /// Back from an announced incapacitation, without sending a heartbeat.
+    Alive,
+    /// e.g. announced before a surgery. Held until the next heartbeat.
+    Incapacitated,
+}
+
+impl From<OverrideState> for LifeState {
+    fn from(value: OverrideState) -> Self {
+        match value {
+            OverrideState::Alive => LifeState::Alive,
+            OverrideState::Incapacitated => LifeState::Incapacitated,
+        }
+    }
+}
+
+/// Set the state as the person themself, unless it's the current one already.
+pub async fn set_state(server_state: &ServerState, state: OverrideState) {
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    // make sure we're not acting on a stale state
+    server_state.update(now).await;
+
+    let locked_state: MutexGuard<'_, Redundant<LifeState>> = server_state.state.lock().await;
+    let state: LifeState = state.into();
+
+    if **locked_state == state {
+        return;
+    }
+    println!("State set to \"{}\" by the person themself.", state);
+
+    server_state
+        .transition(locked_state, state, TransitionCause::Override, now)
+        .await;
+    audit::record(OWNER_USERNAME, format!("set the state to {}", state)).await;
+}
+
+/// Handles requests on `/api/v1/admin/state`, where the person sets the state
+/// themself, e.g. to announce they'll be incapacitated ahead of a surgery.
+///
+/// The state is held until their next heartbeat, then the timers take over
+/// again. Trusted users can still verify the "Dead" state meanwhile.
+///
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/state",
+    tag = "admin",
+    request_body = OverrideRequest,
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The state was set"),
+        (status = 401, description = "Not logged in as the person themself"),
+    )
+)]
+pub async fn state_override_api(
+    _admin: Admin,
+    State(server_state): State<ServerState>,
+    Json(req): Json<OverrideRequest>,
+) -> impl IntoResponse {
+    set_state(&server_state, req.state).await;
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::default())
+        .unwrap()
+}
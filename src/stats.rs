@@ -0,0 +1,189 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::config::Role;
+use crate::database::{self, Database, load_database};
+use crate::state::{LifeState, Redundant, ServerState};
+use crate::trusted::TrustedUser;
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::MutexGuard;
+use utoipa::ToSchema;
+
+/// Every state, in the order they are listed in the statistics.
+const STATES: [LifeState; 5] = [
+    LifeState::Alive,
+    LifeState::ProbablyAlive,
+    LifeState::MissingOrDead,
+    LifeState::Incapacitated,
+    LifeState::Dead,
+];
+
+#[derive(Serialize, ToSchema)]
+struct StatsResponse {
+    total_heartbeats: usize,
+    /// Unix timestamp of the first heartbeat, if any.
+    first_heartbeat: Option<u64>,
+    /// Average time between two heartbeats, in seconds.
+    average_interval: Option<u64>,
+    /// Longest time without heartbeats, including the current one.
+    longest_silence: Option<Silence>,
+    /// Time spent in each state since the first heartbeat.
+    time_in_state: Vec<StateTime>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct Silence {
+    /// Unix timestamp of the heartbeat before the silence.
+    start: u64,
+    /// Unix timestamp of the heartbeat that broke the silence, or of now.
+    end: u64,
+    /// In seconds
+    duration: u64,
+}
+
+#[derive(Serialize, ToSchema)]
+struct StateTime {
+    /// [`std::fmt::Display`] output of [`crate::state::LifeState`]
+    state: String,
+    /// In seconds
+    duration: u64,
+}
+
+/// Derive the statistics from the heartbeat history and the recorded state transitions.
+fn compute_stats(db: &Database, current_state: LifeState, now: u64) -> StatsResponse {
+    let timestamps: Vec<u64> = db
+        .heartbeat_history
+        .iter()
+        .map(|log| log.timestamp)
+        .collect();
+
+    let average_interval: Option<u64> = match (timestamps.first(), timestamps.last()) {
+        (Some(first), Some(last)) if timestamps.len() > 1 => {
+            Some((last - first) / (timestamps.len() as u64 - 1))
+        }
+        _ => None,
+    };
+    // the silence since the latest heartbeat counts too
+    let longest_silence: Option<Silence> = timestamps
+        .windows(2)
+        .map(|pair| (pair[0], pair[1]))
+        .chain(timestamps.last().map(|last| (*last, now.max(*last))))
+        .max_by_key(|(start, end)| end - start)
+        .map(|(start, end)| Silence {
+            start,
+            end,
+            duration: end - start,
+        });
+
+    // walk the transitions from the first heartbeat on, adding up the time between them
+    let mut durations: [u64; STATES.len()] = [0; STATES.len()];
+
+    if let Some(first) = timestamps.first() {
+        let mut state: LifeState = db
+            .transitions
+            .first()
+            .map(|transition| transition.from)
+            .unwrap_or(current_state);
+        let mut since: u64 = *first;
+
+        for transition in db.transitions.iter() {
+            let index: usize = STATES.iter().position(|s| *s == state).unwrap();
+            durations[index] += transition.timestamp.saturating_sub(since);
+
+            state = transition.to;
+            since = since.max(transition.timestamp);
+        }
+        let index: usize = STATES.iter().position(|s| *s == state).unwrap();
+        durations[index] += now.saturating_sub(since);
+    }
+
+    StatsResponse {
+        total_heartbeats: timestamps.len(),
+        first_heartbeat: timestamps.first().copied(),
+        average_interval,
+        longest_silence,
+        time_in_state: STATES
+            .iter()
+            .zip(durations)
+            .map(|(state, duration)| StateTime {
+                state: state.to_string(),
+                duration,
+            })
+            .collect(),
+    }
+}
+
+/// Handles requests on `/api/v1/stats`, which shows statistics derived from
+/// the heartbeat history, e.g. to tune `time_until_uncertain` with.
+#[utoipa::path(
+    get,
+    path = "/api/v1/stats",
+    tag = "trusted users",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "Statistics of the heartbeat history", body = StatsResponse),
+        (status = 401, description = "Not logged in"),
+        (status = 403, description = "Requires the viewer role"),
+    )
+)]
+pub async fn stats_api(
+    State(server_state): State<ServerState>,
+    user: TrustedUser,
+) -> impl IntoResponse {
+    if let Err(forbidden) = user.require(Role::Viewer) {
+        return forbidden.into_response();
+    }
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    // make sure we're not acting on a stale state
+    server_state.update(now).await;
+
+    let locked_state: MutexGuard<'_, Redundant<LifeState>> = server_state.state.lock().await;
+    let current_state: LifeState = **locked_state;
+    drop(locked_state);
+
+    let db: Database = {
+        let _db_guard: MutexGuard<'_, ()> = database::lock().await;
+
+        match load_database(crate::DB_PATH) {
+            Err(err) => {
+                eprintln!("Could not read the heartbeat history: {}", err);
+                return Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(Body::default())
+                    .unwrap();
+            }
+            Ok(db) => db,
+        }
+    };
+    let resp: StatsResponse = compute_stats(&db, current_state, now);
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&resp).unwrap()))
+        .unwrap()
+}
@@ -0,0 +1,91 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::config::StatusSigning;
+use crate::state::ServerState;
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use ed25519_dalek::{Signer, SigningKey};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema)]
+struct StatusKeyResponse {
+    algorithm: &'static str,
+    /// Base64 encoded public key to verify the `X-Status-Signature` header with.
+    public_key: String,
+}
+
+/// Parse the Ed25519 signing key from our configuration.
+pub fn signing_key(config: &StatusSigning) -> SigningKey {
+    let bytes: Vec<u8> = STANDARD
+        .decode(config.signing_key.trim())
+        .expect("Status signing key is not valid base64.");
+    let seed: [u8; 32] = bytes
+        .try_into()
+        .expect("Status signing key must be 32 bytes long.");
+    SigningKey::from_bytes(&seed)
+}
+
+/// Sign a status response body, as sent at the given Unix timestamp.
+///
+/// The timestamp is signed along with the body, as `<timestamp>.<body>`, so that
+/// an old response can't be passed off as a current one. Returns the value of
+/// the `X-Status-Signature` HTTP header: `t=<timestamp>,ed25519=<base64 signature>`
+///
+pub fn signature_header(config: &StatusSigning, timestamp: u64, body: &str) -> String {
+    let message: String = format!("{}.{}", timestamp, body);
+    let signature: String =
+        STANDARD.encode(signing_key(config).sign(message.as_bytes()).to_bytes());
+
+    format!("t={},ed25519={}", timestamp, signature)
+}
+
+/// Handles requests on `/api/v1/status/key`, which gives out the public key
+/// that status responses are signed with.
+#[utoipa::path(
+    get,
+    path = "/api/v1/status/key",
+    tag = "status",
+    responses(
+        (status = 200, description = "The public key", body = StatusKeyResponse),
+        (status = 404, description = "Status responses aren't signed"),
+    )
+)]
+pub async fn key_api(State(server_state): State<ServerState>) -> impl IntoResponse {
+    let Some(config) = server_state.config.status_signing.as_ref() else {
+        return Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::default())
+            .unwrap();
+    };
+    let resp: StatusKeyResponse = StatusKeyResponse {
+        algorithm: "Ed25519",
+        public_key: STANDARD.encode(signing_key(config).verifying_key().as_bytes()),
+    };
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&resp).unwrap()))
+        .unwrap()
+}
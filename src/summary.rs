@@ -0,0 +1,141 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::MAX_DISPLAYED_HEARTBEATS;
+use crate::api::{StatusApiResponse, current_status};
+use crate::database::{self, Database, load_database};
+use crate::media::media_url;
+use crate::state::{HeartbeatDisplay, ServerState};
+use crate::transitions::TransitionEntry;
+use crate::trusted::Reader;
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+use tokio::sync::MutexGuard;
+use utoipa::ToSchema;
+
+/// Number of the latest state transitions in the summary.
+const RECENT_TRANSITIONS: usize = 5;
+
+#[derive(Serialize, ToSchema)]
+struct DisplayedHeartbeat {
+    /// Unix timestamp
+    timestamp: u64,
+    /// Formatted timestamp, in the configured UTC offset, as shown on the page.
+    displayed_at: String,
+    message: String,
+    /// URL of the media sent along with the heartbeat, if anyone may view it.
+    media: Option<String>,
+    passive: bool,
+    label: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct SummaryResponse {
+    /// Same as `/api/v1/status`, including the active note.
+    status: StatusApiResponse,
+    /// The heartbeats shown on the status page, newest first.
+    heartbeats: Vec<DisplayedHeartbeat>,
+    /// The latest state transitions, newest first.
+    transitions: Vec<TransitionEntry>,
+}
+
+/// Handles requests on `/api/v1/summary`, which bundles everything shown on the
+/// status page, so that it (or another frontend) can be rendered in one request.
+///
+#[utoipa::path(
+    get,
+    path = "/api/v1/summary",
+    tag = "status",
+    responses(
+        (status = 200, description = "The status, displayed heartbeats and latest transitions", body = SummaryResponse),
+        (status = 401, description = "The instance is private"),
+    ),
+    security((), ("bearer" = [])),
+)]
+pub async fn summary_api(
+    _reader: Reader,
+    State(server_state): State<ServerState>,
+) -> impl IntoResponse {
+    let status: StatusApiResponse = current_status(&server_state).await;
+
+    // only link to heartbeat media if anyone may view it
+    let public_media: bool = server_state
+        .config
+        .heartbeat_media
+        .as_ref()
+        .is_some_and(|media| media.public);
+
+    let heartbeats: Vec<DisplayedHeartbeat> = {
+        let displayed: MutexGuard<'_, [HeartbeatDisplay; MAX_DISPLAYED_HEARTBEATS]> =
+            server_state.displayed_heartbeats.lock().await;
+
+        displayed
+            .iter()
+            // empty rows are placeholders on the page
+            .filter(|heartbeat| heartbeat.unix_timestamp != 0)
+            .map(|heartbeat| DisplayedHeartbeat {
+                timestamp: heartbeat.unix_timestamp,
+                displayed_at: heartbeat.timestamp.clone(),
+                message: heartbeat.message.clone(),
+                media: heartbeat
+                    .media
+                    .as_deref()
+                    .filter(|_| public_media)
+                    .map(media_url),
+                passive: heartbeat.passive,
+                label: heartbeat.label.clone(),
+            })
+            .collect()
+    };
+    let db: Database = {
+        let _db_guard: MutexGuard<'_, ()> = database::lock().await;
+
+        match load_database(crate::DB_PATH) {
+            Err(err) => {
+                eprintln!("Could not read the state transitions: {}", err);
+                return Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(Body::default())
+                    .unwrap();
+            }
+            Ok(db) => db,
+        }
+    };
+    let transitions: Vec<TransitionEntry> = db
+        .transitions
+        .into_iter()
+        .rev()
+        .take(RECENT_TRANSITIONS)
+        .map(TransitionEntry::from)
+        .collect();
+
+    let summary: SummaryResponse = SummaryResponse {
+        status,
+        heartbeats,
+        transitions,
+    };
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&summary).unwrap()))
+        .unwrap()
+}
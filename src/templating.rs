@@ -17,13 +17,35 @@
     License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
 */
 
-use crate::state::{AssociatedColor, HeartbeatDisplay, LifeState, Redundant, ServerState};
+use crate::api::ClientIp;
+use crate::cache::CacheHeaders;
+use crate::config::{Link, Memorial, Profile, Role, ThemeMode};
+use crate::database::CheckInLog;
+use crate::guestbook::{self, GuestbookDisplay};
+use crate::heartbeat_form::{self, Feedback};
+use crate::heatmap::{self, CalendarDay};
+use crate::i18n::{self, Catalog};
+use crate::media::media_url;
+use crate::push::vapid_public_key;
+use crate::qr_code;
+use crate::state::{HeartbeatDisplay, LifeState, Redundant, ServerState};
+use crate::theme;
+use crate::trusted::Reader;
+use crate::web_session::WebSession;
 use askama::Template;
 use axum::{
+    body::Body,
     extract::State,
-    response::{Html, IntoResponse},
+    http::{HeaderMap, HeaderValue, Method, StatusCode},
+    response::{Html, IntoResponse, Response},
 };
+use chrono::{FixedOffset, TimeZone};
+use minijinja::{AutoEscape, Environment, path_loader};
 use rand::rand_core::{OsRng, TryRngCore};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::PathBuf;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::sync::MutexGuard;
 
@@ -32,36 +54,252 @@ use tokio::sync::MutexGuard;
 const HIDE_CSS_ID: &str = "hidden";
 const DEAD_CSS_ID: &str = "dead";
 
-#[derive(Template)]
+/// Text on the left side of the status badge.
+const BADGE_LABEL: &str = "am i alive";
+
+/// Templates overriding the compiled-in ones, from the configured `templates_directory`.
+///
+/// They are Jinja2 templates, named after the compiled-in ones, e.g. `index.html`, given the
+/// same variables. They are reloaded once they change, so the pages can be restyled live.
+///
+pub struct TemplateOverrides {
+    directory: PathBuf,
+    /// Loaded templates, and when the files they were loaded from were last modified.
+    loaded: std::sync::Mutex<(Environment<'static>, HashMap<String, SystemTime>)>,
+}
+
+impl TemplateOverrides {
+    pub fn new(directory: &str) -> Self {
+        let mut environment: Environment<'static> = Environment::new();
+
+        environment.set_loader(path_loader(directory));
+        // like askama, which escapes both HTML and SVG templates
+        environment.set_auto_escape_callback(|_| AutoEscape::Html);
+
+        Self {
+            directory: PathBuf::from(directory),
+            loaded: std::sync::Mutex::new((environment, HashMap::new())),
+        }
+    }
+
+    /// Render the override of the given template, if there is one.
+    fn render<T: Serialize>(&self, name: &str, page: &T) -> Result<Option<String>, String> {
+        let path: PathBuf = self.directory.join(name);
+
+        let Ok(modified) = path.metadata().and_then(|meta| meta.modified()) else {
+            return Ok(None);
+        };
+        let mut loaded = self.loaded.lock().unwrap();
+        let (environment, modified_at) = &mut *loaded;
+
+        if modified_at.get(name) != Some(&modified) {
+            // the others are loaded again on demand too
+            environment.clear_templates();
+            modified_at.insert(name.to_owned(), modified);
+        }
+        environment
+            .get_template(name)
+            .and_then(|template| template.render(page))
+            .map(Some)
+            .map_err(|err| err.to_string())
+    }
+}
+
+/// Render a page with its template from the `templates_directory`, if there is one,
+/// or else the compiled-in one.
+///
+/// An override that fails to render is skipped, after logging why,
+/// so that a mistake in it doesn't take the page down.
+///
+pub fn render<T: Template + Serialize>(server_state: &ServerState, name: &str, page: &T) -> String {
+    if let Some(overrides) = server_state.template_overrides.as_ref() {
+        match overrides.render(name, page) {
+            Ok(Some(rendered)) => return rendered,
+            Ok(None) => (),
+            Err(err) => eprintln!("Could not render the {} template override: {}", name, err),
+        }
+    }
+    page.render().unwrap()
+}
+
+#[derive(Template, Serialize)]
+#[template(path = "badge.svg")]
+struct BadgeTemplate {
+    label: &'static str,
+    label_width: usize,
+    status: String,
+    status_width: usize,
+    status_color: String,
+}
+
+#[derive(Template, Serialize)]
+#[template(path = "favicon.svg")]
+struct FaviconTemplate {
+    status: String,
+    status_color: String,
+}
+
+/// Machine-readable form of a Unix timestamp, for the `datetime` attribute of a
+/// `<time>` element, which `local_time.js` shows in the visitor's time zone.
+/// Empty for 0, which stands for no timestamp.
+///
+pub fn datetime(timezone: FixedOffset, timestamp: u64) -> String {
+    match timestamp {
+        0 => String::default(),
+        _ => timezone
+            .timestamp_opt(timestamp as i64, 0)
+            .unwrap()
+            .to_rfc3339(),
+    }
+}
+
+/// Time left until a transition, rounded up to the minute, e.g. "2d 3h", "7h 12m", or "5m".
+/// The same as `countdown.js` keeps it up-to-date with.
+pub fn time_left(seconds: u64) -> String {
+    let minutes: u64 = seconds.div_ceil(60);
+
+    match (minutes / (24 * 60), minutes / 60 % 24, minutes % 60) {
+        (0, 0, minutes) => format!("{}m", minutes),
+        (0, hours, minutes) => format!("{}h {}m", hours, minutes),
+        (days, hours, _) => format!("{}d {}h", days, hours),
+    }
+}
+
+/// Countdown to the state degrading on its own, unless a heartbeat arrives.
+#[derive(Serialize, Default)]
+struct Countdown {
+    /// e.g. "Becomes PROBABLY ALIVE in 7h 12m, unless a heartbeat arrives."
+    text: String,
+    /// The text with `{1}` in place of the time left, for `countdown.js`
+    template: String,
+    seconds: u64,
+    /// Database code of the state counted down from, which the countdown
+    /// stops being right about once it changes.
+    state: &'static str,
+}
+
+impl Countdown {
+    async fn new(
+        server_state: &ServerState,
+        state: LifeState,
+        t: &'static Catalog,
+        now: u64,
+    ) -> Option<Self> {
+        let next_state: LifeState = state.degrades_to()?;
+        let seconds: u64 = server_state
+            .next_transition(state, now)
+            .await?
+            .saturating_sub(now);
+        let template: String = t.degrades_in.replace("{0}", t.state_name(next_state));
+
+        Some(Countdown {
+            text: template.replace("{1}", &time_left(seconds)),
+            template,
+            seconds,
+            state: state.db_code(),
+        })
+    }
+}
+
+#[derive(Template, Serialize)]
 #[template(path = "index.html")]
 struct IndexTemplate {
+    theme: ThemeMode,
+    t: &'static Catalog,
+    title: String,
+    /// Changes with the state, so browsers don't keep showing the icon of the last one.
+    favicon: String,
     name: String,
     status_color: String,
     status_image: String,
     status_title: String,
     status_message: String,
+    show_countdown: String,
+    countdown: Countdown,
     row_1_timestamp: String,
+    row_1_datetime: String,
     row_1_message: String,
+    row_1_media: String,
+    row_1_passive: bool,
+    row_1_label: String,
     row_2_timestamp: String,
+    row_2_datetime: String,
     row_2_message: String,
+    row_2_media: String,
+    row_2_passive: bool,
+    row_2_label: String,
     row_3_timestamp: String,
+    row_3_datetime: String,
     row_3_message: String,
+    row_3_media: String,
+    row_3_passive: bool,
+    row_3_label: String,
     row_4_timestamp: String,
+    row_4_datetime: String,
     row_4_message: String,
+    row_4_media: String,
+    row_4_passive: bool,
+    row_4_label: String,
     row_5_timestamp: String,
+    row_5_datetime: String,
     row_5_message: String,
+    row_5_media: String,
+    row_5_passive: bool,
+    row_5_label: String,
     show_note: String,
     note_message: String,
+    show_check_in: String,
+    check_in_timestamp: String,
+    check_in_datetime: String,
+    check_in_message: String,
+    show_calendar: String,
+    calendar: Vec<Vec<CalendarDay>>,
     is_dead: String,
+    show_profile: String,
+    avatar: String,
+    pronouns: String,
+    bio: String,
+    links: Vec<Link>,
+    /// Visitors may only sign the guestbook once the person is dead.
+    guestbook_open: bool,
+    guestbook: Vec<GuestbookDisplay>,
+    guestbook_prompt: String,
+    note_heading: String,
+    show_push: String,
+    push_link: String,
+    vapid_public_key: String,
 }
 
-pub async fn index(State(server_state): State<ServerState>) -> impl IntoResponse {
+pub async fn index(
+    _reader: Reader,
+    method: Method,
+    headers: HeaderMap,
+    State(server_state): State<ServerState>,
+) -> Response {
     let now: u64 = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs();
     server_state.update(now).await;
 
+    let cache: CacheHeaders = CacheHeaders::new(&server_state, now).await;
+
+    // the page is in the visitor's language, so caches must keep one per language
+    let vary = [("Vary", "Accept-Language")];
+
+    if cache.not_modified(&headers) {
+        return (StatusCode::NOT_MODIFIED, cache, vary, ()).into_response();
+    }
+    // probes only want the headers, so don't render the page for them
+    if method == Method::HEAD {
+        return (cache, vary, [("Content-Type", "text/html; charset=utf-8")]).into_response();
+    }
+
+    let t: &'static Catalog = i18n::negotiate(
+        &headers,
+        i18n::catalog(&server_state.config.global.locale).expect("Unsupported locale."),
+    );
+
     // first get a random number from the OS rng
     let img_randint: u64 = OsRng.try_next_u64().expect("OS RNG error.");
     let msg_randint: u64 = OsRng.try_next_u64().expect("OS RNG error.");
@@ -74,8 +312,15 @@ pub async fn index(State(server_state): State<ServerState>) -> impl IntoResponse
         _ => server_state.config.global.full_name.clone(),
     };
 
-    let status_title: String = locked_state.to_string();
-    let status_color: String = locked_state.css_color();
+    let status_title: String = t.state_name(**locked_state).into();
+    let status_color: String = theme::state_color(&server_state.config.theme, **locked_state);
+
+    // the state comes first, to be seen in a narrow, pinned tab
+    let title: String = format!("{} · {}", status_title, t.title.replace("{0}", &name));
+    let favicon: String = format!("/favicon.svg?state={}", locked_state.db_code());
+
+    let state: LifeState = **locked_state;
+    let guestbook_open: bool = state == LifeState::Dead;
 
     // whether we want to grayscale certain UI elements out of respect
     let is_dead: String = match **locked_state {
@@ -134,6 +379,8 @@ pub async fn index(State(server_state): State<ServerState>) -> impl IntoResponse
 
             formatted_status_msg =
                 formatted_status_msg.replace("{1}", &hours_since_last_seen.to_string());
+            formatted_status_msg =
+                formatted_status_msg.replace("{3}", &t.hours_ago(hours_since_last_seen as u32));
 
             let mut plural_str: &str = "";
 
@@ -147,76 +394,457 @@ pub async fn index(State(server_state): State<ServerState>) -> impl IntoResponse
     }
     drop(locked_state); // drop mutex as we no longer will read state
 
+    // once dead, the final statement replaces both the status message and the note
+    let final_statement: Option<&str> = server_state.final_statement().await;
+
+    if let Some(statement) = final_statement {
+        formatted_status_msg = statement.into();
+    }
+    let timezone: FixedOffset =
+        FixedOffset::east_opt(server_state.config.global.utc_offset * 60 * 60).unwrap();
+
+    // once the "Dead" state is confirmed, the page becomes a memorial, if one is configured
+    if let Some(memorial) = server_state.config.memorial.as_ref()
+        && server_state.dead_confirmed().await
+    {
+        let page: MemorialTemplate = memorial_page(
+            &server_state,
+            memorial,
+            t,
+            timezone,
+            &name,
+            img_path,
+            formatted_status_msg,
+        )
+        .await;
+        let html: String = render(&server_state, "memorial.html", &page);
+
+        return (cache, vary, Html(html)).into_response();
+    }
+
     // get latest heartbeat table / note to display
     let heartbeats: MutexGuard<'_, [HeartbeatDisplay; 5]> =
         server_state.displayed_heartbeats.lock().await;
+
+    // only link to heartbeat media if anyone may view it
+    let media_link = |heartbeat: &HeartbeatDisplay| -> String {
+        match server_state.config.heartbeat_media.as_ref() {
+            Some(media) if media.public => heartbeat
+                .media
+                .as_deref()
+                .map(media_url)
+                .unwrap_or_default(),
+            _ => String::default(),
+        }
+    };
     let locked_note: MutexGuard<'_, Option<String>> = server_state.note.lock().await;
 
-    let html = IndexTemplate {
+    // check-ins are only shown until the person is heard from again
+    let last_heartbeat: u64 = **server_state.last_heartbeat.lock().await;
+    let check_in: Option<CheckInLog> = server_state
+        .last_check_in
+        .lock()
+        .await
+        .clone()
+        .filter(|check_in| check_in.timestamp > last_heartbeat);
+
+    let calendar: Vec<Vec<CalendarDay>> = match server_state.config.global.heartbeat_calendar {
+        true => heatmap::calendar(
+            &*server_state.heartbeat_days.lock().await,
+            heatmap::local_date(timezone, now),
+            t,
+        ),
+        false => vec![],
+    };
+
+    let guestbook: Vec<GuestbookDisplay> = match guestbook_open {
+        true => guestbook::display(&server_state.guestbook.lock().await, timezone, t),
+        false => vec![],
+    };
+
+    let profile: &Profile = &server_state.config.profile;
+    let countdown: Option<Countdown> = Countdown::new(&server_state, state, t, now).await;
+
+    let page: IndexTemplate = IndexTemplate {
+        theme: server_state.config.theme.mode,
+        t,
+        title,
+        favicon,
+        guestbook_prompt: t.guestbook_prompt.replace("{0}", &name),
+        note_heading: t.note_from.replace("{0}", &name),
+        push_link: t.notify_me.replace("{0}", &name),
         name,
         status_title,
         status_color,
         status_image: img_path,
         status_message: formatted_status_msg,
+        show_countdown: match countdown {
+            Some(_) => String::default(),
+            None => HIDE_CSS_ID.into(),
+        },
+        countdown: countdown.unwrap_or_default(),
         row_1_timestamp: heartbeats[0].timestamp.clone(),
+        row_1_datetime: datetime(timezone, heartbeats[0].unix_timestamp),
         row_1_message: heartbeats[0].message.clone(),
+        row_1_media: media_link(&heartbeats[0]),
+        row_1_passive: heartbeats[0].passive,
+        row_1_label: heartbeats[0].label.clone().unwrap_or_default(),
         row_2_timestamp: heartbeats[1].timestamp.clone(),
+        row_2_datetime: datetime(timezone, heartbeats[1].unix_timestamp),
         row_2_message: heartbeats[1].message.clone(),
+        row_2_media: media_link(&heartbeats[1]),
+        row_2_passive: heartbeats[1].passive,
+        row_2_label: heartbeats[1].label.clone().unwrap_or_default(),
         row_3_timestamp: heartbeats[2].timestamp.clone(),
+        row_3_datetime: datetime(timezone, heartbeats[2].unix_timestamp),
         row_3_message: heartbeats[2].message.clone(),
+        row_3_media: media_link(&heartbeats[2]),
+        row_3_passive: heartbeats[2].passive,
+        row_3_label: heartbeats[2].label.clone().unwrap_or_default(),
         row_4_timestamp: heartbeats[3].timestamp.clone(),
+        row_4_datetime: datetime(timezone, heartbeats[3].unix_timestamp),
         row_4_message: heartbeats[3].message.clone(),
+        row_4_media: media_link(&heartbeats[3]),
+        row_4_passive: heartbeats[3].passive,
+        row_4_label: heartbeats[3].label.clone().unwrap_or_default(),
         row_5_timestamp: heartbeats[4].timestamp.clone(),
+        row_5_datetime: datetime(timezone, heartbeats[4].unix_timestamp),
         row_5_message: heartbeats[4].message.clone(),
-        show_note: match *locked_note {
-            Some(_) => String::default(),
-            None => HIDE_CSS_ID.into(),
+        row_5_media: media_link(&heartbeats[4]),
+        row_5_passive: heartbeats[4].passive,
+        row_5_label: heartbeats[4].label.clone().unwrap_or_default(),
+        show_note: match (final_statement, &*locked_note) {
+            (None, Some(_)) => String::default(),
+            _ => HIDE_CSS_ID.into(),
         },
         note_message: match &*locked_note {
             Some(note) => note.clone(),
             None => String::default(),
         },
+        show_check_in: match (final_statement, &check_in) {
+            (None, Some(_)) => String::default(),
+            _ => HIDE_CSS_ID.into(),
+        },
+        check_in_timestamp: match &check_in {
+            Some(check_in) => timezone
+                .timestamp_opt(check_in.timestamp as i64, 0)
+                .unwrap()
+                .to_rfc2822(),
+            None => String::default(),
+        },
+        check_in_datetime: match &check_in {
+            Some(check_in) => datetime(timezone, check_in.timestamp),
+            None => String::default(),
+        },
+        check_in_message: check_in
+            .map(|check_in| check_in.message)
+            .unwrap_or_default(),
+        show_calendar: match calendar.is_empty() {
+            true => HIDE_CSS_ID.into(),
+            false => String::default(),
+        },
+        calendar,
         is_dead,
-    }
-    .render()
-    .unwrap();
+        show_profile: match *profile == Profile::default() {
+            true => HIDE_CSS_ID.into(),
+            false => String::default(),
+        },
+        avatar: profile.avatar.clone().unwrap_or_default(),
+        pronouns: profile.pronouns.clone().unwrap_or_default(),
+        bio: profile.bio.clone().unwrap_or_default(),
+        links: profile.links.clone(),
+        guestbook_open,
+        guestbook,
+        show_push: match server_state.config.web_push {
+            Some(_) => String::default(),
+            None => HIDE_CSS_ID.into(),
+        },
+        vapid_public_key: match server_state.config.web_push.as_ref() {
+            Some(web_push) => vapid_public_key(web_push),
+            None => String::default(),
+        },
+    };
+    let html: String = render(&server_state, "index.html", &page);
 
-    Html(html)
+    (cache, vary, Html(html)).into_response()
 }
 
-#[derive(Template)]
+#[derive(Template, Serialize)]
+#[template(path = "memorial.html")]
+struct MemorialTemplate {
+    theme: ThemeMode,
+    t: &'static Catalog,
+    title: String,
+    favicon: String,
+    /// e.g. "1970-04-01 – 2026-10-03"
+    dates: String,
+    image: String,
+    /// The final statement, or a status message of the "Dead" state.
+    statement: String,
+    links: Vec<Link>,
+    guestbook: Vec<GuestbookDisplay>,
+    guestbook_prompt: String,
+    is_dead: String,
+}
+
+/// The status page once the "Dead" state is confirmed, in memory of the person.
+async fn memorial_page(
+    server_state: &ServerState,
+    memorial: &Memorial,
+    t: &'static Catalog,
+    timezone: FixedOffset,
+    name: &str,
+    status_image: String,
+    statement: String,
+) -> MemorialTemplate {
+    let last_heartbeat: u64 = **server_state.last_heartbeat.lock().await;
+
+    let died: String = match memorial.died.as_ref() {
+        Some(died) => died.clone(),
+        None => heatmap::local_date(timezone, last_heartbeat).to_string(),
+    };
+
+    MemorialTemplate {
+        theme: server_state.config.theme.mode,
+        t,
+        title: t.in_memory_of.replace("{0}", name),
+        favicon: format!("/favicon.svg?state={}", LifeState::Dead.db_code()),
+        dates: match memorial.born.as_ref() {
+            Some(born) => format!("{} – {}", born, died),
+            None => died,
+        },
+        image: memorial.image.clone().unwrap_or(status_image),
+        statement,
+        links: memorial.links.clone(),
+        guestbook: guestbook::display(&server_state.guestbook.lock().await, timezone, t),
+        guestbook_prompt: t.guestbook_prompt.replace("{0}", name),
+        is_dead: DEAD_CSS_ID.into(),
+    }
+}
+
+#[derive(Template, Serialize)]
 #[template(path = "heartbeat.html")]
 struct HeartbeatTemplate {
+    theme: ThemeMode,
     name: String,
     show_note: String,
     note_message: String,
+    show_qr_code: String,
+    /// `data:` URL of the QR code image, only shown to the person themself.
+    qr_code: String,
+    qr_code_minutes: u64,
+    /// For the form to work without JavaScript, if enabled, see [`crate::heartbeat_form`]
+    form_token: String,
+    show_feedback: String,
+    feedback: String,
+    feedback_color: String,
+    show_countdown: String,
+    countdown: Countdown,
+}
+
+/// Width of a badge side holding the given text, in pixels. Roughly
+/// what the text takes in 11px Verdana, which the badge is set in.
+fn badge_width(text: &str) -> usize {
+    text.chars().count() * 7 + 10
 }
 
-pub async fn heartbeat(State(server_state): State<ServerState>) -> impl IntoResponse {
+/// Handles requests on `/badge.svg`, a shields.io-style badge
+/// of the current state, for embedding on websites.
+///
+pub async fn badge(
+    _reader: Reader,
+    method: Method,
+    headers: HeaderMap,
+    State(server_state): State<ServerState>,
+) -> Response {
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    server_state.update(now).await;
+
+    // embedding sites' image proxies shouldn't hold on to a stale state
+    let cache: CacheHeaders = CacheHeaders::new(&server_state, now).await;
+
+    if cache.not_modified(&headers) {
+        return (StatusCode::NOT_MODIFIED, cache, ()).into_response();
+    }
+    if method == Method::HEAD {
+        return (cache, [("Content-Type", "image/svg+xml")]).into_response();
+    }
+
+    let state: LifeState = **server_state.state.lock().await;
+    let status: String = state.to_string();
+
+    let page: BadgeTemplate = BadgeTemplate {
+        label: BADGE_LABEL,
+        label_width: badge_width(BADGE_LABEL),
+        status_width: badge_width(&status),
+        status,
+        status_color: theme::state_color(&server_state.config.theme, state),
+    };
+    let svg: String = render(&server_state, "badge.svg", &page);
+
+    let resp: Response = Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "image/svg+xml")
+        .body(Body::from(svg))
+        .unwrap();
+    (cache, resp).into_response()
+}
+
+/// Handles requests on `/favicon.svg`, the icon of the status page, a heart in
+/// the color of the current state, so that a change shows on a pinned tab.
+///
+pub async fn favicon(
+    _reader: Reader,
+    method: Method,
+    headers: HeaderMap,
+    State(server_state): State<ServerState>,
+) -> Response {
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    server_state.update(now).await;
+
+    let cache: CacheHeaders = CacheHeaders::new(&server_state, now).await;
+
+    if cache.not_modified(&headers) {
+        return (StatusCode::NOT_MODIFIED, cache, ()).into_response();
+    }
+    if method == Method::HEAD {
+        return (cache, [("Content-Type", "image/svg+xml")]).into_response();
+    }
+
+    let state: LifeState = **server_state.state.lock().await;
+
+    let page: FaviconTemplate = FaviconTemplate {
+        status: state.to_string(),
+        status_color: theme::state_color(&server_state.config.theme, state),
+    };
+    let svg: String = render(&server_state, "favicon.svg", &page);
+
+    (cache, [("Content-Type", "image/svg+xml")], svg).into_response()
+}
+
+pub async fn heartbeat(
+    web_session: Option<WebSession>,
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+    State(server_state): State<ServerState>,
+) -> Response {
+    heartbeat_page(&server_state, web_session, &headers, ip, None).await
+}
+
+/// Render the `/heartbeat` page, telling how a heartbeat sent
+/// from its form without JavaScript went, if one was.
+///
+pub async fn heartbeat_page(
+    server_state: &ServerState,
+    web_session: Option<WebSession>,
+    headers: &HeaderMap,
+    ip: IpAddr,
+    feedback: Option<Feedback>,
+) -> Response {
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
     let locked_state: MutexGuard<'_, Redundant<LifeState>> = server_state.state.lock().await;
 
     // short name when alive, full name when in any negative state.
-    let name: String = match **locked_state {
-        LifeState::Alive => server_state.config.global.name.clone(),
+    // private instances don't give the state away on this page, as anyone may load it.
+    let name: String = match (**locked_state, server_state.config.global.private) {
+        (LifeState::Alive, false) => server_state.config.global.name.clone(),
         _ => server_state.config.global.full_name.clone(),
     };
+    let state: LifeState = **locked_state;
     drop(locked_state); // drop mutex as we no longer will read state
 
-    let locked_note: MutexGuard<'_, Option<String>> = server_state.note.lock().await;
+    let note: Option<String> = match server_state.config.global.private {
+        true => None,
+        false => server_state.note.lock().await.clone(),
+    };
+    let role: Option<Role> = web_session.map(|web_session| web_session.session.role);
+
+    // the page isn't translated, and only the person themself sees the countdown of a private instance
+    let countdown: Option<Countdown> =
+        match server_state.config.global.private && role != Some(Role::Owner) {
+            true => None,
+            false => Countdown::new(server_state, state, i18n::catalog("en").unwrap(), now).await,
+        };
+
+    // logged in as themself, the person can scan a QR code to send a heartbeat from their phone
+    let qr_code: String = match role {
+        Some(Role::Owner) => {
+            let token: String = qr_code::issue_token(server_state, now).await;
+
+            qr_code::data_url(&qr_code::heartbeat_link(headers, &token))
+        }
+        _ => String::default(),
+    };
 
-    let html = HeartbeatTemplate {
+    let page: HeartbeatTemplate = HeartbeatTemplate {
+        theme: server_state.config.theme.mode,
         name,
-        show_note: match *locked_note {
+        show_note: match note {
             Some(_) => String::default(),
             None => "hidden".into(),
         },
-        note_message: match &*locked_note {
-            Some(note) => note.clone(),
+        note_message: note.unwrap_or_default(),
+        show_qr_code: match qr_code.is_empty() {
+            true => HIDE_CSS_ID.into(),
+            false => String::default(),
+        },
+        qr_code,
+        qr_code_minutes: qr_code::TOKEN_LIFETIME / 60,
+        form_token: match server_state.config.pow.no_js_fallback {
+            true => heartbeat_form::form_token(server_state, ip, now),
+            false => String::default(),
+        },
+        show_feedback: match feedback {
+            Some(_) => String::default(),
+            None => HIDE_CSS_ID.into(),
+        },
+        feedback: feedback
+            .map(|feedback| feedback.text)
+            .unwrap_or_default()
+            .into(),
+        feedback_color: match feedback.map(|feedback| feedback.sent) {
+            Some(true) => "#067c02".into(),
+            Some(false) => "#870000".into(),
             None => String::default(),
         },
+        show_countdown: match countdown {
+            Some(_) => String::default(),
+            None => HIDE_CSS_ID.into(),
+        },
+        countdown: countdown.unwrap_or_default(),
+    };
+    let mut response: Response =
+        Html(render(server_state, "heartbeat.html", &page)).into_response();
+
+    // the QR code's token must not be kept by any cache
+    if !page.qr_code.is_empty() {
+        response
+            .headers_mut()
+            .insert("Cache-Control", HeaderValue::from_static("no-store"));
     }
-    .render()
-    .unwrap();
+    response
+}
 
-    Html(html)
+#[derive(Template, Serialize)]
+#[template(path = "invite.html")]
+struct InviteTemplate {
+    theme: ThemeMode,
+    name: String,
+}
+
+pub async fn invite(State(server_state): State<ServerState>) -> impl IntoResponse {
+    let page: InviteTemplate = InviteTemplate {
+        theme: server_state.config.theme.mode,
+        name: server_state.config.global.full_name.clone(),
+    };
+    Html(render(&server_state, "invite.html", &page))
 }
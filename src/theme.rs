@@ -0,0 +1,110 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::config::{Theme, ThemeMode};
+use crate::state::{AssociatedColor, LifeState, ServerState};
+use axum::extract::State;
+use axum::response::IntoResponse;
+
+/// Color of a state on the pages, the configured one if any.
+pub fn state_color(theme: &Theme, state: LifeState) -> String {
+    let configured: Option<&String> = match state {
+        LifeState::Alive => theme.state_colors.alive.as_ref(),
+        LifeState::ProbablyAlive => theme.state_colors.uncertain.as_ref(),
+        LifeState::MissingOrDead => theme.state_colors.missing.as_ref(),
+        LifeState::Incapacitated => theme.state_colors.incapacitated.as_ref(),
+        LifeState::Dead => theme.state_colors.dead.as_ref(),
+    };
+    configured.cloned().unwrap_or_else(|| state.css_color())
+}
+
+/// Background color of the pages, for the browser's UI around them.
+pub fn background_color(theme: &Theme) -> String {
+    if let Some(background) = theme.colors.get("background") {
+        return background.clone();
+    }
+    // those of `styles.css`, with "auto" starting out dark like it
+    match theme.mode {
+        ThemeMode::Light => "#f2f4f8".into(),
+        ThemeMode::Dark | ThemeMode::Auto => "#0b0f18".into(),
+    }
+}
+
+/// Whether the name of a CSS variable, without its leading `--`, is valid.
+pub fn is_css_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// Whether a configured color can't break out of its CSS declaration.
+pub fn is_css_value(value: &str) -> bool {
+    !value.trim().is_empty() && !value.contains([';', '{', '}', '<', '>', '\\'])
+}
+
+/// Configured colors of the palette, keyed by the name of their CSS variable.
+fn palette(theme: &Theme) -> Vec<(&str, &str)> {
+    let mut palette: Vec<(&str, &str)> = theme
+        .colors
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .collect();
+
+    if let Some(accent) = theme.accent.as_deref() {
+        palette.retain(|(name, _)| *name != "accent");
+        palette.push(("accent", accent));
+    }
+    palette
+}
+
+/// Handles requests on `/theme.css`, the configured colors as CSS variables,
+/// overriding those of `styles.css` in both its dark and light palettes.
+///
+pub async fn theme_css(State(server_state): State<ServerState>) -> impl IntoResponse {
+    let mut css: String = String::new();
+    let palette: Vec<(&str, &str)> = palette(&server_state.config.theme);
+
+    if !palette.is_empty() {
+        // as specific as the light palette's selector, which it comes after
+        css.push_str(":root[data-theme] {\n");
+        for (name, value) in palette {
+            css.push_str(&format!("    --{}: {};\n", name, value));
+        }
+        css.push_str("}\n");
+    }
+    // the configuration only changes with a restart, but then it should show right away
+    (
+        [
+            ("Content-Type", "text/css; charset=utf-8"),
+            ("Cache-Control", "no-cache"),
+        ],
+        css,
+    )
+}
+
+impl std::fmt::Display for ThemeMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Dark => write!(f, "dark"),
+            Self::Light => write!(f, "light"),
+            Self::Auto => write!(f, "auto"),
+        }
+    }
+}
@@ -0,0 +1,211 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::config::{Role, ServerConfig};
+use crate::database::{self, Database, TimestampProof, TransitionLog, load_database};
+use crate::trusted::TrustedUser;
+use axum::body::Body;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use tokio::sync::{Mutex, MutexGuard};
+use utoipa::ToSchema;
+
+/// Held while publishing digests, so two tasks running
+/// at once can't both publish the same transition.
+static TIMESTAMPING_LOCK: Mutex<()> = Mutex::const_new(());
+
+#[derive(Serialize, ToSchema)]
+struct TimestampEntry<'a> {
+    /// The transition's database record, exactly as it was hashed.
+    record: String,
+    /// Hex encoded SHA-256 hash of `record`.
+    digest: String,
+    proofs: Vec<ProofEntry<'a>>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct ProofEntry<'a> {
+    calendar: &'a str,
+    /// Base64 encoded (pending) OpenTimestamps proof.
+    receipt: &'a str,
+}
+
+/// Hash of a state transition's database record, which is what gets timestamped.
+/// e.g. the SHA-256 of "transition 1792145569 2 4 timer\n"
+pub fn transition_digest(transition: &TransitionLog) -> String {
+    hex::encode(Sha256::digest(transition.to_string().as_bytes()))
+}
+
+/// Submit a digest to an OpenTimestamps calendar server, returning its receipt.
+/// See: <https://github.com/opentimestamps/opentimestamps-server>
+async fn submit_digest(client: &reqwest::Client, calendar: &str, digest: &str) -> Option<Vec<u8>> {
+    let endpoint: String = format!("{}/digest", calendar.trim_end_matches('/'));
+
+    let resp: reqwest::Response = match client
+        .post(&endpoint)
+        .header("Accept", "application/vnd.opentimestamps.v1")
+        .body(hex::decode(digest).unwrap())
+        .send()
+        .await
+    {
+        Err(err) => {
+            eprintln!(
+                "Failed to reach timestamping calendar {}: {}",
+                calendar, err
+            );
+            return None;
+        }
+        Ok(resp) => resp,
+    };
+    if !resp.status().is_success() {
+        eprintln!(
+            "Timestamping calendar {} refused a digest with HTTP status {}.",
+            calendar,
+            resp.status()
+        );
+        return None;
+    }
+    resp.bytes()
+        .await
+        .ok()
+        .filter(|bytes| !bytes.is_empty())
+        .map(|bytes| bytes.to_vec())
+}
+
+/// Publish the digest of every state transition that was not timestamped
+/// yet by every configured calendar server.
+///
+/// Called after every transition, and on every tick to retry failures.
+///
+pub async fn publish_pending(config: Arc<ServerConfig>) {
+    let Some(timestamping) = config.timestamping.as_ref() else {
+        return;
+    };
+    let _timestamping_guard: MutexGuard<'_, ()> = TIMESTAMPING_LOCK.lock().await;
+
+    let db: Database = {
+        let _db_guard: MutexGuard<'_, ()> = database::lock().await;
+
+        match load_database(crate::DB_PATH) {
+            Err(err) => {
+                eprintln!("Could not read the state transitions: {}", err);
+                return;
+            }
+            Ok(db) => db,
+        }
+    };
+
+    let client: reqwest::Client = reqwest::Client::new();
+    let mut published: Vec<TimestampProof> = vec![];
+
+    for transition in db.transitions.iter() {
+        let digest: String = transition_digest(transition);
+
+        for calendar in timestamping.calendars.iter() {
+            if db
+                .timestamp_proofs
+                .iter()
+                .any(|proof| proof.digest == digest && proof.calendar == *calendar)
+            {
+                continue;
+            }
+            if let Some(receipt) = submit_digest(&client, calendar, &digest).await {
+                println!("State transition {} timestamped by {}.", digest, calendar);
+
+                published.push(TimestampProof {
+                    digest: digest.clone(),
+                    calendar: calendar.clone(),
+                    receipt: STANDARD.encode(receipt),
+                });
+            }
+        }
+    }
+
+    if published.is_empty() {
+        return;
+    }
+    if let Err(err) = database::modify(|db| db.timestamp_proofs.append(&mut published)).await {
+        eprintln!("Could not sync timestamp proofs to disk: {}", err);
+    }
+}
+
+/// Handles requests on `/api/v1/timestamps`, which lists every state transition
+/// record along with the proofs that it was timestamped externally.
+#[utoipa::path(
+    get,
+    path = "/api/v1/timestamps",
+    tag = "trusted users",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "Every timestamped state transition", body = [TimestampEntry]),
+        (status = 401, description = "Not logged in"),
+        (status = 403, description = "Requires the viewer role"),
+    )
+)]
+pub async fn timestamps_api(user: TrustedUser) -> impl IntoResponse {
+    if let Err(forbidden) = user.require(Role::Viewer) {
+        return forbidden.into_response();
+    }
+    let db: Database = {
+        let _db_guard: MutexGuard<'_, ()> = database::lock().await;
+
+        match load_database(crate::DB_PATH) {
+            Err(err) => {
+                eprintln!("Could not read the timestamp proofs: {}", err);
+                return Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(Body::default())
+                    .unwrap();
+            }
+            Ok(db) => db,
+        }
+    };
+    let entries: Vec<TimestampEntry> = db
+        .transitions
+        .iter()
+        .map(|transition| {
+            let digest: String = transition_digest(transition);
+
+            TimestampEntry {
+                record: transition.to_string(),
+                proofs: db
+                    .timestamp_proofs
+                    .iter()
+                    .filter(|proof| proof.digest == digest)
+                    .map(|proof| ProofEntry {
+                        calendar: &proof.calendar,
+                        receipt: &proof.receipt,
+                    })
+                    .collect(),
+                digest,
+            }
+        })
+        .collect();
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&entries).unwrap()))
+        .unwrap()
+}
@@ -0,0 +1,91 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::database::{self, Database, TransitionLog, load_database};
+use crate::trusted::Reader;
+use axum::body::Body;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+use tokio::sync::MutexGuard;
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema)]
+pub struct TransitionEntry {
+    /// Unix timestamp
+    timestamp: u64,
+    /// Name of the state, e.g. "ProbablyAlive"
+    from: String,
+    /// Name of the state, e.g. "MissingOrDead"
+    to: String,
+    /// What caused the transition, e.g. "timeout" or "heartbeat"
+    cause: String,
+}
+
+impl From<TransitionLog> for TransitionEntry {
+    fn from(transition: TransitionLog) -> Self {
+        TransitionEntry {
+            timestamp: transition.timestamp,
+            from: format!("{:?}", transition.from),
+            to: format!("{:?}", transition.to),
+            cause: transition.cause,
+        }
+    }
+}
+
+/// Handles requests on `/api/v1/transitions`, which lists every state
+/// transition, oldest first, so that anyone can tell when the state changed.
+///
+#[utoipa::path(
+    get,
+    path = "/api/v1/transitions",
+    tag = "status",
+    responses(
+        (status = 200, description = "Every state transition, oldest first", body = [TransitionEntry]),
+        (status = 401, description = "The instance is private"),
+    ),
+    security((), ("bearer" = [])),
+)]
+pub async fn transitions_api(_reader: Reader) -> impl IntoResponse {
+    let db: Database = {
+        let _db_guard: MutexGuard<'_, ()> = database::lock().await;
+
+        match load_database(crate::DB_PATH) {
+            Err(err) => {
+                eprintln!("Could not read the state transitions: {}", err);
+                return Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(Body::default())
+                    .unwrap();
+            }
+            Ok(db) => db,
+        }
+    };
+    let entries: Vec<TransitionEntry> = db
+        .transitions
+        .into_iter()
+        .map(TransitionEntry::from)
+        .collect();
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&entries).unwrap()))
+        .unwrap()
+}
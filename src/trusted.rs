@@ -0,0 +1,890 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::api::{ClientIp, FieldsQuery, PowSolution, select_fields};
+use crate::audit;
+use crate::config::{Role, TrustedUserConfig};
+use crate::database::{self, ConfirmationLog, Database, HeartbeatLog, load_database};
+use crate::media::media_url;
+use crate::password;
+use crate::pow::verify_pow_solution;
+use crate::problem;
+use crate::rate_limit;
+use crate::sources::token_hash;
+use crate::state::{LifeState, RateLimit, Redundant, ServerState, TransitionCause};
+use crate::web_session::cookie_session;
+use crate::{INITIAL_RATE_LIMIT_PERIOD, RATE_LIMIT_PERIOD_FACTOR};
+use argon2::password_hash::PasswordHash;
+use axum::async_trait;
+use axum::body::Body;
+use axum::extract::{FromRequestParts, Json, Query, State};
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use rand::rand_core::{OsRng, TryRngCore};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::MutexGuard;
+use utoipa::{IntoParams, ToSchema};
+
+/// Time period, in seconds, for which a trusted user session is valid for.
+pub const SESSION_LIFETIME: u64 = 12 * 60 * 60;
+
+/// Heartbeats per page of `/api/v1/heartbeats`, unless asked otherwise.
+pub const DEFAULT_PAGE_SIZE: usize = 20;
+/// Most heartbeats on a single page of `/api/v1/heartbeats`.
+pub const MAX_PAGE_SIZE: usize = 100;
+
+/// Username of the person themself, when logged in on `/api/v1/admin/login`.
+/// Reserved, so no trusted user can be mistaken for the owner.
+pub const OWNER_USERNAME: &str = "owner";
+
+/// A logged in trusted user, referenced by its session token.
+#[derive(Clone)]
+pub struct Session {
+    pub username: String,
+    pub role: Role,
+    /// Unix timestamp of when this session expires.
+    pub expires: u64,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct LoginRequest {
+    username: String,
+    password: String,
+    pow: PowSolution,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AdminLoginRequest {
+    password: String,
+    pow: PowSolution,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct VerifyRequest {
+    state: VerifiedState,
+}
+
+/// States that can only be reached by a trusted user verifying them.
+#[derive(Deserialize, Clone, Copy, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum VerifiedState {
+    Incapacitated,
+    Dead,
+}
+
+impl From<VerifiedState> for LifeState {
+    fn from(value: VerifiedState) -> Self {
+        match value {
+            VerifiedState::Incapacitated => LifeState::Incapacitated,
+            VerifiedState::Dead => LifeState::Dead,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LoginResponse {
+    token: String,
+    /// Unix timestamp
+    expires: u64,
+}
+
+/// Response given when a confirmation was recorded, but the quorum is not met yet.
+#[derive(Serialize, ToSchema)]
+struct ConfirmationResponse {
+    confirmations: usize,
+    quorum: usize,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct HistoryEntry<'a> {
+    /// Unix timestamp
+    pub timestamp: u64,
+    pub message: &'a str,
+    /// Path of the media sent along with the heartbeat, if any.
+    pub media: Option<String>,
+    /// Whether the heartbeat was registered from activity seen elsewhere.
+    pub passive: bool,
+    /// What registered the heartbeat, if labeled. (e.g. a sensor's name)
+    pub label: Option<&'a str>,
+    /// Where the heartbeat came from, unless it was sent from an IP address.
+    /// (e.g. "telegram", "github")
+    pub source: Option<&'a str>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct HeartbeatsQuery {
+    /// Starting from 1, the newest heartbeats.
+    page: Option<usize>,
+    /// Up to 100 heartbeats, 20 by default.
+    per_page: Option<usize>,
+    /// Comma separated fields of each heartbeat to keep, e.g. "timestamp,message".
+    fields: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct HeartbeatsPage<'a> {
+    page: usize,
+    per_page: usize,
+    /// Total number of heartbeats, on every page.
+    total: usize,
+    heartbeats: Vec<HistoryEntry<'a>>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct SessionResponse {
+    username: String,
+    role: Role,
+    /// Unix timestamp
+    expires: u64,
+}
+
+/// Authentication layer for endpoints meant for trusted users.
+///
+/// Extracting this from a request requires a valid session token in the
+/// `Authorization: Bearer <token>` HTTP header, otherwise the request
+/// is rejected with `401 Unauthorized`.
+///
+pub struct TrustedUser {
+    pub token: String,
+    pub session: Session,
+}
+
+impl TrustedUser {
+    /// Make sure this user's role grants the permissions of the `required` role.
+    pub fn require(&self, required: Role) -> Result<(), Forbidden> {
+        match self.session.role.permits(required) {
+            true => Ok(()),
+            false => Err(Forbidden),
+        }
+    }
+}
+
+/// Rejection of a trusted user whose role doesn't grant the required permissions.
+pub struct Forbidden;
+
+impl IntoResponse for Forbidden {
+    fn into_response(self) -> Response {
+        Response::builder()
+            .status(StatusCode::FORBIDDEN)
+            .body(Body::default())
+            .unwrap()
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<ServerState> for TrustedUser {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        server_state: &ServerState,
+    ) -> Result<Self, Self::Rejection> {
+        let unauthorized: Response = Response::builder()
+            .status(StatusCode::UNAUTHORIZED)
+            .header("WWW-Authenticate", "Bearer")
+            .body(Body::default())
+            .unwrap();
+
+        let Some(token) = bearer_token(&parts.headers) else {
+            return Err(unauthorized);
+        };
+        match active_session(server_state, &token).await {
+            Some(session) => Ok(TrustedUser { token, session }),
+            None => Err(unauthorized),
+        }
+    }
+}
+
+/// Authentication layer for endpoints meant for the person themself.
+///
+/// Like [`TrustedUser`], but the session must be the owner's,
+/// otherwise the request is rejected with `403 Forbidden`.
+///
+pub struct Admin;
+
+#[async_trait]
+impl FromRequestParts<ServerState> for Admin {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        server_state: &ServerState,
+    ) -> Result<Self, Self::Rejection> {
+        let user: TrustedUser = TrustedUser::from_request_parts(parts, server_state).await?;
+
+        user.require(Role::Owner)
+            .map(|_| Admin)
+            .map_err(IntoResponse::into_response)
+    }
+}
+
+/// Authentication layer for what a private instance only shows to a closed circle,
+/// e.g. the status page, `/api/v1/status`, and the status events.
+///
+/// On a public instance, anyone may read. On a private one, extracting this from a
+/// request requires one of the configured read keys or a trusted user's session token,
+/// in the `Authorization: Bearer <token>` HTTP header or in the `key` query parameter
+/// for browsers, or the session cookie of a trusted user logged in on `/login`,
+/// otherwise the request is rejected with `401 Unauthorized`.
+///
+pub struct Reader;
+
+#[async_trait]
+impl FromRequestParts<ServerState> for Reader {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        server_state: &ServerState,
+    ) -> Result<Self, Self::Rejection> {
+        if !server_state.config.global.private {
+            return Ok(Reader);
+        }
+        let unauthorized: Response = Response::builder()
+            .status(StatusCode::UNAUTHORIZED)
+            .header("WWW-Authenticate", "Bearer")
+            .body(Body::from("This instance is private."))
+            .unwrap();
+
+        let key: Option<String> = bearer_token(&parts.headers).or_else(|| {
+            Query::<HashMap<String, String>>::try_from_uri(&parts.uri)
+                .ok()
+                .and_then(|Query(mut params)| params.remove("key"))
+        });
+        match key {
+            Some(key) if is_reader_key(server_state, &key).await => Ok(Reader),
+            _ if cookie_session(server_state, &parts.headers).await.is_some() => Ok(Reader),
+            _ => Err(unauthorized),
+        }
+    }
+}
+
+/// Whether the given key is one of the configured read keys, or a trusted user's
+/// session token, either of which may read what a private instance hides.
+pub async fn is_reader_key(server_state: &ServerState, key: &str) -> bool {
+    let hash: String = token_hash(key);
+
+    if server_state
+        .config
+        .read_keys
+        .iter()
+        .any(|read_key| read_key.key_hash == hash)
+    {
+        return true;
+    }
+    active_session(server_state, key).await.is_some()
+}
+
+/// Get the session of the given session token, unless it expired.
+pub async fn active_session(server_state: &ServerState, token: &str) -> Option<Session> {
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    server_state
+        .sessions
+        .lock()
+        .await
+        .get(token)
+        .filter(|session| now < session.expires)
+        .cloned()
+}
+
+/// Get the token from the `Authorization: Bearer <token>` HTTP header.
+pub fn bearer_token(headers: &HeaderMap) -> Option<String> {
+    let value: &str = headers.get("Authorization")?.to_str().ok()?;
+    let token: &str = value.strip_prefix("Bearer ")?.trim();
+
+    match token.is_empty() {
+        true => None,
+        false => Some(token.to_owned()),
+    }
+}
+
+/// Generate a new random 256-bit session token.
+pub fn generate_token() -> String {
+    let mut bytes: [u8; 32] = [0; 32];
+    OsRng.try_fill_bytes(&mut bytes).expect("OS RNG error.");
+    hex::encode(bytes)
+}
+
+/// Verify the given credentials against the configured
+/// and registered trusted users, returning their username and role.
+pub async fn authenticate(
+    server_state: &ServerState,
+    username: &str,
+    password: &str,
+) -> Option<(String, Role)> {
+    let locked_users: MutexGuard<'_, Vec<TrustedUserConfig>> =
+        server_state.registered_users.lock().await;
+
+    match server_state
+        .config
+        .trusted_users
+        .iter()
+        .chain(locked_users.iter())
+        .find(|user| user.username == username)
+    {
+        Some(user) => {
+            let hash: PasswordHash = PasswordHash::new(&user.password_hash).ok()?;
+
+            password::verify(password, &hash).then(|| (user.username.clone(), user.role))
+        }
+        None => {
+            // still spend the time to verify a hash, so the response time
+            // doesn't tell whether a username exists or not
+            let _: bool = password::verify(password, &server_state.password_hash);
+            None
+        }
+    }
+}
+
+/// Handles requests on `/api/v1/login` for trusted users.
+#[utoipa::path(
+    post,
+    path = "/api/v1/login",
+    tag = "trusted users",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Logged in", body = LoginResponse),
+        (status = 401, description = "Wrong username or password. Every refusal, with uniform errors"),
+        (status = 406, description = "The proof of work is invalid or expired"),
+        (status = 429, description = "Blocked after too many failed attempts"),
+    )
+)]
+pub async fn login_api(
+    ClientIp(ip): ClientIp,
+    State(server_state): State<ServerState>,
+    Json(req): Json<LoginRequest>,
+) -> impl IntoResponse {
+    let admitted = admit(ip, &server_state, req.pow, async |server_state| {
+        authenticate(server_state, &req.username, &req.password).await
+    })
+    .await;
+
+    match admitted {
+        Ok((username, role)) => start_session(&server_state, username, role).await,
+        Err(resp) => resp,
+    }
+}
+
+/// Handles requests on `/api/v1/admin/login`, where the person logs in
+/// with their heartbeat password to access the admin endpoints.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/login",
+    tag = "admin",
+    request_body = AdminLoginRequest,
+    responses(
+        (status = 200, description = "Logged in", body = LoginResponse),
+        (status = 401, description = "Wrong password. Every refusal, with uniform errors"),
+        (status = 406, description = "The proof of work is invalid or expired"),
+        (status = 429, description = "Blocked after too many failed attempts"),
+    )
+)]
+pub async fn admin_login_api(
+    ClientIp(ip): ClientIp,
+    State(server_state): State<ServerState>,
+    Json(req): Json<AdminLoginRequest>,
+) -> impl IntoResponse {
+    let admitted = admit(ip, &server_state, req.pow, async |server_state| {
+        password::verify(&req.password, &server_state.password_hash).then_some(())
+    })
+    .await;
+
+    match admitted {
+        Ok(_) => start_session(&server_state, OWNER_USERNAME.into(), Role::Owner).await,
+        Err(resp) => resp,
+    }
+}
+
+/// Rate limit, verify the PoW solution, and authenticate a request with the
+/// given `authenticate`, which fails by returning `None`. On failure, the
+/// response to give out is returned instead, see [`crate::problem::uniform`]
+pub async fn admit<T, F>(
+    ip: IpAddr,
+    server_state: &ServerState,
+    pow: PowSolution,
+    authenticate: F,
+) -> Result<T, Response>
+where
+    F: AsyncFnOnce(&ServerState) -> Option<T>,
+{
+    let started: Instant = Instant::now();
+
+    match check_admission(ip, server_state, pow, authenticate).await {
+        Err(_) if server_state.config.global.uniform_errors => {
+            Err(problem::refused(started).await.into_response())
+        }
+        admitted => admitted,
+    }
+}
+
+/// See [`admit`], without hiding which check refused the request.
+async fn check_admission<T, F>(
+    ip: IpAddr,
+    server_state: &ServerState,
+    pow: PowSolution,
+    authenticate: F,
+) -> Result<T, Response>
+where
+    F: AsyncFnOnce(&ServerState) -> Option<T>,
+{
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let mut locked_map: MutexGuard<'_, HashMap<IpAddr, RateLimit>> =
+        server_state.rate_limited_ips.lock().await;
+    let mut previous_rate_limit_period: Option<u64> = None;
+
+    // check if this address is currently rate limited..
+    if let Some(rate_limit) = locked_map.get(&ip) {
+        previous_rate_limit_period = Some(rate_limit.period);
+
+        if now < rate_limit.timestamp {
+            return Err(Response::builder()
+                .status(StatusCode::TOO_MANY_REQUESTS)
+                .header("Retry-After", rate_limit.timestamp - now)
+                .body(Body::default())
+                .unwrap());
+        }
+    }
+    if !verify_pow_solution(server_state.pow_state.clone(), ip, pow).await {
+        return Err(Response::builder()
+            .status(StatusCode::NOT_ACCEPTABLE)
+            .body(Body::default())
+            .unwrap());
+    }
+
+    let Some(admitted) = authenticate(server_state).await else {
+        // auth failed, let's give them (or extend) a rate limit
+        let wait_period: u64 = match previous_rate_limit_period {
+            Some(period) => period * RATE_LIMIT_PERIOD_FACTOR,
+            None => INITIAL_RATE_LIMIT_PERIOD,
+        };
+        rate_limit::block(&mut locked_map, ip, wait_period, now);
+        server_state.pow_state.note_auth_failure(ip);
+
+        return Err(Response::builder()
+            .status(StatusCode::UNAUTHORIZED)
+            .header("Retry-After", wait_period)
+            .body(Body::default())
+            .unwrap());
+    };
+    if previous_rate_limit_period.is_some() {
+        locked_map.remove(&ip);
+    }
+    server_state.pow_state.forgive(ip);
+
+    Ok(admitted)
+}
+
+/// Give out a new session token to an authenticated user.
+pub async fn start_session(server_state: &ServerState, username: String, role: Role) -> Response {
+    let (token, expires): (String, u64) = create_session(server_state, username, role).await;
+
+    let resp: LoginResponse = LoginResponse { token, expires };
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&resp).unwrap()))
+        .unwrap()
+}
+
+/// Register a new session for an authenticated user,
+/// returning its token and when it expires.
+pub async fn create_session(
+    server_state: &ServerState,
+    username: String,
+    role: Role,
+) -> (String, u64) {
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let token: String = generate_token();
+    let session: Session = Session {
+        username,
+        role,
+        expires: now + SESSION_LIFETIME,
+    };
+    println!("User '{}' logged in.", session.username);
+
+    let mut locked_sessions: MutexGuard<'_, HashMap<String, Session>> =
+        server_state.sessions.lock().await;
+    // forget about any expired sessions while we're here
+    locked_sessions.retain(|_, session| now < session.expires);
+    locked_sessions.insert(token.clone(), session);
+
+    (token, now + SESSION_LIFETIME)
+}
+
+/// Handles requests on `/api/v1/logout`, which ends the current session.
+#[utoipa::path(
+    post,
+    path = "/api/v1/logout",
+    tag = "trusted users",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "Logged out"),
+        (status = 401, description = "Not logged in"),
+    )
+)]
+pub async fn logout_api(
+    State(server_state): State<ServerState>,
+    user: TrustedUser,
+) -> impl IntoResponse {
+    server_state.sessions.lock().await.remove(&user.token);
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::default())
+        .unwrap()
+}
+
+/// Handles requests on `/api/v1/session`, describing the current session.
+#[utoipa::path(
+    get,
+    path = "/api/v1/session",
+    tag = "trusted users",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The current session", body = SessionResponse),
+        (status = 401, description = "Not logged in"),
+    )
+)]
+pub async fn session_api(user: TrustedUser) -> impl IntoResponse {
+    let resp: SessionResponse = SessionResponse {
+        username: user.session.username,
+        role: user.session.role,
+        expires: user.session.expires,
+    };
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&resp).unwrap()))
+        .unwrap()
+}
+
+/// Gather what we know about a heartbeat from the database's other records.
+pub fn history_entry<'a>(db: &'a Database, log: &'a HeartbeatLog) -> HistoryEntry<'a> {
+    HistoryEntry {
+        timestamp: log.timestamp,
+        message: &log.message,
+        media: db
+            .heartbeat_media
+            .iter()
+            .find(|media| media.timestamp == log.timestamp)
+            .map(|media| media_url(&media.file_name)),
+        passive: db.passive_heartbeats.contains(&log.timestamp),
+        label: db
+            .heartbeat_labels
+            .iter()
+            .find(|label| label.timestamp == log.timestamp)
+            .map(|label| label.label.as_str()),
+        // addresses of whoever sent a heartbeat stay private
+        source: match log.from_address.parse::<IpAddr>() {
+            Ok(_) => None,
+            Err(_) => Some(log.from_address.as_str()),
+        },
+    }
+}
+
+/// Keep only the given comma separated fields of every entry of a JSON array of heartbeats.
+fn select_entry_fields(entries: &mut Value, fields: &str) {
+    if let Value::Array(entries) = entries {
+        for entry in entries.iter_mut() {
+            select_fields(entry, fields);
+        }
+    }
+}
+
+/// Handles requests on `/api/v1/history`, which shows the full
+/// heartbeat history to trusted users, newest first.
+#[utoipa::path(
+    get,
+    path = "/api/v1/history",
+    tag = "trusted users",
+    security(("bearer" = [])),
+    params(FieldsQuery),
+    responses(
+        (status = 200, description = "Every heartbeat, newest first", body = [HistoryEntry]),
+        (status = 401, description = "Not logged in"),
+        (status = 403, description = "Requires the viewer role"),
+    )
+)]
+pub async fn history_api(user: TrustedUser, Query(query): Query<FieldsQuery>) -> impl IntoResponse {
+    if let Err(forbidden) = user.require(Role::Viewer) {
+        return forbidden.into_response();
+    }
+    let db: Database = {
+        let _db_guard: MutexGuard<'_, ()> = database::lock().await;
+
+        match load_database(crate::DB_PATH) {
+            Err(err) => {
+                eprintln!("Could not read the heartbeat history: {}", err);
+                return Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(Body::default())
+                    .unwrap();
+            }
+            Ok(db) => db,
+        }
+    };
+    let history: Vec<HistoryEntry> = db
+        .heartbeat_history
+        .iter()
+        .rev()
+        .map(|log| history_entry(&db, log))
+        .collect();
+    let body: String = match query.fields.as_deref() {
+        None => serde_json::to_string(&history).unwrap(),
+        Some(fields) => {
+            let mut history: Value = serde_json::to_value(&history).unwrap();
+            select_entry_fields(&mut history, fields);
+            history.to_string()
+        }
+    };
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body))
+        .unwrap()
+}
+
+/// Handles requests on `/api/v1/heartbeats`, which shows the
+/// heartbeat history to trusted users a page at a time, newest first.
+#[utoipa::path(
+    get,
+    path = "/api/v1/heartbeats",
+    tag = "trusted users",
+    params(HeartbeatsQuery),
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "A page of heartbeats, newest first", body = HeartbeatsPage),
+        (status = 401, description = "Not logged in"),
+        (status = 403, description = "Requires the viewer role"),
+    )
+)]
+pub async fn heartbeats_api(
+    user: TrustedUser,
+    Query(query): Query<HeartbeatsQuery>,
+) -> impl IntoResponse {
+    if let Err(forbidden) = user.require(Role::Viewer) {
+        return forbidden.into_response();
+    }
+    let page: usize = query.page.unwrap_or(1).max(1);
+    let per_page: usize = query
+        .per_page
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+
+    let db: Database = {
+        let _db_guard: MutexGuard<'_, ()> = database::lock().await;
+
+        match load_database(crate::DB_PATH) {
+            Err(err) => {
+                eprintln!("Could not read the heartbeat history: {}", err);
+                return Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(Body::default())
+                    .unwrap();
+            }
+            Ok(db) => db,
+        }
+    };
+    let resp: HeartbeatsPage = HeartbeatsPage {
+        page,
+        per_page,
+        total: db.heartbeat_history.len(),
+        heartbeats: db
+            .heartbeat_history
+            .iter()
+            .rev()
+            .skip((page - 1).saturating_mul(per_page))
+            .take(per_page)
+            .map(|log| history_entry(&db, log))
+            .collect(),
+    };
+    let body: String = match query.fields.as_deref() {
+        None => serde_json::to_string(&resp).unwrap(),
+        Some(fields) => {
+            let mut resp: Value = serde_json::to_value(&resp).unwrap();
+            select_entry_fields(&mut resp["heartbeats"], fields);
+            resp.to_string()
+        }
+    };
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body))
+        .unwrap()
+}
+
+/// Handles requests on `/api/v1/verify`, where a trusted user asserts
+/// that the person is incapacitated or dead.
+#[utoipa::path(
+    post,
+    path = "/api/v1/verify",
+    tag = "trusted users",
+    request_body = VerifyRequest,
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The state was verified"),
+        (status = 202, description = "The confirmation was recorded, but the quorum is not met yet", body = ConfirmationResponse),
+        (status = 401, description = "Not logged in"),
+        (status = 403, description = "Requires the verifier role"),
+        (status = 409, description = "The state can't be verified right now"),
+    )
+)]
+pub async fn verify_api(
+    State(server_state): State<ServerState>,
+    user: TrustedUser,
+    Json(req): Json<VerifyRequest>,
+) -> impl IntoResponse {
+    if let Err(forbidden) = user.require(Role::Verifier) {
+        return forbidden.into_response();
+    }
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    // make sure we're not acting on a stale state
+    server_state.update(now).await;
+
+    let locked_state: MutexGuard<'_, Redundant<LifeState>> = server_state.state.lock().await;
+    let state: LifeState = req.state.into();
+
+    // there is nothing to verify while heartbeats are still coming in
+    if **locked_state == LifeState::Alive {
+        return Response::builder()
+            .status(StatusCode::CONFLICT)
+            .body(Body::from(
+                "Cannot verify the state while heartbeats are recent.",
+            ))
+            .unwrap();
+    }
+    if **locked_state == state {
+        return Response::builder()
+            .status(StatusCode::OK)
+            .body(Body::default())
+            .unwrap();
+    }
+    let mut verifiers: Vec<String> = vec![user.session.username.clone()];
+    let last_seen: u64 = **server_state.last_heartbeat.lock().await;
+
+    // once the "Dead" state is proposed, a single confirmation is enough
+    let quorum: usize = match server_state.dead_proposal(last_seen, now).await {
+        Some(proposed) if **locked_state == LifeState::MissingOrDead && now >= proposed => 1,
+        _ => usize::from(server_state.config.verification.dead_quorum),
+    };
+
+    if state == LifeState::Dead && quorum > 1 {
+        let mut locked_confirmations: MutexGuard<'_, Vec<ConfirmationLog>> =
+            server_state.dead_confirmations.lock().await;
+
+        // confirmations made before the last heartbeat no longer count
+        locked_confirmations.retain(|confirmation| confirmation.timestamp > last_seen);
+
+        if !locked_confirmations
+            .iter()
+            .any(|confirmation| confirmation.username == user.session.username)
+        {
+            let confirmation: ConfirmationLog = ConfirmationLog {
+                timestamp: now,
+                username: user.session.username.clone(),
+            };
+            locked_confirmations.push(confirmation.clone());
+
+            if let Err(err) = database::modify(|db| db.confirmations.push(confirmation)).await {
+                eprintln!("Could not sync confirmation to disk: {}", err);
+            }
+        }
+        verifiers = locked_confirmations
+            .iter()
+            .map(|confirmation| confirmation.username.clone())
+            .collect();
+
+        if verifiers.len() < quorum {
+            println!(
+                "Trusted user '{}' confirmed state as \"{}\" ({} of {}).",
+                user.session.username,
+                state,
+                verifiers.len(),
+                quorum
+            );
+            audit::record(
+                &user.session.username,
+                format!(
+                    "confirmed state as {} ({} of {})",
+                    state,
+                    verifiers.len(),
+                    quorum
+                ),
+            )
+            .await;
+
+            let resp: ConfirmationResponse = ConfirmationResponse {
+                confirmations: verifiers.len(),
+                quorum,
+            };
+            return Response::builder()
+                .status(StatusCode::ACCEPTED)
+                .header("Content-Type", "application/json")
+                .body(Body::from(serde_json::to_string(&resp).unwrap()))
+                .unwrap();
+        }
+        locked_confirmations.clear();
+    }
+    println!(
+        "Trusted user(s) '{}' verified state as \"{}\".",
+        verifiers.join("', '"),
+        state
+    );
+
+    server_state
+        .transition(
+            locked_state,
+            state,
+            TransitionCause::Verified(verifiers),
+            now,
+        )
+        .await;
+    audit::record(
+        &user.session.username,
+        format!("verified state as {}", state),
+    )
+    .await;
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::default())
+        .unwrap()
+}
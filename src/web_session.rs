@@ -0,0 +1,422 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::api::{ClientIp, PowSolution};
+use crate::config::{Role, ThemeMode};
+use crate::database::{self, Database, load_database};
+use crate::password;
+use crate::state::ServerState;
+use crate::templating::{datetime, render};
+use crate::trusted::{
+    HistoryEntry, OWNER_USERNAME, SESSION_LIFETIME, Session, active_session, admit, authenticate,
+    create_session, generate_token, history_entry,
+};
+use askama::Template;
+use axum::async_trait;
+use axum::extract::{Form, FromRequestParts, Query, State};
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{AppendHeaders, Html, IntoResponse, Redirect, Response};
+use chrono::{FixedOffset, TimeZone};
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tokio::sync::MutexGuard;
+
+/// Cookie holding the signed session token.
+pub const SESSION_COOKIE: &str = "amialive_session";
+/// Cookie holding the random value the CSRF token of the login form is derived from.
+pub const LOGIN_CSRF_COOKIE: &str = "amialive_login";
+
+/// Time period, in seconds, for which the login form can be submitted.
+const LOGIN_FORM_LIFETIME: u64 = 60 * 60;
+/// Heartbeats per page of `/history`.
+const HISTORY_PAGE_SIZE: usize = 50;
+
+/// Authentication layer for the web pages of trusted users and the person themself,
+/// who only enter their password once, on `/login`.
+///
+/// Extracting this from a request requires a validly signed, unexpired session
+/// cookie, otherwise the browser is redirected to `/login`. Unlike [`TrustedUser`],
+/// this must never be used on its own for requests that change anything; Check the
+/// CSRF token of the submitted form with [`WebSession::verify_csrf`] as well.
+///
+/// [`TrustedUser`]: crate::trusted::TrustedUser
+///
+pub struct WebSession {
+    pub token: String,
+    pub session: Session,
+    /// To include in every form of the page.
+    pub csrf_token: String,
+}
+
+impl WebSession {
+    /// Whether the given CSRF token, submitted with a form, is this session's.
+    pub fn verify_csrf(&self, server_state: &ServerState, csrf_token: &str) -> bool {
+        verify(server_state, "csrf", &self.token, csrf_token)
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<ServerState> for WebSession {
+    type Rejection = Redirect;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        server_state: &ServerState,
+    ) -> Result<Self, Self::Rejection> {
+        match cookie_session(server_state, &parts.headers).await {
+            Some((token, session)) => Ok(WebSession {
+                csrf_token: sign(server_state, "csrf", &token),
+                token,
+                session,
+            }),
+            None => Err(Redirect::to("/login")),
+        }
+    }
+}
+
+/// The session token of the validly signed session cookie of a request, and its
+/// session, unless there's no such cookie, or the session expired.
+pub async fn cookie_session(
+    server_state: &ServerState,
+    headers: &HeaderMap,
+) -> Option<(String, Session)> {
+    let signed: String = get_cookie(headers, SESSION_COOKIE)?;
+    let (token, signature) = signed.split_once('.')?;
+
+    if !verify(server_state, "session", token, signature) {
+        return None;
+    }
+    let session: Session = active_session(server_state, token).await?;
+
+    Some((token.to_owned(), session))
+}
+
+/// Derive the key session cookies and CSRF tokens are signed with from the
+/// PoW secret, so that no other secret has to be configured for them.
+fn signing_key(server_state: &ServerState) -> [u8; 32] {
+    let mut key: [u8; 32] = [0; 32];
+
+    Hkdf::<Sha256>::new(None, server_state.pow_state.secret.as_bytes())
+        .expand(b"am-i-alive web session", &mut key)
+        .unwrap();
+    key
+}
+
+/// Sign a value for the given purpose, e.g. "session", so that a signature
+/// made for one purpose can't be used for another.
+pub fn sign(server_state: &ServerState, purpose: &str, value: &str) -> String {
+    let mut mac: Hmac<Sha256> = Hmac::new_from_slice(&signing_key(server_state)).unwrap();
+    mac.update(purpose.as_bytes());
+    mac.update(&[0]);
+    mac.update(value.as_bytes());
+
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Verify, in constant time, a signature made with [`sign`].
+pub fn verify(server_state: &ServerState, purpose: &str, value: &str, signature: &str) -> bool {
+    let Ok(signature) = hex::decode(signature) else {
+        return false;
+    };
+    let mut mac: Hmac<Sha256> = Hmac::new_from_slice(&signing_key(server_state)).unwrap();
+    mac.update(purpose.as_bytes());
+    mac.update(&[0]);
+    mac.update(value.as_bytes());
+
+    mac.verify_slice(&signature).is_ok()
+}
+
+/// Get the value of a cookie from the `Cookie` HTTP header.
+fn get_cookie(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get_all("Cookie")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.to_owned())
+}
+
+/// A `Set-Cookie` HTTP header value, only ever sent back to this site.
+fn cookie(name: &str, value: &str, max_age: u64) -> String {
+    format!(
+        "{}={}; Path=/; Max-Age={}; HttpOnly; Secure; SameSite=Strict",
+        name, value, max_age
+    )
+}
+
+#[derive(Template, Serialize)]
+#[template(path = "login.html")]
+struct LoginTemplate {
+    theme: ThemeMode,
+    name: String,
+    csrf_token: String,
+    error: Option<String>,
+}
+
+#[derive(Template, Serialize)]
+#[template(path = "account.html")]
+struct AccountTemplate {
+    theme: ThemeMode,
+    name: String,
+    username: String,
+    role: &'static str,
+    csrf_token: String,
+}
+
+#[derive(Template, Serialize)]
+#[template(path = "history.html")]
+struct HistoryTemplate {
+    theme: ThemeMode,
+    name: String,
+    heartbeats: Vec<HistoryRow>,
+    /// Starting from 1, the newest heartbeats.
+    page: usize,
+    pages: usize,
+    total: usize,
+}
+
+#[derive(Serialize)]
+struct HistoryRow {
+    /// RFC 2822 date, in the configured UTC offset
+    timestamp: String,
+    /// RFC 3339 date, see [`crate::templating::datetime`]
+    datetime: String,
+    message: String,
+    media: String,
+    passive: bool,
+    label: String,
+}
+
+#[derive(Deserialize)]
+pub struct HistoryQuery {
+    page: Option<usize>,
+}
+
+#[derive(Deserialize)]
+pub struct LoginForm {
+    /// Empty to log in as the person themself, with their heartbeat password.
+    #[serde(default)]
+    username: String,
+    password: String,
+    csrf_token: String,
+    pow_nonce: u64,
+    pow_hash: String,
+    // form bodies can't be deserialized into a `u128`
+    pow_timestamp_ms: u64,
+}
+
+#[derive(Deserialize)]
+pub struct CsrfForm {
+    pub csrf_token: String,
+}
+
+/// Render the login page, with a new CSRF token, and the cookie it's checked against.
+fn login_page(server_state: &ServerState, status: StatusCode, error: Option<String>) -> Response {
+    let nonce: String = generate_token();
+
+    let page: LoginTemplate = LoginTemplate {
+        theme: server_state.config.theme.mode,
+        name: server_state.config.global.full_name.clone(),
+        csrf_token: sign(server_state, "login", &nonce),
+        error,
+    };
+    let html: String = render(server_state, "login.html", &page);
+
+    (
+        status,
+        [(
+            "Set-Cookie",
+            cookie(LOGIN_CSRF_COOKIE, &nonce, LOGIN_FORM_LIFETIME),
+        )],
+        Html(html),
+    )
+        .into_response()
+}
+
+/// Handles `GET` requests on `/login`, the login page of trusted users and the person themself.
+pub async fn login_page_handler(State(server_state): State<ServerState>) -> impl IntoResponse {
+    login_page(&server_state, StatusCode::OK, None)
+}
+
+/// Handles `POST` requests on `/login`, which starts a cookie session
+/// once the CSRF token, proof of work and password are checked.
+pub async fn login_handler(
+    ClientIp(ip): ClientIp,
+    State(server_state): State<ServerState>,
+    headers: HeaderMap,
+    Form(form): Form<LoginForm>,
+) -> impl IntoResponse {
+    // the CSRF token must have been given out along with the cookie, to this browser
+    let csrf_checked: bool = get_cookie(&headers, LOGIN_CSRF_COOKIE)
+        .is_some_and(|nonce| verify(&server_state, "login", &nonce, &form.csrf_token));
+
+    if !csrf_checked {
+        return login_page(
+            &server_state,
+            StatusCode::FORBIDDEN,
+            Some("The login form expired. Please try again.".into()),
+        );
+    }
+    let pow: PowSolution = PowSolution {
+        nonce: form.pow_nonce,
+        hash: form.pow_hash,
+        timestamp_ms: u128::from(form.pow_timestamp_ms),
+        payload_digest: None,
+    };
+    let admitted = admit(ip, &server_state, pow, async |server_state| {
+        match form.username.is_empty() {
+            true => password::verify(&form.password, &server_state.password_hash)
+                .then(|| (OWNER_USERNAME.to_owned(), Role::Owner)),
+            false => authenticate(server_state, &form.username, &form.password).await,
+        }
+    })
+    .await;
+
+    let (username, role): (String, Role) = match admitted {
+        Ok(user) => user,
+        Err(resp) => {
+            let error: &str = match resp.status() {
+                StatusCode::UNAUTHORIZED => "Wrong username or password.",
+                StatusCode::TOO_MANY_REQUESTS => "Too many failed attempts. Try again later.",
+                _ => "The proof of work was rejected. Please try again.",
+            };
+            return login_page(&server_state, resp.status(), Some(error.into()));
+        }
+    };
+    let (token, _): (String, u64) = create_session(&server_state, username, role).await;
+    let signed: String = format!("{}.{}", token, sign(&server_state, "session", &token));
+
+    (
+        // both cookies are set, which a plain array of headers wouldn't do
+        AppendHeaders([
+            (
+                "Set-Cookie",
+                cookie(SESSION_COOKIE, &signed, SESSION_LIFETIME),
+            ),
+            ("Set-Cookie", cookie(LOGIN_CSRF_COOKIE, "", 0)),
+        ]),
+        Redirect::to("/account"),
+    )
+        .into_response()
+}
+
+/// Handles requests on `/account`, which tells who is logged in.
+pub async fn account_handler(
+    State(server_state): State<ServerState>,
+    web_session: WebSession,
+) -> impl IntoResponse {
+    let page: AccountTemplate = AccountTemplate {
+        theme: server_state.config.theme.mode,
+        name: server_state.config.global.full_name.clone(),
+        username: web_session.session.username,
+        role: web_session.session.role.db_code(),
+        csrf_token: web_session.csrf_token,
+    };
+    Html(render(&server_state, "account.html", &page))
+}
+
+/// Handles requests on `/history`, which shows the whole heartbeat history,
+/// a page at a time, newest first, to anyone logged in, as `/api/v1/history` does.
+///
+pub async fn history_handler(
+    State(server_state): State<ServerState>,
+    _web_session: WebSession,
+    Query(query): Query<HistoryQuery>,
+) -> impl IntoResponse {
+    let db: Database = {
+        let _db_guard: MutexGuard<'_, ()> = database::lock().await;
+
+        match load_database(crate::DB_PATH) {
+            Err(err) => {
+                eprintln!("Could not read the heartbeat history: {}", err);
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+            Ok(db) => db,
+        }
+    };
+    let total: usize = db.heartbeat_history.len();
+    let pages: usize = total.div_ceil(HISTORY_PAGE_SIZE).max(1);
+    let page: usize = query.page.unwrap_or(1).clamp(1, pages);
+
+    let timezone: FixedOffset =
+        FixedOffset::east_opt(server_state.config.global.utc_offset * 60 * 60).unwrap();
+
+    let heartbeats: Vec<HistoryRow> = db
+        .heartbeat_history
+        .iter()
+        .rev()
+        .skip((page - 1) * HISTORY_PAGE_SIZE)
+        .take(HISTORY_PAGE_SIZE)
+        .map(|log| {
+            let entry: HistoryEntry<'_> = history_entry(&db, log);
+
+            HistoryRow {
+                timestamp: timezone
+                    .timestamp_opt(entry.timestamp as i64, 0)
+                    .unwrap()
+                    .to_rfc2822(),
+                datetime: datetime(timezone, entry.timestamp),
+                message: match entry.message.is_empty() {
+                    true => "N/A".into(),
+                    false => entry.message.to_owned(),
+                },
+                media: entry.media.unwrap_or_default(),
+                passive: entry.passive,
+                label: entry.label.or(entry.source).unwrap_or_default().to_owned(),
+            }
+        })
+        .collect();
+
+    let page: HistoryTemplate = HistoryTemplate {
+        theme: server_state.config.theme.mode,
+        name: server_state.config.global.full_name.clone(),
+        heartbeats,
+        page,
+        pages,
+        total,
+    };
+    Html(render(&server_state, "history.html", &page)).into_response()
+}
+
+/// Handles `POST` requests on `/logout`, which ends the cookie session.
+pub async fn logout_handler(
+    State(server_state): State<ServerState>,
+    web_session: WebSession,
+    Form(form): Form<CsrfForm>,
+) -> impl IntoResponse {
+    if !web_session.verify_csrf(&server_state, &form.csrf_token) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    server_state
+        .sessions
+        .lock()
+        .await
+        .remove(&web_session.token);
+
+    (
+        [("Set-Cookie", cookie(SESSION_COOKIE, "", 0))],
+        Redirect::to("/login"),
+    )
+        .into_response()
+}
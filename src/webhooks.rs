@@ -0,0 +1,316 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::audit;
+use crate::database::{self, Database, WebhookSubscription, load_database};
+use crate::notify::{EVENT_NAMES, NotificationEvent};
+use crate::trusted::{Admin, OWNER_USERNAME};
+use axum::body::Body;
+use axum::extract::{Json, Path as UrlPath};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use hmac::{Hmac, Mac};
+use rand::rand_core::{OsRng, TryRngCore};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::time::Duration;
+use tokio::sync::MutexGuard;
+use utoipa::ToSchema;
+
+/// Maximum amount of webhooks registered at once.
+const MAX_WEBHOOKS: usize = 100;
+/// Longest webhook URL accepted, in bytes.
+const MAX_URL_LENGTH: usize = 2048;
+/// Longest webhook secret accepted, in bytes.
+const MAX_SECRET_LENGTH: usize = 256;
+/// How long a webhook has to answer a delivery, in seconds.
+const DELIVERY_TIMEOUT: u64 = 10;
+
+#[derive(Deserialize, ToSchema)]
+pub struct WebhookRequest {
+    /// HTTP(S) URL the events are `POST`ed to.
+    url: String,
+    /// Shared secret, with which every delivery is signed in the
+    /// `X-Webhook-Signature: sha256=<HMAC-SHA256 of the body>` HTTP header.
+    secret: String,
+    /// Names of the events to deliver, e.g. "state_changed". All of them if empty.
+    #[serde(default)]
+    events: Vec<String>,
+}
+
+/// A registered webhook, without its secret.
+#[derive(Serialize, ToSchema)]
+struct WebhookResponse<'a> {
+    id: &'a str,
+    url: &'a str,
+    events: &'a [String],
+}
+
+impl<'a> From<&'a WebhookSubscription> for WebhookResponse<'a> {
+    fn from(webhook: &'a WebhookSubscription) -> Self {
+        Self {
+            id: &webhook.id,
+            url: &webhook.url,
+            events: &webhook.events,
+        }
+    }
+}
+
+/// Body of every webhook delivery.
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    /// Name of the event, e.g. "state_changed"
+    event: &'a str,
+    title: &'a str,
+    body: &'a str,
+    /// Name of the previous state, e.g. "Alive", if the state changed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    from: Option<String>,
+    /// Name of the new state, e.g. "ProbablyAlive", if the state changed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    to: Option<String>,
+    /// Unix timestamp
+    timestamp: u64,
+}
+
+/// Serialize the JSON body delivered to webhooks for an event.
+pub fn payload(event: &NotificationEvent, title: &str, body: &str, now: u64) -> String {
+    let (from, to): (Option<String>, Option<String>) = match event {
+        NotificationEvent::StateChanged { from, to, .. } => {
+            (Some(format!("{:?}", from)), Some(format!("{:?}", to)))
+        }
+        _ => (None, None),
+    };
+    serde_json::to_string(&WebhookPayload {
+        event: event.name(),
+        title,
+        body,
+        from,
+        to,
+        timestamp: now,
+    })
+    .unwrap()
+}
+
+/// Deliver a payload to a webhook, signed with its secret, returning whether it was accepted.
+pub async fn send(client: &reqwest::Client, webhook: &WebhookSubscription, payload: &str) -> bool {
+    let mut mac: Hmac<Sha256> = Hmac::new_from_slice(webhook.secret.as_bytes()).unwrap();
+    mac.update(payload.as_bytes());
+    let signature: String = hex::encode(mac.finalize().into_bytes());
+
+    let result: Result<reqwest::Response, reqwest::Error> = client
+        .post(&webhook.url)
+        .timeout(Duration::from_secs(DELIVERY_TIMEOUT))
+        .header("Content-Type", "application/json")
+        .header("X-Webhook-Signature", format!("sha256={}", signature))
+        .body(payload.to_owned())
+        .send()
+        .await;
+
+    match result {
+        Err(err) => {
+            eprintln!(
+                "Failed to reach webhook {}: {}",
+                webhook.id,
+                err.without_url()
+            );
+            false
+        }
+        Ok(resp) if !resp.status().is_success() => {
+            eprintln!(
+                "Webhook {} refused a notification with HTTP status {}.",
+                webhook.id,
+                resp.status()
+            );
+            false
+        }
+        Ok(_) => true,
+    }
+}
+
+/// Check a webhook registration, returning why it can't be accepted if so.
+fn validate(req: &WebhookRequest) -> Result<(), &'static str> {
+    if req.url.len() > MAX_URL_LENGTH || req.url.contains(char::is_whitespace) {
+        return Err("Invalid webhook URL.");
+    }
+    match reqwest::Url::parse(&req.url) {
+        Ok(url) if ["http", "https"].contains(&url.scheme()) => (),
+        _ => return Err("Webhook URLs must be HTTP(S) URLs."),
+    }
+    if req.secret.is_empty()
+        || req.secret.len() > MAX_SECRET_LENGTH
+        || req.secret.contains(char::is_whitespace)
+    {
+        return Err("Webhook secrets must be non-empty, without whitespace.");
+    }
+    if req
+        .events
+        .iter()
+        .any(|event| !EVENT_NAMES.contains(&event.as_str()))
+    {
+        return Err("Unknown event name.");
+    }
+    Ok(())
+}
+
+/// Handles `GET` requests on `/api/v1/admin/webhooks`, which lists the registered webhooks.
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/webhooks",
+    tag = "admin",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The registered webhooks", body = [WebhookResponse]),
+        (status = 401, description = "Not logged in as the person themself"),
+    )
+)]
+pub async fn list_api(_admin: Admin) -> impl IntoResponse {
+    let db: Database = {
+        let _db_guard: MutexGuard<'_, ()> = database::lock().await;
+
+        match load_database(crate::DB_PATH) {
+            Err(err) => {
+                eprintln!("Could not read the webhooks: {}", err);
+                return Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(Body::default())
+                    .unwrap();
+            }
+            Ok(db) => db,
+        }
+    };
+    let webhooks: Vec<WebhookResponse> = db.webhooks.iter().map(Into::into).collect();
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&webhooks).unwrap()))
+        .unwrap()
+}
+
+/// Handles `POST` requests on `/api/v1/admin/webhooks`, which registers a webhook
+/// to be notified of state changes and other events, as the notification URLs are.
+///
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/webhooks",
+    tag = "admin",
+    request_body = WebhookRequest,
+    security(("bearer" = [])),
+    responses(
+        (status = 201, description = "The webhook was registered", body = WebhookResponse),
+        (status = 401, description = "Not logged in as the person themself"),
+        (status = 422, description = "Invalid URL, secret, or event name"),
+        (status = 507, description = "Too many webhooks"),
+    )
+)]
+pub async fn create_api(_admin: Admin, Json(req): Json<WebhookRequest>) -> impl IntoResponse {
+    if let Err(reason) = validate(&req) {
+        return Response::builder()
+            .status(StatusCode::UNPROCESSABLE_ENTITY)
+            .body(Body::from(reason))
+            .unwrap();
+    }
+    let mut id: [u8; 8] = [0; 8];
+    OsRng.try_fill_bytes(&mut id).expect("OS RNG error.");
+
+    let webhook: WebhookSubscription = WebhookSubscription {
+        id: hex::encode(id),
+        url: req.url,
+        secret: req.secret,
+        events: match req.events.is_empty() {
+            true => vec!["*".into()],
+            false => req.events,
+        },
+    };
+    let mut full: bool = false;
+
+    let result = database::modify(|db| match db.webhooks.len() >= MAX_WEBHOOKS {
+        true => full = true,
+        false => db.webhooks.push(webhook.clone()),
+    })
+    .await;
+
+    if let Err(err) = result {
+        eprintln!("Could not sync the webhook to disk: {}", err);
+        return Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::default())
+            .unwrap();
+    }
+    if full {
+        return Response::builder()
+            .status(StatusCode::INSUFFICIENT_STORAGE)
+            .body(Body::default())
+            .unwrap();
+    }
+    audit::record(OWNER_USERNAME, format!("registered webhook {}", webhook.id)).await;
+
+    Response::builder()
+        .status(StatusCode::CREATED)
+        .header("Content-Type", "application/json")
+        .body(Body::from(
+            serde_json::to_string(&WebhookResponse::from(&webhook)).unwrap(),
+        ))
+        .unwrap()
+}
+
+/// Handles `DELETE` requests on `/api/v1/admin/webhooks/:id`, which unregisters a webhook.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/webhooks/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "ID of the webhook")),
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The webhook was unregistered"),
+        (status = 401, description = "Not logged in as the person themself"),
+        (status = 404, description = "No such webhook"),
+    )
+)]
+pub async fn delete_api(_admin: Admin, UrlPath(id): UrlPath<String>) -> impl IntoResponse {
+    let mut found: bool = false;
+
+    let result = database::modify(|db| {
+        let before: usize = db.webhooks.len();
+        db.webhooks.retain(|webhook| webhook.id != id);
+        found = db.webhooks.len() < before;
+    })
+    .await;
+
+    if let Err(err) = result {
+        eprintln!("Could not sync the webhooks to disk: {}", err);
+        return Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::default())
+            .unwrap();
+    }
+    if !found {
+        return Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::default())
+            .unwrap();
+    }
+    audit::record(OWNER_USERNAME, format!("unregistered webhook {}", id)).await;
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::default())
+        .unwrap()
+}
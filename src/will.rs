@@ -0,0 +1,337 @@
+/*
+    This file is part of "Am I Alive".
+
+    Copyright © 2026 Max Rodriguez <me@maxrdz.com>
+
+    "Am I Alive" is free software; you can redistribute it and/or modify
+    it under the terms of the GNU Affero General Public License,
+    as published by the Free Software Foundation, either version 3
+    of the License, or (at your option) any later version.
+
+    "Am I Alive" is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+    GNU Affero General Public License for more details.
+
+    You should have received a copy of the GNU Affero General Public
+    License along with "Am I Alive". If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use crate::audit;
+use crate::config::{Attachments, Role, Will};
+use crate::database::CountdownStatus;
+use crate::state::{LifeState, ServerState};
+use crate::trusted::TrustedUser;
+use axum::body::Body;
+use axum::extract::{Path as UrlPath, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema)]
+struct KeyResponse<'a> {
+    key: &'a str,
+    /// Where to get the will payload from, if it is stored externally.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    location: Option<&'a str>,
+}
+
+fn not_configured() -> Response {
+    Response::builder()
+        .status(StatusCode::NOT_FOUND)
+        .body(Body::default())
+        .unwrap()
+}
+
+/// Handles requests on `/api/v1/will`, which serves the encrypted will payload.
+///
+/// The payload is useless without its key, so trusted users
+/// may download it ahead of time, whatever the current state is.
+///
+/// Responds with `404 Not Found` if the payload is stored externally,
+/// as its location is only released along with the key.
+///
+#[utoipa::path(
+    get,
+    path = "/api/v1/will",
+    tag = "will",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The encrypted will payload", content_type = "application/octet-stream", body = Vec<u8>),
+        (status = 401, description = "Not logged in"),
+        (status = 403, description = "Requires the executor role"),
+        (status = 404, description = "No will, or it is stored externally"),
+    )
+)]
+pub async fn payload_api(
+    State(server_state): State<ServerState>,
+    user: TrustedUser,
+) -> impl IntoResponse {
+    if let Err(forbidden) = user.require(Role::Executor) {
+        return forbidden.into_response();
+    }
+    let Some(will) = server_state.config.will.as_ref() else {
+        return not_configured();
+    };
+    let payload: Vec<u8> = match (will.path.as_ref(), will.inline.as_ref()) {
+        (Some(path), _) => match tokio::fs::read(path).await {
+            Ok(payload) => payload,
+            Err(err) => {
+                eprintln!("Could not read the will payload: {}", err);
+                return Response::builder()
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(Body::default())
+                    .unwrap();
+            }
+        },
+        (None, Some(inline)) => inline.as_bytes().to_vec(),
+        (None, None) => return not_configured(),
+    };
+
+    audit::record(&user.session.username, "downloaded the will payload".into()).await;
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/octet-stream")
+        .body(Body::from(payload))
+        .unwrap()
+}
+
+/// Handles requests on `/api/v1/will/key`, which releases the key to decrypt
+/// the will payload once the person is dead, and the release countdown is over.
+/// Also releases the payload's location, if it is stored externally.
+#[utoipa::path(
+    get,
+    path = "/api/v1/will/key",
+    tag = "will",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The key to decrypt the will payload", body = KeyResponse),
+        (status = 401, description = "Not logged in"),
+        (status = 403, description = "Requires the executor role, once the person is dead"),
+        (status = 404, description = "No will is configured"),
+    )
+)]
+pub async fn key_api(
+    State(server_state): State<ServerState>,
+    user: TrustedUser,
+) -> impl IntoResponse {
+    if let Err(forbidden) = user.require(Role::Executor) {
+        return forbidden.into_response();
+    }
+    let Some(will): Option<&Will> = server_state.config.will.as_ref() else {
+        return not_configured();
+    };
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    // make sure we're not acting on a stale state
+    server_state.update(now).await;
+
+    if **server_state.state.lock().await != LifeState::Dead {
+        return Response::builder()
+            .status(StatusCode::FORBIDDEN)
+            .body(Body::from(
+                "The will is only released once the \"Dead\" state is verified.",
+            ))
+            .unwrap();
+    }
+    if let Some(countdown) = server_state.release_countdown.lock().await.as_ref()
+        && !countdown.is_over(now)
+    {
+        let resp: Response = match countdown.status {
+            CountdownStatus::Cancelled => Response::builder()
+                .status(StatusCode::FORBIDDEN)
+                .body(Body::from("The will release was cancelled."))
+                .unwrap(),
+            _ => Response::builder()
+                .status(StatusCode::FORBIDDEN)
+                .header("Retry-After", countdown.ends - now)
+                .body(Body::from("The release countdown is not over yet."))
+                .unwrap(),
+        };
+        return resp;
+    }
+    println!(
+        "Will key released to trusted user '{}'.",
+        user.session.username
+    );
+    audit::record(&user.session.username, "retrieved the will key".into()).await;
+
+    let resp: KeyResponse = KeyResponse {
+        key: &will.key,
+        location: will.location.as_deref(),
+    };
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&resp).unwrap()))
+        .unwrap()
+}
+
+/// Get the attachments configuration, but only if the given trusted user is an
+/// executor, and may currently download attachments. (i.e. while dead or incapacitated)
+async fn released_attachments<'a>(
+    server_state: &'a ServerState,
+    user: &Option<TrustedUser>,
+) -> Option<&'a Attachments> {
+    let attachments: &Attachments = server_state.config.attachments.as_ref()?;
+
+    if !user.as_ref()?.session.role.permits(Role::Executor) {
+        return None;
+    }
+
+    let now: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    // make sure we're not acting on a stale state
+    server_state.update(now).await;
+
+    match **server_state.state.lock().await {
+        LifeState::Dead | LifeState::Incapacitated => Some(attachments),
+        _ => None,
+    }
+}
+
+/// Recursively list the files in a directory, relative to the given root.
+fn list_files(root: &Path, directory: &Path, files: &mut Vec<String>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(directory)? {
+        let path: PathBuf = entry?.path();
+
+        // symbolic links are not served, see [`attachment_api`]
+        if path.is_symlink() {
+            continue;
+        }
+        if path.is_dir() {
+            list_files(root, &path, files)?;
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            files.push(relative.to_string_lossy().into_owned());
+        }
+    }
+    Ok(())
+}
+
+/// Handles requests on `/api/v1/attachments`, which lists the will attachments.
+///
+/// Responds with `404 Not Found` to anyone, unless an executor
+/// asks while the person is dead or incapacitated.
+///
+#[utoipa::path(
+    get,
+    path = "/api/v1/attachments",
+    tag = "will",
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "Paths of the will attachments", body = [String]),
+        (status = 404, description = "No attachments released to the current user"),
+    )
+)]
+pub async fn attachments_api(
+    State(server_state): State<ServerState>,
+    user: Option<TrustedUser>,
+) -> impl IntoResponse {
+    let Some(attachments) = released_attachments(&server_state, &user).await else {
+        return not_configured();
+    };
+    let root: &Path = Path::new(&attachments.directory);
+    let mut files: Vec<String> = vec![];
+
+    if let Err(err) = list_files(root, root, &mut files) {
+        eprintln!("Could not list the will attachments: {}", err);
+        return Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::default())
+            .unwrap();
+    }
+    files.sort();
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::to_string(&files).unwrap()))
+        .unwrap()
+}
+
+/// Handles requests on `/api/v1/attachments/*path`, which downloads a will attachment.
+///
+/// Responds with `404 Not Found` to anyone, unless an executor
+/// asks while the person is dead or incapacitated.
+///
+#[utoipa::path(
+    get,
+    path = "/api/v1/attachments/{path}",
+    tag = "will",
+    params(("path" = String, Path, description = "Path of the attachment")),
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The attachment", content_type = "application/octet-stream", body = Vec<u8>),
+        (status = 404, description = "No such attachment released to the current user"),
+    )
+)]
+pub async fn attachment_api(
+    State(server_state): State<ServerState>,
+    user: Option<TrustedUser>,
+    UrlPath(requested): UrlPath<String>,
+) -> impl IntoResponse {
+    let Some(attachments) = released_attachments(&server_state, &user).await else {
+        return not_configured();
+    };
+    // refuse anything that could point outside of the attachments directory
+    if Path::new(&requested)
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_)))
+    {
+        return not_configured();
+    }
+    let (Ok(root), Ok(path)) = (
+        tokio::fs::canonicalize(&attachments.directory).await,
+        tokio::fs::canonicalize(Path::new(&attachments.directory).join(&requested)).await,
+    ) else {
+        return not_configured();
+    };
+    // don't follow symbolic links, they could lead us anywhere
+    if path != root.join(&requested) || !path.is_file() {
+        return not_configured();
+    }
+    let contents: Vec<u8> = match tokio::fs::read(&path).await {
+        Ok(contents) => contents,
+        Err(err) => {
+            eprintln!("Could not read will attachment '{}': {}", requested, err);
+            return Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Body::default())
+                .unwrap();
+        }
+    };
+    let file_name: String = path
+        .file_name()
+        .map(|name| name.to_string_lossy().replace('"', ""))
+        .unwrap_or_default();
+
+    let username: String = user.map(|user| user.session.username).unwrap_or_default();
+
+    println!(
+        "Trusted user '{}' downloaded will attachment '{}'.",
+        username, requested
+    );
+    audit::record(
+        &username,
+        format!("downloaded will attachment {}", requested),
+    )
+    .await;
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/octet-stream")
+        .header(
+            "Content-Disposition",
+            format!("attachment; filename=\"{}\"", file_name),
+        )
+        .body(Body::from(contents))
+        .unwrap()
+}
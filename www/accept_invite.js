@@ -0,0 +1,96 @@
+const pow = new PoW();
+
+document.getElementById("accept-invite-form").addEventListener("submit", async function (e) {
+    e.preventDefault(); // stop normal form submit
+
+    if (pow.busy) {
+        return;
+    }
+    pow.busy = true;
+
+    const ws = new WebSocket("/api/v1/pow");
+
+    document.getElementsByClassName("auth-feedback")[0].id = "";
+    let feedback_container = document.getElementsByClassName("auth-feedback")[0];
+    let feedback_text = document.getElementById("auth-feedback-text");
+
+    feedback_container.style.backgroundColor = "#7c7402";
+    feedback_text.textContent = "Waiting for Challenge from Server..";
+
+    ws.onmessage = async function (event) {
+        if (pow.isRunning) {
+            return;
+        }
+        pow.isRunning = true;
+
+        const challenge = JSON.parse(event.data);
+
+        // solve PoW challenge
+        feedback_text.textContent = "Solving Cryptographic Challenge..";
+        let pow_result = await pow.handleChallenge(challenge);
+
+        const accept_request = {
+            token: new URLSearchParams(window.location.search).get("token") || "",
+            username: document.getElementById("username").value,
+            password: document.getElementById("pwd").value,
+            pow: pow_result
+        };
+        try {
+            feedback_text.textContent = "Submitting..";
+
+            const response = await fetch("/api/v1/invitations/accept", {
+                method: "POST",
+                headers: {
+                    "Content-Type": "application/json",
+                },
+                body: JSON.stringify(accept_request),
+            });
+
+            // show feedback to the user
+            if (response.status === 401) {
+                let rate_limit_period = response.headers.get("Retry-After");
+                feedback_container.style.backgroundColor = "#870000";
+                feedback_text.textContent = rate_limit_period
+                    ? `Invalid or expired invitation. Rate limited for ${formatDuration(rate_limit_period)}.`
+                    : "Invalid or expired invitation.";
+            } else if (response.status === 429) {
+                let rate_limit_period = response.headers.get("Retry-After");
+                feedback_container.style.backgroundColor = "#7a3f01";
+                feedback_text.textContent = `Rate limited. Try again in ${formatDuration(rate_limit_period)}.`;
+            } else if (response.status === 406) {
+                feedback_container.style.backgroundColor = "#7a3f01";
+                feedback_text.textContent = `PoW challenge rejected. Please try again.`;
+            } else if (response.status === 409 || response.status === 422) {
+                feedback_container.style.backgroundColor = "#7a3f01";
+                feedback_text.textContent = await response.text();
+            } else if (response.ok) {
+                feedback_container.style.backgroundColor = "#067c02";
+                feedback_text.textContent = "Invitation accepted! You can now log in as a trusted user.";
+                document.getElementById("accept-invite-form").remove();
+            } else {
+                feedback_container.style.backgroundColor = "#7a3f01";
+                feedback_text.textContent = `Received HTTP status code ${response.status} ${response.statusText}.`;
+            }
+        } catch (err) {
+            console.error(err);
+        }
+        pow.busy = false;
+        pow.isRunning = false;
+        ws.close();
+    };
+
+    ws.onerror = function (error) {
+        pow.busy = false;
+        pow.isRunning = false;
+
+        feedback_container.style.backgroundColor = "#870000";
+        feedback_text.textContent = "WebSocket connection closed.";
+
+        console.error("WebSocket error:", error);
+    };
+
+    ws.onclose = function () {
+        pow.busy = false;
+        pow.isRunning = false;
+    };
+});
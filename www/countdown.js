@@ -0,0 +1,47 @@
+// Counts down to the state degrading on its own, unless a heartbeat arrives, from the
+// time left as of the page, then as of every `status` event of the server's
+// `/api/v1/events` stream, so a heartbeat resets it and the visitor's clock doesn't matter.
+
+const countdown = document.getElementsByClassName("countdown")[0];
+let deadline = Date.now() + Number(countdown.dataset.seconds) * 1000;
+
+// rounded up to the minute, like the server's `templating::time_left`
+function timeLeft(seconds) {
+    const minutes = Math.ceil(seconds / 60);
+    const days = Math.floor(minutes / (24 * 60));
+    const hours = Math.floor(minutes / 60) % 24;
+
+    if (days > 0) {
+        return `${days}d ${hours}h`;
+    }
+    if (hours > 0) {
+        return `${hours}h ${minutes % 60}m`;
+    }
+    return `${minutes}m`;
+}
+
+function showCountdown() {
+    const seconds = Math.max(0, (deadline - Date.now()) / 1000);
+    countdown.textContent = countdown.dataset.template.replace("{1}", timeLeft(seconds));
+}
+
+if (countdown.id !== "hidden") {
+    setInterval(showCountdown, 10 * 1000);
+
+    // the status page's own stream, if it has one
+    const statusEvents = typeof events === "undefined"
+        ? new EventSource("/api/v1/events" + window.location.search)
+        : events;
+
+    statusEvents.addEventListener("status", (event) => {
+        const status = JSON.parse(event.data);
+
+        // counting down to another transition than the page says, until it's reloaded
+        if (String(status.state_code) !== countdown.dataset.state || status.seconds_until_next_transition === null) {
+            countdown.id = "hidden";
+            return;
+        }
+        deadline = Date.now() + status.seconds_until_next_transition * 1000;
+        showCountdown();
+    });
+}
@@ -0,0 +1,59 @@
+const pow = new PoW();
+
+document.getElementById("guestbook-form").addEventListener("submit", async function (e) {
+    e.preventDefault(); // sign the guestbook once the proof of work is solved
+
+    if (pow.busy) {
+        return;
+    }
+    pow.busy = true;
+
+    const form = this;
+    const feedback = document.getElementById("guestbook-feedback");
+    const ws = new WebSocket("/api/v1/pow");
+
+    feedback.textContent = "Waiting for Challenge from Server..";
+
+    ws.onmessage = async function (event) {
+        if (pow.isRunning) {
+            return;
+        }
+        pow.isRunning = true;
+
+        const challenge = JSON.parse(event.data);
+
+        // solve PoW challenge
+        feedback.textContent = "Solving Cryptographic Challenge..";
+        const guestbook_request = {
+            name: document.getElementById("guestbook-name").value,
+            message: document.getElementById("guestbook-message").value,
+            pow: await pow.handleChallenge(challenge),
+        };
+        ws.close();
+
+        const response = await fetch("/api/v1/guestbook", {
+            method: "POST",
+            headers: { "Content-Type": "application/json" },
+            body: JSON.stringify(guestbook_request),
+        });
+
+        if (response.ok) {
+            form.reset();
+            feedback.textContent = form.dataset.thanks;
+        } else {
+            const problem = await response.json().catch(() => ({}));
+            feedback.textContent = problem.detail ?? `Could not sign the guestbook. (HTTP ${response.status})`;
+        }
+        pow.busy = false;
+        pow.isRunning = false;
+    };
+
+    ws.onerror = function (error) {
+        pow.busy = false;
+        pow.isRunning = false;
+
+        feedback.textContent = "WebSocket connection closed.";
+
+        console.error("WebSocket error:", error);
+    };
+});
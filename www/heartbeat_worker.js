@@ -0,0 +1,168 @@
+// Service worker of the installable `/heartbeat` page. Keeps the page available
+// offline, and queues heartbeats sent while offline, to send them (with a fresh
+// proof of work) once we're back online. Queued heartbeats keep the time they
+// were composed at, so the server counts them from then.
+
+importScripts("/pow.js");
+
+const CACHE = "heartbeat-v1";
+const PAGE_RESOURCES = [
+    "/heartbeat",
+    "/styles.css",
+    "/pow.js",
+    "/pow_solver.wasm",
+    "/send_heartbeat.js",
+    "/resources/heart.svg",
+    "/favicon/favicon-32x32.png",
+    "/favicon/android-chrome-192x192.png",
+];
+
+self.addEventListener("install", function (event) {
+    event.waitUntil(caches.open(CACHE).then(cache => cache.addAll(PAGE_RESOURCES)));
+    self.skipWaiting();
+});
+
+self.addEventListener("activate", function (event) {
+    event.waitUntil(self.clients.claim().then(sendQueuedHeartbeats).catch(console.error));
+});
+
+// network first, so the page (and its note) stays up-to-date when online
+self.addEventListener("fetch", function (event) {
+    const url = new URL(event.request.url);
+
+    if (event.request.method !== "GET" || !PAGE_RESOURCES.includes(url.pathname)) {
+        return;
+    }
+    event.respondWith(
+        fetch(event.request)
+            .then(response => {
+                const copy = response.clone();
+                caches.open(CACHE).then(cache => cache.put(event.request, copy));
+                return response;
+            })
+            .catch(() => caches.match(event.request))
+    );
+});
+
+// IndexedDB store of the heartbeat requests waiting to be sent, without their PoW
+function openQueue() {
+    return new Promise((resolve, reject) => {
+        const request = indexedDB.open("heartbeat-queue", 1);
+        request.onupgradeneeded = () => request.result.createObjectStore("queue", { autoIncrement: true });
+        request.onsuccess = () => resolve(request.result);
+        request.onerror = () => reject(request.error);
+    });
+}
+
+async function withQueue(mode, action) {
+    const db = await openQueue();
+
+    return new Promise((resolve, reject) => {
+        const transaction = db.transaction("queue", mode);
+        const result = action(transaction.objectStore("queue"));
+        transaction.oncomplete = () => resolve(result.result);
+        transaction.onerror = () => reject(transaction.error);
+    });
+}
+
+async function notifyPages(message) {
+    for (const client of await self.clients.matchAll()) {
+        client.postMessage(message);
+    }
+}
+
+// error responses are RFC 7807 problem details, with a human-readable `detail`
+async function problemDetail(response) {
+    try {
+        return (await response.json()).detail ?? "";
+    } catch {
+        return "";
+    }
+}
+
+const pow = new PoW();
+
+// get a fresh PoW challenge, and solve it with `pow.js`, committing to the heartbeat
+function solveChallenge(heartbeat_request) {
+    return new Promise((resolve, reject) => {
+        const ws = new WebSocket(new URL("/api/v1/pow", self.location.origin).href.replace(/^http/, "ws"));
+
+        ws.onmessage = async function (event) {
+            ws.onmessage = null;
+            ws.close();
+
+            const solution = await pow.handleChallenge(JSON.parse(event.data), heartbeat_request);
+
+            if (solution) {
+                resolve(solution);
+            } else {
+                reject(new Error("Could not solve the PoW challenge."));
+            }
+        };
+        ws.onerror = () => reject(new Error("Could not get a PoW challenge."));
+    });
+}
+
+let sending = false;
+
+// fails while we're still offline, so that background sync retries later
+async function sendQueuedHeartbeats() {
+    if (sending) {
+        return;
+    }
+    sending = true;
+
+    try {
+        const keys = await withQueue("readonly", store => store.getAllKeys());
+
+        for (const key of keys) {
+            const heartbeat_request = await withQueue("readonly", store => store.get(key));
+            heartbeat_request.pow = await solveChallenge(heartbeat_request);
+
+            const response = await fetch("/api/v1/heartbeat", {
+                method: "POST",
+                headers: {
+                    "Content-Type": "application/json",
+                },
+                body: JSON.stringify(heartbeat_request),
+            });
+
+            // keep it for later if we're rate limited, or the PoW took too long
+            if (response.status === 429 || response.status === 406) {
+                break;
+            }
+            // anything else won't get better by retrying
+            await withQueue("readwrite", store => store.delete(key));
+
+            await notifyPages({
+                type: "heartbeat-sent",
+                ok: response.ok,
+                status: response.status,
+                text: await problemDetail(response),
+            });
+        }
+    } finally {
+        sending = false;
+    }
+}
+
+self.addEventListener("message", function (event) {
+    if (event.data.type === "queue-heartbeat") {
+        event.waitUntil(
+            withQueue("readwrite", store => store.add(event.data.heartbeat_request)).then(async () => {
+                if (self.registration.sync) {
+                    await self.registration.sync.register("send-heartbeats");
+                }
+            })
+        );
+    } else if (event.data.type === "send-queued-heartbeats") {
+        event.waitUntil(sendQueuedHeartbeats().catch(console.error));
+    }
+});
+
+// background sync, where supported, sends them even with the page closed
+self.addEventListener("sync", function (event) {
+    if (event.tag === "send-heartbeats") {
+        event.waitUntil(sendQueuedHeartbeats());
+    }
+});
@@ -0,0 +1,22 @@
+// Reloads the status page whenever the status changes, as told by
+// the server's `/api/v1/events` stream, so it never shows a stale state.
+// The page's query string is passed on, for the read key of private instances.
+
+const events = new EventSource("/api/v1/events" + window.location.search);
+let firstStatus = null;
+
+events.addEventListener("status", (event) => {
+    // a copy kept by `status_worker.js` from before is replaced once we're back online
+    if (document.documentElement.dataset.staleSince) {
+        window.location.reload();
+        return;
+    }
+    // the first event is the status the page was just rendered with
+    if (firstStatus === null) {
+        firstStatus = event.data;
+        return;
+    }
+    if (event.data !== firstStatus) {
+        window.location.reload();
+    }
+});
@@ -0,0 +1,24 @@
+// Shows the dates of the page's `<time>` elements in the visitor's own time zone,
+// and in the language of the page. Without JavaScript, or when a date can't be
+// read, they stay in the owner's time zone, as rendered by the server.
+
+const format = new Intl.DateTimeFormat(document.documentElement.lang || undefined, {
+    year: "numeric",
+    month: "short",
+    day: "numeric",
+    hour: "2-digit",
+    minute: "2-digit",
+    second: "2-digit",
+    timeZoneName: "short",
+});
+
+for (const time of document.querySelectorAll("time[datetime]")) {
+    const date = new Date(time.dateTime);
+
+    if (isNaN(date.getTime())) {
+        continue;
+    }
+    // the owner's time is still there to hover over
+    time.title = time.textContent;
+    time.textContent = format.format(date);
+}
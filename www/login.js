@@ -0,0 +1,51 @@
+const pow = new PoW();
+
+document.getElementById("login-form").addEventListener("submit", async function (e) {
+    e.preventDefault(); // submit once the proof of work is solved
+
+    if (pow.busy) {
+        return;
+    }
+    pow.busy = true;
+
+    const form = this;
+    const ws = new WebSocket("/api/v1/pow");
+
+    document.getElementsByClassName("auth-feedback")[0].id = "";
+    let feedback_container = document.getElementsByClassName("auth-feedback")[0];
+    let feedback_text = document.getElementById("auth-feedback-text");
+
+    feedback_container.style.backgroundColor = "#7c7402";
+    feedback_text.textContent = "Waiting for Challenge from Server..";
+
+    ws.onmessage = async function (event) {
+        if (pow.isRunning) {
+            return;
+        }
+        pow.isRunning = true;
+
+        const challenge = JSON.parse(event.data);
+
+        // solve PoW challenge
+        feedback_text.textContent = "Solving Cryptographic Challenge..";
+        let pow_result = await pow.handleChallenge(challenge);
+
+        document.getElementById("pow-nonce").value = pow_result.nonce;
+        document.getElementById("pow-hash").value = pow_result.hash;
+        document.getElementById("pow-timestamp-ms").value = pow_result.timestamp_ms;
+
+        feedback_text.textContent = "Logging In..";
+        ws.close();
+        form.submit();
+    };
+
+    ws.onerror = function (error) {
+        pow.busy = false;
+        pow.isRunning = false;
+
+        feedback_container.style.backgroundColor = "#870000";
+        feedback_text.textContent = "WebSocket connection closed.";
+
+        console.error("WebSocket error:", error);
+    };
+});
@@ -0,0 +1,37 @@
+// Installs `status_worker.js`, so the status page can be opened without a connection,
+// and tells the visitor when what they see is the last status it loaded, and from when.
+// Also tells them when they lose their connection with the page open.
+
+const staleNotice = document.getElementsByClassName("stale-notice")[0];
+const staleTime = staleNotice.getElementsByTagName("time")[0];
+const loadedAt = Date.now();
+
+// the installed app of a private instance needs the read key too
+document.querySelector('link[rel="manifest"]').href += window.location.search;
+
+if ("serviceWorker" in navigator) {
+    navigator.serviceWorker.register("/status_worker.js").catch(console.error);
+}
+
+// in the visitor's time zone, with the `format` of `local_time.js`, which comes before
+function showStale(since) {
+    const date = new Date(since);
+
+    staleTime.dateTime = date.toISOString();
+    staleTime.textContent = format.format(date);
+    staleNotice.id = "";
+}
+
+const staleSince = Number(document.documentElement.dataset.staleSince);
+
+if (staleSince) {
+    showStale(staleSince);
+}
+
+window.addEventListener("offline", () => showStale(staleSince || loadedAt));
+window.addEventListener("online", function () {
+    // a stale copy is reloaded by `live_status.js`, once it reaches the server
+    if (!staleSince) {
+        staleNotice.id = "hidden";
+    }
+});
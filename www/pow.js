@@ -0,0 +1,121 @@
+// Solves the server's PoW challenges with `pow_solver.wasm`, built from the
+// `pow-solver` crate, so that this never has to know how a challenge is solved.
+class PoW {
+    constructor() {
+        this.busy = false;
+        this.isRunning = false;
+        this.shouldStop = false;
+        this.solver = null;
+    }
+
+    async init() {
+        if (this.solver) {
+            return;
+        }
+        const response = await fetch("/pow_solver.wasm");
+        const { instance } = await WebAssembly.instantiate(await response.arrayBuffer());
+        this.solver = instance.exports;
+    }
+
+    // try `attempts` nonces from `start`, see `pow-solver/src/wasm.rs`
+    solveRange(challenge, start, attempts) {
+        const input = new TextEncoder().encode(challenge);
+        const ptr = this.solver.alloc(input.length);
+        new Uint8Array(this.solver.memory.buffer, ptr, input.length).set(input);
+
+        const length = this.solver.solve(ptr, input.length, start, attempts);
+        this.solver.dealloc(ptr, input.length);
+
+        const output = new Uint8Array(this.solver.memory.buffer, this.solver.output(), length);
+        return JSON.parse(new TextDecoder().decode(output));
+    }
+
+    // `payload` is the heartbeat to commit to, if any, see `pow_solver::payload_digest`
+    async computePoW(challenge, payload = null, maxAttempts = 5000000) {
+        await this.init();
+
+        const startTime = performance.now();
+        const input = JSON.stringify({
+            challenge,
+            payload: payload && {
+                message: payload.message,
+                updated_note: payload.updated_note,
+                remove_current_note: payload.remove_current_note
+            }
+        });
+        let nonce = 0;
+        let attempts = 1;
+
+        while (nonce < maxAttempts) {
+            if (this.shouldStop) {
+                return { success: false, timeMs: performance.now() - startTime, attempts: nonce };
+            }
+            const chunkStart = performance.now();
+            const answer = this.solveRange(input, nonce, attempts);
+
+            if (answer && answer.error) {
+                return {
+                    success: false,
+                    error: answer.error,
+                    timeMs: performance.now() - startTime,
+                    attempts: nonce
+                };
+            }
+            if (answer) {
+                return {
+                    ...answer,
+                    attempts: answer.nonce + 1,
+                    timeMs: performance.now() - startTime,
+                    success: true
+                };
+            }
+            // solve in chunks of about 50ms, so the page stays responsive
+            // whatever the algorithm, then let it update in between
+            const chunkTime = Math.max(performance.now() - chunkStart, 1);
+            nonce += attempts;
+            attempts = Math.max(1, Math.min(attempts * 2, Math.floor(attempts * 50 / chunkTime)));
+
+            await new Promise(resolve => setTimeout(resolve, 0));
+        }
+        return {
+            success: false,
+            timeMs: performance.now() - startTime,
+            attempts: maxAttempts
+        };
+    }
+
+    async handleChallenge(challenge, payload = null) {
+        const result = await this.computePoW(challenge, payload);
+
+        if (result.success) {
+            console.log("Found valid PoW:", result);
+            return {
+                nonce: result.nonce,
+                hash: result.hash,
+                timestamp_ms: result.timestamp_ms,
+                payload_digest: result.payload_digest
+            }
+        } else {
+            console.log("Failed to compute valid PoW.", result.error ?? "");
+        }
+    }
+}
+
+function formatDuration(seconds) {
+    if (seconds < 60) {
+        return `${seconds} second${seconds === 1 ? "" : "s"}`;
+    }
+
+    const minutes = Math.floor(seconds / 60);
+    if (minutes < 60) {
+        return `${minutes} minute${minutes === 1 ? "" : "s"}`;
+    }
+
+    const hours = Math.floor(minutes / 60);
+    if (hours < 24) {
+        return `${hours} hour${hours === 1 ? "" : "s"}`;
+    }
+
+    const days = Math.floor(hours / 24);
+    return `${days} hour${days === 1 ? "" : "s"}`;
+}
\ No newline at end of file
@@ -0,0 +1,60 @@
+// Subscribes visitors of the status page to Web Push notifications,
+// which are sent by the server whenever the displayed state changes.
+
+const pushContainer = document.getElementsByClassName("push-subscribe")[0];
+const pushLink = document.getElementById("push-subscribe-link");
+
+function urlBase64ToUint8Array(base64String) {
+    const padding = "=".repeat((4 - base64String.length % 4) % 4);
+    const base64 = (base64String + padding).replace(/-/g, "+").replace(/_/g, "/");
+    const raw = window.atob(base64);
+    return Uint8Array.from([...raw].map(c => c.charCodeAt(0)));
+}
+
+async function subscribeToPush() {
+    // the worker of the status page, which shows them with `push_worker.js`
+    const registration = await navigator.serviceWorker.register("/status_worker.js");
+
+    const permission = await Notification.requestPermission();
+    if (permission !== "granted") {
+        pushLink.textContent = "Notifications were blocked by your browser.";
+        return;
+    }
+
+    const subscription = await registration.pushManager.subscribe({
+        userVisibleOnly: true,
+        applicationServerKey: urlBase64ToUint8Array(pushContainer.dataset.vapidKey),
+    });
+
+    // subscribing takes a proof of work, like signing the guestbook
+    pushLink.textContent = "Solving Cryptographic Challenge..";
+    const challenge = await (await fetch("/api/v1/pow/challenge")).json();
+    const solution = await new PoW().handleChallenge(challenge);
+
+    const response = await fetch("/api/v1/push/subscribe" + window.location.search, {
+        method: "POST",
+        headers: {
+            "Content-Type": "application/json",
+        },
+        body: JSON.stringify({ ...subscription.toJSON(), pow: solution }),
+    });
+
+    if (response.ok) {
+        pushLink.textContent = "You will be notified when the status changes.";
+    } else {
+        pushLink.textContent = `Could not subscribe. (HTTP ${response.status})`;
+    }
+}
+
+if (pushContainer.id !== "hidden") {
+    if (!("serviceWorker" in navigator) || !("PushManager" in window)) {
+        pushContainer.id = "hidden";
+    }
+    pushLink.addEventListener("click", function (e) {
+        e.preventDefault();
+        subscribeToPush().catch(err => {
+            console.error(err);
+            pushLink.textContent = "Could not subscribe to notifications.";
+        });
+    });
+}
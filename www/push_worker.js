@@ -0,0 +1,17 @@
+// Service worker that displays the Web Push notifications sent by the server.
+
+self.addEventListener("push", function (event) {
+    const data = event.data ? event.data.json() : {};
+
+    event.waitUntil(
+        self.registration.showNotification(data.title || "Status changed", {
+            body: data.body || "",
+            icon: "/favicon/android-chrome-192x192.png",
+        })
+    );
+});
+
+self.addEventListener("notificationclick", function (event) {
+    event.notification.close();
+    event.waitUntil(clients.openWindow("/"));
+});
@@ -1,174 +1,81 @@
-class PoW {
-    constructor() {
-        this.busy = false;
-        this.isRunning = false;
-        this.shouldStop = false;
-        this.hashWasm = null;
-        this.hasWebCrypto = window.crypto && window.crypto.subtle;
-    }
-
-    async init() {
-        // wait for hash-wasm to be available
-        let attempts = 0;
-        while (attempts < 30) {
-            if (window.hashWasm || window.HashWasm || (window.hashwasm && window.hashwasm.createBLAKE3)) {
-                this.hashWasm = window.hashWasm || window.HashWasm || window.hashwasm;
-                log('hash-wasm library loaded successfully', 'success');
-                return;
-            }
-
-            // check if individual functions are available
-            if (window.createBLAKE3 && window.createXXHash3 && window.createSHA256) {
-                log('hash-wasm functions detected individually', 'success');
-                this.hashWasm = {
-                    blake3: async (data) => {
-                        const hasher = await window.createBLAKE3();
-                        hasher.update(data);
-                        return hasher.digest('hex');
-                    },
-                    xxhash3: async (data) => {
-                        const hasher = await window.createXXHash3();
-                        hasher.update(data);
-                        return hasher.digest('hex');
-                    },
-                    sha256: async (data) => {
-                        const hasher = await window.createSHA256();
-                        hasher.update(data);
-                        return hasher.digest('hex');
-                    }
-                };
-                return;
-            }
-
-            await new Promise(resolve => setTimeout(resolve, 100));
-            attempts++;
-        }
-        log('hash-wasm library not detected! Using Web Crypto API for SHA-256 only', 'warning');
-
-        if (!this.hasWebCrypto) {
-            throw new Error('No hash algorithms available');
-        }
-    }
+const pow = new PoW();
 
-    async sha256WebCrypto(message) {
-        const encoder = new TextEncoder();
-        const data = encoder.encode(message);
-        const hashBuffer = await crypto.subtle.digest('SHA-256', data);
-        const hashArray = Array.from(new Uint8Array(hashBuffer));
-        return hashArray.map(b => b.toString(16).padStart(2, '0')).join('');
-    }
+// the QR code shown on this page links here with a token taking the place of the password
+const qr_token = new URLSearchParams(location.hash.slice(1)).get("token");
 
-    isAlgorithmAvailable(algorithm) {
-        switch (algorithm) {
-            case 'blake3':
-            case 'xxhash3':
-                return this.hashWasm && (this.hashWasm.blake3 || this.hashWasm.xxhash3);
-            case 'sha256':
-                return this.hashWasm || this.hasWebCrypto;
-            default:
-                return false;
-        }
-    }
+if (qr_token) {
+    document.getElementById("pwd").value = qr_token;
+    history.replaceState(null, "", location.pathname);
+}
 
-    hashMeetsTarget(hash, target, algorithm) {
-        switch (algorithm) {
-            case 'blake3':
-            case 'sha256':
-                return hash < target;
+// read a file's contents as a base64 string, without the "data:" URL prefix
+function readAsBase64(file) {
+    return new Promise((resolve, reject) => {
+        const reader = new FileReader();
+        reader.onload = () => resolve(reader.result.split(",")[1]);
+        reader.onerror = () => reject(reader.error);
+        reader.readAsDataURL(file);
+    });
+}
 
-            case 'xxhash3':
-                const hashValue = parseInt(hash.substring(0, 8), 16);
-                return hashValue < target;
+// the service worker queues heartbeats sent while offline, see `heartbeat_worker.js`
+if ("serviceWorker" in navigator) {
+    navigator.serviceWorker.register("/heartbeat_worker.js", { scope: "/heartbeat" });
 
-            default:
-                return false;
+    navigator.serviceWorker.addEventListener("message", function (event) {
+        if (event.data.type !== "heartbeat-sent") {
+            return;
         }
-    }
+        document.getElementsByClassName("auth-feedback")[0].id = "";
+        let feedback_container = document.getElementsByClassName("auth-feedback")[0];
+        let feedback_text = document.getElementById("auth-feedback-text");
 
-    async hashMessage(message, algorithm) {
-        switch (algorithm) {
-            case 'blake3':
-                if (this.hashWasm && this.hashWasm.blake3) {
-                    return await this.hashWasm.blake3(message);
-                }
-                throw new Error('BLAKE3 not available');
-            case 'xxhash3':
-                if (this.hashWasm && this.hashWasm.xxhash3) {
-                    return await this.hashWasm.xxhash3(message);
-                }
-                throw new Error('xxHash3 not available');
-            case 'sha256':
-                if (this.hashWasm && this.hashWasm.sha256) {
-                    return await this.hashWasm.sha256(message);
-                } else if (this.hasWebCrypto) {
-                    return await this.sha256WebCrypto(message);
-                }
-                throw new Error('SHA-256 not available');
-            default:
-                throw new Error(`Unknown algorithm: ${algorithm}`);
+        if (event.data.ok) {
+            feedback_container.style.backgroundColor = "#067c02";
+            feedback_text.textContent = "Your queued heartbeat was sent!";
+        } else {
+            feedback_container.style.backgroundColor = "#870000";
+            feedback_text.textContent = `Your queued heartbeat was refused. (HTTP ${event.data.status}) ${event.data.text}`;
         }
-    }
-
-    async computePoW(userAddress, seed, target, algorithm, maxAttempts = 5000000) {
-        const startTime = performance.now();
-
-        for (let nonce = 0; nonce < maxAttempts; nonce++) {
-            if (this.shouldStop) {
-                return { success: false, timeMs: performance.now() - startTime, attempts: nonce };
-            }
-            const message = userAddress + seed + nonce;
+    });
+
+    // send anything still queued, now that we're (maybe) back online
+    const sendQueued = () => navigator.serviceWorker.ready.then(registration =>
+        registration.active.postMessage({ type: "send-queued-heartbeats" })
+    );
+    window.addEventListener("online", sendQueued);
+    sendQueued();
+}
 
-            try {
-                const hash = await this.hashMessage(message, algorithm);
+// hand a heartbeat over to the service worker, to be sent once we're back online
+async function queueHeartbeat(feedback_container, feedback_text) {
+    const heartbeat_request = {
+        updated_note: document.getElementById("newnote").value,
+        remove_current_note: document.getElementById("rmnote").checked,
+        message: document.getElementById("msg").value,
+        password: document.getElementById("pwd").value,
+        composed_at: Math.floor(Date.now() / 1000),
+    };
+    const media = document.getElementById("media").files[0];
 
-                if (this.hashMeetsTarget(hash, target, algorithm)) {
-                    const endTime = performance.now();
-                    return {
-                        nonce,
-                        hash,
-                        attempts: nonce + 1,
-                        timeMs: endTime - startTime,
-                        success: true
-                    };
-                }
-            } catch (error) {
-                return {
-                    success: false,
-                    error: error.message,
-                    timeMs: performance.now() - startTime,
-                    attempts: nonce
-                };
-            }
-        }
-        return {
-            success: false,
-            timeMs: performance.now() - startTime,
-            attempts: maxAttempts
+    if (media) {
+        heartbeat_request.attachment = {
+            content_type: media.type,
+            data: await readAsBase64(media),
         };
     }
-
-    async handleChallenge(challenge) {
-        const userAddress = challenge.user_address;
-        const seed = challenge.seed;
-        const difficulty = challenge.difficulty;
-        const timestamp = challenge.timestamp;
-
-        const result = await this.computePoW(userAddress, seed, difficulty, 'sha256');
-
-        if (result) {
-            console.log("Found valid PoW:", result);
-            return {
-                nonce: result.nonce,
-                hash: result.hash,
-                timestamp_ms: timestamp
-            }
-        } else {
-            console.log("Failed to compute valid PoW.");
-        }
+    // a TOTP code or QR code token would have expired by the time we're back online
+    if (/^\d{6}$/.test(heartbeat_request.password) || heartbeat_request.password === qr_token) {
+        feedback_container.style.backgroundColor = "#870000";
+        feedback_text.textContent = "You're offline. Use your password to queue the heartbeat, TOTP codes and QR codes expire.";
+        return;
     }
-}
+    const registration = await navigator.serviceWorker.ready;
+    registration.active.postMessage({ type: "queue-heartbeat", heartbeat_request });
 
-const pow = new PoW();
+    feedback_container.style.backgroundColor = "#7c7402";
+    feedback_text.textContent = "You're offline. The heartbeat will be sent once you're back online.";
+}
 
 document.getElementById("send-heartbeat-form").addEventListener("submit", async function (e) {
     e.preventDefault(); // stop normal form submit
@@ -176,14 +83,19 @@ document.getElementById("send-heartbeat-form").addEventListener("submit", async
     if (pow.busy) {
         return;
     }
-    pow.busy = true;
-
-    const ws = new WebSocket("/api/pow");
 
     document.getElementsByClassName("auth-feedback")[0].id = "";
     let feedback_container = document.getElementsByClassName("auth-feedback")[0];
     let feedback_text = document.getElementById("auth-feedback-text");
 
+    if (!navigator.onLine && "serviceWorker" in navigator) {
+        await queueHeartbeat(feedback_container, feedback_text);
+        return;
+    }
+    pow.busy = true;
+
+    const ws = new WebSocket("/api/v1/pow");
+
     feedback_container.style.backgroundColor = "#7c7402";
     feedback_text.textContent = "Waiting for Challenge from Server..";
 
@@ -200,22 +112,30 @@ document.getElementById("send-heartbeat-form").addEventListener("submit", async
         const challenge = JSON.parse(event.data);
         console.log("Received challenge:", challenge);
 
-        // solve PoW challenge
-        feedback_container.style.backgroundColor = "#7c7402";
-        feedback_text.textContent = "Solving Cryptographic Challenge..";
-        let pow_result = await pow.handleChallenge(challenge);
-
         const heartbeat_request = {
             updated_note: document.getElementById("newnote").value,
             remove_current_note: document.getElementById("rmnote").checked,
             message: document.getElementById("msg").value,
             password: document.getElementById("pwd").value,
-            pow: pow_result
         };
+
+        // solve PoW challenge, committing to this very heartbeat
+        feedback_container.style.backgroundColor = "#7c7402";
+        feedback_text.textContent = "Solving Cryptographic Challenge..";
+        heartbeat_request.pow = await pow.handleChallenge(challenge, heartbeat_request);
+
+        const media = document.getElementById("media").files[0];
+
+        if (media) {
+            heartbeat_request.attachment = {
+                content_type: media.type,
+                data: await readAsBase64(media),
+            };
+        }
         try {
             feedback_text.textContent = "Submitting..";
 
-            const response = await fetch("/api/heartbeat", {
+            const response = await fetch("/api/v1/heartbeat", {
                 method: "POST",
                 headers: {
                     "Content-Type": "application/json",
@@ -235,6 +155,9 @@ document.getElementById("send-heartbeat-form").addEventListener("submit", async
             } else if (response.status === 406) {
                 feedback_container.style.backgroundColor = "#7a3f01";
                 feedback_text.textContent = `PoW challenge rejected. Please try again.`;
+            } else if (response.status === 422) {
+                feedback_container.style.backgroundColor = "#7a3f01";
+                feedback_text.textContent = (await response.json()).detail;
             } else if (response.ok) {
                 feedback_container.style.backgroundColor = "#067c02";
                 feedback_text.textContent = "Heartbeat Authenticated! Redirecting...";
@@ -277,22 +200,3 @@ document.getElementById("send-heartbeat-form").addEventListener("submit", async
         console.log("WebSocket connection closed.");
     };
 });
-
-function formatDuration(seconds) {
-    if (seconds < 60) {
-        return `${seconds} second${seconds === 1 ? "" : "s"}`;
-    }
-
-    const minutes = Math.floor(seconds / 60);
-    if (minutes < 60) {
-        return `${minutes} minute${minutes === 1 ? "" : "s"}`;
-    }
-
-    const hours = Math.floor(minutes / 60);
-    if (hours < 24) {
-        return `${hours} hour${hours === 1 ? "" : "s"}`;
-    }
-
-    const days = Math.floor(hours / 24);
-    return `${days} hour${days === 1 ? "" : "s"}`;
-}
\ No newline at end of file
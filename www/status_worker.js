@@ -0,0 +1,64 @@
+// Service worker of the status page, installed as an app or not. Keeps the last
+// status page loaded, and what it needs, so it still opens without a connection,
+// marked with when it was loaded for `offline_status.js` to say it's stale.
+// Also shows the Web Push notifications, with `push_worker.js`.
+
+importScripts("/push_worker.js");
+
+const CACHE = "status-v1";
+
+self.addEventListener("install", function () {
+    self.skipWaiting();
+});
+
+self.addEventListener("activate", function (event) {
+    event.waitUntil(self.clients.claim());
+});
+
+// the status page itself, and the styles, scripts, and images it's shown with,
+// but nothing of the API, nor any other page, which may be private
+function isKept(request) {
+    const url = new URL(request.url);
+
+    if (request.method !== "GET" || url.origin !== self.location.origin || url.pathname.startsWith("/api")) {
+        return false;
+    }
+    if (request.mode === "navigate") {
+        return url.pathname === "/";
+    }
+    return ["style", "script", "image"].includes(request.destination);
+}
+
+// the copy of the status page is marked on its `<html>` element with when it was loaded
+async function staleCopy(request) {
+    const cached = await caches.match(request);
+
+    if (!cached || request.mode !== "navigate") {
+        return cached;
+    }
+    const loadedAt = cached.headers.get("X-Loaded-At");
+    const html = (await cached.text()).replace("<html ", `<html data-stale-since="${loadedAt}" `);
+
+    return new Response(html, { headers: cached.headers });
+}
+
+// network first, so that the status is never stale when it doesn't have to be
+self.addEventListener("fetch", function (event) {
+    if (!isKept(event.request)) {
+        return;
+    }
+    event.respondWith(
+        fetch(event.request)
+            .then(async response => {
+                if (response.ok) {
+                    const headers = new Headers(response.headers);
+                    headers.set("X-Loaded-At", Date.now().toString());
+
+                    const copy = new Response(await response.clone().blob(), { headers });
+                    await (await caches.open(CACHE)).put(event.request, copy);
+                }
+                return response;
+            })
+            .catch(() => staleCopy(event.request))
+    );
+});